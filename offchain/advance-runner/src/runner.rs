@@ -95,6 +95,22 @@ impl<Snap: SnapshotManager + std::fmt::Debug + 'static> Runner<Snap> {
                         )
                         .await?;
                 }
+                RollupsData::RejectedInput(input) => {
+                    // The server-manager expects consecutive input indices,
+                    // so the rejected input still takes its slot in the epoch
+                    tracing::warn!(
+                        rejection = ?input.rejection,
+                        "input rejected by the dispatcher; advancing with an empty payload"
+                    );
+                    runner
+                        .handle_advance(
+                            event.payload.epoch_index,
+                            event.payload.inputs_sent_count,
+                            input.metadata,
+                            vec![],
+                        )
+                        .await?;
+                }
                 RollupsData::FinishEpoch {} => {
                     runner
                         .handle_finish(
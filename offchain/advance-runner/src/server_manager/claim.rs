@@ -1,20 +1,24 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
-use rollups_events::{Hash, HASH_SIZE};
-use sha3::{Digest, Keccak256};
+use output_proofs::validity;
+use rollups_events::Hash;
 
 pub fn compute_epoch_hash(
+    vouchers_epoch_root_hash: &Hash,
+    notices_epoch_root_hash: &Hash,
     machine_state_hash: &Hash,
-    vouchers_metadata_hash: &Hash,
-    notices_metadata_hash: &Hash,
 ) -> Hash {
-    let mut hasher = Keccak256::new();
-    hasher.update(machine_state_hash.inner());
-    hasher.update(vouchers_metadata_hash.inner());
-    hasher.update(notices_metadata_hash.inner());
-    let data: [u8; HASH_SIZE] = hasher.finalize().into();
-    Hash::new(data)
+    let epoch_hash = validity::compute_epoch_hash(
+        &convert_hash(vouchers_epoch_root_hash),
+        &convert_hash(notices_epoch_root_hash),
+        &convert_hash(machine_state_hash),
+    );
+    Hash::new(epoch_hash.data().to_owned())
+}
+
+fn convert_hash(hash: &Hash) -> output_proofs::hash::Hash {
+    hash.inner().to_owned().into()
 }
 
 #[cfg(test)]
@@ -5,12 +5,13 @@
 //! rollups-events types
 use grpc_interfaces::cartesi_machine::Hash;
 use grpc_interfaces::cartesi_server_manager::{
-    Address, CompletionStatus, OutputEnum, OutputValidityProof, Proof,
+    Address, CompletionStatus, OutputEnum, OutputValidityProof, Proof, Voucher,
+    VoucherKind,
 };
 use rollups_events::{
     Address as RollupsAddress, Hash as RollupsHash, Payload,
     RollupsCompletionStatus, RollupsOutputEnum, RollupsOutputValidityProof,
-    RollupsProof, ADDRESS_SIZE, HASH_SIZE,
+    RollupsProof, RollupsVoucher, ADDRESS_SIZE, HASH_SIZE,
 };
 
 use super::error::ServerManagerError;
@@ -133,3 +134,104 @@ fn convert_validity(
         output_hashes_in_epoch_siblings,
     })
 }
+
+/// Convert gRPC voucher to broker equivalent
+pub fn convert_voucher(
+    voucher: Voucher,
+    index: u64,
+    input_index: u64,
+) -> Result<RollupsVoucher, ServerManagerError> {
+    let destination = convert_address(get_field!(voucher.destination))?;
+    let kind = VoucherKind::from_i32(voucher.kind).ok_or(
+        ServerManagerError::InvalidEnumError {
+            name: "VoucherKind".to_owned(),
+            value: voucher.kind,
+        },
+    )?;
+    let value = match kind {
+        VoucherKind::Call => {
+            let value = convert_hash(get_field!(voucher.value))?;
+            Some(Payload::new(value.into_inner().into()))
+        }
+        VoucherKind::Legacy | VoucherKind::DelegateCall => None,
+    };
+    Ok(RollupsVoucher {
+        index,
+        input_index,
+        destination,
+        value,
+        payload: Payload::new(voucher.payload),
+        is_delegate_call: kind == VoucherKind::DelegateCall,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESTINATION: [u8; ADDRESS_SIZE] = [0xfa; ADDRESS_SIZE];
+
+    fn grpc_voucher(kind: VoucherKind, value: Option<Vec<u8>>) -> Voucher {
+        Voucher {
+            destination: Some(Address {
+                data: DESTINATION.into(),
+            }),
+            payload: vec![1, 2, 3],
+            kind: kind.into(),
+            value: value.map(|data| Hash { data }),
+        }
+    }
+
+    #[test]
+    fn test_it_converts_legacy_voucher() {
+        let voucher =
+            convert_voucher(grpc_voucher(VoucherKind::Legacy, None), 1, 2)
+                .unwrap();
+        assert_eq!(
+            voucher,
+            RollupsVoucher {
+                index: 1,
+                input_index: 2,
+                destination: RollupsAddress::new(DESTINATION),
+                value: None,
+                payload: Payload::new(vec![1, 2, 3]),
+                is_delegate_call: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_it_converts_voucher_with_value() {
+        let mut value = vec![0; HASH_SIZE];
+        value[HASH_SIZE - 1] = 10;
+        let voucher = convert_voucher(
+            grpc_voucher(VoucherKind::Call, Some(value.clone())),
+            0,
+            0,
+        )
+        .unwrap();
+        assert_eq!(voucher.value, Some(Payload::new(value)));
+        assert_eq!(voucher.payload, Payload::new(vec![1, 2, 3]));
+        assert!(!voucher.is_delegate_call);
+    }
+
+    #[test]
+    fn test_it_converts_delegate_call_voucher() {
+        let voucher = convert_voucher(
+            grpc_voucher(VoucherKind::DelegateCall, None),
+            0,
+            0,
+        )
+        .unwrap();
+        assert_eq!(voucher.value, None);
+        assert_eq!(voucher.payload, Payload::new(vec![1, 2, 3]));
+        assert!(voucher.is_delegate_call);
+    }
+
+    #[test]
+    fn test_it_fails_to_convert_voucher_without_value() {
+        let err = convert_voucher(grpc_voucher(VoucherKind::Call, None), 0, 0)
+            .unwrap_err();
+        assert!(matches!(err, ServerManagerError::MissingFieldError { .. }));
+    }
+}
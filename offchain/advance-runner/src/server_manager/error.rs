@@ -39,6 +39,9 @@ pub enum ServerManagerError {
         got: usize,
     },
 
+    #[snafu(display("invalid value {} for {} enum", value, name))]
+    InvalidEnumError { name: String, value: i32 },
+
     #[snafu(display("missing processed input in get epoch status"))]
     MissingProcessedInputError {},
 
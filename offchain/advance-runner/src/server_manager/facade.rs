@@ -4,7 +4,7 @@
 use backoff::{future::retry, Error, ExponentialBackoff};
 use rollups_events::{
     InputMetadata as RollupsInputMetadata, Payload, RollupsAdvanceResult,
-    RollupsClaim, RollupsNotice, RollupsOutput, RollupsReport, RollupsVoucher,
+    RollupsClaim, RollupsNotice, RollupsOutput, RollupsReport,
 };
 use snafu::{OptionExt, ResultExt};
 use std::path::Path;
@@ -23,7 +23,7 @@ use grpc_interfaces::cartesi_server_manager::{
 use super::claim::compute_epoch_hash;
 use super::config::ServerManagerConfig;
 use super::conversions::{
-    convert_address, convert_completion_status, convert_hash, convert_proof,
+    convert_completion_status, convert_hash, convert_proof, convert_voucher,
     get_field,
 };
 use super::error::{
@@ -246,14 +246,11 @@ impl ServerManagerFacade {
                     for (index, voucher) in
                         data.vouchers.into_iter().enumerate()
                     {
-                        let destination =
-                            convert_address(get_field!(voucher.destination))?;
-                        let voucher = RollupsVoucher {
-                            index: index as u64,
-                            input_index: current_input_index,
-                            payload: Payload::new(voucher.payload),
-                            destination,
-                        };
+                        let voucher = convert_voucher(
+                            voucher,
+                            index as u64,
+                            current_input_index,
+                        )?;
                         outputs.push(RollupsOutput::Voucher(voucher));
                     }
                     for (index, notice) in data.notices.into_iter().enumerate()
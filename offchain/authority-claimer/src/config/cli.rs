@@ -7,6 +7,7 @@ use eth_tx_manager::{
     Priority,
 };
 use log::{LogConfig, LogEnvCliConfig};
+use redacted::Redacted;
 use rollups_events::{BrokerCLIConfig, BrokerConfig};
 use rusoto_core::Region;
 use snafu::ResultExt;
@@ -14,8 +15,9 @@ use std::{fs, path::PathBuf, str::FromStr};
 
 use crate::config::{
     error::{
-        AuthorityClaimerConfigError, InvalidRegionSnafu, MnemonicFileSnafu,
-        TxManagerSnafu, TxSigningConfigError, TxSigningSnafu,
+        AuthorityClaimerConfigError, InvalidRegionSnafu,
+        KeystorePassphraseFileSnafu, MnemonicFileSnafu, TxManagerSnafu,
+        TxSigningConfigError, TxSigningSnafu,
     },
     json::{read_json_file, DappDeployment},
     AuthorityClaimerConfig, TxSigningConfig,
@@ -90,7 +92,7 @@ pub(crate) struct TxSigningCLIConfig {
     #[arg(long, env)]
     tx_signing_mnemonic: Option<String>,
 
-    /// Signer mnemonic file path, overrides `tx_signing_aws_kms_*`
+    /// Signer mnemonic file path, overrides `tx_signing_keystore_*` and `tx_signing_aws_kms_*`
     #[arg(long, env)]
     tx_signing_mnemonic_file: Option<String>,
 
@@ -98,6 +100,14 @@ pub(crate) struct TxSigningCLIConfig {
     #[arg(long, env)]
     tx_signing_mnemonic_account_index: Option<u32>,
 
+    /// Signer encrypted JSON keystore (V3) file path, overrides `tx_signing_aws_kms_*`
+    #[arg(long, env)]
+    tx_signing_keystore_file: Option<String>,
+
+    /// Path to a file with the passphrase of the JSON keystore
+    #[arg(long, env)]
+    tx_signing_keystore_passphrase_file: Option<String>,
+
     /// AWS KMS signer key-id
     #[arg(long, env)]
     tx_signing_aws_kms_key_id: Option<String>,
@@ -126,6 +136,20 @@ impl TryFrom<TxSigningCLIConfig> for TxSigningConfig {
                 mnemonic,
                 account_index,
             })
+        } else if let Some(path) = cli.tx_signing_keystore_file {
+            let passphrase_path = cli
+                .tx_signing_keystore_passphrase_file
+                .ok_or(TxSigningConfigError::MissingKeystorePassphrase)?;
+            let passphrase = fs::read_to_string(passphrase_path.clone())
+                .context(KeystorePassphraseFileSnafu {
+                    path: passphrase_path,
+                })?
+                .trim_end_matches(&['\r', '\n'][..])
+                .to_string();
+            Ok(TxSigningConfig::Keystore {
+                path,
+                passphrase: Redacted::new(passphrase),
+            })
         } else {
             match (cli.tx_signing_aws_kms_key_id, cli.tx_signing_aws_kms_region)
             {
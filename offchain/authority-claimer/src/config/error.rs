@@ -40,6 +40,18 @@ pub enum TxSigningConfigError {
         source: std::io::Error,
     },
 
+    #[snafu(display("Missing keystore passphrase file"))]
+    MissingKeystorePassphrase,
+
+    #[snafu(display(
+        "Could not read keystore passphrase file at path `{}`",
+        path,
+    ))]
+    KeystorePassphraseFileError {
+        path: String,
+        source: std::io::Error,
+    },
+
     #[snafu(display("Missing AWS region"))]
     MissingRegion,
 
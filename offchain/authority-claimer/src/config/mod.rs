@@ -11,6 +11,7 @@ use cli::AuthorityClaimerCLI;
 use eth_tx_manager::{config::TxManagerConfig, Priority};
 use http_server::HttpServerConfig;
 use log::LogConfig;
+use redacted::Redacted;
 use rollups_events::{Address, BrokerConfig, Hash};
 use rusoto_core::Region;
 
@@ -38,6 +39,11 @@ pub enum TxSigningConfig {
         account_index: Option<u32>,
     },
 
+    Keystore {
+        path: String,
+        passphrase: Redacted<String>,
+    },
+
     Aws {
         key_id: String,
         region: Region,
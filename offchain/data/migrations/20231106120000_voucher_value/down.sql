@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE "vouchers" DROP "is_delegate_call";
+ALTER TABLE "vouchers" DROP "value";
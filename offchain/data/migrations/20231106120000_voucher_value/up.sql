@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+ALTER TABLE "vouchers" ADD "value" BYTEA;
+ALTER TABLE "vouchers" ADD "is_delegate_call" BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+DROP TRIGGER "reports_insert_notify" ON "reports";
+DROP TRIGGER "notices_insert_notify" ON "notices";
+DROP TRIGGER "vouchers_insert_notify" ON "vouchers";
+DROP TRIGGER "inputs_status_notify" ON "inputs";
+DROP TRIGGER "inputs_insert_notify" ON "inputs";
+DROP FUNCTION "notify_rollups_event";
@@ -0,0 +1,39 @@
+-- Your SQL goes here
+
+CREATE FUNCTION "notify_rollups_event"() RETURNS TRIGGER AS $$
+DECLARE
+    "payload" JSON;
+BEGIN
+    IF TG_TABLE_NAME = 'inputs' THEN
+        "payload" := json_build_object(
+            'kind', TG_ARGV[0],
+            'input_index', NEW."index",
+            'index', NEW."index"
+        );
+    ELSE
+        "payload" := json_build_object(
+            'kind', TG_ARGV[0],
+            'input_index', NEW."input_index",
+            'index', NEW."index"
+        );
+    END IF;
+    PERFORM pg_notify('rollups_events', "payload"::TEXT);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER "inputs_insert_notify" AFTER INSERT ON "inputs"
+    FOR EACH ROW EXECUTE FUNCTION "notify_rollups_event"('input_added');
+
+CREATE TRIGGER "inputs_status_notify" AFTER UPDATE OF "status" ON "inputs"
+    FOR EACH ROW WHEN (OLD."status" IS DISTINCT FROM NEW."status")
+    EXECUTE FUNCTION "notify_rollups_event"('input_status_changed');
+
+CREATE TRIGGER "vouchers_insert_notify" AFTER INSERT ON "vouchers"
+    FOR EACH ROW EXECUTE FUNCTION "notify_rollups_event"('voucher_added');
+
+CREATE TRIGGER "notices_insert_notify" AFTER INSERT ON "notices"
+    FOR EACH ROW EXECUTE FUNCTION "notify_rollups_event"('notice_added');
+
+CREATE TRIGGER "reports_insert_notify" AFTER INSERT ON "reports"
+    FOR EACH ROW EXECUTE FUNCTION "notify_rollups_event"('report_added');
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX "vouchers_destination_idx";
+DROP INDEX "inputs_epoch_index_idx";
+DROP INDEX "inputs_status_idx";
+DROP INDEX "inputs_timestamp_idx";
+DROP INDEX "inputs_block_number_idx";
+DROP INDEX "inputs_msg_sender_idx";
+
+ALTER TABLE "inputs" DROP "epoch_index";
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+ALTER TABLE "inputs" ADD "epoch_index" INT;
+
+CREATE INDEX "inputs_msg_sender_idx" ON "inputs"("msg_sender");
+CREATE INDEX "inputs_block_number_idx" ON "inputs"("block_number");
+CREATE INDEX "inputs_timestamp_idx" ON "inputs"("timestamp");
+CREATE INDEX "inputs_status_idx" ON "inputs"("status");
+CREATE INDEX "inputs_epoch_index_idx" ON "inputs"("epoch_index");
+CREATE INDEX "vouchers_destination_idx" ON "vouchers"("destination");
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+CREATE TABLE "epochs"
+(
+    "index" INT NOT NULL,
+    "finished" BOOLEAN NOT NULL DEFAULT false,
+    CONSTRAINT "epochs_pkey" PRIMARY KEY ("index")
+);
+
+CREATE TABLE "claims"
+(
+    "epoch_index" INT NOT NULL,
+    "epoch_hash" BYTEA NOT NULL,
+    "first_input_index" INT NOT NULL,
+    "last_input_index" INT NOT NULL,
+    CONSTRAINT "claims_pkey" PRIMARY KEY ("epoch_index"),
+    CONSTRAINT "claims_epoch_index_fkey" FOREIGN KEY ("epoch_index") REFERENCES "epochs"("index")
+);
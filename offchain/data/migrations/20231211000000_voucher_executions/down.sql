@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE "pending_voucher_executions";
+DROP INDEX "vouchers_execution_tx_hash_idx";
+ALTER TABLE "vouchers" DROP "execution_tx_hash";
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+ALTER TABLE "vouchers" ADD "execution_tx_hash" BYTEA;
+
+CREATE INDEX "vouchers_execution_tx_hash_idx" ON "vouchers"("execution_tx_hash");
+
+CREATE TABLE "pending_voucher_executions"
+(
+    "input_index" INT NOT NULL,
+    "index" INT NOT NULL,
+    "tx_hash" BYTEA NOT NULL,
+    CONSTRAINT "pending_voucher_executions_pkey" PRIMARY KEY ("input_index", "index")
+);
@@ -8,7 +8,7 @@ use snafu::Snafu;
 pub enum Error {
     #[snafu(display("database pool connection error"))]
     DatabaseConnectionError {
-        source: backoff::Error<diesel::r2d2::PoolError>,
+        source: bb8::RunError<diesel_async::pooled_connection::PoolError>,
     },
 
     #[snafu(display("database error"))]
@@ -36,4 +36,19 @@ pub enum Error {
 
     #[snafu(display("invalid pagination limit {}", arg))]
     PaginationLimitError { arg: String },
+
+    #[snafu(display("failed to connect to the database ({})", source))]
+    ConnectError { source: tokio_postgres::Error },
+
+    #[snafu(display("database notification connection error"))]
+    NotificationConnectionError { source: tokio_postgres::Error },
+
+    #[snafu(display("database notification listener closed"))]
+    NotificationListenerClosedError {},
+
+    #[snafu(display("failed to parse database notification {}", payload))]
+    ParseNotificationError {
+        payload: String,
+        source: serde_json::Error,
+    },
 }
@@ -2,8 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 mod config;
+mod connection;
 mod error;
 mod migrations;
+mod notifications;
 mod pagination;
 mod repository;
 mod schema;
@@ -12,9 +14,16 @@ mod types;
 pub use config::{RedactedUrl, RepositoryCLIConfig, RepositoryConfig, Url};
 pub use error::Error;
 pub use migrations::{run_migrations, MigrationError};
-pub use pagination::{Connection, Cursor, Edge, PageInfo};
+pub use notifications::{
+    NotificationListener, RepositoryEvent, RepositoryEventKind,
+    NOTIFICATION_CHANNEL,
+};
+pub use pagination::{
+    Connection, Cursor, Edge, PageInfo, Pagination, DEFAULT_PAGINATION_LIMIT,
+};
 pub use repository::Repository;
 pub use types::{
-    CompletionStatus, Input, InputQueryFilter, Notice, NoticeQueryFilter,
-    OutputEnum, Proof, Report, ReportQueryFilter, Voucher, VoucherQueryFilter,
+    Claim, CompletionStatus, Epoch, EpochQueryFilter, Input, InputQueryFilter,
+    Notice, NoticeQueryFilter, OutputEnum, OutputPage, Proof, Report,
+    ReportQueryFilter, SortDirection, Voucher, VoucherQueryFilter,
 };
@@ -0,0 +1,147 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Notifications sent by the database triggers when the indexer inserts
+//! inputs and outputs or updates the status of an input.
+
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt};
+use tokio::sync::mpsc;
+use tokio_postgres::{AsyncMessage, Client};
+
+use super::connection;
+use super::error::{
+    Error, NotificationConnectionSnafu, NotificationListenerClosedSnafu,
+    ParseNotificationSnafu,
+};
+
+/// Channel in which the triggers of the rollups tables send the events
+pub const NOTIFICATION_CHANNEL: &str = "rollups_events";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoryEventKind {
+    InputAdded,
+    InputStatusChanged,
+    VoucherAdded,
+    NoticeAdded,
+    ReportAdded,
+}
+
+/// Event sent by the database; for inputs, the index is the same as the
+/// input index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RepositoryEvent {
+    pub kind: RepositoryEventKind,
+    pub input_index: i32,
+    pub index: i32,
+}
+
+impl RepositoryEvent {
+    pub fn parse(payload: &str) -> Result<Self, Error> {
+        serde_json::from_str(payload).context(ParseNotificationSnafu {
+            payload: payload.to_owned(),
+        })
+    }
+}
+
+/// Listener of the notifications sent to the NOTIFICATION_CHANNEL
+///
+/// Diesel does not support asynchronous notifications, so the listener
+/// keeps a dedicated tokio-postgres connection open.
+pub struct NotificationListener {
+    _client: Client,
+    messages: mpsc::UnboundedReceiver<AsyncMessage>,
+}
+
+impl NotificationListener {
+    /// Connect to the database and start listening to the notifications
+    pub async fn connect(endpoint: &str) -> Result<Self, Error> {
+        let (client, mut connection) = connection::connect(endpoint).await?;
+        // The connection must be polled to receive the notifications; the
+        // task ends when the connection is closed
+        let (tx, messages) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let message =
+                    std::future::poll_fn(|cx| connection.poll_message(cx))
+                        .await;
+                match message {
+                    Some(Ok(message)) => {
+                        if tx.send(message).is_err() {
+                            return;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        tracing::warn!(
+                            "notification connection failed ({})",
+                            e
+                        );
+                        return;
+                    }
+                    None => return,
+                }
+            }
+        });
+        client
+            .batch_execute(&format!("LISTEN {}", NOTIFICATION_CHANNEL))
+            .await
+            .context(NotificationConnectionSnafu)?;
+        tracing::trace!("listening to {} notifications", NOTIFICATION_CHANNEL);
+        Ok(Self {
+            _client: client,
+            messages,
+        })
+    }
+
+    /// Wait for the next event; fails if the connection is closed
+    pub async fn recv(&mut self) -> Result<RepositoryEvent, Error> {
+        loop {
+            let message = self
+                .messages
+                .recv()
+                .await
+                .context(NotificationListenerClosedSnafu)?;
+            match message {
+                AsyncMessage::Notification(notification)
+                    if notification.channel() == NOTIFICATION_CHANNEL =>
+                {
+                    return RepositoryEvent::parse(notification.payload());
+                }
+                message => {
+                    tracing::trace!(?message, "ignoring database message");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_it_parses_event() {
+        let event = RepositoryEvent::parse(
+            r#"{"kind":"voucher_added","input_index":2,"index":1}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            RepositoryEvent {
+                kind: RepositoryEventKind::VoucherAdded,
+                input_index: 2,
+                index: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_it_fails_to_parse_unknown_event() {
+        let err = RepositoryEvent::parse(
+            r#"{"kind":"proof_added","input_index":2,"index":1}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::ParseNotificationError { .. }));
+    }
+}
@@ -11,7 +11,9 @@ use super::error::{
     ParseCursorSnafu,
 };
 
-const DEFAULT_PAGINATION_LIMIT: i32 = 1000;
+/// Max number of nodes returned by a page, also used when the page size is
+/// not given
+pub const DEFAULT_PAGINATION_LIMIT: i32 = 1000;
 
 macro_rules! ensure_cursor {
     ($arg: ident, $total_count: expr) => {{
@@ -2,19 +2,30 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 use backoff::ExponentialBackoff;
-use diesel::pg::{Pg, PgConnection};
-use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
-use diesel::{insert_into, prelude::*, update};
+use diesel::pg::Pg;
+use diesel::{
+    delete, insert_into, prelude::*, update, ConnectionError, ConnectionResult,
+};
+use diesel_async::pooled_connection::bb8::{Pool, PooledConnection};
+use diesel_async::pooled_connection::{
+    AsyncDieselConnectionManager, ManagerConfig,
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use snafu::ResultExt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
 
 use super::config::RepositoryConfig;
+use super::connection;
 use super::error::{DatabaseConnectionSnafu, DatabaseSnafu, Error};
 use super::pagination::{Connection, Pagination};
 use super::schema;
 use super::types::{
-    CompletionStatus, Input, InputQueryFilter, Notice, NoticeQueryFilter,
-    OutputEnum, Proof, Report, ReportQueryFilter, Voucher, VoucherQueryFilter,
+    Claim, CompletionStatus, Epoch, EpochQueryFilter, Input, InputQueryFilter,
+    Notice, NoticeQueryFilter, OutputEnum, OutputPage, Proof, Report,
+    ReportQueryFilter, SortDirection, Voucher, VoucherQueryFilter,
 };
 
 pub const POOL_CONNECTION_SIZE: u32 = 3;
@@ -22,48 +33,109 @@ pub const POOL_CONNECTION_SIZE: u32 = 3;
 #[derive(Clone, Debug)]
 pub struct Repository {
     // Connection is not thread safe to share between threads, we use connection pool
-    db_pool: Arc<Pool<ConnectionManager<PgConnection>>>,
+    db_pool: Pool<AsyncPgConnection>,
     backoff: ExponentialBackoff,
+    // Shared between the clones so the count covers the whole process
+    query_count: Arc<AtomicU64>,
 }
 
 impl Repository {
     /// Create database connection pool, wait until database server is available with backoff strategy
-    pub fn new(config: RepositoryConfig) -> Result<Self, Error> {
-        let db_pool = backoff::retry(config.backoff.clone(), || {
-            tracing::info!(?config, "trying to create db pool for database");
-            Pool::builder()
-                .max_size(POOL_CONNECTION_SIZE)
-                .build(ConnectionManager::<PgConnection>::new(
+    pub async fn new(config: RepositoryConfig) -> Result<Self, Error> {
+        let db_pool =
+            backoff::future::retry(config.backoff.clone(), || async {
+                tracing::info!(
+                    ?config,
+                    "trying to create db pool for database"
+                );
+                let mut manager_config = ManagerConfig::default();
+                manager_config.custom_setup = Box::new(establish);
+                let manager = AsyncDieselConnectionManager::new_with_config(
                     config.endpoint(),
-                ))
-                .map_err(backoff::Error::transient)
-        })
-        .context(DatabaseConnectionSnafu)?;
+                    manager_config,
+                );
+                // The pool only returns after opening the idle connection, so
+                // the backoff waits for the database
+                Pool::builder()
+                    .max_size(POOL_CONNECTION_SIZE)
+                    .min_idle(Some(1))
+                    .build(manager)
+                    .await
+                    .map_err(|e| backoff::Error::transient(e.into()))
+            })
+            .await
+            .context(DatabaseConnectionSnafu)?;
         Ok(Self {
-            db_pool: Arc::new(db_pool),
+            db_pool,
             backoff: config.backoff,
+            query_count: Arc::new(AtomicU64::new(0)),
         })
     }
 
-    /// Obtain the connection from the connection pool
-    fn conn(
+    /// Obtain the connection from the connection pool; each connection is
+    /// used for a single query, so it is counted as one
+    async fn conn(
         &self,
-    ) -> Result<PooledConnection<ConnectionManager<PgConnection>>, Error> {
-        backoff::retry(self.backoff.clone(), || {
-            self.db_pool.get().map_err(backoff::Error::transient)
+    ) -> Result<PooledConnection<'_, AsyncPgConnection>, Error> {
+        self.count_query();
+        backoff::future::retry(self.backoff.clone(), || async {
+            self.db_pool.get().await.map_err(backoff::Error::transient)
         })
+        .await
         .context(DatabaseConnectionSnafu)
     }
 }
 
+/// The query counter is kept apart from the queries because RunQueryDsl is
+/// implemented for every type, so its `load` hides `AtomicU64::load` wherever
+/// it is in scope
+mod query_count {
+    use std::sync::atomic::Ordering;
+
+    use super::Repository;
+
+    impl Repository {
+        /// Number of queries sent to the database by this repository and its
+        /// clones
+        pub fn query_count(&self) -> u64 {
+            self.query_count.load(Ordering::Relaxed)
+        }
+
+        pub(super) fn count_query(&self) {
+            self.query_count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Establish the connections of the pool, which read the Postgres environment
+/// like the libpq connections do
+fn establish(
+    endpoint: &str,
+) -> Pin<
+    Box<dyn Future<Output = ConnectionResult<AsyncPgConnection>> + Send + '_>,
+> {
+    Box::pin(async move {
+        let (client, conn) = connection::connect(endpoint)
+            .await
+            .map_err(|e| ConnectionError::BadConnection(e.to_string()))?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                tracing::warn!("database connection failed ({})", e);
+            }
+        });
+        AsyncPgConnection::try_from(client).await
+    })
+}
+
 /// Basic queries that fetch by primary_key
 impl Repository {
-    pub fn get_input(&self, index: i32) -> Result<Input, Error> {
+    pub async fn get_input(&self, index: i32) -> Result<Input, Error> {
         use schema::inputs::dsl;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         dsl::inputs
             .filter(dsl::index.eq(index))
             .load::<Input>(&mut conn)
+            .await
             .context(DatabaseSnafu)?
             .pop()
             .ok_or(Error::ItemNotFound {
@@ -71,17 +143,18 @@ impl Repository {
             })
     }
 
-    pub fn get_voucher(
+    pub async fn get_voucher(
         &self,
         index: i32,
         input_index: i32,
     ) -> Result<Voucher, Error> {
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         use schema::vouchers::dsl;
         dsl::vouchers
             .filter(dsl::index.eq(index))
             .filter(dsl::input_index.eq(input_index))
             .load::<Voucher>(&mut conn)
+            .await
             .context(DatabaseSnafu)?
             .pop()
             .ok_or(Error::ItemNotFound {
@@ -89,17 +162,18 @@ impl Repository {
             })
     }
 
-    pub fn get_notice(
+    pub async fn get_notice(
         &self,
         index: i32,
         input_index: i32,
     ) -> Result<Notice, Error> {
         use schema::notices::dsl;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         dsl::notices
             .filter(dsl::index.eq(index))
             .filter(dsl::input_index.eq(input_index))
             .load::<Notice>(&mut conn)
+            .await
             .context(DatabaseSnafu)?
             .pop()
             .ok_or(Error::ItemNotFound {
@@ -107,17 +181,18 @@ impl Repository {
             })
     }
 
-    pub fn get_report(
+    pub async fn get_report(
         &self,
         index: i32,
         input_index: i32,
     ) -> Result<Report, Error> {
         use schema::reports::dsl;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         dsl::reports
             .filter(dsl::index.eq(index))
             .filter(dsl::input_index.eq(input_index))
             .load::<Report>(&mut conn)
+            .await
             .context(DatabaseSnafu)?
             .pop()
             .ok_or(Error::ItemNotFound {
@@ -125,45 +200,220 @@ impl Repository {
             })
     }
 
-    pub fn get_proof(
+    pub async fn get_proof(
         &self,
         input_index: i32,
         output_index: i32,
         output_enum: OutputEnum,
     ) -> Result<Option<Proof>, Error> {
         use schema::proofs::dsl;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         dsl::proofs
             .filter(dsl::input_index.eq(input_index))
             .filter(dsl::output_index.eq(output_index))
             .filter(dsl::output_enum.eq(output_enum))
             .load::<Proof>(&mut conn)
+            .await
             .map(|mut proofs| proofs.pop())
             .context(DatabaseSnafu)
     }
+
+    pub async fn get_epoch(&self, index: i32) -> Result<Epoch, Error> {
+        use schema::epochs::dsl;
+        let mut conn = self.conn().await?;
+        dsl::epochs
+            .filter(dsl::index.eq(index))
+            .load::<Epoch>(&mut conn)
+            .await
+            .context(DatabaseSnafu)?
+            .pop()
+            .ok_or(Error::ItemNotFound {
+                item_type: "epoch".to_owned(),
+            })
+    }
+
+    pub async fn get_claim(
+        &self,
+        epoch_index: i32,
+    ) -> Result<Option<Claim>, Error> {
+        use schema::claims::dsl;
+        let mut conn = self.conn().await?;
+        dsl::claims
+            .filter(dsl::epoch_index.eq(epoch_index))
+            .load::<Claim>(&mut conn)
+            .await
+            .map(|mut claims| claims.pop())
+            .context(DatabaseSnafu)
+    }
+}
+
+/// Queries that fetch several items at once, used to batch the lookups of
+/// nested GraphQL fields
+impl Repository {
+    pub async fn get_inputs_by_indices(
+        &self,
+        indices: &[i32],
+    ) -> Result<Vec<Input>, Error> {
+        use schema::inputs::dsl;
+        let mut conn = self.conn().await?;
+        dsl::inputs
+            .filter(dsl::index.eq_any(indices))
+            .order(dsl::index.asc())
+            .load::<Input>(&mut conn)
+            .await
+            .context(DatabaseSnafu)
+    }
+
+    pub async fn get_proofs_by_input_indices(
+        &self,
+        input_indices: &[i32],
+        output_enum: OutputEnum,
+    ) -> Result<Vec<Proof>, Error> {
+        use schema::proofs::dsl;
+        let mut conn = self.conn().await?;
+        dsl::proofs
+            .filter(dsl::input_index.eq_any(input_indices))
+            .filter(dsl::output_enum.eq(output_enum))
+            .load::<Proof>(&mut conn)
+            .await
+            .context(DatabaseSnafu)
+    }
+
+    pub async fn get_epochs_by_indices(
+        &self,
+        indices: &[i32],
+    ) -> Result<Vec<Epoch>, Error> {
+        use schema::epochs::dsl;
+        let mut conn = self.conn().await?;
+        dsl::epochs
+            .filter(dsl::index.eq_any(indices))
+            .order(dsl::index.asc())
+            .load::<Epoch>(&mut conn)
+            .await
+            .context(DatabaseSnafu)
+    }
+
+    pub async fn get_claims_by_epoch_indices(
+        &self,
+        epoch_indices: &[i32],
+    ) -> Result<Vec<Claim>, Error> {
+        use schema::claims::dsl;
+        let mut conn = self.conn().await?;
+        dsl::claims
+            .filter(dsl::epoch_index.eq_any(epoch_indices))
+            .load::<Claim>(&mut conn)
+            .await
+            .context(DatabaseSnafu)
+    }
 }
 
+/// Implement the queries that count the outputs of the given inputs and that
+/// fetch the given pages of them, sorted by input index and output index.
+/// Only the outputs of the pages are read from the database.
+macro_rules! impl_outputs_by_input_pages_query {
+    ($count_query: ident, $query: ident, $table: ident, $node: ty) => {
+        impl Repository {
+            /// Count the outputs of the inputs; the inputs without outputs are
+            /// left out
+            pub async fn $count_query(
+                &self,
+                input_indices: &[i32],
+            ) -> Result<Vec<(i32, i32)>, Error> {
+                use schema::$table::dsl;
+                let mut conn = self.conn().await?;
+                let counts = dsl::$table
+                    .filter(dsl::input_index.eq_any(input_indices))
+                    .group_by(dsl::input_index)
+                    .select((dsl::input_index, diesel::dsl::count_star()))
+                    .load::<(i32, i64)>(&mut conn)
+                    .await
+                    .context(DatabaseSnafu)?;
+                Ok(counts
+                    .into_iter()
+                    .map(|(input_index, count)| (input_index, count as i32))
+                    .collect())
+            }
+
+            pub async fn $query(
+                &self,
+                pages: &[OutputPage],
+            ) -> Result<Vec<$node>, Error> {
+                use diesel::sql_types::{Array, Integer};
+                let input_indices: Vec<i32> =
+                    pages.iter().map(|page| page.input_index).collect();
+                let offsets: Vec<i32> =
+                    pages.iter().map(|page| page.offset).collect();
+                let ends: Vec<i32> = pages
+                    .iter()
+                    .map(|page| page.offset + page.limit)
+                    .collect();
+                let mut conn = self.conn().await?;
+                diesel::sql_query(concat!(
+                    "SELECT outputs.* FROM (",
+                    "SELECT *, ROW_NUMBER() OVER (",
+                    "PARTITION BY input_index ORDER BY \"index\") - 1 AS position ",
+                    "FROM ",
+                    stringify!($table),
+                    " WHERE input_index = ANY($1)) AS outputs ",
+                    "JOIN UNNEST($1, $2, $3) AS pages(input_index, start_position, end_position) ",
+                    "ON outputs.input_index = pages.input_index ",
+                    "WHERE outputs.position >= pages.start_position ",
+                    "AND outputs.position < pages.end_position ",
+                    "ORDER BY outputs.input_index, outputs.\"index\"",
+                ))
+                .bind::<Array<Integer>, _>(input_indices)
+                .bind::<Array<Integer>, _>(offsets)
+                .bind::<Array<Integer>, _>(ends)
+                .load::<$node>(&mut conn)
+                .await
+                .context(DatabaseSnafu)
+            }
+        }
+    };
+}
+
+impl_outputs_by_input_pages_query!(
+    count_vouchers_by_input_indices,
+    get_vouchers_by_input_pages,
+    vouchers,
+    Voucher
+);
+impl_outputs_by_input_pages_query!(
+    count_notices_by_input_indices,
+    get_notices_by_input_pages,
+    notices,
+    Notice
+);
+impl_outputs_by_input_pages_query!(
+    count_reports_by_input_indices,
+    get_reports_by_input_pages,
+    reports,
+    Report
+);
+
 /// Basic queries to insert rollups' outputs
 impl Repository {
-    pub fn insert_input(&self, input: Input) -> Result<(), Error> {
+    pub async fn insert_input(&self, input: Input) -> Result<(), Error> {
         use schema::inputs;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         insert_into(inputs::table)
             .values(&input)
             .on_conflict_do_nothing()
             .execute(&mut conn)
+            .await
             .context(DatabaseSnafu)?;
         tracing::trace!("Input {} was written to the db", input.index);
         Ok(())
     }
 
-    pub fn insert_notice(&self, notice: Notice) -> Result<(), Error> {
+    pub async fn insert_notice(&self, notice: Notice) -> Result<(), Error> {
         use schema::notices;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         insert_into(notices::table)
             .values(&notice)
             .on_conflict_do_nothing()
             .execute(&mut conn)
+            .await
             .context(DatabaseSnafu)?;
         tracing::trace!(
             "Notice {} from Input {} was written to the db",
@@ -173,13 +423,35 @@ impl Repository {
         Ok(())
     }
 
-    pub fn insert_voucher(&self, voucher: Voucher) -> Result<(), Error> {
-        use schema::vouchers;
-        let mut conn = self.conn()?;
+    /// Insert the voucher, applying the execution received before it
+    pub async fn insert_voucher(
+        &self,
+        mut voucher: Voucher,
+    ) -> Result<(), Error> {
+        use schema::{pending_voucher_executions, vouchers};
+        let mut conn = self.conn().await?;
+        let pending_tx_hash: Option<Vec<u8>> =
+            delete(pending_voucher_executions::table)
+                .filter(
+                    pending_voucher_executions::dsl::input_index
+                        .eq(voucher.input_index),
+                )
+                .filter(
+                    pending_voucher_executions::dsl::index.eq(voucher.index),
+                )
+                .returning(pending_voucher_executions::tx_hash)
+                .get_result(&mut conn)
+                .await
+                .optional()
+                .context(DatabaseSnafu)?;
+        if pending_tx_hash.is_some() {
+            voucher.execution_tx_hash = pending_tx_hash;
+        }
         insert_into(vouchers::table)
             .values(&voucher)
             .on_conflict_do_nothing()
             .execute(&mut conn)
+            .await
             .context(DatabaseSnafu)?;
         tracing::trace!(
             "Voucher {} from Input {} was written to the db",
@@ -189,13 +461,14 @@ impl Repository {
         Ok(())
     }
 
-    pub fn insert_report(&self, report: Report) -> Result<(), Error> {
+    pub async fn insert_report(&self, report: Report) -> Result<(), Error> {
         use schema::reports;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         insert_into(reports::table)
             .values(&report)
             .on_conflict_do_nothing()
             .execute(&mut conn)
+            .await
             .context(DatabaseSnafu)?;
         tracing::trace!(
             "Report {} from Input {} was written to the db",
@@ -205,13 +478,14 @@ impl Repository {
         Ok(())
     }
 
-    pub fn insert_proof(&self, proof: Proof) -> Result<(), Error> {
+    pub async fn insert_proof(&self, proof: Proof) -> Result<(), Error> {
         use schema::proofs;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         insert_into(proofs::table)
             .values(&proof)
             .on_conflict_do_nothing()
             .execute(&mut conn)
+            .await
             .context(DatabaseSnafu)?;
         tracing::trace!(
             "Proof for {:?} {} of Input {} was written to the db",
@@ -221,25 +495,121 @@ impl Repository {
         );
         Ok(())
     }
+
+    pub async fn insert_epoch(&self, epoch: Epoch) -> Result<(), Error> {
+        use schema::epochs;
+        let mut conn = self.conn().await?;
+        insert_into(epochs::table)
+            .values(&epoch)
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await
+            .context(DatabaseSnafu)?;
+        tracing::trace!("Epoch {} was written to the db", epoch.index);
+        Ok(())
+    }
+
+    pub async fn insert_claim(&self, claim: Claim) -> Result<(), Error> {
+        use schema::claims;
+        let mut conn = self.conn().await?;
+        insert_into(claims::table)
+            .values(&claim)
+            .on_conflict_do_nothing()
+            .execute(&mut conn)
+            .await
+            .context(DatabaseSnafu)?;
+        tracing::trace!(
+            "Claim of Epoch {} was written to the db",
+            claim.epoch_index
+        );
+        Ok(())
+    }
 }
 
 /// Update operations
 impl Repository {
-    pub fn update_input_status(
+    pub async fn update_input_status(
         &self,
         input_index: i32,
         status: CompletionStatus,
     ) -> Result<(), Error> {
         use schema::inputs;
-        let mut conn = self.conn()?;
+        let mut conn = self.conn().await?;
         update(inputs::table)
             .filter(inputs::dsl::index.eq(input_index))
             .set(inputs::status.eq(status))
             .execute(&mut conn)
+            .await
             .context(DatabaseSnafu)?;
         tracing::trace!("Set {:?} status to input {}", status, input_index);
         Ok(())
     }
+
+    /// Mark the voucher as executed by the given transaction
+    ///
+    /// The execution of a voucher that is not in the database yet is kept
+    /// until the voucher is inserted, since the voucher may be indexed after
+    /// the contract reports its execution.
+    pub async fn execute_voucher(
+        &self,
+        input_index: i32,
+        index: i32,
+        tx_hash: Vec<u8>,
+    ) -> Result<(), Error> {
+        use schema::{pending_voucher_executions, vouchers};
+        let mut conn = self.conn().await?;
+        let updated = update(vouchers::table)
+            .filter(vouchers::dsl::input_index.eq(input_index))
+            .filter(vouchers::dsl::index.eq(index))
+            .set(vouchers::execution_tx_hash.eq(&tx_hash))
+            .execute(&mut conn)
+            .await
+            .context(DatabaseSnafu)?;
+        if updated == 0 {
+            insert_into(pending_voucher_executions::table)
+                .values((
+                    pending_voucher_executions::input_index.eq(input_index),
+                    pending_voucher_executions::index.eq(index),
+                    pending_voucher_executions::tx_hash.eq(&tx_hash),
+                ))
+                .on_conflict_do_nothing()
+                .execute(&mut conn)
+                .await
+                .context(DatabaseSnafu)?;
+            tracing::trace!(
+                "Execution of voucher {} from Input {} is pending",
+                index,
+                input_index
+            );
+            return Ok(());
+        }
+        tracing::trace!(
+            "Voucher {} from Input {} was marked as executed",
+            index,
+            input_index
+        );
+        Ok(())
+    }
+
+    /// Mark the epoch as finished, inserting it if it does not exist
+    pub async fn finish_epoch(&self, index: i32) -> Result<(), Error> {
+        use schema::epochs;
+        let mut conn = self.conn().await?;
+        let epoch = Epoch {
+            index,
+            finished: true,
+        };
+        insert_into(epochs::table)
+            .values(&epoch)
+            .on_conflict(epochs::index)
+            .do_update()
+            .set(epochs::finished.eq(true))
+            .execute(&mut conn)
+            .await
+            .context(DatabaseSnafu)?;
+        tracing::trace!("Finished epoch {}", index);
+        Ok(())
+    }
 }
 
 /// Generate a boxed query from an input query filter
@@ -253,35 +623,118 @@ impl InputQueryFilter {
         if let Some(other) = self.index_lower_than {
             query = query.filter(dsl::index.lt(other));
         }
+        if let Some(other) = &self.msg_sender {
+            query = query.filter(dsl::msg_sender.eq(other));
+        }
+        if let Some(other) = self.block_number_greater_than {
+            query = query.filter(dsl::block_number.gt(other));
+        }
+        if let Some(other) = self.block_number_lower_than {
+            query = query.filter(dsl::block_number.lt(other));
+        }
+        if let Some(other) = self.timestamp_greater_than {
+            query = query.filter(dsl::timestamp.gt(other));
+        }
+        if let Some(other) = self.timestamp_lower_than {
+            query = query.filter(dsl::timestamp.lt(other));
+        }
+        if let Some(other) = self.status {
+            query = query.filter(dsl::status.eq(other));
+        }
+        if let Some(other) = self.epoch_index {
+            query = query.filter(dsl::epoch_index.eq(other));
+        }
+        query
+    }
+}
+
+/// Generate a boxed query from an epoch query filter
+impl EpochQueryFilter {
+    fn to_query(&self) -> schema::epochs::BoxedQuery<'_, Pg> {
+        use schema::epochs::dsl;
+        let mut query = dsl::epochs.into_boxed();
+        if let Some(other) = self.index_greater_than {
+            query = query.filter(dsl::index.gt(other));
+        }
+        if let Some(other) = self.index_lower_than {
+            query = query.filter(dsl::index.lt(other));
+        }
         query
     }
 }
 
-/// Generate a boxed query from an output query filter
+/// Generate a boxed query from an output query filter, which filters the
+/// given columns by equality; the filters of outputs that have proofs also
+/// have the has_proof field
 macro_rules! impl_output_filter_to_query {
-    ($filter: ty, $table: ident) => {
+    ($filter: ty, $table: ident, [$($column: ident),+] $(, $output_enum: expr)? $(; $execution_column: ident)?) => {
         impl $filter {
             fn to_query(&self) -> schema::$table::BoxedQuery<'_, Pg> {
                 use schema::$table::dsl;
                 let mut query = dsl::$table.into_boxed();
-                if let Some(other) = self.input_index {
-                    query = query.filter(dsl::input_index.eq(other));
+                $(
+                    if let Some(other) = &self.$column {
+                        query = query.filter(dsl::$column.eq(other));
+                    }
+                )+
+                if let Some(other) = self.epoch_index {
+                    use schema::inputs;
+                    let epoch_inputs = inputs::table
+                        .filter(inputs::epoch_index.eq(other))
+                        .select(inputs::index);
+                    query = query.filter(dsl::input_index.eq_any(epoch_inputs));
                 }
+                $(
+                    if let Some(has_proof) = self.has_proof {
+                        use schema::proofs;
+                        let proof = proofs::table
+                            .filter(proofs::input_index.eq(dsl::input_index))
+                            .filter(proofs::output_index.eq(dsl::index))
+                            .filter(proofs::output_enum.eq($output_enum));
+                        let exists = diesel::dsl::exists(proof);
+                        query = if has_proof {
+                            query.filter(exists)
+                        } else {
+                            query.filter(diesel::dsl::not(exists))
+                        };
+                    }
+                )?
+                $(
+                    if let Some(executed) = self.executed {
+                        query = if executed {
+                            query.filter(dsl::$execution_column.is_not_null())
+                        } else {
+                            query.filter(dsl::$execution_column.is_null())
+                        };
+                    }
+                )?
                 query
             }
         }
     };
 }
 
-impl_output_filter_to_query!(VoucherQueryFilter, vouchers);
-impl_output_filter_to_query!(NoticeQueryFilter, notices);
-impl_output_filter_to_query!(ReportQueryFilter, reports);
+impl_output_filter_to_query!(
+    VoucherQueryFilter,
+    vouchers,
+    [input_index, destination],
+    OutputEnum::Voucher;
+    execution_tx_hash
+);
+impl_output_filter_to_query!(
+    NoticeQueryFilter,
+    notices,
+    [input_index],
+    OutputEnum::Notice
+);
+impl_output_filter_to_query!(ReportQueryFilter, reports, [input_index]);
 
-/// Implement a paginated query for the given table
+/// Implement a paginated query for the given table, sorting the entries by
+/// the given columns
 macro_rules! impl_paginated_query {
-    ($query: ident, $table: ident, $node: ty, $filter: ty) => {
+    ($query: ident, $table: ident, $node: ty, $filter: ty, [$($column: ident),+]) => {
         impl Repository {
-            pub fn $query(
+            pub async fn $query(
                 &self,
                 first: Option<i32>,
                 last: Option<i32>,
@@ -289,20 +742,31 @@ macro_rules! impl_paginated_query {
                 before: Option<String>,
                 filter: $filter,
             ) -> Result<Connection<$node>, Error> {
-                let mut conn = self.conn()?;
+                let mut conn = self.conn().await?;
                 let query = filter.to_query().count();
                 let count = query
-                    .get_result::<i64>(&mut conn)
+                    .get_result::<i64>(&mut conn).await
                     .context(DatabaseSnafu)?;
                 let pagination =
                     Pagination::new(first, last, after, before, count as i32)?;
                 let nodes = if pagination.limit() > 0 {
-                    let query = filter
+                    use schema::$table::dsl;
+                    self.count_query();
+                    let mut query = filter
                         .to_query()
                         .limit(pagination.limit().into())
-                        .offset(pagination.offset().into())
-                        .order(schema::$table::dsl::$table.primary_key());
-                    query.load(&mut conn).context(DatabaseSnafu)?
+                        .offset(pagination.offset().into());
+                    $(
+                        query = match filter.order {
+                            SortDirection::Ascending => {
+                                query.then_order_by(dsl::$column.asc())
+                            }
+                            SortDirection::Descending => {
+                                query.then_order_by(dsl::$column.desc())
+                            }
+                        };
+                    )+
+                    query.load(&mut conn).await.context(DatabaseSnafu)?
                 } else {
                     vec![]
                 };
@@ -312,7 +776,26 @@ macro_rules! impl_paginated_query {
     };
 }
 
-impl_paginated_query!(get_inputs, inputs, Input, InputQueryFilter);
-impl_paginated_query!(get_vouchers, vouchers, Voucher, VoucherQueryFilter);
-impl_paginated_query!(get_notices, notices, Notice, NoticeQueryFilter);
-impl_paginated_query!(get_reports, reports, Report, ReportQueryFilter);
+impl_paginated_query!(get_inputs, inputs, Input, InputQueryFilter, [index]);
+impl_paginated_query!(
+    get_vouchers,
+    vouchers,
+    Voucher,
+    VoucherQueryFilter,
+    [input_index, index]
+);
+impl_paginated_query!(
+    get_notices,
+    notices,
+    Notice,
+    NoticeQueryFilter,
+    [input_index, index]
+);
+impl_paginated_query!(
+    get_reports,
+    reports,
+    Report,
+    ReportQueryFilter,
+    [input_index, index]
+);
+impl_paginated_query!(get_epochs, epochs, Epoch, EpochQueryFilter, [index]);
@@ -10,6 +10,22 @@ pub mod sql_types {
     pub struct OutputEnum;
 }
 
+diesel::table! {
+    claims (epoch_index) {
+        epoch_index -> Int4,
+        epoch_hash -> Bytea,
+        first_input_index -> Int4,
+        last_input_index -> Int4,
+    }
+}
+
+diesel::table! {
+    epochs (index) {
+        index -> Int4,
+        finished -> Bool,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::CompletionStatus;
@@ -22,6 +38,7 @@ diesel::table! {
         timestamp -> Timestamp,
         payload -> Bytea,
         status -> CompletionStatus,
+        epoch_index -> Nullable<Int4>,
     }
 }
 
@@ -33,6 +50,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    pending_voucher_executions (input_index, index) {
+        input_index -> Int4,
+        index -> Int4,
+        tx_hash -> Bytea,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::OutputEnum;
@@ -67,13 +92,24 @@ diesel::table! {
         index -> Int4,
         destination -> Bytea,
         payload -> Bytea,
+        value -> Nullable<Bytea>,
+        is_delegate_call -> Bool,
+        execution_tx_hash -> Nullable<Bytea>,
     }
 }
 
+diesel::joinable!(claims -> epochs (epoch_index));
 diesel::joinable!(notices -> inputs (input_index));
 diesel::joinable!(reports -> inputs (input_index));
 diesel::joinable!(vouchers -> inputs (input_index));
 
 diesel::allow_tables_to_appear_in_same_query!(
-    inputs, notices, proofs, reports, vouchers,
+    claims,
+    epochs,
+    inputs,
+    notices,
+    pending_voucher_executions,
+    proofs,
+    reports,
+    vouchers,
 );
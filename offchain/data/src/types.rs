@@ -8,7 +8,7 @@ use diesel::{AsExpression, Insertable, Queryable, QueryableByName};
 use std::io::Write;
 
 use super::schema::{
-    inputs, notices, proofs, reports,
+    claims, epochs, inputs, notices, proofs, reports,
     sql_types::CompletionStatus as SQLCompletionStatus,
     sql_types::OutputEnum as SQLOutputEnum, vouchers,
 };
@@ -78,6 +78,9 @@ pub struct Input {
     pub timestamp: std::time::SystemTime,
     pub payload: Vec<u8>,
     pub status: CompletionStatus,
+    /// Epoch of the input; inputs indexed before the epoch was stored have
+    /// no epoch
+    pub epoch_index: Option<i32>,
 }
 
 #[derive(Clone, Debug, Insertable, PartialEq, Queryable, QueryableByName)]
@@ -95,6 +98,11 @@ pub struct Voucher {
     pub index: i32,
     pub destination: Vec<u8>,
     pub payload: Vec<u8>,
+    /// Amount of wei sent with the call; legacy vouchers have no value
+    pub value: Option<Vec<u8>>,
+    pub is_delegate_call: bool,
+    /// Hash of the transaction that executed the voucher, if it was executed
+    pub execution_tx_hash: Option<Vec<u8>>,
 }
 
 #[derive(Clone, Debug, Insertable, PartialEq, Queryable, QueryableByName)]
@@ -153,21 +161,165 @@ pub struct Proof {
     pub context: Vec<u8>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Insertable, PartialEq, Queryable, QueryableByName)]
+#[diesel(table_name = epochs)]
+pub struct Epoch {
+    pub index: i32,
+    /// Whether the epoch was finished, so no more inputs are added to it
+    pub finished: bool,
+}
+
+#[derive(Clone, Debug, Insertable, PartialEq, Queryable, QueryableByName)]
+#[diesel(table_name = claims)]
+pub struct Claim {
+    pub epoch_index: i32,
+    pub epoch_hash: Vec<u8>,
+    pub first_input_index: i32,
+    pub last_input_index: i32,
+}
+
+/// Order of the entries of a paginated query by their index
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Page of the outputs of an input, by the position of the outputs in the
+/// ascending order of their indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputPage {
+    pub input_index: i32,
+    pub offset: i32,
+    pub limit: i32,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct InputQueryFilter {
     pub index_greater_than: Option<i32>,
     pub index_lower_than: Option<i32>,
+    pub msg_sender: Option<Vec<u8>>,
+    pub block_number_greater_than: Option<i64>,
+    pub block_number_lower_than: Option<i64>,
+    pub timestamp_greater_than: Option<std::time::SystemTime>,
+    pub timestamp_lower_than: Option<std::time::SystemTime>,
+    pub status: Option<CompletionStatus>,
+    pub epoch_index: Option<i32>,
+    pub order: SortDirection,
 }
 
-macro_rules! decl_output_filter {
-    ($name: ident) => {
-        #[derive(Debug, Default)]
-        pub struct $name {
-            pub input_index: Option<i32>,
-        }
-    };
+impl InputQueryFilter {
+    /// Whether the given input matches the filter, as in the query
+    pub fn matches(&self, input: &Input) -> bool {
+        self.index_greater_than
+            .is_none_or(|other| input.index > other)
+            && self
+                .index_lower_than
+                .is_none_or(|other| input.index < other)
+            && self
+                .msg_sender
+                .as_ref()
+                .is_none_or(|other| &input.msg_sender == other)
+            && self
+                .block_number_greater_than
+                .is_none_or(|other| input.block_number > other)
+            && self
+                .block_number_lower_than
+                .is_none_or(|other| input.block_number < other)
+            && self
+                .timestamp_greater_than
+                .is_none_or(|other| input.timestamp > other)
+            && self
+                .timestamp_lower_than
+                .is_none_or(|other| input.timestamp < other)
+            && self.status.is_none_or(|other| input.status == other)
+            && self
+                .epoch_index
+                .is_none_or(|other| input.epoch_index == Some(other))
+    }
 }
 
-decl_output_filter!(VoucherQueryFilter);
-decl_output_filter!(NoticeQueryFilter);
-decl_output_filter!(ReportQueryFilter);
+#[derive(Debug, Default)]
+pub struct VoucherQueryFilter {
+    pub input_index: Option<i32>,
+    pub epoch_index: Option<i32>,
+    pub destination: Option<Vec<u8>>,
+    pub has_proof: Option<bool>,
+    pub executed: Option<bool>,
+    pub order: SortDirection,
+}
+
+#[derive(Debug, Default)]
+pub struct NoticeQueryFilter {
+    pub input_index: Option<i32>,
+    pub epoch_index: Option<i32>,
+    pub has_proof: Option<bool>,
+    pub order: SortDirection,
+}
+
+#[derive(Debug, Default)]
+pub struct ReportQueryFilter {
+    pub input_index: Option<i32>,
+    pub epoch_index: Option<i32>,
+    pub order: SortDirection,
+}
+
+#[derive(Debug, Default)]
+pub struct EpochQueryFilter {
+    pub index_greater_than: Option<i32>,
+    pub index_lower_than: Option<i32>,
+    pub order: SortDirection,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn create_input(index: i32) -> Input {
+        Input {
+            index,
+            msg_sender: "msg-sender".as_bytes().to_vec(),
+            tx_hash: "tx-hash".as_bytes().to_vec(),
+            block_number: 10 * index as i64,
+            timestamp: UNIX_EPOCH + Duration::from_secs(100 * index as u64),
+            payload: vec![],
+            status: CompletionStatus::Accepted,
+            epoch_index: Some(0),
+        }
+    }
+
+    #[test]
+    fn it_matches_inputs_with_default_filter() {
+        let filter = InputQueryFilter::default();
+        assert!(filter.matches(&create_input(0)));
+    }
+
+    #[test]
+    fn it_matches_inputs_in_ranges() {
+        let filter = InputQueryFilter {
+            block_number_greater_than: Some(0),
+            timestamp_lower_than: Some(UNIX_EPOCH + Duration::from_secs(300)),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&create_input(0)));
+        assert!(filter.matches(&create_input(1)));
+        assert!(filter.matches(&create_input(2)));
+        assert!(!filter.matches(&create_input(3)));
+    }
+
+    #[test]
+    fn it_does_not_match_inputs_with_other_sender_or_status() {
+        let filter = InputQueryFilter {
+            msg_sender: Some("other-sender".as_bytes().to_vec()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&create_input(0)));
+        let filter = InputQueryFilter {
+            status: Some(CompletionStatus::Rejected),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&create_input(0)));
+    }
+}
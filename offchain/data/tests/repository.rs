@@ -8,9 +8,11 @@ use diesel::{
 };
 use rollups_data::Connection as PaginationConnection;
 use rollups_data::{
-    CompletionStatus, Cursor, Edge, Error, Input, InputQueryFilter, Notice,
-    PageInfo, Proof, RedactedUrl, Report, Repository, RepositoryConfig, Url,
-    Voucher,
+    Claim, CompletionStatus, Cursor, Edge, Epoch, EpochQueryFilter, Error,
+    Input, InputQueryFilter, Notice, NoticeQueryFilter, NotificationListener,
+    OutputPage, PageInfo, Proof, RedactedUrl, Report, Repository,
+    RepositoryConfig, RepositoryEvent, RepositoryEventKind, SortDirection, Url,
+    Voucher, VoucherQueryFilter,
 };
 use serial_test::serial;
 use std::io::Write;
@@ -32,7 +34,7 @@ impl TestState<'_> {
         TestState { data }
     }
 
-    pub fn get_repository(&self) -> Repository {
+    pub async fn get_repository(&self) -> Repository {
         let backoff = ExponentialBackoffBuilder::new()
             .with_max_elapsed_time(Some(Duration::from_millis(
                 BACKOFF_DURATION,
@@ -55,6 +57,7 @@ impl TestState<'_> {
             connection_pool_size: 3,
             backoff,
         })
+        .await
         .expect("Repository should have connected successfully")
     }
 
@@ -72,7 +75,7 @@ impl TestState<'_> {
     }
 }
 
-pub fn insert_test_input(repo: &Repository) {
+pub async fn insert_test_input(repo: &Repository) {
     let input = Input {
         index: 0,
         msg_sender: "msg-sender".as_bytes().to_vec(),
@@ -81,9 +84,11 @@ pub fn insert_test_input(repo: &Repository) {
         timestamp: UNIX_EPOCH + Duration::from_secs(1676489717),
         payload: "input-0".as_bytes().to_vec(),
         status: CompletionStatus::Accepted,
+        epoch_index: Some(0),
     };
 
     repo.insert_input(input)
+        .await
         .expect("The input should've been inserted")
 }
 
@@ -96,23 +101,24 @@ pub fn create_input() -> Input {
         timestamp: UNIX_EPOCH + Duration::from_secs(1676489717),
         payload: "input-0".as_bytes().to_vec(),
         status: CompletionStatus::Accepted,
+        epoch_index: Some(0),
     }
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_create_repository() {
+async fn test_create_repository() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
 
     // Since we create the repository for every test, we created an auxiliary function
     // to do so, and to avoid code duplication, we are calling this function here
-    test.get_repository();
+    test.get_repository().await;
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_fail_to_create_repository() {
+async fn test_fail_to_create_repository() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
 
@@ -136,14 +142,15 @@ fn test_fail_to_create_repository() {
         connection_pool_size: 3,
         backoff,
     })
+    .await
     .expect_err("Repository::new should fail");
 
     assert!(matches!(err, Error::DatabaseConnectionError { source: _ }));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_postgres_file_configuration() {
+async fn test_postgres_file_configuration() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
 
@@ -192,19 +199,21 @@ fn test_postgres_file_configuration() {
         connection_pool_size: 3,
         backoff,
     })
+    .await
     .expect("failed to create repository");
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_input() {
+async fn test_insert_input() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let input = create_input();
 
     repo.insert_input(input.clone())
+        .await
         .expect("Failed to insert input");
 
     let result: Input = test.get_from_sql("Select * from inputs");
@@ -212,36 +221,39 @@ fn test_insert_input() {
     assert_eq!(result, input);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_input() {
+async fn test_get_input() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let input = create_input();
 
     repo.insert_input(input.clone())
+        .await
         .expect("Failed to insert input");
 
-    let get_input = repo.get_input(0).expect("Failed to get input");
+    let get_input = repo.get_input(0).await.expect("Failed to get input");
 
     assert_eq!(input, get_input);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_input_error() {
+async fn test_get_input_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let input = create_input();
 
     repo.insert_input(input.clone())
+        .await
         .expect("Failed to insert input");
 
-    let input_error = repo.get_input(1).expect_err("Get input should fail");
+    let input_error =
+        repo.get_input(1).await.expect_err("Get input should fail");
 
     assert!(matches!(
         input_error,
@@ -249,34 +261,36 @@ fn test_get_input_error() {
     ));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_update_input_status() {
+async fn test_update_input_status() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let mut input = create_input();
     input.status = CompletionStatus::Unprocessed;
 
     repo.insert_input(input.clone())
+        .await
         .expect("Failed to insert input");
     repo.update_input_status(0, CompletionStatus::Accepted)
+        .await
         .expect("Failed to update input status");
 
-    let get_input = repo.get_input(0).expect("Failed to get input");
+    let get_input = repo.get_input(0).await.expect("Failed to get input");
 
     assert_eq!(get_input.status, CompletionStatus::Accepted);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_notice() {
+async fn test_insert_notice() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let notice = Notice {
         input_index: 0,
@@ -285,6 +299,7 @@ fn test_insert_notice() {
     };
 
     repo.insert_notice(notice.clone())
+        .await
         .expect("Failed to insert notice");
 
     let result: Notice = test.get_from_sql("Select * from notices");
@@ -292,14 +307,14 @@ fn test_insert_notice() {
     assert_eq!(result, notice);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_notice() {
+async fn test_get_notice() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let notice = Notice {
         input_index: 0,
@@ -308,23 +323,25 @@ fn test_get_notice() {
     };
 
     repo.insert_notice(notice.clone())
+        .await
         .expect("Failed to insert notice");
 
     let get_notice = repo
         .get_notice(0, 0)
+        .await
         .expect("Get notice should have returned a value");
 
     assert_eq!(notice, get_notice);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_notice_error() {
+async fn test_insert_notice_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let notice = Notice {
         input_index: 1,
@@ -333,19 +350,20 @@ fn test_insert_notice_error() {
     };
     let notice_error = repo
         .insert_notice(notice.clone())
+        .await
         .expect_err("Insert notice should fail");
 
     assert!(matches!(notice_error, Error::DatabaseError { source: _ }));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_notice_error() {
+async fn test_get_notice_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let notice = Notice {
         input_index: 0,
@@ -353,10 +371,13 @@ fn test_get_notice_error() {
         payload: "notice-0-0".as_bytes().to_vec(),
     };
     repo.insert_notice(notice.clone())
+        .await
         .expect("Insert notice should succeed");
 
-    let notice_error =
-        repo.get_notice(1, 1).expect_err("Get notice should fail");
+    let notice_error = repo
+        .get_notice(1, 1)
+        .await
+        .expect_err("Get notice should fail");
 
     assert!(matches!(
         notice_error,
@@ -364,23 +385,27 @@ fn test_get_notice_error() {
     ));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_voucher() {
+async fn test_insert_voucher() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let voucher = Voucher {
         input_index: 0,
         index: 0,
         destination: "destination".as_bytes().to_vec(),
         payload: "voucher-0-0".as_bytes().to_vec(),
+        value: None,
+        is_delegate_call: false,
+        execution_tx_hash: None,
     };
 
     repo.insert_voucher(voucher.clone())
+        .await
         .expect("Insert voucher should succeed");
 
     let result: Voucher = test.get_from_sql("Select * from vouchers");
@@ -388,72 +413,88 @@ fn test_insert_voucher() {
     assert_eq!(result, voucher);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_voucher() {
+async fn test_get_voucher() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let voucher = Voucher {
         input_index: 0,
         index: 0,
         destination: "destination".as_bytes().to_vec(),
         payload: "voucher-0-0".as_bytes().to_vec(),
+        value: None,
+        is_delegate_call: false,
+        execution_tx_hash: None,
     };
     repo.insert_voucher(voucher.clone())
+        .await
         .expect("Insert voucher should succeed");
 
-    let get_voucher =
-        repo.get_voucher(0, 0).expect("Get voucher should succeed");
+    let get_voucher = repo
+        .get_voucher(0, 0)
+        .await
+        .expect("Get voucher should succeed");
 
     assert_eq!(voucher, get_voucher);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_voucher_error() {
+async fn test_insert_voucher_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let voucher = Voucher {
         input_index: 1,
         index: 0,
         destination: "destination".as_bytes().to_vec(),
         payload: "voucher-1-0".as_bytes().to_vec(),
+        value: None,
+        is_delegate_call: false,
+        execution_tx_hash: None,
     };
     let voucher_error = repo
         .insert_voucher(voucher.clone())
+        .await
         .expect_err("Insert voucher should fail");
 
     assert!(matches!(voucher_error, Error::DatabaseError { source: _ }));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_voucher_error() {
+async fn test_get_voucher_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let voucher = Voucher {
         input_index: 0,
         index: 0,
         destination: "destination".as_bytes().to_vec(),
         payload: "voucher-0-0".as_bytes().to_vec(),
+        value: None,
+        is_delegate_call: false,
+        execution_tx_hash: None,
     };
     repo.insert_voucher(voucher.clone())
+        .await
         .expect("Insert voucher should succeed");
 
-    let voucher_error =
-        repo.get_voucher(1, 1).expect_err("Get voucher should fail");
+    let voucher_error = repo
+        .get_voucher(1, 1)
+        .await
+        .expect_err("Get voucher should fail");
 
     assert!(matches!(
         voucher_error,
@@ -461,14 +502,75 @@ fn test_get_voucher_error() {
     ));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_report() {
+async fn test_execute_voucher() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
+
+    insert_test_input(&repo).await;
+
+    let voucher = Voucher {
+        input_index: 0,
+        index: 0,
+        destination: "destination".as_bytes().to_vec(),
+        payload: "voucher-0-0".as_bytes().to_vec(),
+        value: None,
+        is_delegate_call: false,
+        execution_tx_hash: None,
+    };
+    repo.insert_voucher(voucher.clone())
+        .await
+        .expect("Insert voucher should succeed");
+
+    repo.execute_voucher(0, 0, "<tx-hash>".as_bytes().to_vec())
+        .await
+        .expect("Execute voucher should succeed");
 
-    insert_test_input(&repo);
+    let get_voucher =
+        repo.get_voucher(0, 0).await.expect("Failed to get voucher");
+    assert_eq!(
+        get_voucher.execution_tx_hash,
+        Some("<tx-hash>".as_bytes().to_vec())
+    );
+
+    // The execution of a voucher that was not indexed yet is kept until the
+    // voucher is inserted
+    repo.execute_voucher(0, 1, "<tx-hash-1>".as_bytes().to_vec())
+        .await
+        .expect("Execute pending voucher should succeed");
+    let voucher_error = repo
+        .get_voucher(1, 0)
+        .await
+        .expect_err("Get voucher should fail");
+    assert!(matches!(
+        voucher_error,
+        Error::ItemNotFound { item_type } if item_type == "voucher"
+    ));
+    repo.insert_voucher(Voucher {
+        index: 1,
+        payload: "voucher-0-1".as_bytes().to_vec(),
+        ..voucher
+    })
+    .await
+    .expect("Insert voucher should succeed");
+    let get_voucher =
+        repo.get_voucher(1, 0).await.expect("Failed to get voucher");
+    assert_eq!(
+        get_voucher.execution_tx_hash,
+        Some("<tx-hash-1>".as_bytes().to_vec())
+    );
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_insert_report() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    insert_test_input(&repo).await;
 
     let report = Report {
         input_index: 0,
@@ -477,6 +579,7 @@ fn test_insert_report() {
     };
 
     repo.insert_report(report.clone())
+        .await
         .expect("Insert report should succeed");
 
     let result: Report = test.get_from_sql("Select * from reports");
@@ -484,14 +587,14 @@ fn test_insert_report() {
     assert_eq!(result, report);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_report() {
+async fn test_get_report() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let report = Report {
         input_index: 0,
@@ -499,21 +602,25 @@ fn test_get_report() {
         payload: "report-0-0".as_bytes().to_vec(),
     };
     repo.insert_report(report.clone())
+        .await
         .expect("Insert report should succeed");
 
-    let get_report = repo.get_report(0, 0).expect("Get report should succeed");
+    let get_report = repo
+        .get_report(0, 0)
+        .await
+        .expect("Get report should succeed");
 
     assert_eq!(report, get_report);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_report_error() {
+async fn test_insert_report_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let report = Report {
         input_index: 1,
@@ -522,19 +629,20 @@ fn test_insert_report_error() {
     };
     let report_error = repo
         .insert_report(report.clone())
+        .await
         .expect_err("Insert report should fail");
 
     assert!(matches!(report_error, Error::DatabaseError { source: _ }));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_report_error() {
+async fn test_get_report_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
-    insert_test_input(&repo);
+    insert_test_input(&repo).await;
 
     let report = Report {
         input_index: 0,
@@ -542,10 +650,13 @@ fn test_get_report_error() {
         payload: "report-0-0".as_bytes().to_vec(),
     };
     repo.insert_report(report.clone())
+        .await
         .expect("Insert report should succeed");
 
-    let report_error =
-        repo.get_report(1, 1).expect_err("Get report should fail");
+    let report_error = repo
+        .get_report(1, 1)
+        .await
+        .expect_err("Get report should fail");
 
     assert!(matches!(
         report_error,
@@ -553,12 +664,12 @@ fn test_get_report_error() {
     ));
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_insert_proof() {
+async fn test_insert_proof() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let proof = Proof {
         input_index: 0,
@@ -580,6 +691,7 @@ fn test_insert_proof() {
     };
 
     repo.insert_proof(proof.clone())
+        .await
         .expect("Insert proof should succeed");
 
     let result: Proof = test.get_from_sql("Select * from proofs");
@@ -587,12 +699,12 @@ fn test_insert_proof() {
     assert_eq!(result, proof);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_proof() {
+async fn test_get_proof() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let proof = Proof {
         input_index: 0,
@@ -613,22 +725,24 @@ fn test_get_proof() {
         context: "<context>".as_bytes().to_vec(),
     };
     repo.insert_proof(proof.clone())
+        .await
         .expect("Insert proof should succeed");
 
     let get_proof = repo
         .get_proof(0, 0, rollups_data::OutputEnum::Voucher)
+        .await
         .unwrap()
         .expect("Get proof should succeed");
 
     assert_eq!(proof, get_proof);
 }
 
-#[test]
+#[test(tokio::test)]
 #[serial]
-fn test_get_proof_error() {
+async fn test_get_proof_error() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let proof = Proof {
         input_index: 0,
@@ -649,9 +763,12 @@ fn test_get_proof_error() {
         context: "<context>".as_bytes().to_vec(),
     };
     repo.insert_proof(proof.clone())
+        .await
         .expect("Insert proof should succeed");
 
-    let proof_error = repo.get_proof(1, 1, rollups_data::OutputEnum::Voucher);
+    let proof_error = repo
+        .get_proof(1, 1, rollups_data::OutputEnum::Voucher)
+        .await;
 
     match proof_error {
         Ok(None) => assert!(true),
@@ -660,12 +777,130 @@ fn test_get_proof_error() {
     }
 }
 
-#[test]
+#[test(tokio::test)]
+#[serial]
+async fn test_finish_epoch() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    let epoch = Epoch {
+        index: 0,
+        finished: false,
+    };
+    repo.insert_epoch(epoch.clone())
+        .await
+        .expect("Failed to insert epoch");
+    assert_eq!(repo.get_epoch(0).await.expect("Failed to get epoch"), epoch);
+
+    repo.finish_epoch(0).await.expect("Failed to finish epoch");
+    // Inserting the epoch again does not change its status
+    repo.insert_epoch(epoch)
+        .await
+        .expect("Failed to insert epoch");
+    assert!(
+        repo.get_epoch(0)
+            .await
+            .expect("Failed to get epoch")
+            .finished
+    );
+
+    // Finishing an epoch that does not exist inserts it
+    repo.finish_epoch(1).await.expect("Failed to finish epoch");
+    assert!(
+        repo.get_epoch(1)
+            .await
+            .expect("Failed to get epoch")
+            .finished
+    );
+
+    let epoch_error =
+        repo.get_epoch(2).await.expect_err("Get epoch should fail");
+    assert!(matches!(
+        epoch_error,
+        Error::ItemNotFound { item_type } if item_type == "epoch"
+    ));
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_get_claim() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    repo.finish_epoch(0).await.expect("Failed to finish epoch");
+    let claim = Claim {
+        epoch_index: 0,
+        epoch_hash: "<hash>".as_bytes().to_vec(),
+        first_input_index: 0,
+        last_input_index: 2,
+    };
+    repo.insert_claim(claim.clone())
+        .await
+        .expect("Failed to insert claim");
+
+    assert_eq!(
+        repo.get_claim(0).await.expect("Failed to get claim"),
+        Some(claim)
+    );
+    assert_eq!(repo.get_claim(1).await.expect("Failed to get claim"), None);
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_insert_claim_error() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    let claim_error = repo
+        .insert_claim(Claim {
+            epoch_index: 0,
+            epoch_hash: "<hash>".as_bytes().to_vec(),
+            first_input_index: 0,
+            last_input_index: 0,
+        })
+        .await
+        .expect_err("Insert claim should fail");
+    assert!(matches!(claim_error, Error::DatabaseError { .. }));
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_get_epochs() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    for index in 0..3 {
+        repo.finish_epoch(index)
+            .await
+            .expect("Failed to finish epoch");
+    }
+    let filter = EpochQueryFilter {
+        index_greater_than: Some(0),
+        order: SortDirection::Descending,
+        ..Default::default()
+    };
+    let epochs = repo
+        .get_epochs(None, None, None, None, filter)
+        .await
+        .expect("Failed to get epochs");
+    let indices: Vec<i32> = epochs
+        .edges
+        .into_iter()
+        .map(|edge| edge.node.index)
+        .collect();
+    assert_eq!(indices, vec![2, 1]);
+}
+
+#[test(tokio::test)]
 #[serial]
-fn test_pagination_macro() {
+async fn test_pagination_macro() {
     let docker = Cli::default();
     let test = TestState::setup(&docker);
-    let repo = test.get_repository();
+    let repo = test.get_repository().await;
 
     let input0 = create_input();
 
@@ -677,20 +912,25 @@ fn test_pagination_macro() {
         timestamp: UNIX_EPOCH + Duration::from_secs(1676489717),
         payload: "input-1".as_bytes().to_vec(),
         status: CompletionStatus::Accepted,
+        epoch_index: Some(0),
     };
 
     repo.insert_input(input0.clone())
+        .await
         .expect("Insert input should succeed");
     repo.insert_input(input1.clone())
+        .await
         .expect("Insert input should succeed");
 
     let query_filter = InputQueryFilter {
         index_greater_than: Some(-1),
         index_lower_than: Some(5),
+        ..Default::default()
     };
 
     let pagination_connection = repo
         .get_inputs(Some(5), None, None, None, query_filter)
+        .await
         .expect("The macro should work, creating a pagination connection");
 
     assert_eq!(
@@ -724,3 +964,329 @@ fn test_pagination_macro() {
         }
     );
 }
+
+#[test(tokio::test)]
+#[serial]
+async fn test_get_outputs_by_input_pages() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    for input_index in 0..3 {
+        repo.insert_input(Input {
+            index: input_index,
+            ..create_input()
+        })
+        .await
+        .expect("Insert input should succeed");
+        for index in (0..3).rev() {
+            repo.insert_voucher(Voucher {
+                input_index,
+                index,
+                destination: "destination".as_bytes().to_vec(),
+                payload: format!("voucher-{}-{}", input_index, index)
+                    .as_bytes()
+                    .to_vec(),
+                value: None,
+                is_delegate_call: false,
+                execution_tx_hash: None,
+            })
+            .await
+            .expect("Insert voucher should succeed");
+        }
+    }
+
+    let query_count = repo.query_count();
+    let inputs = repo
+        .get_inputs_by_indices(&[2, 0, 5])
+        .await
+        .expect("Get inputs should succeed");
+    let mut counts = repo
+        .count_vouchers_by_input_indices(&[2, 0, 5])
+        .await
+        .expect("Count vouchers should succeed");
+    let vouchers = repo
+        .get_vouchers_by_input_pages(&[
+            OutputPage {
+                input_index: 2,
+                offset: 1,
+                limit: 2,
+            },
+            OutputPage {
+                input_index: 0,
+                offset: 0,
+                limit: 1,
+            },
+        ])
+        .await
+        .expect("Get vouchers should succeed");
+    let notices = repo
+        .get_notices_by_input_pages(&[OutputPage {
+            input_index: 0,
+            offset: 0,
+            limit: 1,
+        }])
+        .await
+        .expect("Get notices should succeed");
+    assert_eq!(repo.query_count() - query_count, 4);
+
+    assert_eq!(
+        inputs.iter().map(|input| input.index).collect::<Vec<_>>(),
+        vec![0, 2]
+    );
+    counts.sort();
+    assert_eq!(counts, vec![(0, 3), (2, 3)]);
+    assert_eq!(
+        vouchers
+            .iter()
+            .map(|voucher| (voucher.input_index, voucher.index))
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (2, 1), (2, 2)]
+    );
+    assert!(notices.is_empty());
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_filter_inputs() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    for index in 0..4 {
+        let mut input = create_input();
+        input.index = index;
+        input.msg_sender = format!("sender-{}", index % 2).into_bytes();
+        input.block_number = 10 * index as i64;
+        input.timestamp = UNIX_EPOCH + Duration::from_secs(100 * index as u64);
+        if index == 3 {
+            input.status = CompletionStatus::Rejected;
+        }
+        repo.insert_input(input)
+            .await
+            .expect("Failed to insert input");
+    }
+
+    let get_indices = |filter: InputQueryFilter| {
+        let repo = &repo;
+        async move {
+            repo.get_inputs(None, None, None, None, filter)
+                .await
+                .expect("Failed to get inputs")
+                .edges
+                .into_iter()
+                .map(|edge| edge.node.index)
+                .collect::<Vec<i32>>()
+        }
+    };
+
+    let filter = InputQueryFilter {
+        msg_sender: Some("sender-1".as_bytes().to_vec()),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![1, 3]);
+
+    let filter = InputQueryFilter {
+        block_number_greater_than: Some(0),
+        block_number_lower_than: Some(30),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![1, 2]);
+
+    let filter = InputQueryFilter {
+        timestamp_greater_than: Some(UNIX_EPOCH + Duration::from_secs(100)),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![2, 3]);
+
+    let filter = InputQueryFilter {
+        status: Some(CompletionStatus::Rejected),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![3]);
+
+    let filter = InputQueryFilter {
+        index_lower_than: Some(3),
+        order: SortDirection::Descending,
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![2, 1, 0]);
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_filter_outputs() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+
+    for index in 0..2 {
+        let mut input = create_input();
+        input.index = index;
+        input.epoch_index = Some(index);
+        repo.insert_input(input)
+            .await
+            .expect("Failed to insert input");
+    }
+    for (input_index, index, destination) in [
+        (0, 0, "destination-a"),
+        (1, 0, "destination-b"),
+        (1, 1, "destination-a"),
+    ] {
+        repo.insert_voucher(Voucher {
+            input_index,
+            index,
+            destination: destination.as_bytes().to_vec(),
+            payload: "voucher".as_bytes().to_vec(),
+            value: None,
+            is_delegate_call: false,
+            execution_tx_hash: None,
+        })
+        .await
+        .expect("Failed to insert voucher");
+    }
+    repo.insert_notice(Notice {
+        input_index: 1,
+        index: 0,
+        payload: "notice-1-0".as_bytes().to_vec(),
+    })
+    .await
+    .expect("Failed to insert notice");
+    repo.insert_proof(Proof {
+        input_index: 1,
+        output_index: 0,
+        output_enum: rollups_data::OutputEnum::Voucher,
+        validity_input_index_within_epoch: 0,
+        validity_output_index_within_input: 0,
+        validity_output_hashes_root_hash: "<hash>".as_bytes().to_vec(),
+        validity_vouchers_epoch_root_hash: "<hash>".as_bytes().to_vec(),
+        validity_notices_epoch_root_hash: "<hash>".as_bytes().to_vec(),
+        validity_machine_state_hash: "<hash>".as_bytes().to_vec(),
+        validity_output_hash_in_output_hashes_siblings: vec![],
+        validity_output_hashes_in_epoch_siblings: vec![],
+        context: "<context>".as_bytes().to_vec(),
+    })
+    .await
+    .expect("Failed to insert proof");
+
+    let get_indices = |filter: VoucherQueryFilter| {
+        let repo = &repo;
+        async move {
+            repo.get_vouchers(None, None, None, None, filter)
+                .await
+                .expect("Failed to get vouchers")
+                .edges
+                .into_iter()
+                .map(|edge| (edge.node.input_index, edge.node.index))
+                .collect::<Vec<(i32, i32)>>()
+        }
+    };
+
+    let filter = VoucherQueryFilter {
+        destination: Some("destination-a".as_bytes().to_vec()),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![(0, 0), (1, 1)]);
+
+    let filter = VoucherQueryFilter {
+        epoch_index: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![(1, 0), (1, 1)]);
+
+    let filter = VoucherQueryFilter {
+        has_proof: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![(1, 0)]);
+
+    let filter = VoucherQueryFilter {
+        has_proof: Some(false),
+        order: SortDirection::Descending,
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![(1, 1), (0, 0)]);
+
+    repo.execute_voucher(1, 1, "<tx-hash>".as_bytes().to_vec())
+        .await
+        .expect("Failed to execute voucher");
+
+    let filter = VoucherQueryFilter {
+        executed: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![(1, 1)]);
+
+    let filter = VoucherQueryFilter {
+        executed: Some(false),
+        ..Default::default()
+    };
+    assert_eq!(get_indices(filter).await, vec![(0, 0), (1, 0)]);
+
+    // The proof of the voucher is not a proof of the notice
+    let filter = NoticeQueryFilter {
+        has_proof: Some(true),
+        ..Default::default()
+    };
+    let notices = repo
+        .get_notices(None, None, None, None, filter)
+        .await
+        .expect("Failed to get notices");
+    assert_eq!(notices.total_count, 0);
+}
+
+#[test(tokio::test)]
+#[serial]
+async fn test_notify_events() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker);
+    let repo = test.get_repository().await;
+    let mut listener = NotificationListener::connect(&test.data.endpoint)
+        .await
+        .expect("failed to connect listener");
+
+    let mut input = create_input();
+    input.status = CompletionStatus::Unprocessed;
+    repo.insert_input(input)
+        .await
+        .expect("Failed to insert input");
+    repo.insert_notice(Notice {
+        input_index: 0,
+        index: 0,
+        payload: "notice-0-0".as_bytes().to_vec(),
+    })
+    .await
+    .expect("Failed to insert notice");
+    repo.update_input_status(0, CompletionStatus::Accepted)
+        .await
+        .expect("Failed to update input status");
+    // Updating to the same status does not notify
+    repo.update_input_status(0, CompletionStatus::Accepted)
+        .await
+        .expect("Failed to update input status");
+    repo.insert_report(Report {
+        input_index: 0,
+        index: 0,
+        payload: "report-0-0".as_bytes().to_vec(),
+    })
+    .await
+    .expect("Failed to insert report");
+
+    let expected = [
+        RepositoryEventKind::InputAdded,
+        RepositoryEventKind::NoticeAdded,
+        RepositoryEventKind::InputStatusChanged,
+        RepositoryEventKind::ReportAdded,
+    ];
+    for kind in expected {
+        let event = listener.recv().await.expect("failed to receive event");
+        assert_eq!(
+            event,
+            RepositoryEvent {
+                kind,
+                input_index: 0,
+                index: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,362 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! This module exposes the `AdminControl`, which lets operators pause and
+//! resume input forwarding and claim submission independently, and the
+//! authenticated HTTP endpoint that drives it.
+//!
+//! - `GET /admin/state` reports the current state;
+//! - `POST /admin/inputs/pause` and `POST /admin/inputs/resume`;
+//! - `POST /admin/claims/pause` and `POST /admin/claims/resume`.
+//!
+//! Requests must carry the admin token as `Authorization: Bearer <token>`.
+//! The state is persisted to a file on every change, so a paused dispatcher
+//! stays paused after a restart.
+
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use clap::Parser;
+use redacted::Redacted;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use subtle::ConstantTimeEq;
+
+#[derive(Debug, Snafu)]
+pub enum AdminError {
+    #[snafu(display("failed to read admin state file ({})", path.display()))]
+    ReadStateError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("failed to parse admin state file ({})", path.display()))]
+    ParseStateError {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[snafu(display("failed to write admin state file ({})", path.display()))]
+    WriteStateError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "admin_config")]
+#[command(about = "Configuration for the admin HTTP endpoint")]
+pub struct AdminEnvCLIConfig {
+    /// Token required by the admin HTTP endpoint; the endpoint is only started when it is set
+    #[arg(long, env)]
+    pub rd_admin_token: Option<String>,
+
+    /// Port of the admin HTTP endpoint
+    #[arg(long, env, default_value = "8082")]
+    pub rd_admin_http_port: u16,
+
+    /// Path to the file where the pause state is persisted
+    #[arg(long, env, default_value = "./dispatcher_admin_state.json")]
+    pub rd_admin_state_file: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct AdminConfig {
+    pub token: Option<Redacted<String>>,
+    pub http_port: u16,
+    pub state_file: PathBuf,
+}
+
+impl AdminConfig {
+    pub fn initialize(cli: AdminEnvCLIConfig) -> Self {
+        Self {
+            token: cli.rd_admin_token.map(Redacted::new),
+            http_port: cli.rd_admin_http_port,
+            state_file: cli.rd_admin_state_file,
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct AdminState {
+    pub inputs_paused: bool,
+    pub claims_paused: bool,
+}
+
+/// Shared state between the dispatcher and the admin HTTP endpoint.
+#[derive(Debug, Clone)]
+pub struct AdminControl {
+    state: Arc<Mutex<AdminState>>,
+    state_file: Arc<PathBuf>,
+}
+
+impl AdminControl {
+    /// Loads the persisted state; the dispatcher starts unpaused when there
+    /// is no state file.
+    pub fn load(state_file: PathBuf) -> Result<Self, AdminError> {
+        let state = if state_file.exists() {
+            let contents =
+                fs::read_to_string(&state_file).context(ReadStateSnafu {
+                    path: state_file.clone(),
+                })?;
+            serde_json::from_str(&contents).context(ParseStateSnafu {
+                path: state_file.clone(),
+            })?
+        } else {
+            AdminState::default()
+        };
+
+        if state != AdminState::default() {
+            tracing::warn!(?state, "dispatcher starting paused");
+        }
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(state)),
+            state_file: Arc::new(state_file),
+        })
+    }
+
+    pub fn state(&self) -> AdminState {
+        *self.state.lock().unwrap()
+    }
+
+    pub fn inputs_paused(&self) -> bool {
+        self.state().inputs_paused
+    }
+
+    pub fn claims_paused(&self) -> bool {
+        self.state().claims_paused
+    }
+
+    pub fn set_inputs_paused(
+        &self,
+        paused: bool,
+    ) -> Result<AdminState, AdminError> {
+        self.update(|state| state.inputs_paused = paused)
+    }
+
+    pub fn set_claims_paused(
+        &self,
+        paused: bool,
+    ) -> Result<AdminState, AdminError> {
+        self.update(|state| state.claims_paused = paused)
+    }
+
+    /// Persists the updated state before making it visible, so the file
+    /// never falls behind what the dispatcher is doing.
+    fn update(
+        &self,
+        f: impl FnOnce(&mut AdminState),
+    ) -> Result<AdminState, AdminError> {
+        let mut state = self.state.lock().unwrap();
+        let mut updated = *state;
+        f(&mut updated);
+        write_state(&self.state_file, &updated)?;
+        *state = updated;
+        tracing::info!(state = ?updated, "admin state updated");
+        Ok(updated)
+    }
+}
+
+fn write_state(path: &Path, state: &AdminState) -> Result<(), AdminError> {
+    let contents =
+        serde_json::to_string(state).expect("failed to serialize admin state");
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)
+        .and_then(|_| fs::rename(&tmp_path, path))
+        .context(WriteStateSnafu { path })
+}
+
+#[derive(Clone)]
+struct AdminServerState {
+    control: AdminControl,
+    token: Redacted<String>,
+}
+
+pub fn router(control: AdminControl, token: Redacted<String>) -> Router {
+    Router::new()
+        .route("/admin/state", get(get_state))
+        .route("/admin/inputs/pause", post(pause_inputs))
+        .route("/admin/inputs/resume", post(resume_inputs))
+        .route("/admin/claims/pause", post(pause_claims))
+        .route("/admin/claims/resume", post(resume_claims))
+        .with_state(AdminServerState { control, token })
+}
+
+/// Starts the admin HTTP server.
+pub async fn start(
+    port: u16,
+    control: AdminControl,
+    token: Redacted<String>,
+) -> Result<(), hyper::Error> {
+    let ip = "0.0.0.0".parse().expect("could not parse host address");
+    let addr = SocketAddr::new(ip, port);
+    tracing::info!("Starting admin HTTP server at {}", addr);
+    axum::Server::bind(&addr)
+        .serve(router(control, token).into_make_service())
+        .await
+}
+
+/// Checks the admin token sent as `Authorization: Bearer <token>`.
+/// The tokens are compared in constant time, so the comparison doesn't leak
+/// how much of the token was guessed.
+pub(crate) fn is_authorized(
+    headers: &HeaderMap,
+    token: &Redacted<String>,
+) -> bool {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.as_bytes().strip_prefix(b"Bearer "))
+        .is_some_and(|value| value.ct_eq(token.inner().as_bytes()).into())
+}
+
+type AdminResponse = Result<Json<AdminState>, StatusCode>;
+
+fn respond(
+    server: &AdminServerState,
+    headers: &HeaderMap,
+    update: impl FnOnce(&AdminControl) -> Result<AdminState, AdminError>,
+) -> AdminResponse {
+    if !is_authorized(headers, &server.token) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    update(&server.control).map(Json).map_err(|e| {
+        tracing::error!("failed to update admin state: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+async fn get_state(
+    State(server): State<AdminServerState>,
+    headers: HeaderMap,
+) -> AdminResponse {
+    respond(&server, &headers, |control| Ok(control.state()))
+}
+
+async fn pause_inputs(
+    State(server): State<AdminServerState>,
+    headers: HeaderMap,
+) -> AdminResponse {
+    respond(&server, &headers, |control| control.set_inputs_paused(true))
+}
+
+async fn resume_inputs(
+    State(server): State<AdminServerState>,
+    headers: HeaderMap,
+) -> AdminResponse {
+    respond(&server, &headers, |control| {
+        control.set_inputs_paused(false)
+    })
+}
+
+async fn pause_claims(
+    State(server): State<AdminServerState>,
+    headers: HeaderMap,
+) -> AdminResponse {
+    respond(&server, &headers, |control| control.set_claims_paused(true))
+}
+
+async fn resume_claims(
+    State(server): State<AdminServerState>,
+    headers: HeaderMap,
+) -> AdminResponse {
+    respond(&server, &headers, |control| {
+        control.set_claims_paused(false)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
+    use redacted::Redacted;
+    use tempfile::tempdir;
+
+    use super::{is_authorized, AdminControl, AdminState};
+
+    // --------------------------------------------------------------------------------------------
+    // load
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn load_without_state_file() {
+        let dir = tempdir().unwrap();
+        let control =
+            AdminControl::load(dir.path().join("state.json")).unwrap();
+        assert_eq!(control.state(), AdminState::default());
+    }
+
+    #[test]
+    fn load_persisted_state() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let control = AdminControl::load(path.clone()).unwrap();
+        control.set_inputs_paused(true).unwrap();
+
+        let control = AdminControl::load(path.clone()).unwrap();
+        assert!(control.inputs_paused());
+        assert!(!control.claims_paused());
+
+        control.set_inputs_paused(false).unwrap();
+        control.set_claims_paused(true).unwrap();
+        let control = AdminControl::load(path).unwrap();
+        assert!(!control.inputs_paused());
+        assert!(control.claims_paused());
+    }
+
+    #[test]
+    fn load_invalid_state_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        std::fs::write(&path, "not json").unwrap();
+        let error = AdminControl::load(path).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("failed to parse admin state file"));
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // set_*_paused
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn set_paused_fails_without_changing_state() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing").join("state.json");
+        let control = AdminControl::load(path).unwrap();
+        assert!(control.set_claims_paused(true).is_err());
+        assert!(!control.claims_paused());
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // is_authorized
+    // --------------------------------------------------------------------------------------------
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(authorization).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn is_authorized_with_token() {
+        let token = Redacted::new("secret".to_owned());
+        assert!(is_authorized(&headers("Bearer secret"), &token));
+        assert!(!is_authorized(&headers("Bearer other"), &token));
+        assert!(!is_authorized(&headers("secret"), &token));
+        assert!(!is_authorized(&HeaderMap::new(), &token));
+    }
+}
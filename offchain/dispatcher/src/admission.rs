@@ -0,0 +1,260 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! This module exposes the `InputAdmission`, which decides whether an input
+//! from the InputBox is forwarded to the machine.
+//!
+//! Rejected inputs are not dropped; they are sent to the broker as
+//! `RejectedInput` events, without payload, so the input indices stay
+//! consistent with the chain.
+
+use clap::Parser;
+use eth_state_fold_types::ethereum_types::Address;
+use rollups_events::RollupsInputRejection;
+use snafu::Snafu;
+use std::collections::HashSet;
+use types::foldables::input_box::Input;
+
+#[derive(Debug, Snafu)]
+pub enum InputAdmissionError {
+    #[snafu(display("invalid sender address `{}`", address))]
+    InvalidSenderAddress { address: String },
+}
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "input_admission_config")]
+#[command(about = "Configuration for the input admission filters")]
+pub struct InputAdmissionEnvCLIConfig {
+    /// Comma separated list of senders whose inputs are accepted; when set, inputs from other senders are rejected
+    #[arg(long, env, num_args = 1.., value_delimiter = ',')]
+    pub rd_input_sender_allowlist: Option<Vec<String>>,
+
+    /// Comma separated list of senders whose inputs are rejected
+    #[arg(long, env, num_args = 1.., value_delimiter = ',')]
+    pub rd_input_sender_denylist: Option<Vec<String>>,
+
+    /// Maximum payload size in bytes of an input; larger inputs are rejected
+    #[arg(long, env)]
+    pub rd_input_max_payload_size: Option<u64>,
+
+    /// Maximum number of inputs accepted from a single sender in an epoch
+    #[arg(long, env)]
+    pub rd_input_max_per_sender_per_epoch: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InputAdmissionConfig {
+    pub sender_allowlist: Option<HashSet<Address>>,
+    pub sender_denylist: HashSet<Address>,
+    pub max_payload_size: Option<u64>,
+    pub max_inputs_per_sender: Option<u64>,
+}
+
+impl InputAdmissionConfig {
+    pub fn initialize(
+        cli: InputAdmissionEnvCLIConfig,
+    ) -> Result<Self, InputAdmissionError> {
+        let sender_allowlist = cli
+            .rd_input_sender_allowlist
+            .map(parse_addresses)
+            .transpose()?;
+        let sender_denylist = cli
+            .rd_input_sender_denylist
+            .map(parse_addresses)
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Self {
+            sender_allowlist,
+            sender_denylist,
+            max_payload_size: cli.rd_input_max_payload_size,
+            max_inputs_per_sender: cli.rd_input_max_per_sender_per_epoch,
+        })
+    }
+}
+
+fn parse_addresses(
+    addresses: Vec<String>,
+) -> Result<HashSet<Address>, InputAdmissionError> {
+    addresses
+        .into_iter()
+        .map(|address| {
+            address.trim().parse().map_err(|_| {
+                InputAdmissionError::InvalidSenderAddress { address }
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InputAdmission {
+    config: InputAdmissionConfig,
+}
+
+impl InputAdmission {
+    pub fn new(config: InputAdmissionConfig) -> Self {
+        Self { config }
+    }
+
+    /// Checks the input against the filters, given the number of inputs its
+    /// sender already had accepted in the current epoch.
+    pub fn check(
+        &self,
+        input: &Input,
+        sender_inputs_count: u64,
+    ) -> Result<(), RollupsInputRejection> {
+        let sender = input.sender.as_ref();
+        let allowed = self
+            .config
+            .sender_allowlist
+            .as_ref()
+            .is_none_or(|allowlist| allowlist.contains(sender));
+        if !allowed || self.config.sender_denylist.contains(sender) {
+            return Err(RollupsInputRejection::SenderNotAllowed);
+        }
+
+        let size = input.payload.len() as u64;
+        if let Some(max_size) = self.config.max_payload_size {
+            if size > max_size {
+                return Err(RollupsInputRejection::PayloadTooLarge {
+                    size,
+                    max_size,
+                });
+            }
+        }
+
+        if let Some(max_inputs) = self.config.max_inputs_per_sender {
+            if sender_inputs_count >= max_inputs {
+                return Err(RollupsInputRejection::SenderRateLimited {
+                    max_inputs,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_state_fold_types::ethereum_types::H160;
+    use rollups_events::RollupsInputRejection;
+    use std::{collections::HashSet, sync::Arc};
+    use types::foldables::input_box::Input;
+
+    use crate::drivers::mock;
+
+    use super::{
+        InputAdmission, InputAdmissionConfig, InputAdmissionEnvCLIConfig,
+    };
+
+    fn new_input(sender: H160, payload_len: usize) -> Input {
+        let mut input = mock::new_input(0);
+        input.sender = Arc::new(sender);
+        input.payload = vec![0; payload_len];
+        input
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // initialize
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn initialize_parses_addresses() {
+        let cli = InputAdmissionEnvCLIConfig {
+            rd_input_sender_allowlist: Some(vec![
+                "0x0000000000000000000000000000000000000001".to_owned(),
+                " 0x0000000000000000000000000000000000000002".to_owned(),
+            ]),
+            rd_input_sender_denylist: None,
+            rd_input_max_payload_size: None,
+            rd_input_max_per_sender_per_epoch: None,
+        };
+        let config = InputAdmissionConfig::initialize(cli).unwrap();
+        let expected: HashSet<_> =
+            [H160::from_low_u64_be(1), H160::from_low_u64_be(2)].into();
+        assert_eq!(config.sender_allowlist, Some(expected));
+        assert!(config.sender_denylist.is_empty());
+    }
+
+    #[test]
+    fn initialize_with_invalid_address() {
+        let cli = InputAdmissionEnvCLIConfig {
+            rd_input_sender_allowlist: None,
+            rd_input_sender_denylist: Some(vec!["0x1234".to_owned()]),
+            rd_input_max_payload_size: None,
+            rd_input_max_per_sender_per_epoch: None,
+        };
+        let error = InputAdmissionConfig::initialize(cli).unwrap_err();
+        assert_eq!(error.to_string(), "invalid sender address `0x1234`");
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // check
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn check_accepts_by_default() {
+        let admission = InputAdmission::default();
+        let input = new_input(H160::random(), 1024);
+        assert_eq!(admission.check(&input, 1000), Ok(()));
+    }
+
+    #[test]
+    fn check_sender_allowlist() {
+        let allowed = H160::random();
+        let admission = InputAdmission::new(InputAdmissionConfig {
+            sender_allowlist: Some([allowed].into()),
+            ..Default::default()
+        });
+        assert_eq!(admission.check(&new_input(allowed, 0), 0), Ok(()));
+        assert_eq!(
+            admission.check(&new_input(H160::random(), 0), 0),
+            Err(RollupsInputRejection::SenderNotAllowed)
+        );
+    }
+
+    #[test]
+    fn check_sender_denylist() {
+        let denied = H160::random();
+        let admission = InputAdmission::new(InputAdmissionConfig {
+            sender_denylist: [denied].into(),
+            ..Default::default()
+        });
+        assert_eq!(
+            admission.check(&new_input(denied, 0), 0),
+            Err(RollupsInputRejection::SenderNotAllowed)
+        );
+        assert_eq!(admission.check(&new_input(H160::random(), 0), 0), Ok(()));
+    }
+
+    #[test]
+    fn check_max_payload_size() {
+        let admission = InputAdmission::new(InputAdmissionConfig {
+            max_payload_size: Some(4),
+            ..Default::default()
+        });
+        assert_eq!(admission.check(&new_input(H160::random(), 4), 0), Ok(()));
+        assert_eq!(
+            admission.check(&new_input(H160::random(), 5), 0),
+            Err(RollupsInputRejection::PayloadTooLarge {
+                size: 5,
+                max_size: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn check_max_inputs_per_sender() {
+        let admission = InputAdmission::new(InputAdmissionConfig {
+            max_inputs_per_sender: Some(2),
+            ..Default::default()
+        });
+        let input = new_input(H160::random(), 0);
+        assert_eq!(admission.check(&input, 1), Ok(()));
+        assert_eq!(
+            admission.check(&input, 2),
+            Err(RollupsInputRejection::SenderRateLimited { max_inputs: 2 })
+        );
+    }
+}
@@ -3,9 +3,11 @@
 
 //! This module handles the authentication configuration used by the transaction manager.
 //!
-//! It supports local authentication (given a mnemonic) and AWS KMS authentication.
+//! It supports local authentication (given a mnemonic or an encrypted JSON
+//! keystore) and AWS KMS authentication.
 
 use clap::Parser;
+use redacted::Redacted;
 use rusoto_core::{region::ParseRegionError, Region};
 use snafu::{ResultExt, Snafu};
 use std::{fs, str::FromStr};
@@ -25,6 +27,19 @@ pub enum AuthError {
         source: std::io::Error,
     },
 
+    #[snafu(display("Missing keystore passphrase file"))]
+    MissingKeystorePassphrase,
+
+    #[snafu(display(
+        "Could not read keystore passphrase file at path `{}`: {}",
+        path,
+        source
+    ))]
+    KeystorePassphraseFileError {
+        path: String,
+        source: std::io::Error,
+    },
+
     #[snafu(display("Missing AWS region"))]
     MissingRegion,
 
@@ -40,7 +55,7 @@ pub struct AuthEnvCLIConfig {
     #[arg(long, env)]
     pub auth_mnemonic: Option<String>,
 
-    /// Signer mnemonic file path, overrides `auth_aws_kms_*`
+    /// Signer mnemonic file path, overrides `auth_keystore_*` and `auth_aws_kms_*`
     #[arg(long, env)]
     pub auth_mnemonic_file: Option<String>,
 
@@ -48,6 +63,14 @@ pub struct AuthEnvCLIConfig {
     #[arg(long, env)]
     pub auth_mnemonic_account_index: Option<u32>,
 
+    /// Signer encrypted JSON keystore (V3) file path, overrides `auth_aws_kms_*`
+    #[arg(long, env)]
+    pub auth_keystore_file: Option<String>,
+
+    /// Path to a file with the passphrase of the JSON keystore
+    #[arg(long, env)]
+    pub auth_keystore_passphrase_file: Option<String>,
+
     /// AWS KMS signer key-id
     #[arg(long, env)]
     pub auth_aws_kms_key_id: Option<String>,
@@ -64,6 +87,11 @@ pub enum AuthConfig {
         account_index: Option<u32>,
     },
 
+    Keystore {
+        path: String,
+        passphrase: Redacted<String>,
+    },
+
     Aws {
         key_id: String,
         region: Region,
@@ -87,6 +115,20 @@ impl AuthConfig {
                 mnemonic,
                 account_index,
             })
+        } else if let Some(path) = cli.auth_keystore_file {
+            let passphrase_path = cli
+                .auth_keystore_passphrase_file
+                .ok_or(AuthError::MissingKeystorePassphrase)?;
+            let passphrase = fs::read_to_string(passphrase_path.clone())
+                .context(KeystorePassphraseFileSnafu {
+                    path: passphrase_path,
+                })?
+                .trim_end_matches(&['\r', '\n'][..])
+                .to_string();
+            Ok(AuthConfig::Keystore {
+                path,
+                passphrase: Redacted::new(passphrase),
+            })
         } else {
             match (cli.auth_aws_kms_key_id, cli.auth_aws_kms_region) {
                 (None, _) => Err(AuthError::MissingConfiguration),
@@ -0,0 +1,602 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! This module exposes the configuration and the checks of the backfill mode,
+//! in which the dispatcher rebuilds the inputs stream from the chain before
+//! dispatching new blocks.
+//!
+//! The inputs already in the broker are never overwritten; they are checked
+//! against the chain, and the backfill refuses to run if any of them diverged.
+//! The epochs in the broker are checked against the claims on the chain.
+//! Only the missing inputs, and the finish-epoch events between them, are
+//! produced.
+
+use clap::Parser;
+use eth_state_fold_types::Block;
+use rollups_events::{Hash, InputMetadata, Payload, RollupsData, RollupsInput};
+use snafu::{ensure, Snafu};
+use std::sync::Arc;
+use types::foldables::{claims::Claim, input_box::Input};
+
+#[derive(Debug, Snafu)]
+pub enum BackfillError {
+    #[snafu(display(
+        "backfill start block {} is after the end block {}",
+        from_block,
+        to_block
+    ))]
+    InvalidBlockRange { from_block: u64, to_block: u64 },
+
+    #[snafu(display(
+        "broker input {} diverges from the chain on the `{}` field",
+        input_index,
+        field
+    ))]
+    DivergentInput { input_index: u64, field: String },
+
+    #[snafu(display(
+        "broker input {} is not on the chain up to block {}",
+        input_index,
+        block_number
+    ))]
+    UnknownInput { input_index: u64, block_number: u64 },
+
+    #[snafu(display(
+        "broker input {} is in epoch {}, but it follows {} finished epochs",
+        input_index,
+        epoch_index,
+        finished_epochs
+    ))]
+    InconsistentEpoch {
+        input_index: u64,
+        epoch_index: u64,
+        finished_epochs: u64,
+    },
+
+    #[snafu(display(
+        "broker input {} is in epoch {}, but the chain claims it in epoch {}",
+        input_index,
+        epoch_index,
+        claimed_epoch_index
+    ))]
+    DivergentEpoch {
+        input_index: u64,
+        epoch_index: u64,
+        claimed_epoch_index: u64,
+    },
+
+    #[snafu(display(
+        "broker epoch {} finishes after {} inputs, but the chain claims it finishes after {}",
+        epoch_index,
+        inputs_sent_count,
+        claimed_inputs_count
+    ))]
+    DivergentEpochBoundary {
+        epoch_index: u64,
+        inputs_sent_count: u64,
+        claimed_inputs_count: u64,
+    },
+
+    #[snafu(display(
+        "input {} of block {} is missing from the broker but is before the backfill start block {}",
+        input_index,
+        block_number,
+        from_block
+    ))]
+    MissingInput {
+        input_index: u64,
+        block_number: u64,
+        from_block: u64,
+    },
+}
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "backfill_config")]
+#[command(about = "Configuration for the inputs stream backfill")]
+pub struct BackfillEnvCLIConfig {
+    /// Rebuild the inputs stream from this block before dispatching; the inputs of earlier blocks must already be in the broker
+    #[arg(long, env)]
+    pub rd_backfill_from_block: Option<u64>,
+
+    /// Last block of the backfill; defaults to the latest confirmed block
+    #[arg(long, env, requires = "rd_backfill_from_block")]
+    pub rd_backfill_to_block: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillConfig {
+    pub from_block: u64,
+    pub to_block: Option<u64>,
+}
+
+impl BackfillConfig {
+    /// Returns `None` when the backfill mode is disabled.
+    pub fn initialize(
+        cli: BackfillEnvCLIConfig,
+    ) -> Result<Option<Self>, BackfillError> {
+        let from_block = match cli.rd_backfill_from_block {
+            None => return Ok(None),
+            Some(from_block) => from_block,
+        };
+
+        let config = Self {
+            from_block,
+            to_block: cli.rd_backfill_to_block,
+        };
+        if let Some(to_block) = config.to_block {
+            config.check_end_block(to_block)?;
+        }
+
+        Ok(Some(config))
+    }
+
+    pub fn check_end_block(&self, to_block: u64) -> Result<(), BackfillError> {
+        ensure!(
+            self.from_block <= to_block,
+            InvalidBlockRangeSnafu {
+                from_block: self.from_block,
+                to_block,
+            }
+        );
+        Ok(())
+    }
+
+    /// Checks the input events already in the broker against the inputs and
+    /// the claims on the chain up to `block`, and ensures the inputs before
+    /// the start block are in the broker.
+    pub fn verify_inputs(
+        &self,
+        block: &Block,
+        broker_inputs: &[RollupsInput],
+        chain_inputs: &[Arc<Input>],
+        chain_claims: &[Arc<Claim>],
+    ) -> Result<(), BackfillError> {
+        let mut inputs_sent_count = 0;
+        let mut finished_epochs = 0;
+        for event in broker_inputs {
+            inputs_sent_count = event.inputs_sent_count;
+            // The payload of rejected inputs is not sent to the broker
+            let (metadata, tx_hash, payload) = match &event.data {
+                RollupsData::AdvanceStateInput(advance) => (
+                    &advance.metadata,
+                    &advance.tx_hash,
+                    Some(&advance.payload),
+                ),
+                RollupsData::RejectedInput(rejected) => {
+                    (&rejected.metadata, &rejected.tx_hash, None)
+                }
+                RollupsData::FinishEpoch {} => {
+                    verify_epoch_boundary(
+                        finished_epochs,
+                        inputs_sent_count,
+                        chain_claims,
+                    )?;
+                    finished_epochs += 1;
+                    continue;
+                }
+            };
+
+            let input_index = metadata.input_index;
+            verify_epoch(
+                input_index,
+                metadata.epoch_index,
+                finished_epochs,
+                chain_claims,
+            )?;
+
+            let chain_input = match chain_inputs.get(input_index as usize) {
+                Some(input) => input,
+                None => {
+                    return UnknownInputSnafu {
+                        input_index,
+                        block_number: block.number.as_u64(),
+                    }
+                    .fail()
+                }
+            };
+
+            if let Some(field) =
+                divergent_field(metadata, tx_hash, payload, chain_input)
+            {
+                return DivergentInputSnafu { input_index, field }.fail();
+            }
+        }
+
+        let first_missing = chain_inputs
+            .iter()
+            .enumerate()
+            .skip(inputs_sent_count as usize)
+            .find(|(_, input)| {
+                input.block_added.number.as_u64() < self.from_block
+            });
+        if let Some((input_index, input)) = first_missing {
+            return MissingInputSnafu {
+                input_index: input_index as u64,
+                block_number: input.block_added.number.as_u64(),
+                from_block: self.from_block,
+            }
+            .fail();
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the epoch of a broker input against the finish-epoch events before
+/// it and against the claim that covers it, if any.
+fn verify_epoch(
+    input_index: u64,
+    epoch_index: u64,
+    finished_epochs: u64,
+    chain_claims: &[Arc<Claim>],
+) -> Result<(), BackfillError> {
+    ensure!(
+        epoch_index == finished_epochs,
+        InconsistentEpochSnafu {
+            input_index,
+            epoch_index,
+            finished_epochs,
+        }
+    );
+
+    let claim_position = chain_claims
+        .partition_point(|claim| (claim.end_input_index as u64) < input_index);
+    if let Some(claim) = chain_claims.get(claim_position) {
+        if claim.start_input_index as u64 <= input_index {
+            let claimed_epoch_index = claim_position as u64;
+            ensure!(
+                epoch_index == claimed_epoch_index,
+                DivergentEpochSnafu {
+                    input_index,
+                    epoch_index,
+                    claimed_epoch_index,
+                }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that a broker epoch finishes after the last input of its claim, if
+/// the epoch was already claimed.
+fn verify_epoch_boundary(
+    epoch_index: u64,
+    inputs_sent_count: u64,
+    chain_claims: &[Arc<Claim>],
+) -> Result<(), BackfillError> {
+    if let Some(claim) = chain_claims.get(epoch_index as usize) {
+        let claimed_inputs_count = claim.end_input_index as u64 + 1;
+        ensure!(
+            inputs_sent_count == claimed_inputs_count,
+            DivergentEpochBoundarySnafu {
+                epoch_index,
+                inputs_sent_count,
+                claimed_inputs_count,
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Returns the name of the first field of the broker input that differs
+/// from the chain input.
+fn divergent_field(
+    metadata: &InputMetadata,
+    tx_hash: &Hash,
+    payload: Option<&Payload>,
+    input: &Input,
+) -> Option<&'static str> {
+    if tx_hash.inner() != &input.tx_hash.0 {
+        Some("tx_hash")
+    } else if metadata.msg_sender.inner() != &input.sender.to_fixed_bytes() {
+        Some("msg_sender")
+    } else if metadata.block_number != input.block_added.number.as_u64() {
+        Some("block_number")
+    } else if metadata.timestamp != input.block_added.timestamp.as_u64() {
+        Some("timestamp")
+    } else if payload.is_some_and(|payload| payload.inner() != &input.payload) {
+        Some("payload")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_state_fold_types::ethereum_types::H256;
+    use rollups_events::{
+        InputMetadata, RollupsAdvanceStateInput, RollupsData, RollupsInput,
+        RollupsInputRejection, RollupsRejectedInput, INITIAL_ID,
+    };
+    use std::sync::Arc;
+    use types::foldables::{claims::Claim, input_box::Input};
+
+    use crate::drivers::mock;
+
+    use super::{BackfillConfig, BackfillEnvCLIConfig};
+
+    fn new_chain_input(block_number: u64) -> Arc<Input> {
+        let mut input = mock::new_input(0);
+        input.block_added =
+            Arc::new(mock::new_block_with_number(0, block_number));
+        input.tx_hash = Arc::new(H256::random());
+        input.payload = vec![1, 2, 3];
+        Arc::new(input)
+    }
+
+    fn new_advance_event(
+        epoch_index: u64,
+        input_index: u64,
+        input: &Input,
+    ) -> RollupsInput {
+        RollupsInput {
+            parent_id: INITIAL_ID.to_owned(),
+            epoch_index,
+            inputs_sent_count: input_index + 1,
+            data: RollupsData::AdvanceStateInput(RollupsAdvanceStateInput {
+                metadata: InputMetadata {
+                    msg_sender: input.sender.to_fixed_bytes().into(),
+                    block_number: input.block_added.number.as_u64(),
+                    timestamp: input.block_added.timestamp.as_u64(),
+                    epoch_index,
+                    input_index,
+                },
+                payload: input.payload.clone().into(),
+                tx_hash: input.tx_hash.0.into(),
+            }),
+        }
+    }
+
+    fn new_finish_epoch_event(inputs_sent_count: u64) -> RollupsInput {
+        RollupsInput {
+            parent_id: INITIAL_ID.to_owned(),
+            epoch_index: 0,
+            inputs_sent_count,
+            data: RollupsData::FinishEpoch {},
+        }
+    }
+
+    fn new_claim(
+        start_input_index: usize,
+        end_input_index: usize,
+    ) -> Arc<Claim> {
+        Arc::new(Claim {
+            epoch_hash: H256::random(),
+            start_input_index,
+            end_input_index,
+            claim_timestamp: 0,
+        })
+    }
+
+    fn new_config(from_block: u64) -> BackfillConfig {
+        BackfillConfig {
+            from_block,
+            to_block: None,
+        }
+    }
+
+    fn chain_inputs(block_numbers: &[u64]) -> Vec<Arc<Input>> {
+        block_numbers.iter().map(|n| new_chain_input(*n)).collect()
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // initialize
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn initialize_disabled() {
+        let cli = BackfillEnvCLIConfig {
+            rd_backfill_from_block: None,
+            rd_backfill_to_block: None,
+        };
+        assert_eq!(BackfillConfig::initialize(cli).unwrap(), None);
+    }
+
+    #[test]
+    fn initialize_with_block_range() {
+        let cli = BackfillEnvCLIConfig {
+            rd_backfill_from_block: Some(10),
+            rd_backfill_to_block: Some(20),
+        };
+        assert_eq!(
+            BackfillConfig::initialize(cli).unwrap(),
+            Some(BackfillConfig {
+                from_block: 10,
+                to_block: Some(20),
+            })
+        );
+    }
+
+    #[test]
+    fn initialize_with_invalid_block_range() {
+        let cli = BackfillEnvCLIConfig {
+            rd_backfill_from_block: Some(20),
+            rd_backfill_to_block: Some(10),
+        };
+        let error = BackfillConfig::initialize(cli).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "backfill start block 20 is after the end block 10"
+        );
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // verify_inputs
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn verify_inputs_with_empty_broker() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20]);
+        assert!(new_config(0)
+            .verify_inputs(&block, &[], &inputs, &[])
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_inputs_with_truncated_broker() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20, 20, 30]);
+        let broker_inputs = vec![
+            new_advance_event(0, 0, &inputs[0]),
+            new_finish_epoch_event(1),
+            new_advance_event(1, 1, &inputs[1]),
+        ];
+        assert!(new_config(20)
+            .verify_inputs(&block, &broker_inputs, &inputs, &[])
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_inputs_with_rejected_input() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10]);
+        let mut event = new_advance_event(0, 0, &inputs[0]);
+        if let RollupsData::AdvanceStateInput(advance) = event.data {
+            event.data = RollupsData::RejectedInput(RollupsRejectedInput {
+                metadata: advance.metadata,
+                tx_hash: advance.tx_hash,
+                rejection: RollupsInputRejection::SenderNotAllowed,
+            });
+        }
+        assert!(new_config(0)
+            .verify_inputs(&block, &[event], &inputs, &[])
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_inputs_with_divergent_input() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20]);
+        let broker_inputs = vec![
+            new_advance_event(0, 0, &inputs[0]),
+            new_advance_event(0, 1, &new_chain_input(20)),
+        ];
+        let error = new_config(0)
+            .verify_inputs(&block, &broker_inputs, &inputs, &[])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "broker input 1 diverges from the chain on the `tx_hash` field"
+        );
+    }
+
+    #[test]
+    fn verify_inputs_with_divergent_payload() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10]);
+        let mut input = (*inputs[0]).clone();
+        input.payload = vec![4, 5, 6];
+        let broker_inputs = vec![new_advance_event(0, 0, &input)];
+        let error = new_config(0)
+            .verify_inputs(&block, &broker_inputs, &inputs, &[])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "broker input 0 diverges from the chain on the `payload` field"
+        );
+    }
+
+    #[test]
+    fn verify_inputs_with_unknown_input() {
+        let block = mock::new_block_with_number(0, 15);
+        let inputs = chain_inputs(&[10]);
+        let broker_inputs = vec![
+            new_advance_event(0, 0, &inputs[0]),
+            new_advance_event(0, 1, &new_chain_input(20)),
+        ];
+        let error = new_config(0)
+            .verify_inputs(&block, &broker_inputs, &inputs, &[])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "broker input 1 is not on the chain up to block 15"
+        );
+    }
+
+    #[test]
+    fn verify_inputs_with_claimed_epochs() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20, 20]);
+        let broker_inputs = vec![
+            new_advance_event(0, 0, &inputs[0]),
+            new_finish_epoch_event(1),
+            new_advance_event(1, 1, &inputs[1]),
+            new_advance_event(1, 2, &inputs[2]),
+        ];
+        let claims = vec![new_claim(0, 0)];
+        assert!(new_config(0)
+            .verify_inputs(&block, &broker_inputs, &inputs, &claims)
+            .is_ok());
+    }
+
+    #[test]
+    fn verify_inputs_with_inconsistent_epoch() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20]);
+        let broker_inputs = vec![
+            new_advance_event(0, 0, &inputs[0]),
+            new_finish_epoch_event(1),
+            new_advance_event(0, 1, &inputs[1]),
+        ];
+        let error = new_config(0)
+            .verify_inputs(&block, &broker_inputs, &inputs, &[])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "broker input 1 is in epoch 0, but it follows 1 finished epochs"
+        );
+    }
+
+    #[test]
+    fn verify_inputs_with_divergent_epoch() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20]);
+        let broker_inputs = vec![
+            new_advance_event(0, 0, &inputs[0]),
+            new_advance_event(0, 1, &inputs[1]),
+        ];
+        let claims = vec![new_claim(0, 0), new_claim(1, 1)];
+        let error = new_config(0)
+            .verify_inputs(&block, &broker_inputs, &inputs, &claims)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "broker input 1 is in epoch 0, but the chain claims it in epoch 1"
+        );
+    }
+
+    #[test]
+    fn verify_inputs_with_divergent_epoch_boundary() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20]);
+        let broker_inputs = vec![
+            new_advance_event(0, 0, &inputs[0]),
+            new_finish_epoch_event(1),
+            new_advance_event(1, 1, &inputs[1]),
+        ];
+        let claims = vec![new_claim(0, 1)];
+        let error = new_config(0)
+            .verify_inputs(&block, &broker_inputs, &inputs, &claims)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "broker epoch 0 finishes after 1 inputs, but the chain claims it finishes after 2"
+        );
+    }
+
+    #[test]
+    fn verify_inputs_with_missing_input_before_start_block() {
+        let block = mock::new_block_with_number(0, 30);
+        let inputs = chain_inputs(&[10, 20, 30]);
+        let broker_inputs = vec![new_advance_event(0, 0, &inputs[0])];
+        let error = new_config(25)
+            .verify_inputs(&block, &broker_inputs, &inputs, &[])
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "input 1 of block 20 is missing from the broker but is before the backfill start block 25"
+        );
+    }
+}
@@ -1,18 +1,19 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use eth_state_client_lib::config::{
     Error as SCError, SCConfig, SCEnvCLIConfig,
 };
+use eth_state_fold_types::ethereum_types::Address;
 use eth_tx_manager::{
     config::{Error as TxError, TxEnvCLIConfig, TxManagerConfig},
     Priority,
 };
 use http_server::HttpServerConfig;
 use log::{LogConfig, LogEnvCliConfig};
-use snafu::{ResultExt, Snafu};
-use std::{fs::File, io::BufReader, path::PathBuf};
+use snafu::{ensure, ResultExt, Snafu};
+use std::{collections::HashSet, fs::File, io::BufReader, path::PathBuf};
 
 use rollups_events::{BrokerCLIConfig, BrokerConfig};
 use types::deployment_files::{
@@ -20,7 +21,16 @@ use types::deployment_files::{
     rollups_deployment::{RollupsDeployment, RollupsDeploymentJson},
 };
 
-use crate::auth::{AuthConfig, AuthEnvCLIConfig, AuthError};
+use crate::{
+    admin::{AdminConfig, AdminEnvCLIConfig},
+    admission::{
+        InputAdmissionConfig, InputAdmissionEnvCLIConfig, InputAdmissionError,
+    },
+    auth::{AuthConfig, AuthEnvCLIConfig, AuthError},
+    backfill::{BackfillConfig, BackfillEnvCLIConfig, BackfillError},
+    drivers::EpochPolicy,
+    policy::{ClaimPolicyConfig, ClaimPolicyEnvCLIConfig},
+};
 
 #[derive(Parser)]
 #[command(name = "rd_config")]
@@ -41,10 +51,26 @@ pub struct DispatcherEnvCLIConfig {
     #[command(flatten)]
     pub log_config: LogEnvCliConfig,
 
+    #[command(flatten)]
+    pub claim_policy_config: ClaimPolicyEnvCLIConfig,
+
+    #[command(flatten)]
+    pub input_admission_config: InputAdmissionEnvCLIConfig,
+
+    #[command(flatten)]
+    pub backfill_config: BackfillEnvCLIConfig,
+
+    #[command(flatten)]
+    pub admin_config: AdminEnvCLIConfig,
+
     /// Path to file with deployment json of dapp
     #[arg(long, env, default_value = "./dapp_deployment.json")]
     pub rd_dapp_deployment_file: PathBuf,
 
+    /// Comma separated list of paths to deployment jsons of dapps; when set, the dispatcher drives all of them and `rd_dapp_deployment_file` is ignored
+    #[arg(long, env, num_args = 1.., value_delimiter = ',')]
+    pub rd_dapp_deployment_files: Option<Vec<PathBuf>>,
+
     /// Path to file with deployment json of rollups
     #[arg(long, env, default_value = "./rollups_deployment.json")]
     pub rd_rollups_deployment_file: PathBuf,
@@ -52,6 +78,41 @@ pub struct DispatcherEnvCLIConfig {
     /// Duration of rollups epoch in seconds, for which dispatcher will make claims.
     #[arg(long, env, default_value = "604800")]
     pub rd_epoch_duration: u64,
+
+    /// Policy used to close rollups epochs.
+    /// `first-of` closes the epoch when any of the configured thresholds is reached.
+    #[arg(long, env, value_enum, default_value_t = EpochPolicyKind::Timestamp)]
+    pub rd_epoch_policy: EpochPolicyKind,
+
+    /// Number of blocks of a rollups epoch, for the `block-number` and `first-of` policies
+    #[arg(long, env)]
+    pub rd_epoch_blocks: Option<u64>,
+
+    /// Maximum number of inputs of a rollups epoch, for the `input-count` and `first-of` policies
+    #[arg(long, env)]
+    pub rd_epoch_max_inputs: Option<u64>,
+
+    /// Maximum accumulated payload size in bytes of a rollups epoch, for the `payload-bytes` and `first-of` policies
+    #[arg(long, env)]
+    pub rd_epoch_max_payload_bytes: Option<u64>,
+
+    /// Number of blocks behind the chain head above which the block lag alarm is raised
+    #[arg(long, env)]
+    pub rd_block_lag_alarm: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EpochPolicyKind {
+    /// Close epochs every `rd_epoch_duration` seconds
+    Timestamp,
+    /// Close epochs every `rd_epoch_blocks` blocks
+    BlockNumber,
+    /// Close epochs once they have `rd_epoch_max_inputs` inputs
+    InputCount,
+    /// Close epochs once their payloads add up to `rd_epoch_max_payload_bytes`
+    PayloadBytes,
+    /// Close epochs when the first of the configured thresholds is reached
+    FirstOf,
 }
 
 #[derive(Clone, Debug)]
@@ -61,11 +122,16 @@ pub struct DispatcherConfig {
     pub broker_config: BrokerConfig,
     pub auth_config: AuthConfig,
     pub log_config: LogConfig,
+    pub claim_policy_config: ClaimPolicyConfig,
+    pub input_admission_config: InputAdmissionConfig,
+    pub backfill_config: Option<BackfillConfig>,
+    pub admin_config: AdminConfig,
 
-    pub dapp_deployment: DappDeployment,
+    pub dapp_deployments: Vec<DappDeployment>,
     pub rollups_deployment: RollupsDeployment,
     pub epoch_duration: u64,
-    pub priority: Priority,
+    pub epoch_policy: EpochPolicy,
+    pub block_lag_alarm: Option<u64>,
 }
 
 #[derive(Debug, Snafu)]
@@ -85,6 +151,12 @@ pub enum Error {
     #[snafu(display("Auth configuration error: {}", source))]
     AuthError { source: AuthError },
 
+    #[snafu(display("Input admission configuration error: {}", source))]
+    InputAdmissionError { source: InputAdmissionError },
+
+    #[snafu(display("Backfill configuration error: {}", source))]
+    BackfillError { source: BackfillError },
+
     #[snafu(display("Json parse error ({})", path.display()))]
     JsonParseError {
         path: PathBuf,
@@ -96,6 +168,20 @@ pub enum Error {
 
     #[snafu(display("Rollups json parse error"))]
     RollupsJsonParseError { source: serde_json::Error },
+
+    #[snafu(display("Dapp {:?} is deployed more than once", address))]
+    DuplicateDappError { address: Address },
+
+    #[snafu(display("Epoch policy `{}` requires `{}`", policy, arg))]
+    MissingEpochPolicyArgError { policy: String, arg: String },
+
+    #[snafu(display("`{}` has to be greater than zero", arg))]
+    ZeroEpochPolicyArgError { arg: String },
+
+    #[snafu(display(
+        "Manual claim approval requires `rd_admin_token` to authenticate the approvals"
+    ))]
+    MissingApprovalTokenError,
 }
 
 #[derive(Debug)]
@@ -109,6 +195,8 @@ impl Config {
         let (http_server_config, dispatcher_config) =
             HttpServerConfig::parse::<DispatcherEnvCLIConfig>("dispatcher");
 
+        let epoch_policy = build_epoch_policy(&dispatcher_config)?;
+
         let sc_config = SCConfig::initialize(dispatcher_config.sc_config)
             .context(StateClientSnafu)?;
 
@@ -121,8 +209,31 @@ impl Config {
 
         let log_config = LogConfig::initialize(dispatcher_config.log_config);
 
-        let path = dispatcher_config.rd_dapp_deployment_file;
-        let dapp_deployment: DappDeployment = read_json(path)?;
+        let claim_policy_config = ClaimPolicyConfig::initialize(
+            dispatcher_config.claim_policy_config,
+            Priority::Normal,
+        );
+
+        let input_admission_config = InputAdmissionConfig::initialize(
+            dispatcher_config.input_admission_config,
+        )
+        .context(InputAdmissionSnafu)?;
+
+        let backfill_config =
+            BackfillConfig::initialize(dispatcher_config.backfill_config)
+                .context(BackfillSnafu)?;
+
+        let admin_config =
+            AdminConfig::initialize(dispatcher_config.admin_config);
+
+        if claim_policy_config.manual_approval && admin_config.token.is_none() {
+            return MissingApprovalTokenSnafu.fail();
+        }
+
+        let paths = dispatcher_config
+            .rd_dapp_deployment_files
+            .unwrap_or_else(|| vec![dispatcher_config.rd_dapp_deployment_file]);
+        let dapp_deployments = read_dapp_deployments(paths)?;
 
         let path = dispatcher_config.rd_rollups_deployment_file;
         let rollups_deployment = read_json::<RollupsDeploymentJson>(path)
@@ -141,11 +252,16 @@ impl Config {
             broker_config,
             auth_config,
             log_config,
+            claim_policy_config,
+            input_admission_config,
+            backfill_config,
+            admin_config,
 
-            dapp_deployment,
+            dapp_deployments,
             rollups_deployment,
             epoch_duration: dispatcher_config.rd_epoch_duration,
-            priority: Priority::Normal,
+            epoch_policy,
+            block_lag_alarm: dispatcher_config.rd_block_lag_alarm,
         };
 
         Ok(Config {
@@ -164,3 +280,82 @@ where
     let reader = BufReader::new(file);
     serde_json::from_reader(reader).context(JsonParseSnafu { path })
 }
+
+fn read_dapp_deployments(
+    paths: Vec<PathBuf>,
+) -> Result<Vec<DappDeployment>, Error> {
+    let mut addresses = HashSet::new();
+    let mut dapp_deployments = vec![];
+    for path in paths {
+        let dapp_deployment: DappDeployment = read_json(path)?;
+        let address = dapp_deployment.dapp_address;
+        if !addresses.insert(address) {
+            return DuplicateDappSnafu { address }.fail();
+        }
+        dapp_deployments.push(dapp_deployment);
+    }
+    Ok(dapp_deployments)
+}
+
+fn build_epoch_policy(
+    config: &DispatcherEnvCLIConfig,
+) -> Result<EpochPolicy, Error> {
+    let require = |value: Option<u64>, arg: &str| {
+        value.ok_or_else(|| Error::MissingEpochPolicyArgError {
+            policy: config
+                .rd_epoch_policy
+                .to_possible_value()
+                .expect("epoch policy without value")
+                .get_name()
+                .to_owned(),
+            arg: arg.to_owned(),
+        })
+    };
+
+    let policy = match config.rd_epoch_policy {
+        EpochPolicyKind::Timestamp => EpochPolicy::default(),
+        EpochPolicyKind::BlockNumber => EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: Some(require(
+                config.rd_epoch_blocks,
+                "rd_epoch_blocks",
+            )?),
+            max_inputs: None,
+            max_payload_bytes: None,
+        },
+        EpochPolicyKind::InputCount => EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: None,
+            max_inputs: Some(require(
+                config.rd_epoch_max_inputs,
+                "rd_epoch_max_inputs",
+            )?),
+            max_payload_bytes: None,
+        },
+        EpochPolicyKind::PayloadBytes => EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: None,
+            max_inputs: None,
+            max_payload_bytes: Some(require(
+                config.rd_epoch_max_payload_bytes,
+                "rd_epoch_max_payload_bytes",
+            )?),
+        },
+        EpochPolicyKind::FirstOf => EpochPolicy {
+            by_timestamp: true,
+            epoch_blocks: config.rd_epoch_blocks,
+            max_inputs: config.rd_epoch_max_inputs,
+            max_payload_bytes: config.rd_epoch_max_payload_bytes,
+        },
+    };
+
+    for (value, arg) in [
+        (policy.epoch_blocks, "rd_epoch_blocks"),
+        (policy.max_inputs, "rd_epoch_max_inputs"),
+        (policy.max_payload_bytes, "rd_epoch_max_payload_bytes"),
+    ] {
+        ensure!(value != Some(0), ZeroEpochPolicyArgSnafu { arg });
+    }
+
+    Ok(policy)
+}
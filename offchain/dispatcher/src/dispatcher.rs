@@ -1,42 +1,83 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
-use eth_state_client_lib::StateServer;
-use eth_state_fold_types::{Block, BlockStreamItem};
+use eth_state_client_lib::{BlockServer, StateServer};
+use eth_state_fold_types::{
+    ethereum_types::U64, Block, BlockState, BlockStreamItem, QueryBlock,
+};
 use rollups_events::{Address, DAppMetadata};
 use tokio_stream::StreamExt;
 use tracing::{error, info, instrument, trace, warn};
-use types::foldables::authority::rollups::{RollupsInitialState, RollupsState};
+use types::foldables::{
+    authority::rollups::{RollupsInitialState, RollupsState},
+    claims::History,
+    input_box::InputBox,
+};
 
 use crate::{
+    admin::AdminControl,
+    admission::InputAdmission,
+    backfill::BackfillConfig,
     config::DispatcherConfig,
-    drivers::{blockchain::BlockchainDriver, machine::MachineDriver, Context},
-    error::{BrokerSnafu, DispatcherError, SenderSnafu, StateServerSnafu},
-    machine::{rollups_broker::BrokerFacade, BrokerReceive, BrokerSend},
+    drivers::{
+        blockchain::BlockchainDriver, machine::MachineDriver,
+        vouchers::VoucherDriver, Context,
+    },
+    error::{
+        BackfillSnafu, BrokerSnafu, DispatcherError, SenderSnafu,
+        StateServerSnafu,
+    },
+    health::ChainHealth,
+    machine::{rollups_broker::BrokerFacade, BrokerSend, BrokerStatus},
     metrics::DispatcherMetrics,
+    policy::{approval::ClaimApprovals, ClaimPolicy},
     sender::ClaimSender,
     setup::{create_block_subscription, create_context, create_state_server},
 };
 
 use snafu::{whatever, ResultExt};
+use std::time::Instant;
+
+/// Drivers, context and broker streams of one of the DApps driven by the
+/// dispatcher.
+struct DAppDispatcher {
+    dapp_metadata: DAppMetadata,
+    broker: BrokerFacade,
+    context: Context,
+    machine_driver: MachineDriver,
+    blockchain_driver: BlockchainDriver,
+    voucher_driver: VoucherDriver,
+    admin: AdminControl,
+}
 
 #[instrument(level = "trace", skip_all)]
 pub async fn start(
     config: DispatcherConfig,
     metrics: DispatcherMetrics,
+    claim_approvals: ClaimApprovals,
+    admin: AdminControl,
 ) -> Result<(), DispatcherError> {
     info!("Setting up dispatcher with config: {:?}", config);
 
-    let dapp_metadata = DAppMetadata {
-        chain_id: config.tx_config.chain_id,
-        dapp_address: Address::new(config.dapp_deployment.dapp_address.into()),
-    };
+    let dapps_metadata: Vec<_> = config
+        .dapp_deployments
+        .iter()
+        .map(|dapp_deployment| DAppMetadata {
+            chain_id: config.tx_config.chain_id,
+            dapp_address: Address::new(dapp_deployment.dapp_address.into()),
+        })
+        .collect();
 
     trace!("Creating transaction manager");
-    let mut claim_sender =
-        ClaimSender::new(&config, dapp_metadata.clone(), metrics.clone())
-            .await
-            .context(SenderSnafu)?;
+    let mut claim_sender = ClaimSender::new(&config, metrics.clone())
+        .await
+        .context(SenderSnafu)?;
+
+    let health = ChainHealth::new(
+        config.block_lag_alarm,
+        dapps_metadata.clone(),
+        metrics.clone(),
+    );
 
     trace!("Creating state-server connection");
     let state_server = create_state_server(&config.sc_config).await?;
@@ -45,31 +86,101 @@ pub async fn start(
     let mut block_subscription = create_block_subscription(
         &state_server,
         config.sc_config.default_confirmations,
+        health.clone(),
     )
     .await?;
 
-    trace!("Creating broker connection");
-    let broker =
-        BrokerFacade::new(config.broker_config.clone(), dapp_metadata.clone())
-            .await
-            .context(BrokerSnafu)?;
+    let mut dapps = vec![];
+    for (dapp_deployment, dapp_metadata) in
+        config.dapp_deployments.iter().zip(dapps_metadata)
+    {
+        info!("Setting up dapp {:?}", dapp_deployment.dapp_address);
+
+        trace!("Creating broker connection");
+        let broker = BrokerFacade::new(
+            config.broker_config.clone(),
+            dapp_metadata.clone(),
+        )
+        .await
+        .context(BrokerSnafu)?;
 
-    trace!("Creating context");
-    let mut context =
-        create_context(&config, &state_server, &broker, dapp_metadata, metrics)
-            .await?;
+        trace!("Reading inputs stream");
+        let input_events = broker.input_events().await.context(BrokerSnafu)?;
 
-    trace!("Creating machine driver and blockchain driver");
-    let mut machine_driver =
-        MachineDriver::new(config.dapp_deployment.dapp_address);
-    let mut blockchain_driver =
-        BlockchainDriver::new(config.dapp_deployment.dapp_address);
+        trace!("Creating context");
+        let context = create_context(
+            &config,
+            dapp_deployment,
+            &state_server,
+            &broker,
+            &input_events,
+            dapp_metadata.clone(),
+            metrics.clone(),
+        )
+        .await?;
+
+        trace!("Creating machine driver and blockchain driver");
+        let mut machine_driver = MachineDriver::new(
+            dapp_deployment.dapp_address,
+            InputAdmission::new(config.input_admission_config.clone()),
+        );
+        machine_driver.load_sent_inputs(&input_events);
+        let claim_policy = ClaimPolicy::new(
+            config.claim_policy_config.clone(),
+            claim_approvals.clone(),
+            dapp_metadata.clone(),
+            metrics.clone(),
+        );
+        let blockchain_driver =
+            BlockchainDriver::new(dapp_deployment.dapp_address, claim_policy);
+        let voucher_driver = VoucherDriver::new(
+            dapp_deployment.dapp_address,
+            &broker,
+            dapp_metadata.clone(),
+            metrics.clone(),
+        )
+        .await
+        .context(BrokerSnafu)?;
+
+        dapps.push(DAppDispatcher {
+            dapp_metadata,
+            broker,
+            context,
+            machine_driver,
+            blockchain_driver,
+            voucher_driver,
+            admin: admin.clone(),
+        });
+    }
 
     let initial_state = RollupsInitialState {
         history_address: config.rollups_deployment.history_address,
         input_box_address: config.rollups_deployment.input_box_address,
+        dapp_addresses: config
+            .dapp_deployments
+            .iter()
+            .map(|dapp_deployment| dapp_deployment.dapp_address)
+            .collect(),
     };
 
+    if let Some(backfill_config) = &config.backfill_config {
+        if admin.inputs_paused() {
+            whatever!(
+                "cannot backfill the inputs stream while inputs are paused"
+            );
+        }
+        info!("Backfilling inputs stream from {:?}", backfill_config);
+        process_backfill(
+            backfill_config,
+            config.sc_config.default_confirmations,
+            &state_server,
+            &initial_state,
+            &health,
+            &mut dapps,
+        )
+        .await?;
+    }
+
     trace!("Starting dispatcher...");
     loop {
         match block_subscription.next().await {
@@ -81,32 +192,45 @@ pub async fn start(
                     b.hash,
                     b.parent_hash
                 );
+                observe_block(&b, &state_server, &health).await;
                 claim_sender = process_block(
                     &b,
                     &state_server,
                     &initial_state,
-                    &mut context,
-                    &mut machine_driver,
-                    &mut blockchain_driver,
-                    &broker,
+                    &health,
+                    &mut dapps,
                     claim_sender,
                 )
                 .await?
             }
 
             Some(Ok(BlockStreamItem::Reorg(bs))) => {
-                error!(
+                let b = match bs.last() {
+                    Some(b) => b,
+                    None => whatever!("empty blockchain reorg"),
+                };
+                warn!(
                     "Deep blockchain reorg of {} blocks; new latest has number {:?}, hash {:?}, and parent {:?}",
                     bs.len(),
-                    bs.last().map(|b| b.number),
-                    bs.last().map(|b| b.hash),
-                    bs.last().map(|b| b.parent_hash)
+                    b.number,
+                    b.hash,
+                    b.parent_hash
                 );
-                error!("Bailing...");
-                whatever!("deep blockchain reorg");
+                health.observe_reorg();
+                observe_block(b, &state_server, &health).await;
+                claim_sender = process_reorg(
+                    b,
+                    &state_server,
+                    &initial_state,
+                    &health,
+                    &mut dapps,
+                    claim_sender,
+                )
+                .await?
             }
 
             Some(Err(e)) => {
+                health.observe_subscription_error();
                 warn!(
                     "Subscription returned error `{}`; waiting for next block...",
                     e
@@ -120,8 +244,8 @@ pub async fn start(
     }
 }
 
+/// Queries the state of the block once and reacts to it for every DApp.
 #[instrument(level = "trace", skip_all)]
-#[allow(clippy::too_many_arguments)]
 async fn process_block(
     block: &Block,
 
@@ -130,31 +254,393 @@ async fn process_block(
         State = RollupsState,
     >,
     initial_state: &RollupsInitialState,
+    health: &ChainHealth,
+
+    dapps: &mut [DAppDispatcher],
+
+    mut claim_sender: ClaimSender,
+) -> Result<ClaimSender, DispatcherError> {
+    let state = query_state(block, state_server, initial_state, health).await?;
+    for dapp in dapps.iter_mut() {
+        claim_sender = react(&state, dapp, claim_sender).await?;
+    }
+    Ok(claim_sender)
+}
+
+/// Halts the dispatcher if the reorg changed an input that was already sent
+/// to the broker by any DApp, reporting the first divergent input of that
+/// DApp, or a voucher execution that was already sent; otherwise, processes
+/// the new head as a regular block.
+#[instrument(level = "trace", skip_all)]
+async fn process_reorg(
+    block: &Block,
+
+    state_server: &impl StateServer<
+        InitialState = RollupsInitialState,
+        State = RollupsState,
+    >,
+    initial_state: &RollupsInitialState,
+    health: &ChainHealth,
+
+    dapps: &mut [DAppDispatcher],
+
+    mut claim_sender: ClaimSender,
+) -> Result<ClaimSender, DispatcherError> {
+    let state = query_state(block, state_server, initial_state, health).await?;
+
+    trace!("Comparing sent inputs with reorged state");
+    for dapp in dapps.iter() {
+        let reorg = dapp.machine_driver.find_reorged_input(
+            &dapp.context,
+            &state.block,
+            &state.state.input_box,
+        );
+        if let Some(reorg) = reorg {
+            let input_index = reorg.input_index;
+            error!(
+                ?reorg,
+                "Blockchain reorg changed input {} of dapp {:?} that was already sent",
+                input_index,
+                dapp.dapp_metadata.dapp_address
+            );
+            dapp.broker.report_reorg(reorg).await.context(BrokerSnafu)?;
+            error!("Bailing...");
+            whatever!("deep blockchain reorg changed input {}", input_index);
+        }
+
+        let reorg = dapp
+            .voucher_driver
+            .find_reorged_execution(&state.state.voucher_executions);
+        if let Some(reorg) = reorg {
+            let position = reorg.position;
+            error!(
+                ?reorg,
+                "Blockchain reorg changed voucher execution {} of dapp {:?} that was already sent",
+                position,
+                dapp.dapp_metadata.dapp_address
+            );
+            error!("Bailing...");
+            whatever!(
+                "deep blockchain reorg changed voucher execution {}",
+                position
+            );
+        }
+    }
+
+    info!("Blockchain reorg did not change sent inputs or voucher executions; continuing...");
+    for dapp in dapps.iter_mut() {
+        claim_sender = react(&state, dapp, claim_sender).await?;
+    }
+    Ok(claim_sender)
+}
+
+/// Checks the inputs already in the broker against the chain up to the end
+/// block of the backfill, then sends the missing ones, for every DApp.
+#[instrument(level = "trace", skip_all)]
+async fn process_backfill(
+    config: &BackfillConfig,
+    confirmations: usize,
+
+    state_server: &(impl StateServer<InitialState = RollupsInitialState, State = RollupsState>
+          + BlockServer),
+    initial_state: &RollupsInitialState,
+    health: &ChainHealth,
+
+    dapps: &mut [DAppDispatcher],
+) -> Result<(), DispatcherError> {
+    let query_block = match config.to_block {
+        Some(to_block) => QueryBlock::from(U64::from(to_block)),
+        None => QueryBlock::BlockDepth(confirmations),
+    };
+    let block = state_server
+        .query_block(query_block)
+        .await
+        .context(StateServerSnafu)?;
+    config
+        .check_end_block(block.number.as_u64())
+        .context(BackfillSnafu)?;
+
+    let state =
+        query_state(&block, state_server, initial_state, health).await?;
+
+    for dapp in dapps.iter_mut() {
+        let backfilled_count = backfill_dapp(
+            config,
+            &state.block,
+            &state.state.input_box,
+            &state.state.history,
+            &mut dapp.context,
+            &mut dapp.machine_driver,
+            &dapp.broker,
+        )
+        .await?;
+        info!(
+            "Backfilled {} inputs of dapp {:?} up to block {}",
+            backfilled_count,
+            dapp.dapp_metadata.dapp_address,
+            state.block.number
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks the inputs of one DApp already in the broker against the chain,
+/// then sends the missing ones; returns how many inputs were sent.
+#[instrument(level = "trace", skip_all)]
+async fn backfill_dapp(
+    config: &BackfillConfig,
+    block: &Block,
+    input_box: &InputBox,
+    history: &History,
 
     context: &mut Context,
     machine_driver: &mut MachineDriver,
-    blockchain_driver: &mut BlockchainDriver,
+    broker: &(impl BrokerSend + BrokerStatus),
+) -> Result<u64, DispatcherError> {
+    let dapp_address = machine_driver.dapp_address();
+    let chain_inputs: Vec<_> = input_box
+        .dapp_input_boxes
+        .get(&dapp_address)
+        .map(|d| d.inputs.iter().cloned().collect())
+        .unwrap_or_default();
+    let chain_claims: Vec<_> = history
+        .dapp_claims
+        .get(&dapp_address)
+        .map(|d| d.claims.iter().cloned().collect())
+        .unwrap_or_default();
 
-    broker: &(impl BrokerSend + BrokerReceive),
+    trace!("Reading inputs stream");
+    let broker_inputs = broker.input_events().await.context(BrokerSnafu)?;
 
-    claim_sender: ClaimSender,
-) -> Result<ClaimSender, DispatcherError> {
+    trace!("Comparing broker inputs with chain inputs and claims");
+    config
+        .verify_inputs(block, &broker_inputs, &chain_inputs, &chain_claims)
+        .context(BackfillSnafu)?;
+
+    let inputs_sent_count = context.inputs_sent_count();
+    machine_driver
+        .react(context, block, input_box, broker)
+        .await
+        .context(BrokerSnafu)?;
+
+    Ok(context.inputs_sent_count() - inputs_sent_count)
+}
+
+#[instrument(level = "trace", skip_all)]
+async fn query_state(
+    block: &Block,
+
+    state_server: &impl StateServer<
+        InitialState = RollupsInitialState,
+        State = RollupsState,
+    >,
+    initial_state: &RollupsInitialState,
+    health: &ChainHealth,
+) -> Result<BlockState<RollupsState>, DispatcherError> {
     trace!("Querying rollup state");
+    let start = Instant::now();
     let state = state_server
         .query_state(initial_state, block.hash)
         .await
         .context(StateServerSnafu)?;
+    health.observe_state_query(start.elapsed());
+    Ok(state)
+}
+
+/// Updates the chain health metrics for the block, comparing it with the
+/// latest block known by the state-server.
+#[instrument(level = "trace", skip_all)]
+async fn observe_block(
+    block: &Block,
+    block_server: &impl BlockServer,
+    health: &ChainHealth,
+) {
+    match block_server.query_block(QueryBlock::Latest).await {
+        Ok(head) => {
+            let lag = health.observe_block(block, head.number.as_u64());
+            trace!("Block {} is {} blocks behind the head", block.number, lag);
+        }
+        Err(e) => {
+            warn!("Failed to query the chain head: `{}`", e);
+        }
+    }
+}
+
+#[instrument(level = "trace", skip_all)]
+async fn react(
+    state: &BlockState<RollupsState>,
+    dapp: &mut DAppDispatcher,
+    claim_sender: ClaimSender,
+) -> Result<ClaimSender, DispatcherError> {
+    dapp.context.observe_epoch(&state.block);
 
     // Drive machine
-    trace!("Reacting to state with `machine_driver`");
-    machine_driver
-        .react(context, &state.block, &state.state.input_box, broker)
+    if dapp.admin.inputs_paused() {
+        trace!("Input forwarding is paused; skipping `machine_driver`");
+    } else {
+        trace!("Reacting to state with `machine_driver`");
+        dapp.machine_driver
+            .react(
+                &mut dapp.context,
+                &state.block,
+                &state.state.input_box,
+                &dapp.broker,
+            )
+            .await
+            .context(BrokerSnafu)?;
+    }
+
+    // Forward voucher executions
+    trace!("Reacting to state with `voucher_driver`");
+    dapp.voucher_driver
+        .react(&state.state.voucher_executions, &dapp.broker)
         .await
         .context(BrokerSnafu)?;
 
     // Drive blockchain
+    if dapp.admin.claims_paused() {
+        trace!("Claim submission is paused; skipping `blockchain_driver`");
+        return Ok(claim_sender);
+    }
     trace!("Reacting to state with `blockchain_driver`");
-    blockchain_driver
-        .react(&state.state.history, broker, claim_sender)
+    dapp.blockchain_driver
+        .react(&state.state.history, &dapp.broker, claim_sender)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_state_fold_types::ethereum_types::H160;
+    use rollups_events::{
+        DAppMetadata, InputMetadata, RollupsAdvanceStateInput, RollupsData,
+        RollupsInput, INITIAL_ID,
+    };
+    use std::sync::Arc;
+    use types::foldables::input_box::Input;
+
+    use crate::{
+        admission::InputAdmission,
+        backfill::BackfillConfig,
+        drivers::{
+            machine::MachineDriver,
+            mock::{self, SendInteraction},
+            Context, EpochTracker,
+        },
+        error::DispatcherError,
+        metrics::DispatcherMetrics,
+    };
+
+    use super::backfill_dapp;
+
+    fn new_input_event(input_index: u64, input: &Input) -> RollupsInput {
+        RollupsInput {
+            parent_id: INITIAL_ID.to_owned(),
+            epoch_index: 0,
+            inputs_sent_count: input_index + 1,
+            data: RollupsData::AdvanceStateInput(RollupsAdvanceStateInput {
+                metadata: InputMetadata {
+                    msg_sender: input.sender.to_fixed_bytes().into(),
+                    block_number: input.block_added.number.as_u64(),
+                    timestamp: input.block_added.timestamp.as_u64(),
+                    epoch_index: 0,
+                    input_index,
+                },
+                payload: input.payload.clone().into(),
+                tx_hash: input.tx_hash.0.into(),
+            }),
+        }
+    }
+
+    async fn test_backfill_dapp(
+        input_events: Vec<RollupsInput>,
+        chain_inputs: Vec<Arc<Input>>,
+        expected: Vec<SendInteraction>,
+    ) -> Result<u64, DispatcherError> {
+        let dapp_address = H160::random();
+        let broker = mock::Broker::with_input_events(input_events);
+        let mut context = Context::new(
+            0,
+            5,
+            EpochTracker::default(),
+            &broker,
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
         .await
+        .unwrap();
+        let mut machine_driver =
+            MachineDriver::new(dapp_address, InputAdmission::default());
+        let input_box = mock::update_input_box_with_inputs(
+            mock::new_input_box(),
+            dapp_address,
+            chain_inputs.into(),
+        );
+        let config = BackfillConfig {
+            from_block: 0,
+            to_block: None,
+        };
+
+        let result = backfill_dapp(
+            &config,
+            &mock::new_block(2),
+            &input_box,
+            &mock::new_history(),
+            &mut context,
+            &mut machine_driver,
+            &broker,
+        )
+        .await;
+
+        broker.assert_send_interactions(expected);
+        result
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // backfill_dapp
+    // --------------------------------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn backfill_dapp_sends_missing_inputs() {
+        let chain_inputs: Vec<_> =
+            (0..3).map(|_| Arc::new(mock::new_input(1))).collect();
+        let input_events = vec![new_input_event(0, &chain_inputs[0])];
+        let send_interactions = vec![
+            SendInteraction::EnqueuedInput(1),
+            SendInteraction::EnqueuedInput(2),
+        ];
+        let backfilled_count =
+            test_backfill_dapp(input_events, chain_inputs, send_interactions)
+                .await
+                .unwrap();
+        assert_eq!(backfilled_count, 2);
+    }
+
+    #[tokio::test]
+    async fn backfill_dapp_with_complete_broker() {
+        let chain_inputs: Vec<_> =
+            (0..2).map(|_| Arc::new(mock::new_input(1))).collect();
+        let input_events = vec![
+            new_input_event(0, &chain_inputs[0]),
+            new_input_event(1, &chain_inputs[1]),
+        ];
+        let backfilled_count =
+            test_backfill_dapp(input_events, chain_inputs, vec![])
+                .await
+                .unwrap();
+        assert_eq!(backfilled_count, 0);
+    }
+
+    #[tokio::test]
+    async fn backfill_dapp_with_divergent_input() {
+        let chain_inputs: Vec<_> =
+            (0..2).map(|_| Arc::new(mock::new_input(1))).collect();
+        let mut divergent_input = (*chain_inputs[0]).clone();
+        divergent_input.payload = vec![1, 2, 3];
+        let input_events = vec![new_input_event(0, &divergent_input)];
+        let error = test_backfill_dapp(input_events, chain_inputs, vec![])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DispatcherError::BackfillError { .. }));
+    }
 }
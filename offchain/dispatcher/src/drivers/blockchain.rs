@@ -3,24 +3,35 @@
 
 use eth_state_fold_types::ethereum_types::Address;
 use snafu::ResultExt;
+use std::{collections::VecDeque, time::Instant};
 use tracing::{info, instrument, trace};
 use types::foldables::claims::History;
 
 use crate::error::{BrokerSnafu, DispatcherError, SenderSnafu};
-use crate::{machine::BrokerReceive, sender::Sender};
+use crate::{
+    machine::BrokerReceive,
+    policy::{ClaimDecision, ClaimPolicy, PendingClaim},
+    sender::Sender,
+};
 
 pub struct BlockchainDriver {
     dapp_address: Address,
+    claim_policy: ClaimPolicy,
+    pending_claims: VecDeque<PendingClaim>,
 }
 
 impl BlockchainDriver {
-    pub fn new(dapp_address: Address) -> Self {
-        Self { dapp_address }
+    pub fn new(dapp_address: Address, claim_policy: ClaimPolicy) -> Self {
+        Self {
+            dapp_address,
+            claim_policy,
+            pending_claims: VecDeque::new(),
+        }
     }
 
     #[instrument(level = "trace", skip_all)]
     pub async fn react<S: Sender>(
-        &self,
+        &mut self,
         history: &History,
         broker: &impl BrokerReceive,
         mut claim_sender: S,
@@ -32,13 +43,38 @@ impl BlockchainDriver {
             broker.next_claim().await.context(BrokerSnafu)?
         {
             trace!("Got claim `{:?}` from broker", rollups_claim);
-            if rollups_claim.epoch_index >= claims_sent {
-                info!("Sending claim `{:?}`", rollups_claim);
-                claim_sender = claim_sender
-                    .submit_claim(self.dapp_address, rollups_claim)
-                    .await
-                    .context(SenderSnafu)?
+            self.pending_claims
+                .push_back(PendingClaim::new(rollups_claim, Instant::now()));
+        }
+
+        // Claims are submitted in order, so a held claim blocks the ones
+        // that come after it.
+        while let Some(pending) = self.pending_claims.front_mut() {
+            if pending.rollups_claim.epoch_index < claims_sent {
+                self.pending_claims.pop_front();
+                continue;
             }
+
+            let decision = self
+                .claim_policy
+                .evaluate(pending, &claim_sender, Instant::now())
+                .await
+                .context(SenderSnafu)?;
+            let priority = match decision {
+                ClaimDecision::Submit(priority) => priority,
+                ClaimDecision::Hold(_) => break,
+            };
+
+            let rollups_claim = self
+                .pending_claims
+                .pop_front()
+                .expect("pending claim should exist")
+                .rollups_claim;
+            info!("Sending claim `{:?}`", rollups_claim);
+            claim_sender = claim_sender
+                .submit_claim(self.dapp_address, rollups_claim, priority)
+                .await
+                .context(SenderSnafu)?
         }
 
         Ok(claim_sender)
@@ -54,13 +90,37 @@ fn claims_sent(history: &History, dapp_address: &Address) -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use eth_state_fold_types::ethereum_types::H160;
-    use rollups_events::{RollupsClaim, HASH_SIZE};
+    use eth_state_fold_types::ethereum_types::{H160, U256};
+    use eth_tx_manager::Priority;
+    use rollups_events::{DAppMetadata, RollupsClaim, HASH_SIZE};
 
-    use crate::drivers::mock;
+    use crate::{
+        drivers::mock,
+        metrics::DispatcherMetrics,
+        policy::{approval::ClaimApprovals, ClaimPolicy, ClaimPolicyConfig},
+    };
 
     use super::BlockchainDriver;
 
+    fn new_claim_policy(config: ClaimPolicyConfig) -> ClaimPolicy {
+        ClaimPolicy::new(
+            config,
+            ClaimApprovals::default(),
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
+    }
+
+    fn default_claim_policy_config() -> ClaimPolicyConfig {
+        ClaimPolicyConfig {
+            priority: Priority::Normal,
+            max_fee_per_gas: None,
+            escalation_deadline: None,
+            manual_approval: false,
+            approval_http_port: 0,
+        }
+    }
+
     // --------------------------------------------------------------------------------------------
     // new
     // --------------------------------------------------------------------------------------------
@@ -68,8 +128,12 @@ mod tests {
     #[test]
     fn new() {
         let dapp_address = H160::default();
-        let blockchain_driver = BlockchainDriver::new(dapp_address);
+        let blockchain_driver = BlockchainDriver::new(
+            dapp_address,
+            new_claim_policy(default_claim_policy_config()),
+        );
         assert_eq!(blockchain_driver.dapp_address, dapp_address);
+        assert!(blockchain_driver.pending_claims.is_empty());
     }
 
     // --------------------------------------------------------------------------------------------
@@ -125,15 +189,8 @@ mod tests {
     // react
     // --------------------------------------------------------------------------------------------
 
-    async fn test_react(next_claims: Vec<u64>, n: usize) {
-        let dapp_address = H160::random();
-        let blockchain_driver = BlockchainDriver::new(dapp_address);
-
-        let history = mock::new_history();
-        let history = mock::update_history(&history, dapp_address, 5);
-        let history = mock::update_history(&history, H160::random(), 2);
-
-        let next_claims = next_claims
+    fn new_claims(epoch_indices: Vec<u64>) -> Vec<RollupsClaim> {
+        epoch_indices
             .iter()
             .map(|i| RollupsClaim {
                 epoch_hash: [*i as u8; HASH_SIZE].into(),
@@ -141,8 +198,21 @@ mod tests {
                 first_index: *i as u128,
                 last_index: *i as u128,
             })
-            .collect();
-        let broker = mock::Broker::new(vec![], next_claims);
+            .collect()
+    }
+
+    async fn test_react(next_claims: Vec<u64>, n: usize) {
+        let dapp_address = H160::random();
+        let mut blockchain_driver = BlockchainDriver::new(
+            dapp_address,
+            new_claim_policy(default_claim_policy_config()),
+        );
+
+        let history = mock::new_history();
+        let history = mock::update_history(&history, dapp_address, 5);
+        let history = mock::update_history(&history, H160::random(), 2);
+
+        let broker = mock::Broker::new(vec![], new_claims(next_claims));
         let tx_sender = mock::Sender::new();
 
         let result =
@@ -180,4 +250,62 @@ mod tests {
     async fn react_interleaved_old_new_claims_sent_5_claims() {
         test_react(vec![0, 4, 5, 1, 2, 6, 7, 3, 4, 8, 9], 5).await;
     }
+
+    // broker has 2 new claims, but the gas is above the ceiling -- sent 0
+    // claims, and they are sent once the gas goes down
+    #[tokio::test]
+    async fn react_holds_claims_above_gas_ceiling() {
+        let dapp_address = H160::random();
+        let mut config = default_claim_policy_config();
+        config.max_fee_per_gas = Some(U256::from(100));
+        let mut blockchain_driver =
+            BlockchainDriver::new(dapp_address, new_claim_policy(config));
+        let history = mock::new_history();
+        let history = mock::update_history(&history, dapp_address, 5);
+        let broker = mock::Broker::new(vec![], new_claims(vec![5, 6]));
+
+        let tx_sender = mock::Sender::with_max_fee_per_gas(U256::from(101));
+        let tx_sender = blockchain_driver
+            .react(&history, &broker, tx_sender)
+            .await
+            .unwrap();
+        assert_eq!(tx_sender.count(), 0);
+        assert_eq!(blockchain_driver.pending_claims.len(), 2);
+
+        let tx_sender = mock::Sender::with_max_fee_per_gas(U256::from(100));
+        let tx_sender = blockchain_driver
+            .react(&history, &broker, tx_sender)
+            .await
+            .unwrap();
+        assert_eq!(tx_sender.count(), 2);
+        assert!(blockchain_driver.pending_claims.is_empty());
+    }
+
+    // held claims that were sent by someone else are dropped
+    #[tokio::test]
+    async fn react_drops_held_claims_already_sent() {
+        let dapp_address = H160::random();
+        let mut config = default_claim_policy_config();
+        config.manual_approval = true;
+        let mut blockchain_driver =
+            BlockchainDriver::new(dapp_address, new_claim_policy(config));
+        let broker = mock::Broker::new(vec![], new_claims(vec![5]));
+
+        let history = mock::new_history();
+        let history = mock::update_history(&history, dapp_address, 5);
+        let tx_sender = blockchain_driver
+            .react(&history, &broker, mock::Sender::new())
+            .await
+            .unwrap();
+        assert_eq!(tx_sender.count(), 0);
+        assert_eq!(blockchain_driver.pending_claims.len(), 1);
+
+        let history = mock::update_history(&history, dapp_address, 6);
+        let tx_sender = blockchain_driver
+            .react(&history, &broker, tx_sender)
+            .await
+            .unwrap();
+        assert_eq!(tx_sender.count(), 0);
+        assert!(blockchain_driver.pending_claims.is_empty());
+    }
 }
@@ -6,14 +6,18 @@ use crate::{
     metrics::DispatcherMetrics,
 };
 
-use rollups_events::DAppMetadata;
+use eth_state_fold_types::{ethereum_types::Address, Block};
+use rollups_events::{DAppMetadata, RollupsInputRejection};
 use types::foldables::input_box::Input;
 
+use super::EpochTracker;
+
 #[derive(Debug)]
 pub struct Context {
     inputs_sent_count: u64,
     last_event_is_finish_epoch: bool,
     last_timestamp: u64,
+    epoch_tracker: EpochTracker,
 
     // constants
     genesis_timestamp: u64,
@@ -27,6 +31,7 @@ impl Context {
     pub async fn new(
         genesis_timestamp: u64,
         epoch_length: u64,
+        epoch_tracker: EpochTracker,
         broker: &impl BrokerStatus,
         dapp_metadata: DAppMetadata,
         metrics: DispatcherMetrics,
@@ -37,6 +42,7 @@ impl Context {
             inputs_sent_count: status.inputs_sent_count,
             last_event_is_finish_epoch: status.last_event_is_finish_epoch,
             last_timestamp: genesis_timestamp,
+            epoch_tracker,
             genesis_timestamp,
             epoch_length,
             dapp_metadata,
@@ -48,13 +54,38 @@ impl Context {
         self.inputs_sent_count
     }
 
+    pub fn sender_inputs_count(&self, sender: &Address) -> u64 {
+        self.epoch_tracker.sender_inputs_count(sender)
+    }
+
+    /// Updates the epoch metrics for the block; they are only meaningful when
+    /// epochs are closed by timestamp.
+    pub fn observe_epoch(&self, block: &Block) {
+        if !self.epoch_tracker.by_timestamp() {
+            return;
+        }
+
+        let timestamp = block.timestamp.as_u64();
+        let epoch = self.calculate_epoch(timestamp);
+        let epoch_end =
+            self.genesis_timestamp + (epoch + 1) * self.epoch_length;
+        self.metrics
+            .current_epoch_index
+            .get_or_create(&self.dapp_metadata)
+            .set(epoch as i64);
+        self.metrics
+            .epoch_seconds_until_close
+            .get_or_create(&self.dapp_metadata)
+            .set((epoch_end - timestamp) as i64);
+    }
+
     pub async fn finish_epoch_if_needed(
         &mut self,
-        event_timestamp: u64,
+        event_block: &Block,
         broker: &impl BrokerSend,
     ) -> Result<(), BrokerFacadeError> {
-        if self.should_finish_epoch(event_timestamp) {
-            self.finish_epoch(event_timestamp, broker).await?;
+        if self.should_finish_epoch(event_block) {
+            self.finish_epoch(event_block, broker).await?;
         }
         Ok(())
     }
@@ -71,6 +102,26 @@ impl Context {
             .inc();
         self.inputs_sent_count += 1;
         self.last_event_is_finish_epoch = false;
+        self.epoch_tracker.enqueue_input(input);
+        Ok(())
+    }
+
+    pub async fn reject_input(
+        &mut self,
+        input: &Input,
+        rejection: RollupsInputRejection,
+        broker: &impl BrokerSend,
+    ) -> Result<(), BrokerFacadeError> {
+        broker
+            .enqueue_rejected_input(self.inputs_sent_count, input, rejection)
+            .await?;
+        self.metrics
+            .advance_inputs_rejected
+            .get_or_create(&self.dapp_metadata)
+            .inc();
+        self.inputs_sent_count += 1;
+        self.last_event_is_finish_epoch = false;
+        self.epoch_tracker.reject_input(input);
         Ok(())
     }
 }
@@ -81,23 +132,28 @@ impl Context {
         (timestamp - self.genesis_timestamp) / self.epoch_length
     }
 
-    // This logic works because we call this function with `event_timestamp` being equal to the
-    // timestamp of each individual input, rather than just the latest from the blockchain.
-    fn should_finish_epoch(&self, event_timestamp: u64) -> bool {
+    // This logic works because we call this function with `event_block` being equal to the
+    // block of each individual input, rather than just the latest from the blockchain.
+    fn should_finish_epoch(&self, event_block: &Block) -> bool {
         if self.inputs_sent_count == 0 || self.last_event_is_finish_epoch {
             false
         } else {
-            let current_epoch = self.calculate_epoch(self.last_timestamp);
-            let event_epoch = self.calculate_epoch(event_timestamp);
-            event_epoch > current_epoch
+            let by_timestamp = self.epoch_tracker.by_timestamp() && {
+                let current_epoch = self.calculate_epoch(self.last_timestamp);
+                let event_epoch =
+                    self.calculate_epoch(event_block.timestamp.as_u64());
+                event_epoch > current_epoch
+            };
+            by_timestamp || self.epoch_tracker.should_finish_epoch(event_block)
         }
     }
 
     async fn finish_epoch(
         &mut self,
-        event_timestamp: u64,
+        event_block: &Block,
         broker: &impl BrokerSend,
     ) -> Result<(), BrokerFacadeError> {
+        let event_timestamp = event_block.timestamp.as_u64();
         assert!(event_timestamp >= self.genesis_timestamp);
         broker.finish_epoch(self.inputs_sent_count).await?;
         self.metrics
@@ -106,6 +162,7 @@ impl Context {
             .inc();
         self.last_timestamp = event_timestamp;
         self.last_event_is_finish_epoch = true;
+        self.epoch_tracker.finish_epoch(event_block);
         Ok(())
     }
 }
@@ -114,7 +171,7 @@ impl Context {
 mod private_tests {
     use crate::{drivers::mock, metrics::DispatcherMetrics};
 
-    use super::{Context, DAppMetadata};
+    use super::{Context, DAppMetadata, EpochTracker};
 
     // --------------------------------------------------------------------------------------------
     // calculate_epoch_for
@@ -128,6 +185,7 @@ mod private_tests {
             inputs_sent_count: 0,
             last_event_is_finish_epoch: false,
             last_timestamp: 0,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp,
             epoch_length,
             dapp_metadata: DAppMetadata::default(),
@@ -179,12 +237,13 @@ mod private_tests {
             inputs_sent_count: 1,
             last_event_is_finish_epoch: false,
             last_timestamp: 3,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 5,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
-        assert!(!context.should_finish_epoch(4));
+        assert!(!context.should_finish_epoch(&mock::new_block(4)));
     }
 
     #[test]
@@ -193,12 +252,13 @@ mod private_tests {
             inputs_sent_count: 1,
             last_event_is_finish_epoch: false,
             last_timestamp: 3,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 5,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
-        assert!(!context.should_finish_epoch(4));
+        assert!(!context.should_finish_epoch(&mock::new_block(4)));
     }
 
     #[test]
@@ -207,12 +267,13 @@ mod private_tests {
             inputs_sent_count: 1,
             last_event_is_finish_epoch: false,
             last_timestamp: 3,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 5,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
-        assert!(context.should_finish_epoch(5));
+        assert!(context.should_finish_epoch(&mock::new_block(5)));
     }
 
     #[test]
@@ -221,12 +282,13 @@ mod private_tests {
             inputs_sent_count: 1,
             last_event_is_finish_epoch: true,
             last_timestamp: 3,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 5,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
-        assert!(!context.should_finish_epoch(5));
+        assert!(!context.should_finish_epoch(&mock::new_block(5)));
     }
 
     // --------------------------------------------------------------------------------------------
@@ -239,6 +301,7 @@ mod private_tests {
             inputs_sent_count: 0,
             last_event_is_finish_epoch: false,
             last_timestamp: 3,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 5,
             dapp_metadata: DAppMetadata::default(),
@@ -246,9 +309,11 @@ mod private_tests {
         };
         let broker = mock::Broker::new(vec![], vec![]);
         let timestamp = 6;
-        let result = context.finish_epoch(timestamp, &broker).await;
+        let result = context
+            .finish_epoch(&mock::new_block(timestamp), &broker)
+            .await;
         assert!(result.is_ok());
-        assert_eq!(context.last_timestamp, timestamp);
+        assert_eq!(context.last_timestamp, timestamp as u64);
         assert!(context.last_event_is_finish_epoch);
     }
 
@@ -259,13 +324,14 @@ mod private_tests {
             inputs_sent_count: 0,
             last_event_is_finish_epoch: false,
             last_timestamp: 6,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 5,
             epoch_length: 5,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
         let broker = mock::Broker::new(vec![], vec![]);
-        let _ = context.finish_epoch(0, &broker).await;
+        let _ = context.finish_epoch(&mock::new_block(0), &broker).await;
     }
 
     #[tokio::test]
@@ -276,13 +342,14 @@ mod private_tests {
             inputs_sent_count: 0,
             last_event_is_finish_epoch,
             last_timestamp,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 5,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
         let broker = mock::Broker::with_finish_epoch_error();
-        let result = context.finish_epoch(6, &broker).await;
+        let result = context.finish_epoch(&mock::new_block(6), &broker).await;
         assert!(result.is_err());
         assert_eq!(context.last_timestamp, last_timestamp);
         assert_eq!(
@@ -294,13 +361,18 @@ mod private_tests {
 
 #[cfg(test)]
 mod public_tests {
+    use rollups_events::RollupsInputRejection;
+
     use crate::{
-        drivers::mock::{self, Broker, SendInteraction},
+        drivers::{
+            mock::{self, Broker, SendInteraction},
+            EpochPolicy,
+        },
         machine::RollupStatus,
         metrics::DispatcherMetrics,
     };
 
-    use super::{Context, DAppMetadata};
+    use super::{Context, DAppMetadata, EpochTracker};
 
     // --------------------------------------------------------------------------------------------
     // new
@@ -320,6 +392,7 @@ mod public_tests {
         let result = Context::new(
             genesis_timestamp,
             epoch_length,
+            EpochTracker::default(),
             &broker,
             DAppMetadata::default(),
             DispatcherMetrics::default(),
@@ -341,6 +414,7 @@ mod public_tests {
         let result = Context::new(
             1337,
             7331,
+            EpochTracker::default(),
             &broker,
             DAppMetadata::default(),
             DispatcherMetrics::default(),
@@ -360,14 +434,75 @@ mod public_tests {
             inputs_sent_count,
             last_event_is_finish_epoch: false, // ignored
             last_timestamp: 0,                 // ignored
-            genesis_timestamp: 0,              // ignored
-            epoch_length: 0,                   // ignored
+            epoch_tracker: EpochTracker::default(),
+            genesis_timestamp: 0, // ignored
+            epoch_length: 0,      // ignored
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
         assert_eq!(context.inputs_sent_count(), inputs_sent_count);
     }
 
+    // --------------------------------------------------------------------------------------------
+    // observe_epoch
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn observe_epoch_by_timestamp() {
+        let context = Context {
+            inputs_sent_count: 0,
+            last_event_is_finish_epoch: false,
+            last_timestamp: 0,
+            epoch_tracker: EpochTracker::default(),
+            genesis_timestamp: 2,
+            epoch_length: 10,
+            dapp_metadata: DAppMetadata::default(),
+            metrics: DispatcherMetrics::default(),
+        };
+        context.observe_epoch(&mock::new_block(25));
+        let metadata = DAppMetadata::default();
+        let metrics = &context.metrics;
+        assert_eq!(
+            metrics.current_epoch_index.get_or_create(&metadata).get(),
+            2
+        );
+        assert_eq!(
+            metrics
+                .epoch_seconds_until_close
+                .get_or_create(&metadata)
+                .get(),
+            7
+        );
+    }
+
+    #[test]
+    fn observe_epoch_not_by_timestamp() {
+        let context = Context {
+            inputs_sent_count: 0,
+            last_event_is_finish_epoch: false,
+            last_timestamp: 0,
+            epoch_tracker: EpochTracker::new(
+                EpochPolicy {
+                    by_timestamp: false,
+                    max_inputs: Some(1),
+                    ..Default::default()
+                },
+                0,
+            ),
+            genesis_timestamp: 2,
+            epoch_length: 10,
+            dapp_metadata: DAppMetadata::default(),
+            metrics: DispatcherMetrics::default(),
+        };
+        context.observe_epoch(&mock::new_block(25));
+        let metadata = DAppMetadata::default();
+        let metrics = &context.metrics;
+        assert_eq!(
+            metrics.current_epoch_index.get_or_create(&metadata).get(),
+            0
+        );
+    }
+
     // --------------------------------------------------------------------------------------------
     // finish_epoch_if_needed
     // --------------------------------------------------------------------------------------------
@@ -378,13 +513,16 @@ mod public_tests {
             inputs_sent_count: 1,
             last_event_is_finish_epoch: false,
             last_timestamp: 2,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 4,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
         let broker = mock::Broker::new(vec![], vec![]);
-        let result = context.finish_epoch_if_needed(4, &broker).await;
+        let result = context
+            .finish_epoch_if_needed(&mock::new_block(4), &broker)
+            .await;
         assert!(result.is_ok());
         broker
             .assert_send_interactions(vec![SendInteraction::FinishedEpoch(1)]);
@@ -396,13 +534,16 @@ mod public_tests {
             inputs_sent_count: 0,
             last_event_is_finish_epoch: false,
             last_timestamp: 2,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 2,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
         let broker = mock::Broker::new(vec![], vec![]);
-        let result = context.finish_epoch_if_needed(3, &broker).await;
+        let result = context
+            .finish_epoch_if_needed(&mock::new_block(3), &broker)
+            .await;
         assert!(result.is_ok());
         broker.assert_send_interactions(vec![]);
     }
@@ -413,13 +554,16 @@ mod public_tests {
             inputs_sent_count: 1,
             last_event_is_finish_epoch: false,
             last_timestamp: 2,
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0,
             epoch_length: 4,
             dapp_metadata: DAppMetadata::default(),
             metrics: DispatcherMetrics::default(),
         };
         let broker = mock::Broker::with_finish_epoch_error();
-        let result = context.finish_epoch_if_needed(4, &broker).await;
+        let result = context
+            .finish_epoch_if_needed(&mock::new_block(4), &broker)
+            .await;
         assert!(result.is_err());
     }
 
@@ -433,7 +577,8 @@ mod public_tests {
         let mut context = Context {
             inputs_sent_count,
             last_event_is_finish_epoch: true,
-            last_timestamp: 0,    // ignored
+            last_timestamp: 0, // ignored
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0, // ignored
             epoch_length: 0,      // ignored
             dapp_metadata: DAppMetadata::default(),
@@ -455,7 +600,8 @@ mod public_tests {
         let mut context = Context {
             inputs_sent_count: 42,
             last_event_is_finish_epoch: true,
-            last_timestamp: 0,    // ignored
+            last_timestamp: 0, // ignored
+            epoch_tracker: EpochTracker::default(),
             genesis_timestamp: 0, // ignored
             epoch_length: 0,      // ignored
             dapp_metadata: DAppMetadata::default(),
@@ -465,4 +611,39 @@ mod public_tests {
         let result = context.enqueue_input(&mock::new_input(2), &broker).await;
         assert!(result.is_err());
     }
+
+    // --------------------------------------------------------------------------------------------
+    // reject_input
+    // --------------------------------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn reject_input_ok() {
+        let inputs_sent_count = 42;
+        let mut context = Context {
+            inputs_sent_count,
+            last_event_is_finish_epoch: true,
+            last_timestamp: 0, // ignored
+            epoch_tracker: EpochTracker::default(),
+            genesis_timestamp: 0, // ignored
+            epoch_length: 0,      // ignored
+            dapp_metadata: DAppMetadata::default(),
+            metrics: DispatcherMetrics::default(),
+        };
+        let input = mock::new_input(2);
+        let broker = mock::Broker::new(vec![], vec![]);
+        let result = context
+            .reject_input(
+                &input,
+                RollupsInputRejection::SenderNotAllowed,
+                &broker,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(context.inputs_sent_count, inputs_sent_count + 1);
+        assert!(!context.last_event_is_finish_epoch);
+        assert_eq!(context.sender_inputs_count(&input.sender), 0);
+        broker.assert_send_interactions(vec![SendInteraction::RejectedInput(
+            inputs_sent_count,
+        )]);
+    }
 }
@@ -0,0 +1,444 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use eth_state_fold_types::{ethereum_types::Address, Block};
+use rollups_events::{RollupsData, RollupsInput};
+use std::collections::HashMap;
+use types::foldables::input_box::Input;
+
+/// Thresholds that close an epoch; the epoch is closed as soon as any of them
+/// is reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochPolicy {
+    /// Close the epoch when the epoch duration elapses
+    pub by_timestamp: bool,
+
+    /// Close the epoch every `epoch_blocks` blocks since the DApp deployment
+    pub epoch_blocks: Option<u64>,
+
+    /// Close the epoch once it has `max_inputs` inputs
+    pub max_inputs: Option<u64>,
+
+    /// Close the epoch once its inputs add up to `max_payload_bytes` bytes
+    pub max_payload_bytes: Option<u64>,
+}
+
+impl Default for EpochPolicy {
+    fn default() -> Self {
+        Self {
+            by_timestamp: true,
+            epoch_blocks: None,
+            max_inputs: None,
+            max_payload_bytes: None,
+        }
+    }
+}
+
+/// Keeps track of the current epoch for the thresholds that are not based on
+/// timestamps, and of the inputs accepted from each sender in the epoch.
+///
+/// The counters of an epoch that was open when the dispatcher restarted are
+/// restored from the inputs stream with `restore`.
+#[derive(Debug, Clone, Default)]
+pub struct EpochTracker {
+    policy: EpochPolicy,
+    genesis_block_number: u64,
+    /// Block where the last epoch was finished; after a restart, the block of
+    /// the first input of the open epoch stands for it, since the inputs
+    /// stream doesn't record the block of each finish epoch
+    last_block_number: Option<u64>,
+    inputs_count: u64,
+    payload_bytes: u64,
+    sender_inputs: HashMap<Address, u64>,
+}
+
+impl EpochTracker {
+    pub fn new(policy: EpochPolicy, genesis_block_number: u64) -> Self {
+        Self {
+            policy,
+            genesis_block_number,
+            last_block_number: Some(genesis_block_number),
+            inputs_count: 0,
+            payload_bytes: 0,
+            sender_inputs: HashMap::new(),
+        }
+    }
+
+    pub fn by_timestamp(&self) -> bool {
+        self.policy.by_timestamp
+    }
+
+    pub fn should_finish_epoch(&self, block: &Block) -> bool {
+        let by_block_number = match self.last_block_number {
+            Some(last_block_number) => {
+                self.policy.epoch_blocks.is_some_and(|n| {
+                    let current_epoch =
+                        self.calculate_epoch(last_block_number, n);
+                    let event_epoch =
+                        self.calculate_epoch(block.number.as_u64(), n);
+                    event_epoch > current_epoch
+                })
+            }
+            // The open epoch has no inputs yet
+            None => false,
+        };
+        let by_input_count = self
+            .policy
+            .max_inputs
+            .is_some_and(|max| self.inputs_count >= max);
+        let by_payload_bytes = self
+            .policy
+            .max_payload_bytes
+            .is_some_and(|max| self.payload_bytes >= max);
+        by_block_number || by_input_count || by_payload_bytes
+    }
+
+    pub fn sender_inputs_count(&self, sender: &Address) -> u64 {
+        self.sender_inputs.get(sender).copied().unwrap_or(0)
+    }
+
+    pub fn enqueue_input(&mut self, input: &Input) {
+        self.count_input(input.block_added.number.as_u64());
+        self.payload_bytes += input.payload.len() as u64;
+        *self.sender_inputs.entry(*input.sender).or_default() += 1;
+    }
+
+    /// Restores the counters of the open epoch from the events already sent
+    /// to the inputs stream.
+    pub fn restore(&mut self, input_events: &[RollupsInput]) {
+        let open_epoch_start = match input_events.iter().rposition(|event| {
+            matches!(event.data, RollupsData::FinishEpoch {})
+        }) {
+            Some(position) => {
+                // Taken from the first input of the open epoch
+                self.last_block_number = None;
+                position + 1
+            }
+            None => 0,
+        };
+        for event in &input_events[open_epoch_start..] {
+            match &event.data {
+                RollupsData::AdvanceStateInput(input) => {
+                    self.count_input(input.metadata.block_number);
+                    self.payload_bytes += input.payload.inner().len() as u64;
+                    *self
+                        .sender_inputs
+                        .entry(Address::from(input.metadata.msg_sender.inner()))
+                        .or_default() += 1;
+                }
+                RollupsData::RejectedInput(input) => {
+                    self.count_input(input.metadata.block_number)
+                }
+                RollupsData::FinishEpoch {} => {}
+            }
+        }
+    }
+
+    /// Rejected inputs count towards the epoch, but their payload is not sent.
+    pub fn reject_input(&mut self, input: &Input) {
+        self.count_input(input.block_added.number.as_u64());
+    }
+
+    pub fn finish_epoch(&mut self, block: &Block) {
+        self.last_block_number = Some(block.number.as_u64());
+        self.inputs_count = 0;
+        self.payload_bytes = 0;
+        self.sender_inputs.clear();
+    }
+
+    fn count_input(&mut self, block_number: u64) {
+        self.inputs_count += 1;
+        self.last_block_number.get_or_insert(block_number);
+    }
+
+    fn calculate_epoch(&self, block_number: u64, epoch_blocks: u64) -> u64 {
+        assert!(block_number >= self.genesis_block_number);
+        (block_number - self.genesis_block_number) / epoch_blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::drivers::mock;
+    use eth_state_fold_types::ethereum_types::Address;
+    use rollups_events::{
+        InputMetadata, Payload, RollupsAdvanceStateInput, RollupsData,
+        RollupsInput, RollupsInputRejection, RollupsRejectedInput,
+    };
+    use std::sync::Arc;
+
+    use super::{EpochPolicy, EpochTracker};
+
+    fn new_tracker(policy: EpochPolicy) -> EpochTracker {
+        EpochTracker::new(policy, 10)
+    }
+
+    fn new_input(payload_len: usize) -> types::foldables::input_box::Input {
+        let mut input = mock::new_input(0);
+        input.payload = vec![0; payload_len];
+        input
+    }
+
+    fn new_input_at(
+        payload_len: usize,
+        block_number: u64,
+    ) -> types::foldables::input_box::Input {
+        let mut input = new_input(payload_len);
+        input.block_added =
+            Arc::new(mock::new_block_with_number(0, block_number));
+        input
+    }
+
+    fn new_event(data: RollupsData) -> RollupsInput {
+        RollupsInput {
+            parent_id: String::new(),
+            epoch_index: 0,
+            inputs_sent_count: 0,
+            data,
+        }
+    }
+
+    fn new_advance_event(sender: Address, payload_len: usize) -> RollupsInput {
+        new_advance_event_at(sender, payload_len, 10)
+    }
+
+    fn new_advance_event_at(
+        sender: Address,
+        payload_len: usize,
+        block_number: u64,
+    ) -> RollupsInput {
+        new_event(RollupsData::AdvanceStateInput(RollupsAdvanceStateInput {
+            metadata: InputMetadata {
+                msg_sender: sender.to_fixed_bytes().into(),
+                block_number,
+                ..Default::default()
+            },
+            payload: Payload::new(vec![0; payload_len]),
+            ..Default::default()
+        }))
+    }
+
+    fn new_rejected_event() -> RollupsInput {
+        new_event(RollupsData::RejectedInput(RollupsRejectedInput {
+            metadata: InputMetadata {
+                block_number: 10,
+                ..Default::default()
+            },
+            tx_hash: Default::default(),
+            rejection: RollupsInputRejection::SenderNotAllowed,
+        }))
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // should_finish_epoch
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn should_not_finish_epoch_by_default() {
+        let mut tracker = new_tracker(EpochPolicy::default());
+        for _ in 0..100 {
+            tracker.enqueue_input(&new_input(100));
+        }
+        assert!(
+            !tracker.should_finish_epoch(&mock::new_block_with_number(0, 1000))
+        );
+    }
+
+    #[test]
+    fn should_finish_epoch_by_block_number() {
+        let tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: Some(5),
+            ..Default::default()
+        });
+        assert!(
+            !tracker.should_finish_epoch(&mock::new_block_with_number(0, 14))
+        );
+        assert!(
+            tracker.should_finish_epoch(&mock::new_block_with_number(0, 15))
+        );
+    }
+
+    #[test]
+    fn should_finish_epoch_by_block_number_after_finish_epoch() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: Some(5),
+            ..Default::default()
+        });
+        tracker.finish_epoch(&mock::new_block_with_number(0, 17));
+        assert!(
+            !tracker.should_finish_epoch(&mock::new_block_with_number(0, 19))
+        );
+        assert!(
+            tracker.should_finish_epoch(&mock::new_block_with_number(0, 20))
+        );
+    }
+
+    #[test]
+    fn should_finish_epoch_by_input_count() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            max_inputs: Some(2),
+            ..Default::default()
+        });
+        let block = mock::new_block_with_number(0, 10);
+        tracker.enqueue_input(&new_input(0));
+        assert!(!tracker.should_finish_epoch(&block));
+        tracker.enqueue_input(&new_input(0));
+        assert!(tracker.should_finish_epoch(&block));
+        tracker.finish_epoch(&block);
+        assert!(!tracker.should_finish_epoch(&block));
+    }
+
+    #[test]
+    fn should_finish_epoch_by_payload_bytes() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            max_payload_bytes: Some(100),
+            ..Default::default()
+        });
+        let block = mock::new_block_with_number(0, 10);
+        tracker.enqueue_input(&new_input(60));
+        assert!(!tracker.should_finish_epoch(&block));
+        tracker.enqueue_input(&new_input(40));
+        assert!(tracker.should_finish_epoch(&block));
+        tracker.finish_epoch(&block);
+        assert!(!tracker.should_finish_epoch(&block));
+    }
+
+    #[test]
+    fn should_finish_epoch_by_first_threshold() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: true,
+            epoch_blocks: Some(100),
+            max_inputs: Some(3),
+            max_payload_bytes: Some(100),
+        });
+        let block = mock::new_block_with_number(0, 10);
+        tracker.enqueue_input(&new_input(10));
+        tracker.enqueue_input(&new_input(10));
+        assert!(!tracker.should_finish_epoch(&block));
+        tracker.enqueue_input(&new_input(10));
+        assert!(tracker.should_finish_epoch(&block));
+    }
+
+    #[test]
+    fn should_finish_epoch_counting_rejected_inputs() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            max_inputs: Some(2),
+            max_payload_bytes: Some(100),
+            ..Default::default()
+        });
+        let block = mock::new_block_with_number(0, 10);
+        tracker.reject_input(&new_input(0));
+        assert!(!tracker.should_finish_epoch(&block));
+        tracker.reject_input(&new_input(0));
+        assert!(tracker.should_finish_epoch(&block));
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_finish_epoch_invalid_block_number() {
+        let tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: Some(5),
+            ..Default::default()
+        });
+        tracker.should_finish_epoch(&mock::new_block_with_number(0, 9));
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // sender_inputs_count
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn sender_inputs_count_resets_on_finish_epoch() {
+        let mut tracker = new_tracker(EpochPolicy::default());
+        let input = new_input(0);
+        let sender = *input.sender;
+        tracker.enqueue_input(&input);
+        tracker.enqueue_input(&input);
+        tracker.enqueue_input(&new_input(0));
+        tracker.reject_input(&new_input(0));
+        assert_eq!(tracker.sender_inputs_count(&sender), 2);
+        tracker.finish_epoch(&mock::new_block_with_number(0, 10));
+        assert_eq!(tracker.sender_inputs_count(&sender), 0);
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // restore
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn restore_counts_only_the_open_epoch() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            max_inputs: Some(4),
+            max_payload_bytes: Some(100),
+            ..Default::default()
+        });
+        let sender = Address::random();
+        let input_events = vec![
+            new_advance_event(sender, 90),
+            new_event(RollupsData::FinishEpoch {}),
+            new_advance_event(sender, 10),
+            new_advance_event(sender, 10),
+            new_rejected_event(),
+        ];
+        tracker.restore(&input_events);
+        assert_eq!(tracker.sender_inputs_count(&sender), 2);
+        let block = mock::new_block_with_number(0, 10);
+        assert!(!tracker.should_finish_epoch(&block));
+        tracker.enqueue_input(&new_input(0));
+        assert!(tracker.should_finish_epoch(&block));
+    }
+
+    #[test]
+    fn restore_block_number_of_the_open_epoch() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: Some(5),
+            ..Default::default()
+        });
+        let sender = Address::random();
+        let input_events = vec![
+            new_advance_event_at(sender, 0, 11),
+            new_event(RollupsData::FinishEpoch {}),
+            new_advance_event_at(sender, 0, 31),
+            new_advance_event_at(sender, 0, 33),
+        ];
+        tracker.restore(&input_events);
+        assert!(
+            !tracker.should_finish_epoch(&mock::new_block_with_number(0, 34))
+        );
+        assert!(
+            tracker.should_finish_epoch(&mock::new_block_with_number(0, 35))
+        );
+    }
+
+    #[test]
+    fn restore_block_number_of_an_empty_open_epoch() {
+        let mut tracker = new_tracker(EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: Some(5),
+            ..Default::default()
+        });
+        let input_events = vec![
+            new_advance_event_at(Address::random(), 0, 11),
+            new_event(RollupsData::FinishEpoch {}),
+        ];
+        tracker.restore(&input_events);
+        assert!(
+            !tracker.should_finish_epoch(&mock::new_block_with_number(0, 31))
+        );
+        tracker.enqueue_input(&new_input_at(0, 31));
+        assert!(
+            !tracker.should_finish_epoch(&mock::new_block_with_number(0, 34))
+        );
+        assert!(
+            tracker.should_finish_epoch(&mock::new_block_with_number(0, 35))
+        );
+    }
+}
@@ -3,25 +3,75 @@
 
 use super::Context;
 
-use crate::machine::{rollups_broker::BrokerFacadeError, BrokerSend};
+use crate::{
+    admission::InputAdmission,
+    machine::{rollups_broker::BrokerFacadeError, BrokerSend},
+};
 
 use eth_state_fold_types::{ethereum_types::Address, Block};
+use rollups_events::{
+    RollupsData, RollupsInput, RollupsReorg, RollupsReorgedInput,
+};
 use types::foldables::input_box::{DAppInputBox, Input, InputBox};
 
-use tracing::{debug, instrument, trace};
+use tracing::{debug, info, instrument, trace, warn};
 
 pub struct MachineDriver {
     dapp_address: Address,
+    admission: InputAdmission,
+
+    /// Where each input sent to the broker was added to the blockchain,
+    /// indexed by input index.
+    sent_inputs: Vec<RollupsReorgedInput>,
 }
 
 impl MachineDriver {
-    pub fn new(dapp_address: Address) -> Self {
-        Self { dapp_address }
+    pub fn new(dapp_address: Address, admission: InputAdmission) -> Self {
+        Self {
+            dapp_address,
+            admission,
+            sent_inputs: Vec::new(),
+        }
+    }
+
+    /// Loads the inputs sent by previous runs from the inputs stream, so a
+    /// reorg that changes them is detected after a restart. The stream
+    /// doesn't record the block hash, so these inputs are compared by
+    /// transaction hash and block number.
+    pub fn load_sent_inputs(&mut self, input_events: &[RollupsInput]) {
+        self.sent_inputs = input_events
+            .iter()
+            .filter_map(|event| {
+                let (metadata, tx_hash) = match &event.data {
+                    RollupsData::AdvanceStateInput(input) => {
+                        (&input.metadata, &input.tx_hash)
+                    }
+                    RollupsData::RejectedInput(input) => {
+                        (&input.metadata, &input.tx_hash)
+                    }
+                    RollupsData::FinishEpoch {} => return None,
+                };
+                Some(RollupsReorgedInput {
+                    tx_hash: tx_hash.clone(),
+                    block_number: metadata.block_number,
+                    block_hash: None,
+                })
+            })
+            .collect();
+        info!(
+            "Loaded {} inputs of dapp {} sent by previous runs",
+            self.sent_inputs.len(),
+            self.dapp_address
+        );
+    }
+
+    pub fn dapp_address(&self) -> Address {
+        self.dapp_address
     }
 
     #[instrument(level = "trace", skip_all)]
     pub async fn react(
-        &self,
+        &mut self,
         context: &mut Context,
         block: &Block,
         input_box: &InputBox,
@@ -38,13 +88,76 @@ impl MachineDriver {
             };
 
         self.process_inputs(context, dapp_input_box, broker).await?;
+        let known_count = self.sent_inputs.len();
+        let sent_count = context.inputs_sent_count() as usize;
+        self.sent_inputs.extend(
+            dapp_input_box
+                .inputs
+                .iter()
+                .skip(known_count)
+                .take(sent_count.saturating_sub(known_count))
+                .map(|input| reorged_input(input)),
+        );
 
-        context
-            .finish_epoch_if_needed(block.timestamp.as_u64(), broker)
-            .await?;
+        context.finish_epoch_if_needed(block, broker).await?;
 
         Ok(())
     }
+
+    /// Compares the inputs already sent to the broker with the inputs of the
+    /// state after a reorg, returning the first one that diverged.
+    /// Inputs are the same if they were added by the same transaction in the
+    /// same block.
+    #[instrument(level = "trace", skip_all)]
+    pub fn find_reorged_input(
+        &self,
+        context: &Context,
+        block: &Block,
+        input_box: &InputBox,
+    ) -> Option<RollupsReorg> {
+        let new_inputs = input_box
+            .dapp_input_boxes
+            .get(&self.dapp_address)
+            .map(|d| d.inputs.clone())
+            .unwrap_or_default();
+
+        let sent_count = context.inputs_sent_count() as usize;
+        if self.sent_inputs.len() < sent_count {
+            warn!(
+                "Only {} of the {} sent inputs are known; comparing those",
+                self.sent_inputs.len(),
+                sent_count
+            );
+        }
+        for (input_index, sent_input) in
+            self.sent_inputs.iter().take(sent_count).enumerate()
+        {
+            let new_input = new_inputs
+                .get(input_index)
+                .map(|input| reorged_input(input));
+            let diverged = match &new_input {
+                Some(new_input) => {
+                    new_input.tx_hash != sent_input.tx_hash
+                        || new_input.block_number != sent_input.block_number
+                        || (sent_input.block_hash.is_some()
+                            && new_input.block_hash != sent_input.block_hash)
+                }
+                None => true,
+            };
+
+            if diverged {
+                return Some(RollupsReorg {
+                    input_index: input_index as u64,
+                    sent_input: sent_input.clone(),
+                    new_input,
+                    block_number: block.number.as_u64(),
+                    block_hash: block.hash.0.into(),
+                });
+            }
+        }
+
+        None
+    }
 }
 
 impl MachineDriver {
@@ -79,29 +192,51 @@ impl MachineDriver {
         input: &Input,
         broker: &impl BrokerSend,
     ) -> Result<(), BrokerFacadeError> {
-        let input_timestamp = input.block_added.timestamp.as_u64();
-        trace!(?context, ?input_timestamp);
-
-        context
-            .finish_epoch_if_needed(input_timestamp, broker)
-            .await?;
-
-        context.enqueue_input(input, broker).await?;
+        let input_block = &input.block_added;
+        trace!(?context, ?input_block);
+
+        context.finish_epoch_if_needed(input_block, broker).await?;
+
+        let sender_inputs_count = context.sender_inputs_count(&input.sender);
+        match self.admission.check(input, sender_inputs_count) {
+            Ok(()) => context.enqueue_input(input, broker).await?,
+            Err(rejection) => {
+                warn!(?input, ?rejection, "rejecting input");
+                context.reject_input(input, rejection, broker).await?
+            }
+        }
 
         Ok(())
     }
 }
 
+fn reorged_input(input: &Input) -> RollupsReorgedInput {
+    RollupsReorgedInput {
+        tx_hash: input.tx_hash.0.into(),
+        block_number: input.block_added.number.as_u64(),
+        block_hash: Some(input.block_added.hash.0.into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use eth_state_fold_types::{ethereum_types::H160, Block};
-    use rollups_events::DAppMetadata;
+    use eth_state_fold_types::{
+        ethereum_types::{H160, H256},
+        Block,
+    };
+    use im::Vector;
+    use rollups_events::{
+        DAppMetadata, InputMetadata, RollupsAdvanceStateInput, RollupsData,
+        RollupsInput,
+    };
     use std::sync::Arc;
+    use types::foldables::input_box::{Input, InputBox};
 
     use crate::{
+        admission::{InputAdmission, InputAdmissionConfig},
         drivers::{
             mock::{self, SendInteraction},
-            Context,
+            Context, EpochPolicy, EpochTracker,
         },
         machine::RollupStatus,
         metrics::DispatcherMetrics,
@@ -122,13 +257,15 @@ mod tests {
         let mut context = Context::new(
             0,
             5,
+            EpochTracker::default(),
             &broker,
             DAppMetadata::default(),
             DispatcherMetrics::default(),
         )
         .await
         .unwrap(); // zero indexed!
-        let machine_driver = MachineDriver::new(H160::random());
+        let machine_driver =
+            MachineDriver::new(H160::random(), InputAdmission::default());
         for block_timestamp in input_timestamps {
             let input = mock::new_input(block_timestamp);
             let result = machine_driver
@@ -218,6 +355,60 @@ mod tests {
             .await;
     }
 
+    #[tokio::test]
+    async fn process_input_with_admission_filters() {
+        let rollup_status = RollupStatus {
+            inputs_sent_count: 0,
+            last_event_is_finish_epoch: false,
+        };
+        let broker = mock::Broker::new(vec![rollup_status], Vec::new());
+        let mut context = Context::new(
+            0,
+            5,
+            EpochTracker::default(),
+            &broker,
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
+        .await
+        .unwrap(); // zero indexed!
+        let admission = InputAdmission::new(InputAdmissionConfig {
+            max_payload_size: Some(4),
+            max_inputs_per_sender: Some(2),
+            ..Default::default()
+        });
+        let machine_driver = MachineDriver::new(H160::random(), admission);
+
+        let spammer = H160::random();
+        let new_input = |sender: H160, payload_len: usize| {
+            let mut input = mock::new_input(1);
+            input.sender = Arc::new(sender);
+            input.payload = vec![0; payload_len];
+            input
+        };
+        let inputs = vec![
+            new_input(spammer, 0),
+            new_input(spammer, 0),
+            new_input(spammer, 0),
+            new_input(H160::random(), 8),
+            new_input(H160::random(), 4),
+        ];
+        for input in inputs {
+            let result = machine_driver
+                .process_input(&mut context, &input, &broker)
+                .await;
+            assert!(result.is_ok());
+        }
+
+        broker.assert_send_interactions(vec![
+            SendInteraction::EnqueuedInput(0),
+            SendInteraction::EnqueuedInput(1),
+            SendInteraction::RejectedInput(2),
+            SendInteraction::RejectedInput(3),
+            SendInteraction::EnqueuedInput(4),
+        ]);
+    }
+
     // --------------------------------------------------------------------------------------------
     // process_inputs
     // --------------------------------------------------------------------------------------------
@@ -231,13 +422,15 @@ mod tests {
         let mut context = Context::new(
             0,
             5,
+            EpochTracker::default(),
             &broker,
             DAppMetadata::default(),
             DispatcherMetrics::default(),
         )
         .await
         .unwrap(); // zero indexed!
-        let machine_driver = MachineDriver::new(H160::random());
+        let machine_driver =
+            MachineDriver::new(H160::random(), InputAdmission::default());
         let dapp_input_box = types::foldables::input_box::DAppInputBox {
             inputs: input_timestamps
                 .iter()
@@ -308,6 +501,7 @@ mod tests {
         let mut context = Context::new(
             0,
             5,
+            EpochTracker::default(),
             &broker,
             DAppMetadata::default(),
             DispatcherMetrics::default(),
@@ -316,7 +510,8 @@ mod tests {
         .unwrap(); // zero indexed!
 
         let dapp_address = H160::random();
-        let machine_driver = MachineDriver::new(dapp_address);
+        let mut machine_driver =
+            MachineDriver::new(dapp_address, InputAdmission::default());
 
         let input_box = mock::new_input_box();
         let input_box =
@@ -390,6 +585,7 @@ mod tests {
         let mut context = Context::new(
             0,
             5,
+            EpochTracker::default(),
             &broker,
             DAppMetadata::default(),
             DispatcherMetrics::default(),
@@ -398,7 +594,8 @@ mod tests {
         .unwrap(); // zero indexed!
         let block = mock::new_block(5);
         let input_box = mock::new_input_box();
-        let machine_driver = MachineDriver::new(H160::random());
+        let mut machine_driver =
+            MachineDriver::new(H160::random(), InputAdmission::default());
         let result = machine_driver
             .react(&mut context, &block, &input_box, &broker)
             .await;
@@ -422,4 +619,385 @@ mod tests {
         test_react(block, rollup_status, input_timestamps, send_interactions)
             .await;
     }
+
+    // --------------------------------------------------------------------------------------------
+    // react with epoch policies
+    // --------------------------------------------------------------------------------------------
+
+    async fn test_react_with_epoch_policy(
+        epoch_policy: EpochPolicy,
+        block: Block,
+        inputs: Vec<Input>,
+        expected: Vec<SendInteraction>,
+    ) {
+        let rollup_status = RollupStatus {
+            inputs_sent_count: 0,
+            last_event_is_finish_epoch: false,
+        };
+        let broker = mock::Broker::new(vec![rollup_status], Vec::new());
+        let mut context = Context::new(
+            0,
+            5,
+            EpochTracker::new(epoch_policy, 0),
+            &broker,
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
+        .await
+        .unwrap(); // zero indexed!
+
+        let dapp_address = H160::random();
+        let mut machine_driver =
+            MachineDriver::new(dapp_address, InputAdmission::default());
+
+        let inputs = inputs.into_iter().map(Arc::new).collect();
+        let input_box = mock::update_input_box_with_inputs(
+            mock::new_input_box(),
+            dapp_address,
+            inputs,
+        );
+
+        let result = machine_driver
+            .react(&mut context, &block, &input_box, &broker)
+            .await;
+        assert!(result.is_ok());
+
+        broker.assert_send_interactions(expected);
+    }
+
+    fn new_input_with(number: u64, payload_len: usize) -> Input {
+        let mut input = mock::new_input(0);
+        input.block_added = Arc::new(mock::new_block_with_number(0, number));
+        input.payload = vec![0; payload_len];
+        input
+    }
+
+    #[tokio::test]
+    async fn react_with_block_number_policy() {
+        let epoch_policy = EpochPolicy {
+            by_timestamp: false,
+            epoch_blocks: Some(10),
+            ..Default::default()
+        };
+        let block = mock::new_block_with_number(100, 25);
+        let inputs = vec![
+            new_input_with(3, 0),
+            new_input_with(9, 0),
+            new_input_with(10, 0),
+            new_input_with(15, 0),
+        ];
+        let send_interactions = vec![
+            SendInteraction::EnqueuedInput(0),
+            SendInteraction::EnqueuedInput(1),
+            SendInteraction::FinishedEpoch(2),
+            SendInteraction::EnqueuedInput(2),
+            SendInteraction::EnqueuedInput(3),
+            SendInteraction::FinishedEpoch(4),
+        ];
+        test_react_with_epoch_policy(
+            epoch_policy,
+            block,
+            inputs,
+            send_interactions,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn react_with_input_count_policy() {
+        let epoch_policy = EpochPolicy {
+            by_timestamp: false,
+            max_inputs: Some(2),
+            ..Default::default()
+        };
+        let block = mock::new_block_with_number(100, 0);
+        let inputs = (0..5).map(|_| new_input_with(0, 0)).collect();
+        let send_interactions = vec![
+            SendInteraction::EnqueuedInput(0),
+            SendInteraction::EnqueuedInput(1),
+            SendInteraction::FinishedEpoch(2),
+            SendInteraction::EnqueuedInput(2),
+            SendInteraction::EnqueuedInput(3),
+            SendInteraction::FinishedEpoch(4),
+            SendInteraction::EnqueuedInput(4),
+        ];
+        test_react_with_epoch_policy(
+            epoch_policy,
+            block,
+            inputs,
+            send_interactions,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn react_with_payload_bytes_policy() {
+        let epoch_policy = EpochPolicy {
+            by_timestamp: false,
+            max_payload_bytes: Some(100),
+            ..Default::default()
+        };
+        let block = mock::new_block_with_number(100, 0);
+        let inputs = vec![
+            new_input_with(0, 50),
+            new_input_with(0, 30),
+            new_input_with(0, 30),
+            new_input_with(0, 10),
+        ];
+        let send_interactions = vec![
+            SendInteraction::EnqueuedInput(0),
+            SendInteraction::EnqueuedInput(1),
+            SendInteraction::EnqueuedInput(2),
+            SendInteraction::FinishedEpoch(3),
+            SendInteraction::EnqueuedInput(3),
+        ];
+        test_react_with_epoch_policy(
+            epoch_policy,
+            block,
+            inputs,
+            send_interactions,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn react_with_first_of_policy() {
+        let epoch_policy = EpochPolicy {
+            by_timestamp: true,
+            epoch_blocks: None,
+            max_inputs: Some(3),
+            max_payload_bytes: None,
+        };
+        let block = mock::new_block_with_number(7, 0);
+        let mut inputs: Vec<_> = (0..4).map(|_| new_input_with(0, 0)).collect();
+        inputs.push(mock::new_input(6));
+        let send_interactions = vec![
+            SendInteraction::EnqueuedInput(0),
+            SendInteraction::EnqueuedInput(1),
+            SendInteraction::EnqueuedInput(2),
+            SendInteraction::FinishedEpoch(3),
+            SendInteraction::EnqueuedInput(3),
+            SendInteraction::FinishedEpoch(4),
+            SendInteraction::EnqueuedInput(4),
+        ];
+        test_react_with_epoch_policy(
+            epoch_policy,
+            block,
+            inputs,
+            send_interactions,
+        )
+        .await;
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // find_reorged_input
+    // --------------------------------------------------------------------------------------------
+
+    async fn setup_find_reorged_input(
+        input_timestamps: Vec<u32>,
+    ) -> (MachineDriver, Context, InputBox, H160) {
+        let rollup_status = RollupStatus {
+            inputs_sent_count: 0,
+            last_event_is_finish_epoch: false,
+        };
+        let broker = mock::Broker::new(vec![rollup_status], Vec::new());
+        let mut context = Context::new(
+            0,
+            5,
+            EpochTracker::default(),
+            &broker,
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
+        .await
+        .unwrap(); // zero indexed!
+
+        let dapp_address = H160::random();
+        let mut machine_driver =
+            MachineDriver::new(dapp_address, InputAdmission::default());
+
+        let input_box = mock::new_input_box();
+        let input_box =
+            mock::update_input_box(input_box, dapp_address, input_timestamps);
+        let block = mock::new_block(3);
+        machine_driver
+            .react(&mut context, &block, &input_box, &broker)
+            .await
+            .unwrap();
+
+        (machine_driver, context, input_box, dapp_address)
+    }
+
+    fn dapp_inputs(
+        input_box: &InputBox,
+        dapp_address: H160,
+    ) -> Vector<Arc<Input>> {
+        input_box
+            .dapp_input_boxes
+            .get(&dapp_address)
+            .unwrap()
+            .inputs
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn find_reorged_input_without_changes() {
+        let (machine_driver, context, input_box, _) =
+            setup_find_reorged_input(vec![1, 2, 3]).await;
+        let block = mock::new_block(4);
+        let reorg =
+            machine_driver.find_reorged_input(&context, &block, &input_box);
+        assert_eq!(reorg, None);
+    }
+
+    #[tokio::test]
+    async fn find_reorged_input_with_new_inputs() {
+        let (machine_driver, context, input_box, dapp_address) =
+            setup_find_reorged_input(vec![1, 2, 3]).await;
+        let mut inputs = dapp_inputs(&input_box, dapp_address);
+        inputs.push_back(Arc::new(mock::new_input(4)));
+        let input_box =
+            mock::update_input_box_with_inputs(input_box, dapp_address, inputs);
+        let block = mock::new_block(4);
+        let reorg =
+            machine_driver.find_reorged_input(&context, &block, &input_box);
+        assert_eq!(reorg, None);
+    }
+
+    #[tokio::test]
+    async fn find_reorged_input_with_changed_block() {
+        let (machine_driver, context, input_box, dapp_address) =
+            setup_find_reorged_input(vec![1, 2, 3]).await;
+        let sent_inputs = dapp_inputs(&input_box, dapp_address);
+        let mut inputs = sent_inputs.clone();
+        inputs.set(1, Arc::new(mock::new_input(2)));
+        let input_box =
+            mock::update_input_box_with_inputs(input_box, dapp_address, inputs);
+        let block = mock::new_block(4);
+        let reorg = machine_driver
+            .find_reorged_input(&context, &block, &input_box)
+            .expect("reorg not found");
+        assert_eq!(reorg.input_index, 1);
+        assert_eq!(
+            reorg.sent_input.block_hash,
+            Some(sent_inputs[1].block_added.hash.0.into())
+        );
+        assert!(reorg.new_input.is_some());
+        assert_eq!(reorg.block_hash, block.hash.0.into());
+    }
+
+    #[tokio::test]
+    async fn find_reorged_input_with_removed_input() {
+        let (machine_driver, context, input_box, dapp_address) =
+            setup_find_reorged_input(vec![1, 2, 3]).await;
+        let mut inputs = dapp_inputs(&input_box, dapp_address);
+        inputs.truncate(2);
+        let input_box =
+            mock::update_input_box_with_inputs(input_box, dapp_address, inputs);
+        let block = mock::new_block(4);
+        let reorg = machine_driver
+            .find_reorged_input(&context, &block, &input_box)
+            .expect("reorg not found");
+        assert_eq!(reorg.input_index, 2);
+        assert_eq!(reorg.new_input, None);
+    }
+
+    async fn setup_find_reorged_input_after_restart(
+        inputs: &Vector<Arc<Input>>,
+    ) -> (MachineDriver, Context) {
+        let rollup_status = RollupStatus {
+            inputs_sent_count: inputs.len() as u64,
+            last_event_is_finish_epoch: false,
+        };
+        let input_events: Vec<_> = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| RollupsInput {
+                parent_id: String::new(),
+                epoch_index: 0,
+                inputs_sent_count: i as u64 + 1,
+                data: RollupsData::AdvanceStateInput(
+                    RollupsAdvanceStateInput {
+                        metadata: InputMetadata {
+                            block_number: input.block_added.number.as_u64(),
+                            input_index: i as u64,
+                            ..Default::default()
+                        },
+                        tx_hash: input.tx_hash.0.into(),
+                        ..Default::default()
+                    },
+                ),
+            })
+            .collect();
+        let broker = mock::Broker::new(vec![rollup_status], Vec::new());
+        let context = Context::new(
+            0,
+            5,
+            EpochTracker::default(),
+            &broker,
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
+        .await
+        .unwrap();
+        let mut machine_driver =
+            MachineDriver::new(H160::random(), InputAdmission::default());
+        machine_driver.load_sent_inputs(&input_events);
+        (machine_driver, context)
+    }
+
+    fn new_input_with_tx_hash(tx_hash: u64) -> Arc<Input> {
+        Arc::new(Input {
+            tx_hash: Arc::new(H256::from_low_u64_be(tx_hash)),
+            ..mock::new_input(1)
+        })
+    }
+
+    #[tokio::test]
+    async fn find_reorged_input_after_restart() {
+        let inputs: Vector<_> = (0..3).map(new_input_with_tx_hash).collect();
+        let (machine_driver, context) =
+            setup_find_reorged_input_after_restart(&inputs).await;
+        let dapp_address = machine_driver.dapp_address();
+        let block = mock::new_block(4);
+
+        let input_box = mock::update_input_box_with_inputs(
+            mock::new_input_box(),
+            dapp_address,
+            inputs.clone(),
+        );
+        let reorg =
+            machine_driver.find_reorged_input(&context, &block, &input_box);
+        assert_eq!(reorg, None);
+
+        let mut new_inputs = inputs.clone();
+        new_inputs.set(1, new_input_with_tx_hash(10));
+        let input_box = mock::update_input_box_with_inputs(
+            mock::new_input_box(),
+            dapp_address,
+            new_inputs,
+        );
+        let reorg = machine_driver
+            .find_reorged_input(&context, &block, &input_box)
+            .expect("reorg not found");
+        assert_eq!(reorg.input_index, 1);
+        assert_eq!(reorg.sent_input.tx_hash, inputs[1].tx_hash.0.into());
+        assert_eq!(reorg.sent_input.block_hash, None);
+        assert_eq!(
+            reorg.new_input.map(|input| input.tx_hash),
+            Some(H256::from_low_u64_be(10).0.into())
+        );
+    }
+
+    #[tokio::test]
+    async fn find_reorged_input_without_dapp_inputs() {
+        let (machine_driver, context, _, _) =
+            setup_find_reorged_input(vec![1]).await;
+        let block = mock::new_block(4);
+        let reorg = machine_driver
+            .find_reorged_input(&context, &block, &mock::new_input_box())
+            .expect("reorg not found");
+        assert_eq!(reorg.input_index, 0);
+        assert_eq!(reorg.new_input, None);
+    }
 }
@@ -3,11 +3,15 @@
 
 use async_trait::async_trait;
 use eth_state_fold_types::{
-    ethereum_types::{Address, Bloom, H160, H256},
+    ethereum_types::{Address, Bloom, H160, H256, U256},
     Block,
 };
-use im::{hashmap, Vector};
-use rollups_events::RollupsClaim;
+use eth_tx_manager::Priority;
+use im::{hashmap, HashMap, Vector};
+use rollups_events::{
+    RollupsClaim, RollupsData, RollupsInput, RollupsInputRejection,
+    RollupsReorg, RollupsVoucherExecution,
+};
 use snafu::whatever;
 use std::{
     collections::VecDeque,
@@ -17,6 +21,7 @@ use std::{
 use types::foldables::{
     claims::{Claim, DAppClaims, History},
     input_box::{DAppInputBox, Input, InputBox},
+    vouchers::{VoucherExecuted, VoucherExecutions},
 };
 
 use crate::{
@@ -74,9 +79,13 @@ pub fn update_history(
 }
 
 pub fn new_block(timestamp: u32) -> Block {
+    new_block_with_number(timestamp, 0)
+}
+
+pub fn new_block_with_number(timestamp: u32, number: u64) -> Block {
     Block {
         hash: H256::random(),
-        number: 0.into(),
+        number: number.into(),
         parent_hash: H256::random(),
         timestamp: timestamp.into(),
         logs_bloom: Bloom::default(),
@@ -109,7 +118,14 @@ pub fn update_input_box(
         .iter()
         .map(|timestamp| Arc::new(new_input(*timestamp)))
         .collect::<Vec<_>>();
-    let inputs = Vector::from(inputs);
+    update_input_box_with_inputs(input_box, dapp_address, inputs.into())
+}
+
+pub fn update_input_box_with_inputs(
+    input_box: InputBox,
+    dapp_address: Address,
+    inputs: Vector<Arc<Input>>,
+) -> InputBox {
     let dapp_input_boxes = input_box
         .dapp_input_boxes
         .update(Arc::new(dapp_address), Arc::new(DAppInputBox { inputs }));
@@ -119,6 +135,29 @@ pub fn update_input_box(
     }
 }
 
+pub fn new_voucher_executions(
+    dapp_address: Address,
+    n: usize,
+) -> HashMap<Arc<Address>, Arc<VoucherExecutions>> {
+    let dapp_address = Arc::new(dapp_address);
+    let executions = (0..n)
+        .map(|i| {
+            Arc::new(VoucherExecuted {
+                input_index: i,
+                output_index: 0,
+                tx_hash: Arc::new(H256::random()),
+                block_number: i as u64,
+            })
+        })
+        .collect();
+    hashmap! {
+        dapp_address.clone() => Arc::new(VoucherExecutions {
+            dapp_address,
+            executions,
+        })
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // Broker
 // ------------------------------------------------------------------------------------------------
@@ -126,7 +165,10 @@ pub fn update_input_box(
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SendInteraction {
     EnqueuedInput(u64),
+    RejectedInput(u64),
     FinishedEpoch(u64),
+    ReportedReorg(u64),
+    EnqueuedVoucherExecution(u64),
 }
 
 #[derive(Debug)]
@@ -134,6 +176,9 @@ pub struct Broker {
     pub rollup_statuses: Mutex<VecDeque<RollupStatus>>,
     pub next_claims: Mutex<VecDeque<RollupsClaim>>,
     pub send_interactions: Mutex<Vec<SendInteraction>>,
+    input_events: Vec<RollupsInput>,
+    voucher_execution_events: Vec<RollupsVoucherExecution>,
+    voucher_executions_sent_count: u64,
     status_error: bool,
     enqueue_input_error: bool,
     finish_epoch_error: bool,
@@ -145,6 +190,9 @@ impl Broker {
             rollup_statuses: Mutex::new(VecDeque::new()),
             next_claims: Mutex::new(VecDeque::new()),
             send_interactions: Mutex::new(Vec::new()),
+            input_events: Vec::new(),
+            voucher_execution_events: Vec::new(),
+            voucher_executions_sent_count: 0,
             status_error: false,
             enqueue_input_error: false,
             finish_epoch_error: false,
@@ -161,6 +209,42 @@ impl Broker {
         broker
     }
 
+    /// The rollup status is derived from the last input event.
+    pub fn with_input_events(input_events: Vec<RollupsInput>) -> Self {
+        let rollup_status = RollupStatus {
+            inputs_sent_count: input_events
+                .last()
+                .map_or(0, |event| event.inputs_sent_count),
+            last_event_is_finish_epoch: matches!(
+                input_events.last(),
+                Some(RollupsInput {
+                    data: RollupsData::FinishEpoch {},
+                    ..
+                })
+            ),
+        };
+        let mut broker = Self::new(vec![rollup_status], Vec::new());
+        broker.input_events = input_events;
+        broker
+    }
+
+    pub fn with_voucher_executions_sent_count(count: u64) -> Self {
+        let mut broker = Self::default();
+        broker.voucher_executions_sent_count = count;
+        broker
+    }
+
+    /// The sent count is derived from the last voucher execution event.
+    pub fn with_voucher_execution_events(
+        events: Vec<RollupsVoucherExecution>,
+    ) -> Self {
+        let mut broker = Self::default();
+        broker.voucher_executions_sent_count =
+            events.last().map_or(0, |event| event.executions_sent_count);
+        broker.voucher_execution_events = events;
+        broker
+    }
+
     pub fn with_status_error() -> Self {
         let mut broker = Self::default();
         broker.status_error = true;
@@ -215,6 +299,36 @@ impl BrokerStatus for Broker {
             Ok(mutex_guard.deref_mut().pop_front().unwrap())
         }
     }
+
+    async fn voucher_executions_sent_count(
+        &self,
+    ) -> Result<u64, BrokerFacadeError> {
+        if self.status_error {
+            whatever!("status error")
+        } else {
+            Ok(self.voucher_executions_sent_count)
+        }
+    }
+
+    async fn input_events(
+        &self,
+    ) -> Result<Vec<RollupsInput>, BrokerFacadeError> {
+        if self.status_error {
+            whatever!("status error")
+        } else {
+            Ok(self.input_events.clone())
+        }
+    }
+
+    async fn voucher_execution_events(
+        &self,
+    ) -> Result<Vec<RollupsVoucherExecution>, BrokerFacadeError> {
+        if self.status_error {
+            whatever!("status error")
+        } else {
+            Ok(self.voucher_execution_events.clone())
+        }
+    }
 }
 
 #[async_trait]
@@ -245,6 +359,23 @@ impl BrokerSend for Broker {
         }
     }
 
+    async fn enqueue_rejected_input(
+        &self,
+        input_index: u64,
+        _: &Input,
+        _: RollupsInputRejection,
+    ) -> Result<(), BrokerFacadeError> {
+        if self.enqueue_input_error {
+            whatever!("enqueue_rejected_input error")
+        } else {
+            let mut mutex_guard = self.send_interactions.lock().unwrap();
+            mutex_guard
+                .deref_mut()
+                .push(SendInteraction::RejectedInput(input_index));
+            Ok(())
+        }
+    }
+
     async fn finish_epoch(
         &self,
         inputs_sent_count: u64,
@@ -259,6 +390,29 @@ impl BrokerSend for Broker {
             Ok(())
         }
     }
+
+    async fn report_reorg(
+        &self,
+        reorg: RollupsReorg,
+    ) -> Result<(), BrokerFacadeError> {
+        let mut mutex_guard = self.send_interactions.lock().unwrap();
+        mutex_guard
+            .deref_mut()
+            .push(SendInteraction::ReportedReorg(reorg.input_index));
+        Ok(())
+    }
+
+    async fn enqueue_voucher_execution(
+        &self,
+        executions_sent_count: u64,
+        _: &VoucherExecuted,
+    ) -> Result<(), BrokerFacadeError> {
+        let mut mutex_guard = self.send_interactions.lock().unwrap();
+        mutex_guard.deref_mut().push(
+            SendInteraction::EnqueuedVoucherExecution(executions_sent_count),
+        );
+        Ok(())
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -267,13 +421,19 @@ impl BrokerSend for Broker {
 
 #[derive(Debug)]
 pub struct Sender {
-    pub sent_rollups_claims: Mutex<Vec<(Address, RollupsClaim)>>,
+    pub sent_rollups_claims: Mutex<Vec<(Address, RollupsClaim, Priority)>>,
+    max_fee_per_gas: U256,
 }
 
 impl Sender {
     pub fn new() -> Self {
+        Self::with_max_fee_per_gas(U256::zero())
+    }
+
+    pub fn with_max_fee_per_gas(max_fee_per_gas: U256) -> Self {
         Self {
             sent_rollups_claims: Mutex::new(vec![]),
+            max_fee_per_gas,
         }
     }
 
@@ -288,10 +448,17 @@ impl crate::sender::Sender for Sender {
         self,
         dapp_address: Address,
         rollups_claim: RollupsClaim,
+        priority: Priority,
     ) -> Result<Self, SenderError> {
         let mut mutex_guard = self.sent_rollups_claims.lock().unwrap();
-        mutex_guard.deref_mut().push((dapp_address, rollups_claim));
+        mutex_guard
+            .deref_mut()
+            .push((dapp_address, rollups_claim, priority));
         drop(mutex_guard);
         Ok(self)
     }
+
+    async fn max_fee_per_gas(&self) -> Result<U256, SenderError> {
+        Ok(self.max_fee_per_gas)
+    }
 }
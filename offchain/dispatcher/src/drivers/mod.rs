@@ -3,11 +3,15 @@
 
 pub mod blockchain;
 pub mod context;
+pub mod epoch;
 pub mod machine;
+pub mod vouchers;
 
 pub use blockchain::BlockchainDriver;
 pub use context::Context;
+pub use epoch::{EpochPolicy, EpochTracker};
 pub use machine::MachineDriver;
+pub use vouchers::VoucherDriver;
 
 #[cfg(test)]
-mod mock;
+pub(crate) mod mock;
@@ -0,0 +1,376 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use crate::{
+    machine::{rollups_broker::BrokerFacadeError, BrokerSend, BrokerStatus},
+    metrics::DispatcherMetrics,
+};
+
+use eth_state_fold_types::ethereum_types::Address;
+use im::HashMap;
+use rollups_events::{DAppMetadata, Hash, RollupsVoucherExecution};
+use std::sync::Arc;
+use tracing::{info, instrument, trace, warn};
+use types::foldables::vouchers::{VoucherExecuted, VoucherExecutions};
+
+/// Voucher execution sent to the broker. Executions are the same if they
+/// executed the same voucher in the same transaction and block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentVoucherExecution {
+    pub input_index: u64,
+    pub index: u64,
+    pub tx_hash: Hash,
+    pub block_number: u64,
+}
+
+impl From<&VoucherExecuted> for SentVoucherExecution {
+    fn from(execution: &VoucherExecuted) -> Self {
+        Self {
+            input_index: execution.input_index as u64,
+            index: execution.output_index as u64,
+            tx_hash: execution.tx_hash.0.into(),
+            block_number: execution.block_number,
+        }
+    }
+}
+
+impl From<&RollupsVoucherExecution> for SentVoucherExecution {
+    fn from(event: &RollupsVoucherExecution) -> Self {
+        Self {
+            input_index: event.input_index,
+            index: event.index,
+            tx_hash: event.tx_hash.clone(),
+            block_number: event.block_number,
+        }
+    }
+}
+
+/// Voucher execution already sent to the broker that a reorg removed or
+/// replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReorgedVoucherExecution {
+    /// Position of the execution among the executions of the DApp
+    pub position: u64,
+    pub sent_execution: SentVoucherExecution,
+    pub new_execution: Option<SentVoucherExecution>,
+}
+
+/// Forwards the voucher executions of the DApp contract to the broker, so
+/// the indexer can mark the executed vouchers.
+#[derive(Debug)]
+pub struct VoucherDriver {
+    dapp_address: Address,
+    executions_sent_count: u64,
+    /// Executions sent to the broker by their position, kept to detect the
+    /// reorgs that change them
+    sent_executions: Vec<(u64, SentVoucherExecution)>,
+    dapp_metadata: DAppMetadata,
+    metrics: DispatcherMetrics,
+}
+
+impl VoucherDriver {
+    pub async fn new(
+        dapp_address: Address,
+        broker: &impl BrokerStatus,
+        dapp_metadata: DAppMetadata,
+        metrics: DispatcherMetrics,
+    ) -> Result<Self, BrokerFacadeError> {
+        let executions_sent_count =
+            broker.voucher_executions_sent_count().await?;
+        let sent_executions: Vec<_> = broker
+            .voucher_execution_events()
+            .await?
+            .iter()
+            .map(|event| {
+                (
+                    event.executions_sent_count - 1,
+                    SentVoucherExecution::from(event),
+                )
+            })
+            .collect();
+        info!(
+            "Loaded {} voucher executions of dapp {} sent by previous runs",
+            sent_executions.len(),
+            dapp_address
+        );
+
+        Ok(Self {
+            dapp_address,
+            executions_sent_count,
+            sent_executions,
+            dapp_metadata,
+            metrics,
+        })
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    pub async fn react(
+        &mut self,
+        voucher_executions: &HashMap<Arc<Address>, Arc<VoucherExecutions>>,
+        broker: &impl BrokerSend,
+    ) -> Result<(), BrokerFacadeError> {
+        let executions = match voucher_executions.get(&self.dapp_address) {
+            Some(executions) => &executions.executions,
+            None => {
+                trace!("No voucher executions for dapp");
+                return Ok(());
+            }
+        };
+
+        let executions_sent_count = self.executions_sent_count as usize;
+        trace!(
+            "Last voucher execution sent: {}, len: {}",
+            executions_sent_count,
+            executions.len()
+        );
+
+        for execution in executions.iter().skip(executions_sent_count) {
+            info!("Sending voucher execution `{:?}`", execution);
+            broker
+                .enqueue_voucher_execution(
+                    self.executions_sent_count,
+                    execution,
+                )
+                .await?;
+            self.metrics
+                .voucher_executions_sent
+                .get_or_create(&self.dapp_metadata)
+                .inc();
+            self.sent_executions.push((
+                self.executions_sent_count,
+                SentVoucherExecution::from(execution.as_ref()),
+            ));
+            self.executions_sent_count += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Compares the voucher executions already sent to the broker with the
+    /// executions of the state after a reorg, returning the first one that
+    /// diverged.
+    #[instrument(level = "trace", skip_all)]
+    pub fn find_reorged_execution(
+        &self,
+        voucher_executions: &HashMap<Arc<Address>, Arc<VoucherExecutions>>,
+    ) -> Option<ReorgedVoucherExecution> {
+        let new_executions = voucher_executions
+            .get(&self.dapp_address)
+            .map(|executions| executions.executions.clone())
+            .unwrap_or_default();
+
+        if (self.sent_executions.len() as u64) < self.executions_sent_count {
+            warn!(
+                "Only {} of the {} sent voucher executions are known; comparing those",
+                self.sent_executions.len(),
+                self.executions_sent_count
+            );
+        }
+        for (position, sent_execution) in &self.sent_executions {
+            let new_execution =
+                new_executions.get(*position as usize).map(|execution| {
+                    SentVoucherExecution::from(execution.as_ref())
+                });
+            if new_execution.as_ref() != Some(sent_execution) {
+                return Some(ReorgedVoucherExecution {
+                    position: *position,
+                    sent_execution: sent_execution.clone(),
+                    new_execution,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_state_fold_types::ethereum_types::{H160, H256};
+    use im::{hashmap, HashMap};
+    use rollups_events::{DAppMetadata, RollupsVoucherExecution};
+    use std::sync::Arc;
+    use types::foldables::vouchers::{VoucherExecuted, VoucherExecutions};
+
+    use crate::{
+        drivers::mock::{self, SendInteraction},
+        metrics::DispatcherMetrics,
+    };
+
+    use super::{SentVoucherExecution, VoucherDriver};
+
+    async fn new_driver(
+        dapp_address: H160,
+        broker: &mock::Broker,
+    ) -> VoucherDriver {
+        VoucherDriver::new(
+            dapp_address,
+            broker,
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn react_without_executions() {
+        let dapp_address = H160::random();
+        let broker = mock::Broker::with_voucher_executions_sent_count(0);
+        let mut driver = new_driver(dapp_address, &broker).await;
+        driver.react(&hashmap! {}, &broker).await.unwrap();
+        broker.assert_send_interactions(vec![]);
+    }
+
+    #[tokio::test]
+    async fn react_sends_new_executions() {
+        let dapp_address = H160::random();
+        let broker = mock::Broker::with_voucher_executions_sent_count(1);
+        let mut driver = new_driver(dapp_address, &broker).await;
+        let voucher_executions = mock::new_voucher_executions(dapp_address, 3);
+        driver.react(&voucher_executions, &broker).await.unwrap();
+        driver.react(&voucher_executions, &broker).await.unwrap();
+        broker.assert_send_interactions(vec![
+            SendInteraction::EnqueuedVoucherExecution(1),
+            SendInteraction::EnqueuedVoucherExecution(2),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn react_ignores_other_dapps() {
+        let broker = mock::Broker::with_voucher_executions_sent_count(0);
+        let mut driver = new_driver(H160::random(), &broker).await;
+        let voucher_executions =
+            mock::new_voucher_executions(H160::random(), 2);
+        driver.react(&voucher_executions, &broker).await.unwrap();
+        broker.assert_send_interactions(vec![]);
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // find_reorged_execution
+    // --------------------------------------------------------------------------------------------
+
+    fn sent_events(
+        voucher_executions: &HashMap<Arc<H160>, Arc<VoucherExecutions>>,
+        dapp_address: H160,
+    ) -> Vec<RollupsVoucherExecution> {
+        voucher_executions[&dapp_address]
+            .executions
+            .iter()
+            .enumerate()
+            .map(|(i, execution)| RollupsVoucherExecution {
+                executions_sent_count: i as u64 + 1,
+                input_index: execution.input_index as u64,
+                index: execution.output_index as u64,
+                tx_hash: execution.tx_hash.0.into(),
+                block_number: execution.block_number,
+            })
+            .collect()
+    }
+
+    fn with_executions(
+        dapp_address: H160,
+        executions: Vec<VoucherExecuted>,
+    ) -> HashMap<Arc<H160>, Arc<VoucherExecutions>> {
+        let dapp_address = Arc::new(dapp_address);
+        hashmap! {
+            dapp_address.clone() => Arc::new(VoucherExecutions {
+                dapp_address,
+                executions: executions.into_iter().map(Arc::new).collect(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn find_reorged_execution_without_changes() {
+        let dapp_address = H160::random();
+        let voucher_executions = mock::new_voucher_executions(dapp_address, 2);
+        let broker = mock::Broker::with_voucher_execution_events(sent_events(
+            &voucher_executions,
+            dapp_address,
+        ));
+        let mut driver = new_driver(dapp_address, &broker).await;
+        assert_eq!(driver.find_reorged_execution(&voucher_executions), None);
+
+        let mut executions: Vec<_> = voucher_executions[&dapp_address]
+            .executions
+            .iter()
+            .map(|execution| (**execution).clone())
+            .collect();
+        executions.push(VoucherExecuted {
+            input_index: 5,
+            output_index: 0,
+            tx_hash: Arc::new(H256::random()),
+            block_number: 5,
+        });
+        let voucher_executions = with_executions(dapp_address, executions);
+        driver.react(&voucher_executions, &broker).await.unwrap();
+        broker.assert_send_interactions(vec![
+            SendInteraction::EnqueuedVoucherExecution(2),
+        ]);
+        assert_eq!(driver.find_reorged_execution(&voucher_executions), None);
+    }
+
+    #[tokio::test]
+    async fn find_reorged_execution_sent_by_previous_run() {
+        let dapp_address = H160::random();
+        let voucher_executions = mock::new_voucher_executions(dapp_address, 2);
+        let sent_events = sent_events(&voucher_executions, dapp_address);
+        let broker =
+            mock::Broker::with_voucher_execution_events(sent_events.clone());
+        let driver = new_driver(dapp_address, &broker).await;
+
+        let reorged_executions = mock::new_voucher_executions(dapp_address, 1);
+        let reorg = driver
+            .find_reorged_execution(&reorged_executions)
+            .expect("should find reorged execution");
+        assert_eq!(reorg.position, 0);
+        assert_eq!(
+            reorg.sent_execution,
+            SentVoucherExecution::from(&sent_events[0])
+        );
+        assert!(reorg.new_execution.is_some());
+    }
+
+    #[tokio::test]
+    async fn find_reorged_execution_removed_by_reorg() {
+        let dapp_address = H160::random();
+        let broker = mock::Broker::with_voucher_executions_sent_count(0);
+        let mut driver = new_driver(dapp_address, &broker).await;
+        let execution = VoucherExecuted {
+            input_index: 1,
+            output_index: 2,
+            tx_hash: Arc::new(H256::random()),
+            block_number: 10,
+        };
+        let voucher_executions =
+            with_executions(dapp_address, vec![execution.clone()]);
+        driver.react(&voucher_executions, &broker).await.unwrap();
+
+        let reorg = driver
+            .find_reorged_execution(&with_executions(dapp_address, vec![]))
+            .expect("should find reorged execution");
+        assert_eq!(reorg.position, 0);
+        assert_eq!(
+            reorg.sent_execution,
+            SentVoucherExecution::from(&execution)
+        );
+        assert_eq!(reorg.new_execution, None);
+
+        // Executed again in another block
+        let reexecuted = VoucherExecuted {
+            block_number: 11,
+            ..execution
+        };
+        let reorg = driver
+            .find_reorged_execution(&with_executions(
+                dapp_address,
+                vec![reexecuted.clone()],
+            ))
+            .expect("should find reorged execution");
+        assert_eq!(
+            reorg.new_execution,
+            Some(SentVoucherExecution::from(&reexecuted))
+        );
+    }
+}
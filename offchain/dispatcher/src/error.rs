@@ -7,7 +7,7 @@ use snafu::Snafu;
 use std::net::AddrParseError;
 use tonic::transport::Error as TonicError;
 
-use crate::{machine, sender};
+use crate::{admin, backfill, machine, sender};
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub(crate)))]
@@ -32,6 +32,12 @@ pub enum DispatcherError {
     #[snafu(display("state server error"))]
     StateServerError { source: StateServerError },
 
+    #[snafu(display("backfill error"))]
+    BackfillError { source: backfill::BackfillError },
+
+    #[snafu(display("admin error"))]
+    AdminError { source: admin::AdminError },
+
     #[snafu(display("sender error"))]
     SenderError { source: sender::SenderError },
 
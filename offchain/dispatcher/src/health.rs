@@ -0,0 +1,232 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! This module exposes the `ChainHealth`, which reports through metrics how
+//! far the dispatcher is behind the chain and how the state-server behaves.
+//!
+//! The chain is shared by every DApp driven by the dispatcher, so the metrics
+//! are reported under the labels of each one of them.
+
+use eth_state_fold_types::Block;
+use http_server::{CounterRef, FamilyRef, GaugeRef};
+use rollups_events::DAppMetadata;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::metrics::DispatcherMetrics;
+
+#[derive(Debug, Clone)]
+pub struct ChainHealth {
+    /// Number of blocks behind the chain head above which the alarm is raised
+    block_lag_alarm: Option<u64>,
+
+    dapps: Vec<DAppMetadata>,
+    metrics: DispatcherMetrics,
+}
+
+impl ChainHealth {
+    pub fn new(
+        block_lag_alarm: Option<u64>,
+        dapps: Vec<DAppMetadata>,
+        metrics: DispatcherMetrics,
+    ) -> Self {
+        Self {
+            block_lag_alarm,
+            dapps,
+            metrics,
+        }
+    }
+
+    /// Updates the block metrics, given the number of the chain head when the
+    /// block was processed, and returns the block lag.
+    pub fn observe_block(&self, block: &Block, head_block_number: u64) -> u64 {
+        let block_number = block.number.as_u64();
+        let lag = head_block_number.saturating_sub(block_number);
+        self.set(&self.metrics.latest_block_number, block_number as i64);
+        self.set(
+            &self.metrics.latest_block_timestamp,
+            block.timestamp.as_u64() as i64,
+        );
+        self.set(&self.metrics.block_lag, lag as i64);
+
+        let alarm = self.block_lag_alarm.is_some_and(|max| lag > max);
+        if alarm {
+            warn!(
+                "Dispatcher is {} blocks behind the chain head (block {})",
+                lag, head_block_number
+            );
+        }
+        self.set(&self.metrics.block_lag_alarm, alarm as i64);
+
+        lag
+    }
+
+    pub fn observe_state_query(&self, latency: Duration) {
+        for dapp_metadata in &self.dapps {
+            self.metrics
+                .state_query_latency_seconds
+                .get_or_create(dapp_metadata)
+                .set(latency.as_secs_f64());
+        }
+    }
+
+    pub fn observe_subscription_error(&self) {
+        self.inc_by(&self.metrics.subscription_errors, 1);
+    }
+
+    /// Accounts for a chunk of `len` blocks received at once from the block
+    /// subscription; only the last block of the chunk is processed, and full
+    /// chunks are dropped entirely.
+    pub fn observe_block_chunk(&self, len: usize, dropped: bool) {
+        let skipped = if dropped { len } else { len.saturating_sub(1) };
+        self.inc_by(&self.metrics.subscription_blocks_skipped, skipped as u64);
+        if dropped {
+            warn!(
+                "Dropped a chunk of {} blocks from the block subscription",
+                len
+            );
+            self.inc_by(&self.metrics.subscription_chunks_dropped, 1);
+        }
+    }
+
+    pub fn observe_reorg(&self) {
+        self.inc_by(&self.metrics.reorgs, 1);
+    }
+
+    fn set(&self, family: &FamilyRef<DAppMetadata, GaugeRef>, value: i64) {
+        for dapp_metadata in &self.dapps {
+            family.get_or_create(dapp_metadata).set(value);
+        }
+    }
+
+    fn inc_by(&self, family: &FamilyRef<DAppMetadata, CounterRef>, v: u64) {
+        for dapp_metadata in &self.dapps {
+            family.get_or_create(dapp_metadata).inc_by(v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_state_fold_types::ethereum_types::H160;
+    use http_server::{CounterRef, FamilyRef, GaugeRef};
+    use rollups_events::{Address, DAppMetadata};
+    use std::time::Duration;
+
+    use crate::{drivers::mock, metrics::DispatcherMetrics};
+
+    use super::ChainHealth;
+
+    fn new_health(block_lag_alarm: Option<u64>) -> ChainHealth {
+        ChainHealth::new(
+            block_lag_alarm,
+            vec![DAppMetadata::default()],
+            DispatcherMetrics::default(),
+        )
+    }
+
+    fn gauge(family: &FamilyRef<DAppMetadata, GaugeRef>) -> i64 {
+        family.get_or_create(&DAppMetadata::default()).get()
+    }
+
+    fn counter(family: &FamilyRef<DAppMetadata, CounterRef>) -> u64 {
+        family.get_or_create(&DAppMetadata::default()).get()
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // observe_block
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn observe_block_sets_gauges() {
+        let health = new_health(None);
+        let block = mock::new_block_with_number(1234, 10);
+        assert_eq!(health.observe_block(&block, 15), 5);
+        assert_eq!(gauge(&health.metrics.latest_block_number), 10);
+        assert_eq!(gauge(&health.metrics.latest_block_timestamp), 1234);
+        assert_eq!(gauge(&health.metrics.block_lag), 5);
+        assert_eq!(gauge(&health.metrics.block_lag_alarm), 0);
+    }
+
+    #[test]
+    fn observe_block_with_head_behind() {
+        let health = new_health(None);
+        let block = mock::new_block_with_number(0, 10);
+        assert_eq!(health.observe_block(&block, 8), 0);
+    }
+
+    #[test]
+    fn observe_block_raises_and_clears_alarm() {
+        let health = new_health(Some(3));
+        let block = mock::new_block_with_number(0, 10);
+        health.observe_block(&block, 13);
+        assert_eq!(gauge(&health.metrics.block_lag_alarm), 0);
+        health.observe_block(&block, 14);
+        assert_eq!(gauge(&health.metrics.block_lag_alarm), 1);
+        health.observe_block(&block, 12);
+        assert_eq!(gauge(&health.metrics.block_lag_alarm), 0);
+    }
+
+    #[test]
+    fn observe_block_for_every_dapp() {
+        let dapps: Vec<_> = (0..3)
+            .map(|_| DAppMetadata {
+                chain_id: 0,
+                dapp_address: Address::new(H160::random().into()),
+            })
+            .collect();
+        let health =
+            ChainHealth::new(None, dapps.clone(), DispatcherMetrics::default());
+        health.observe_block(&mock::new_block_with_number(0, 10), 12);
+        health.observe_reorg();
+        for dapp_metadata in &dapps {
+            let metrics = &health.metrics;
+            assert_eq!(metrics.block_lag.get_or_create(dapp_metadata).get(), 2);
+            assert_eq!(metrics.reorgs.get_or_create(dapp_metadata).get(), 1);
+        }
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // observe_state_query
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn observe_state_query_sets_latency() {
+        let health = new_health(None);
+        health.observe_state_query(Duration::from_millis(250));
+        let latency = health
+            .metrics
+            .state_query_latency_seconds
+            .get_or_create(&DAppMetadata::default())
+            .get();
+        assert_eq!(latency, 0.25);
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // observe_block_chunk
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn observe_block_chunk_with_single_block() {
+        let health = new_health(None);
+        health.observe_block_chunk(1, false);
+        assert_eq!(counter(&health.metrics.subscription_blocks_skipped), 0);
+        assert_eq!(counter(&health.metrics.subscription_chunks_dropped), 0);
+    }
+
+    #[test]
+    fn observe_block_chunk_with_many_blocks() {
+        let health = new_health(None);
+        health.observe_block_chunk(4, false);
+        assert_eq!(counter(&health.metrics.subscription_blocks_skipped), 3);
+        assert_eq!(counter(&health.metrics.subscription_chunks_dropped), 0);
+    }
+
+    #[test]
+    fn observe_block_chunk_dropped() {
+        let health = new_health(None);
+        health.observe_block_chunk(256, true);
+        assert_eq!(counter(&health.metrics.subscription_blocks_skipped), 256);
+        assert_eq!(counter(&health.metrics.subscription_chunks_dropped), 1);
+    }
+}
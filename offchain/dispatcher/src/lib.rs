@@ -1,34 +1,79 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
+pub mod admin;
+pub mod admission;
+pub mod backfill;
 pub mod config;
 pub mod dispatcher;
 pub mod machine;
+pub mod policy;
 pub mod sender;
 
 mod auth;
 mod drivers;
 mod error;
+mod health;
 mod metrics;
 mod setup;
 mod signer;
 
+use admin::AdminControl;
 use config::Config;
 use error::DispatcherError;
 use metrics::DispatcherMetrics;
+use policy::approval::{self, ClaimApprovals};
 use snafu::ResultExt;
 
 #[tracing::instrument(level = "trace", skip_all)]
 pub async fn run(config: Config) -> Result<(), DispatcherError> {
     let metrics = DispatcherMetrics::default();
-    let dispatcher_handle =
-        dispatcher::start(config.dispatcher_config, metrics.clone());
+    let claim_approvals = ClaimApprovals::default();
+    let admin_config = config.dispatcher_config.admin_config.clone();
+    let admin_control = AdminControl::load(admin_config.state_file)
+        .context(error::AdminSnafu)?;
+    let claim_policy_config =
+        config.dispatcher_config.claim_policy_config.clone();
+    let dispatcher_handle = dispatcher::start(
+        config.dispatcher_config,
+        metrics.clone(),
+        claim_approvals.clone(),
+        admin_control.clone(),
+    );
     let http_server_handle =
         http_server::start(config.http_server_config, metrics.into());
+    let approval_token = admin_config.token.clone();
+    let approval_server_handle = async {
+        match approval_token {
+            Some(token) if claim_policy_config.manual_approval => {
+                approval::start(
+                    claim_policy_config.approval_http_port,
+                    claim_approvals,
+                    token,
+                )
+                .await
+            }
+            _ => std::future::pending().await,
+        }
+    };
+    let admin_server_handle = async {
+        match admin_config.token {
+            Some(token) => {
+                admin::start(admin_config.http_port, admin_control, token).await
+            }
+            None => std::future::pending().await,
+        }
+    };
     tokio::select! {
         ret = http_server_handle => {
             ret.context(error::HttpServerSnafu)
         }
+        ret = approval_server_handle => {
+            ret.context(error::HttpServerSnafu)
+        }
+        ret = admin_server_handle => {
+            ret.context(error::HttpServerSnafu)
+        }
         ret = dispatcher_handle => {
             ret
         }
@@ -3,8 +3,11 @@
 
 pub mod rollups_broker;
 
-use rollups_events::RollupsClaim;
-use types::foldables::input_box::Input;
+use rollups_events::{
+    RollupsClaim, RollupsInput, RollupsInputRejection, RollupsReorg,
+    RollupsVoucherExecution,
+};
+use types::foldables::{input_box::Input, vouchers::VoucherExecuted};
 
 use async_trait::async_trait;
 
@@ -19,6 +22,15 @@ pub struct RollupStatus {
 #[async_trait]
 pub trait BrokerStatus: std::fmt::Debug {
     async fn status(&self) -> Result<RollupStatus, BrokerFacadeError>;
+    async fn voucher_executions_sent_count(
+        &self,
+    ) -> Result<u64, BrokerFacadeError>;
+    async fn input_events(
+        &self,
+    ) -> Result<Vec<RollupsInput>, BrokerFacadeError>;
+    async fn voucher_execution_events(
+        &self,
+    ) -> Result<Vec<RollupsVoucherExecution>, BrokerFacadeError>;
 }
 
 #[async_trait]
@@ -28,10 +40,25 @@ pub trait BrokerSend: std::fmt::Debug {
         input_index: u64,
         input: &Input,
     ) -> Result<(), BrokerFacadeError>;
+    async fn enqueue_rejected_input(
+        &self,
+        input_index: u64,
+        input: &Input,
+        rejection: RollupsInputRejection,
+    ) -> Result<(), BrokerFacadeError>;
     async fn finish_epoch(
         &self,
         inputs_sent_count: u64,
     ) -> Result<(), BrokerFacadeError>;
+    async fn report_reorg(
+        &self,
+        reorg: RollupsReorg,
+    ) -> Result<(), BrokerFacadeError>;
+    async fn enqueue_voucher_execution(
+        &self,
+        executions_sent_count: u64,
+        execution: &VoucherExecuted,
+    ) -> Result<(), BrokerFacadeError>;
 }
 
 #[async_trait]
@@ -6,14 +6,19 @@ use snafu::{ResultExt, Snafu};
 use tokio::sync::{self, Mutex};
 
 use rollups_events::{
-    Broker, BrokerConfig, BrokerError, DAppMetadata, Event, InputMetadata,
-    RollupsAdvanceStateInput, RollupsClaim, RollupsClaimsStream, RollupsData,
-    RollupsInput, RollupsInputsStream, INITIAL_ID,
+    Broker, BrokerConfig, BrokerError, BrokerStream, DAppMetadata, Event,
+    InputMetadata, RollupsAdvanceStateInput, RollupsClaim, RollupsClaimsStream,
+    RollupsData, RollupsInput, RollupsInputRejection, RollupsInputsStream,
+    RollupsRejectedInput, RollupsReorg, RollupsReorgsStream,
+    RollupsVoucherExecution, RollupsVoucherExecutionsStream, INITIAL_ID,
 };
-use types::foldables::input_box::Input;
+use types::foldables::{input_box::Input, vouchers::VoucherExecuted};
 
 use super::{BrokerReceive, BrokerSend, BrokerStatus, RollupStatus};
 
+/// Maximum number of events read from a stream at once
+const EVENTS_PAGE_SIZE: usize = 1000;
+
 #[derive(Debug, Snafu)]
 pub enum BrokerFacadeError {
     #[snafu(display("error connecting to the broker"))]
@@ -28,6 +33,23 @@ pub enum BrokerFacadeError {
     #[snafu(display("error producing finish-epoch event"))]
     ProduceFinishError { source: BrokerError },
 
+    #[snafu(display("error producing reorg event"))]
+    ProduceReorgError { source: BrokerError },
+
+    #[snafu(display(
+        "error peeking at the end of the voucher executions stream"
+    ))]
+    PeekVoucherExecutionError { source: BrokerError },
+
+    #[snafu(display("error producing voucher execution event"))]
+    ProduceVoucherExecutionError { source: BrokerError },
+
+    #[snafu(display("error consuming input event"))]
+    ConsumeInputError { source: BrokerError },
+
+    #[snafu(display("error consuming voucher execution event"))]
+    ConsumeVoucherExecutionError { source: BrokerError },
+
     #[snafu(display("error consuming claim event"))]
     ConsumeClaimError { source: BrokerError },
 
@@ -44,6 +66,8 @@ pub struct BrokerFacade {
     broker: Mutex<Broker>,
     inputs_stream: RollupsInputsStream,
     claims_stream: RollupsClaimsStream,
+    reorgs_stream: RollupsReorgsStream,
+    voucher_executions_stream: RollupsVoucherExecutionsStream,
     last_claim_id: Mutex<String>,
 }
 
@@ -66,6 +90,10 @@ impl BrokerFacade {
             ),
             inputs_stream: RollupsInputsStream::new(&dapp_metadata),
             claims_stream: RollupsClaimsStream::new(&dapp_metadata),
+            reorgs_stream: RollupsReorgsStream::new(&dapp_metadata),
+            voucher_executions_stream: RollupsVoucherExecutionsStream::new(
+                &dapp_metadata,
+            ),
             last_claim_id: Mutex::new(INITIAL_ID.to_owned()),
         })
     }
@@ -109,6 +137,45 @@ impl BrokerFacade {
 
         Ok(event)
     }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn peek_voucher_executions(
+        &self,
+        broker: &mut sync::MutexGuard<'_, Broker>,
+    ) -> Result<u64, BrokerFacadeError> {
+        let event = broker
+            .peek_latest(&self.voucher_executions_stream)
+            .await
+            .context(PeekVoucherExecutionSnafu)?;
+        tracing::trace!(?event, "got last voucher execution");
+
+        Ok(event.map_or(0, |event| event.payload.executions_sent_count))
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn consume_all<S: BrokerStream>(
+        &self,
+        stream: &S,
+    ) -> Result<Vec<S::Payload>, BrokerError> {
+        let mut events = vec![];
+        let mut last_id = INITIAL_ID.to_owned();
+        loop {
+            // Release the lock between pages, so other users of the broker
+            // aren't blocked by a long stream
+            let page = self
+                .broker
+                .lock()
+                .await
+                .consume_batch_nonblocking(stream, &last_id, EVENTS_PAGE_SIZE)
+                .await?;
+            match page.last() {
+                Some(event) => last_id = event.id.clone(),
+                None => break,
+            }
+            events.extend(page.into_iter().map(|event| event.payload));
+        }
+        Ok(events)
+    }
 }
 
 #[async_trait]
@@ -121,31 +188,60 @@ impl BrokerStatus for BrokerFacade {
         tracing::trace!(?status, "returning rollup status");
         Ok(status)
     }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn voucher_executions_sent_count(
+        &self,
+    ) -> Result<u64, BrokerFacadeError> {
+        let mut broker = self.broker.lock().await;
+        self.peek_voucher_executions(&mut broker).await
+    }
+
+    /// Reads all the events of the inputs stream, from the oldest to the
+    /// newest.
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn input_events(
+        &self,
+    ) -> Result<Vec<RollupsInput>, BrokerFacadeError> {
+        let events = self
+            .consume_all(&self.inputs_stream)
+            .await
+            .context(ConsumeInputSnafu)?;
+        tracing::trace!(count = events.len(), "read input events");
+        Ok(events)
+    }
+
+    /// Reads all the events of the voucher executions stream, from the
+    /// oldest to the newest.
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn voucher_execution_events(
+        &self,
+    ) -> Result<Vec<RollupsVoucherExecution>, BrokerFacadeError> {
+        let events = self
+            .consume_all(&self.voucher_executions_stream)
+            .await
+            .context(ConsumeVoucherExecutionSnafu)?;
+        tracing::trace!(count = events.len(), "read voucher execution events");
+        Ok(events)
+    }
 }
 
 macro_rules! input_sanity_check {
     ($event:expr, $input_index:expr) => {
         assert_eq!($event.inputs_sent_count, $input_index + 1);
-        assert!(matches!(
-            $event.data,
+        let metadata = match &$event.data {
             RollupsData::AdvanceStateInput(RollupsAdvanceStateInput {
-                metadata: InputMetadata {
-                    epoch_index,
-                    ..
-                },
+                metadata,
                 ..
-            }) if epoch_index == 0
-        ));
-        assert!(matches!(
-            $event.data,
-            RollupsData::AdvanceStateInput(RollupsAdvanceStateInput {
-                metadata: InputMetadata {
-                    input_index,
-                    ..
-                },
+            }) => metadata,
+            RollupsData::RejectedInput(RollupsRejectedInput {
+                metadata,
                 ..
-            }) if input_index == $input_index
-        ));
+            }) => metadata,
+            RollupsData::FinishEpoch {} => panic!("expected an input event"),
+        };
+        assert_eq!(metadata.epoch_index, 0);
+        assert_eq!(metadata.input_index, $input_index);
     };
 }
 
@@ -156,20 +252,18 @@ macro_rules! epoch_sanity_check {
     };
 }
 
-#[async_trait]
-impl BrokerSend for BrokerFacade {
+impl BrokerFacade {
     #[tracing::instrument(level = "trace", skip_all)]
-    async fn enqueue_input(
+    async fn produce_input(
         &self,
         input_index: u64,
         input: &Input,
+        rejection: Option<RollupsInputRejection>,
     ) -> Result<(), BrokerFacadeError> {
-        tracing::trace!(?input_index, ?input, "enqueueing input");
-
         let mut broker = self.broker.lock().await;
         let status = self.broker_status(&mut broker).await?;
 
-        let event = build_next_input(input, &status);
+        let event = build_next_input(input, rejection, &status);
         tracing::info!(?event, "producing input event");
 
         input_sanity_check!(event, input_index);
@@ -182,6 +276,36 @@ impl BrokerSend for BrokerFacade {
 
         Ok(())
     }
+}
+
+#[async_trait]
+impl BrokerSend for BrokerFacade {
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn enqueue_input(
+        &self,
+        input_index: u64,
+        input: &Input,
+    ) -> Result<(), BrokerFacadeError> {
+        tracing::trace!(?input_index, ?input, "enqueueing input");
+        self.produce_input(input_index, input, None).await
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn enqueue_rejected_input(
+        &self,
+        input_index: u64,
+        input: &Input,
+        rejection: RollupsInputRejection,
+    ) -> Result<(), BrokerFacadeError> {
+        tracing::trace!(
+            ?input_index,
+            ?input,
+            ?rejection,
+            "enqueueing rejected input"
+        );
+        self.produce_input(input_index, input, Some(rejection))
+            .await
+    }
 
     #[tracing::instrument(level = "trace", skip_all)]
     async fn finish_epoch(
@@ -207,6 +331,53 @@ impl BrokerSend for BrokerFacade {
 
         Ok(())
     }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn report_reorg(
+        &self,
+        reorg: RollupsReorg,
+    ) -> Result<(), BrokerFacadeError> {
+        tracing::info!(?reorg, "producing reorg event");
+
+        let mut broker = self.broker.lock().await;
+        let id = broker
+            .produce(&self.reorgs_stream, reorg)
+            .await
+            .context(ProduceReorgSnafu)?;
+
+        tracing::trace!(id, "produced event with id");
+
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    async fn enqueue_voucher_execution(
+        &self,
+        executions_sent_count: u64,
+        execution: &VoucherExecuted,
+    ) -> Result<(), BrokerFacadeError> {
+        let mut broker = self.broker.lock().await;
+        let sent_count = self.peek_voucher_executions(&mut broker).await?;
+        assert_eq!(sent_count, executions_sent_count);
+
+        let event = RollupsVoucherExecution {
+            executions_sent_count: executions_sent_count + 1,
+            input_index: execution.input_index as u64,
+            index: execution.output_index as u64,
+            tx_hash: execution.tx_hash.0.into(),
+            block_number: execution.block_number,
+        };
+        tracing::info!(?event, "producing voucher execution event");
+
+        let id = broker
+            .produce(&self.voucher_executions_stream, event)
+            .await
+            .context(ProduceVoucherExecutionSnafu)?;
+
+        tracing::trace!(id, "produced event with id");
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -233,7 +404,8 @@ impl From<RollupsInput> for RollupStatus {
         let inputs_sent_count = payload.inputs_sent_count;
 
         match payload.data {
-            RollupsData::AdvanceStateInput { .. } => RollupStatus {
+            RollupsData::AdvanceStateInput { .. }
+            | RollupsData::RejectedInput { .. } => RollupStatus {
                 inputs_sent_count,
                 last_event_is_finish_epoch: false,
             },
@@ -253,7 +425,8 @@ impl From<Event<RollupsInput>> for BrokerStreamStatus {
         let epoch_index = payload.epoch_index;
 
         match payload.data {
-            RollupsData::AdvanceStateInput { .. } => Self {
+            RollupsData::AdvanceStateInput { .. }
+            | RollupsData::RejectedInput { .. } => Self {
                 id,
                 epoch_number: epoch_index,
                 status: payload.into(),
@@ -284,6 +457,7 @@ impl From<Option<Event<RollupsInput>>> for BrokerStreamStatus {
 
 fn build_next_input(
     input: &Input,
+    rejection: Option<RollupsInputRejection>,
     status: &BrokerStreamStatus,
 ) -> RollupsInput {
     let metadata = InputMetadata {
@@ -294,11 +468,19 @@ fn build_next_input(
         input_index: status.status.inputs_sent_count,
     };
 
-    let data = RollupsData::AdvanceStateInput(RollupsAdvanceStateInput {
-        metadata,
-        payload: input.payload.clone().into(),
-        tx_hash: input.tx_hash.0.into(),
-    });
+    let tx_hash = input.tx_hash.0.into();
+    let data = match rejection {
+        None => RollupsData::AdvanceStateInput(RollupsAdvanceStateInput {
+            metadata,
+            payload: input.payload.clone().into(),
+            tx_hash,
+        }),
+        Some(rejection) => RollupsData::RejectedInput(RollupsRejectedInput {
+            metadata,
+            tx_hash,
+            rejection,
+        }),
+    };
 
     RollupsInput {
         parent_id: status.id.clone(),
@@ -329,11 +511,12 @@ mod broker_facade_tests {
     use rollups_events::{
         BrokerConfig, BrokerEndpoint, DAppMetadata, Hash, InputMetadata,
         Payload, RedactedUrl, RollupsAdvanceStateInput, RollupsClaim,
-        RollupsData, Url, HASH_SIZE,
+        RollupsData, RollupsInputRejection, RollupsReorg, RollupsReorgedInput,
+        RollupsVoucherExecution, Url, HASH_SIZE,
     };
     use test_fixtures::broker::BrokerFixture;
     use testcontainers::clients::Cli;
-    use types::foldables::input_box::Input;
+    use types::foldables::{input_box::Input, vouchers::VoucherExecuted};
 
     use crate::machine::{
         rollups_broker::BrokerFacadeError, BrokerReceive, BrokerSend,
@@ -456,6 +639,33 @@ mod broker_facade_tests {
         let _ = broker.enqueue_input(5, &new_enqueue_input()).await;
     }
 
+    #[tokio::test]
+    async fn enqueue_rejected_input_ok() {
+        let docker = Cli::default();
+        let (fixture, broker) = setup(&docker).await;
+        let mut input = new_enqueue_input();
+        input.payload = vec![1; 8];
+        let rejection = RollupsInputRejection::PayloadTooLarge {
+            size: 8,
+            max_size: 4,
+        };
+        broker
+            .enqueue_rejected_input(0, &input, rejection.clone())
+            .await
+            .expect("'enqueue_rejected_input' function failed");
+        let event = fixture
+            .get_latest_input_event()
+            .await
+            .expect("no input event produced");
+        match event.payload.data {
+            RollupsData::RejectedInput(input) => {
+                assert_eq!(input.metadata.input_index, 0);
+                assert_eq!(input.rejection, rejection);
+            }
+            data => panic!("unexpected input event {:?}", data),
+        }
+    }
+
     // NOTE: cannot test result error because the dependency is not injectable.
 
     // --------------------------------------------------------------------------------------------
@@ -565,6 +775,146 @@ mod broker_facade_tests {
         }
     }
 
+    // --------------------------------------------------------------------------------------------
+    // input_events
+    // --------------------------------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn input_events_is_empty() {
+        let docker = Cli::default();
+        let (_fixture, broker) = setup(&docker).await;
+        let events = broker
+            .input_events()
+            .await
+            .expect("'input_events' function failed");
+        assert!(events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn input_events_ok() {
+        let docker = Cli::default();
+        let (fixture, broker) = setup(&docker).await;
+        produce_advance_state_inputs(&fixture, 2).await;
+        produce_finish_epoch_input(&fixture).await;
+        produce_advance_state_inputs(&fixture, 1).await;
+        let events = broker
+            .input_events()
+            .await
+            .expect("'input_events' function failed");
+        let inputs_sent_counts: Vec<_> =
+            events.iter().map(|event| event.inputs_sent_count).collect();
+        assert_eq!(inputs_sent_counts, vec![1, 2, 2, 3]);
+        assert!(matches!(events[2].data, RollupsData::FinishEpoch {}));
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // report_reorg
+    // --------------------------------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn report_reorg_ok() {
+        let docker = Cli::default();
+        let (fixture, broker) = setup(&docker).await;
+        let reorg = RollupsReorg {
+            input_index: 2,
+            sent_input: RollupsReorgedInput {
+                tx_hash: Hash::new([1; HASH_SIZE]),
+                block_number: 10,
+                block_hash: Some(Hash::new([2; HASH_SIZE])),
+            },
+            new_input: None,
+            block_number: 12,
+            block_hash: Hash::new([3; HASH_SIZE]),
+        };
+        broker
+            .report_reorg(reorg.clone())
+            .await
+            .expect("'report_reorg' function failed");
+        assert_eq!(fixture.consume_all_reorgs().await, vec![reorg]);
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // voucher executions
+    // --------------------------------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn voucher_executions_sent_count_equals_0() {
+        let docker = Cli::default();
+        let (_fixture, broker) = setup(&docker).await;
+        let count = broker.voucher_executions_sent_count().await.unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn enqueue_voucher_execution_ok() {
+        let docker = Cli::default();
+        let (fixture, broker) = setup(&docker).await;
+        for i in 0..2 {
+            let execution = VoucherExecuted {
+                input_index: 3,
+                output_index: i,
+                tx_hash: Arc::new(H256::repeat_byte(i as u8)),
+                block_number: 10,
+            };
+            broker
+                .enqueue_voucher_execution(i as u64, &execution)
+                .await
+                .expect("'enqueue_voucher_execution' function failed");
+        }
+        let count = broker.voucher_executions_sent_count().await.unwrap();
+        assert_eq!(count, 2);
+        let expected: Vec<_> = (0..2)
+            .map(|i| RollupsVoucherExecution {
+                executions_sent_count: i + 1,
+                input_index: 3,
+                index: i,
+                tx_hash: Hash::new([i as u8; HASH_SIZE]),
+                block_number: 10,
+            })
+            .collect();
+        assert_eq!(fixture.consume_all_voucher_executions().await, expected);
+    }
+
+    #[tokio::test]
+    async fn voucher_execution_events_ok() {
+        let docker = Cli::default();
+        let (_fixture, broker) = setup(&docker).await;
+        for i in 0..2 {
+            let execution = VoucherExecuted {
+                input_index: i,
+                output_index: 0,
+                tx_hash: Arc::new(H256::repeat_byte(i as u8)),
+                block_number: 10 + i as u64,
+            };
+            broker
+                .enqueue_voucher_execution(i as u64, &execution)
+                .await
+                .expect("'enqueue_voucher_execution' function failed");
+        }
+        let events = broker
+            .voucher_execution_events()
+            .await
+            .expect("'voucher_execution_events' function failed");
+        let block_numbers: Vec<_> =
+            events.iter().map(|event| event.block_number).collect();
+        assert_eq!(block_numbers, vec![10, 11]);
+        assert_eq!(events[1].executions_sent_count, 2);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "left: `0`,\n right: `1`")]
+    async fn enqueue_voucher_execution_assertion_error() {
+        let docker = Cli::default();
+        let (_fixture, broker) = setup(&docker).await;
+        let execution = VoucherExecuted {
+            input_index: 0,
+            output_index: 0,
+            tx_hash: Arc::new(H256::random()),
+            block_number: 0,
+        };
+        let _ = broker.enqueue_voucher_execution(1, &execution).await;
+    }
+
     // --------------------------------------------------------------------------------------------
     // auxiliary
     // --------------------------------------------------------------------------------------------
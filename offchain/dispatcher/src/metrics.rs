@@ -1,8 +1,9 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
-use http_server::{CounterRef, FamilyRef, Registry};
+use http_server::{CounterRef, FamilyRef, GaugeRef, Registry};
 use rollups_events::DAppMetadata;
+use std::sync::atomic::AtomicU64;
 
 const METRICS_PREFIX: &str = "cartesi_rollups_dispatcher";
 
@@ -14,7 +15,24 @@ fn prefixed_metrics(name: &str) -> String {
 pub struct DispatcherMetrics {
     pub claims_sent: FamilyRef<DAppMetadata, CounterRef>,
     pub advance_inputs_sent: FamilyRef<DAppMetadata, CounterRef>,
+    pub advance_inputs_rejected: FamilyRef<DAppMetadata, CounterRef>,
     pub finish_epochs_sent: FamilyRef<DAppMetadata, CounterRef>,
+    pub voucher_executions_sent: FamilyRef<DAppMetadata, CounterRef>,
+    pub claims_held_for_approval: FamilyRef<DAppMetadata, CounterRef>,
+    pub claims_held_for_gas_ceiling: FamilyRef<DAppMetadata, CounterRef>,
+    pub claims_escalated: FamilyRef<DAppMetadata, CounterRef>,
+    pub latest_block_number: FamilyRef<DAppMetadata, GaugeRef>,
+    pub latest_block_timestamp: FamilyRef<DAppMetadata, GaugeRef>,
+    pub block_lag: FamilyRef<DAppMetadata, GaugeRef>,
+    pub block_lag_alarm: FamilyRef<DAppMetadata, GaugeRef>,
+    pub state_query_latency_seconds:
+        FamilyRef<DAppMetadata, GaugeRef<f64, AtomicU64>>,
+    pub subscription_errors: FamilyRef<DAppMetadata, CounterRef>,
+    pub subscription_blocks_skipped: FamilyRef<DAppMetadata, CounterRef>,
+    pub subscription_chunks_dropped: FamilyRef<DAppMetadata, CounterRef>,
+    pub reorgs: FamilyRef<DAppMetadata, CounterRef>,
+    pub current_epoch_index: FamilyRef<DAppMetadata, GaugeRef>,
+    pub epoch_seconds_until_close: FamilyRef<DAppMetadata, GaugeRef>,
 }
 
 impl From<DispatcherMetrics> for Registry {
@@ -30,11 +48,91 @@ impl From<DispatcherMetrics> for Registry {
             "Counts the number of <advance_input>s sent",
             metrics.advance_inputs_sent,
         );
+        registry.register(
+            prefixed_metrics("advance_inputs_rejected"),
+            "Counts the number of <advance_input>s rejected by the admission filters",
+            metrics.advance_inputs_rejected,
+        );
         registry.register(
             prefixed_metrics("finish_epochs_sent"),
             "Counts the number of <finish_epoch>s sent",
             metrics.finish_epochs_sent,
         );
+        registry.register(
+            prefixed_metrics("voucher_executions_sent"),
+            "Counts the number of voucher executions sent",
+            metrics.voucher_executions_sent,
+        );
+        registry.register(
+            prefixed_metrics("claims_held_for_approval"),
+            "Counts the number of claims held until manual approval",
+            metrics.claims_held_for_approval,
+        );
+        registry.register(
+            prefixed_metrics("claims_held_for_gas_ceiling"),
+            "Counts the number of times a claim was held because of the gas ceiling",
+            metrics.claims_held_for_gas_ceiling,
+        );
+        registry.register(
+            prefixed_metrics("claims_escalated"),
+            "Counts the number of claim priority escalations",
+            metrics.claims_escalated,
+        );
+        registry.register(
+            prefixed_metrics("latest_block_number"),
+            "Number of the latest block processed",
+            metrics.latest_block_number,
+        );
+        registry.register(
+            prefixed_metrics("latest_block_timestamp"),
+            "Timestamp of the latest block processed",
+            metrics.latest_block_timestamp,
+        );
+        registry.register(
+            prefixed_metrics("block_lag"),
+            "Number of blocks between the latest block processed and the chain head",
+            metrics.block_lag,
+        );
+        registry.register(
+            prefixed_metrics("block_lag_alarm"),
+            "Set to 1 while the block lag is above the alarm threshold",
+            metrics.block_lag_alarm,
+        );
+        registry.register(
+            prefixed_metrics("state_query_latency_seconds"),
+            "Latency of the latest state-server state query",
+            metrics.state_query_latency_seconds,
+        );
+        registry.register(
+            prefixed_metrics("subscription_errors"),
+            "Counts the number of errors returned by the block subscription",
+            metrics.subscription_errors,
+        );
+        registry.register(
+            prefixed_metrics("subscription_blocks_skipped"),
+            "Counts the number of blocks of the block subscription that were not processed",
+            metrics.subscription_blocks_skipped,
+        );
+        registry.register(
+            prefixed_metrics("subscription_chunks_dropped"),
+            "Counts the number of full chunks of blocks dropped by the block subscription",
+            metrics.subscription_chunks_dropped,
+        );
+        registry.register(
+            prefixed_metrics("reorgs"),
+            "Counts the number of blockchain reorgs seen",
+            metrics.reorgs,
+        );
+        registry.register(
+            prefixed_metrics("current_epoch_index"),
+            "Index of the current epoch, by timestamp",
+            metrics.current_epoch_index,
+        );
+        registry.register(
+            prefixed_metrics("epoch_seconds_until_close"),
+            "Seconds until the current epoch closes, by timestamp",
+            metrics.epoch_seconds_until_close,
+        );
         registry
     }
 }
@@ -0,0 +1,458 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! This module keeps track of the claims held by the `ClaimPolicy` and serves
+//! the HTTP endpoint used to inspect and approve them.
+//!
+//! - `GET /claims` lists the held claims of every DApp;
+//! - `POST /dapps/:dapp_address/claims/:epoch_index/:epoch_hash/approve`
+//!   approves a held claim of a DApp;
+//! - `POST /claims/:epoch_index/:epoch_hash/approve` approves a held claim, as
+//!   long as a single DApp has that claim held for that epoch.
+//!
+//! Approvals name the epoch hash of the claim, so a claim recomputed for the
+//! same epoch, after a reorg for instance, is held again until it is approved
+//! on its own.
+//!
+//! Requests must carry the admin token as `Authorization: Bearer <token>`,
+//! like the ones sent to the admin endpoint.
+//!
+//! Approvals are kept in memory; after a restart, held claims have to be
+//! approved again.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use eth_state_fold_types::ethereum_types::{H160, H256};
+use eth_tx_manager::Priority;
+use redacted::Redacted;
+use rollups_events::{Address, Hash, RollupsClaim};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::HoldReason;
+use crate::admin::is_authorized;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HeldClaim {
+    pub dapp_address: Address,
+    pub epoch_index: u64,
+    pub first_index: u128,
+    pub last_index: u128,
+    pub epoch_hash: Hash,
+    pub reason: String,
+    pub priority: Priority,
+    pub pending_seconds: u64,
+    pub approved: bool,
+}
+
+/// Claims are identified by the DApp address, the epoch index and the epoch
+/// hash.
+type ClaimKey = (Address, u64, Hash);
+
+fn is_same_epoch(
+    key: &ClaimKey,
+    dapp_address: &Address,
+    epoch_index: u64,
+) -> bool {
+    key.0 == *dapp_address && key.1 == epoch_index
+}
+
+#[derive(Debug, Default)]
+struct ApprovalState {
+    held: HashMap<ClaimKey, HeldClaim>,
+    approved: HashSet<ClaimKey>,
+}
+
+/// Shared state between the `ClaimPolicy` and the approval HTTP endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct ClaimApprovals(Arc<Mutex<ApprovalState>>);
+
+impl ClaimApprovals {
+    pub fn is_approved(
+        &self,
+        dapp_address: &Address,
+        epoch_index: u64,
+        epoch_hash: &Hash,
+    ) -> bool {
+        self.0.lock().unwrap().approved.contains(&(
+            dapp_address.clone(),
+            epoch_index,
+            epoch_hash.clone(),
+        ))
+    }
+
+    /// Approves a held claim; returns false if there is no such claim.
+    pub fn approve(
+        &self,
+        dapp_address: &Address,
+        epoch_index: u64,
+        epoch_hash: &Hash,
+    ) -> bool {
+        let mut state = self.0.lock().unwrap();
+        let key = (dapp_address.clone(), epoch_index, epoch_hash.clone());
+        match state.held.get_mut(&key) {
+            Some(held_claim) => {
+                held_claim.approved = true;
+                state.approved.insert(key);
+                tracing::info!(
+                    ?dapp_address,
+                    epoch_index,
+                    ?epoch_hash,
+                    "claim manually approved"
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the DApps with the claim held for the epoch.
+    pub fn dapps_holding(
+        &self,
+        epoch_index: u64,
+        epoch_hash: &Hash,
+    ) -> Vec<Address> {
+        self.0
+            .lock()
+            .unwrap()
+            .held
+            .keys()
+            .filter(|(_, index, hash)| {
+                *index == epoch_index && hash == epoch_hash
+            })
+            .map(|(dapp_address, _, _)| dapp_address.clone())
+            .collect()
+    }
+
+    /// Returns the held claims, sorted by epoch index.
+    pub fn held(&self) -> Vec<HeldClaim> {
+        let mut held: Vec<_> =
+            self.0.lock().unwrap().held.values().cloned().collect();
+        held.sort_by(|a, b| {
+            (a.epoch_index, a.dapp_address.inner())
+                .cmp(&(b.epoch_index, b.dapp_address.inner()))
+        });
+        held
+    }
+
+    pub(super) fn hold(
+        &self,
+        dapp_address: &Address,
+        rollups_claim: &RollupsClaim,
+        reason: &HoldReason,
+        priority: Priority,
+        age: Duration,
+    ) {
+        let mut state = self.0.lock().unwrap();
+        let epoch_index = rollups_claim.epoch_index;
+        let epoch_hash = &rollups_claim.epoch_hash;
+        // A claim with another hash for the same epoch was replaced, so it
+        // can no longer be approved
+        state.held.retain(|key, _| {
+            !is_same_epoch(key, dapp_address, epoch_index)
+                || key.2 == *epoch_hash
+        });
+        state.approved.retain(|key| {
+            !is_same_epoch(key, dapp_address, epoch_index)
+                || key.2 == *epoch_hash
+        });
+        let key = (dapp_address.clone(), epoch_index, epoch_hash.clone());
+        let approved = state.approved.contains(&key);
+        let reason = match reason {
+            HoldReason::AwaitingApproval => "awaiting approval".to_owned(),
+            HoldReason::GasCeiling {
+                max_fee_per_gas,
+                ceiling,
+            } => format!(
+                "max fee per gas {} above ceiling {}",
+                max_fee_per_gas, ceiling
+            ),
+        };
+        state.held.insert(
+            key,
+            HeldClaim {
+                dapp_address: dapp_address.clone(),
+                epoch_index,
+                first_index: rollups_claim.first_index,
+                last_index: rollups_claim.last_index,
+                epoch_hash: epoch_hash.clone(),
+                reason,
+                priority,
+                pending_seconds: age.as_secs(),
+                approved,
+            },
+        );
+    }
+
+    pub(super) fn release(&self, dapp_address: &Address, epoch_index: u64) {
+        let mut state = self.0.lock().unwrap();
+        state
+            .held
+            .retain(|key, _| !is_same_epoch(key, dapp_address, epoch_index));
+        state
+            .approved
+            .retain(|key| !is_same_epoch(key, dapp_address, epoch_index));
+    }
+}
+
+#[derive(Clone)]
+struct ApprovalServerState {
+    approvals: ClaimApprovals,
+    token: Redacted<String>,
+}
+
+pub fn router(approvals: ClaimApprovals, token: Redacted<String>) -> Router {
+    Router::new()
+        .route("/claims", get(list_held_claims))
+        .route(
+            "/claims/:epoch_index/:epoch_hash/approve",
+            post(approve_epoch_claim),
+        )
+        .route(
+            "/dapps/:dapp_address/claims/:epoch_index/:epoch_hash/approve",
+            post(approve_claim),
+        )
+        .with_state(ApprovalServerState { approvals, token })
+}
+
+/// Starts the claim approval HTTP server.
+pub async fn start(
+    port: u16,
+    approvals: ClaimApprovals,
+    token: Redacted<String>,
+) -> Result<(), hyper::Error> {
+    let ip = "0.0.0.0".parse().expect("could not parse host address");
+    let addr = SocketAddr::new(ip, port);
+    tracing::info!("Starting claim approval HTTP server at {}", addr);
+    axum::Server::bind(&addr)
+        .serve(router(approvals, token).into_make_service())
+        .await
+}
+
+async fn list_held_claims(
+    State(server): State<ApprovalServerState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<HeldClaim>>, StatusCode> {
+    if !is_authorized(&headers, &server.token) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(Json(server.approvals.held()))
+}
+
+async fn approve_epoch_claim(
+    State(server): State<ApprovalServerState>,
+    headers: HeaderMap,
+    Path((epoch_index, epoch_hash)): Path<(u64, String)>,
+) -> StatusCode {
+    if !is_authorized(&headers, &server.token) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let epoch_hash = match parse_hash(&epoch_hash) {
+        Some(hash) => hash,
+        None => return StatusCode::BAD_REQUEST,
+    };
+    let approvals = &server.approvals;
+    match approvals.dapps_holding(epoch_index, &epoch_hash).as_slice() {
+        [] => StatusCode::NOT_FOUND,
+        [dapp_address] => {
+            if approvals.approve(dapp_address, epoch_index, &epoch_hash) {
+                StatusCode::OK
+            } else {
+                StatusCode::NOT_FOUND
+            }
+        }
+        _ => StatusCode::CONFLICT,
+    }
+}
+
+async fn approve_claim(
+    State(server): State<ApprovalServerState>,
+    headers: HeaderMap,
+    Path((dapp_address, epoch_index, epoch_hash)): Path<(String, u64, String)>,
+) -> StatusCode {
+    if !is_authorized(&headers, &server.token) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let dapp_address = match dapp_address.parse::<H160>() {
+        Ok(address) => Address::new(address.into()),
+        Err(_) => return StatusCode::BAD_REQUEST,
+    };
+    let epoch_hash = match parse_hash(&epoch_hash) {
+        Some(hash) => hash,
+        None => return StatusCode::BAD_REQUEST,
+    };
+    if server
+        .approvals
+        .approve(&dapp_address, epoch_index, &epoch_hash)
+    {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Parses the epoch hash in hex, with or without the 0x prefix, as it is
+/// listed by `GET /claims`.
+fn parse_hash(epoch_hash: &str) -> Option<Hash> {
+    epoch_hash
+        .parse::<H256>()
+        .ok()
+        .map(|hash| Hash::new(hash.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::{Path, State},
+        http::{header::AUTHORIZATION, HeaderMap, HeaderValue, StatusCode},
+    };
+    use eth_state_fold_types::ethereum_types::H256;
+    use eth_tx_manager::Priority;
+    use redacted::Redacted;
+    use rollups_events::{Address, Hash, RollupsClaim};
+    use std::time::Duration;
+
+    use super::{approve_epoch_claim, ApprovalServerState, ClaimApprovals};
+    use crate::policy::HoldReason;
+
+    #[tokio::test]
+    async fn approve_requires_token() {
+        let approvals = ClaimApprovals::default();
+        let dapp_address = Address::default();
+        approvals.hold(
+            &dapp_address,
+            &RollupsClaim::default(),
+            &HoldReason::AwaitingApproval,
+            Priority::Normal,
+            Duration::ZERO,
+        );
+        let server = ApprovalServerState {
+            approvals: approvals.clone(),
+            token: Redacted::new("secret".to_owned()),
+        };
+
+        let epoch_hash = Hash::default();
+        let status = approve_epoch_claim(
+            State(server.clone()),
+            HeaderMap::new(),
+            Path((0, format!("{:x}", H256::from(epoch_hash.inner())))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(!approvals.is_approved(&dapp_address, 0, &epoch_hash));
+
+        let status = approve_epoch_claim(
+            State(server),
+            authorized_headers(),
+            Path((0, format!("{:x}", H256::from(epoch_hash.inner())))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(approvals.is_approved(&dapp_address, 0, &epoch_hash));
+    }
+
+    #[tokio::test]
+    async fn approve_requires_epoch_hash_of_held_claim() {
+        let approvals = ClaimApprovals::default();
+        let dapp_address = Address::default();
+        let held_claim = RollupsClaim {
+            epoch_hash: Hash::new([1; 32]),
+            ..Default::default()
+        };
+        approvals.hold(
+            &dapp_address,
+            &held_claim,
+            &HoldReason::AwaitingApproval,
+            Priority::Normal,
+            Duration::ZERO,
+        );
+        let server = ApprovalServerState {
+            approvals: approvals.clone(),
+            token: Redacted::new("secret".to_owned()),
+        };
+
+        let status = approve_epoch_claim(
+            State(server.clone()),
+            authorized_headers(),
+            Path((0, "not a hash".to_owned())),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let other_hash = Hash::new([2; 32]);
+        let status = approve_epoch_claim(
+            State(server.clone()),
+            authorized_headers(),
+            Path((0, format!("{:x}", H256::from(other_hash.inner())))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(!approvals.is_approved(&dapp_address, 0, &other_hash));
+
+        let status = approve_epoch_claim(
+            State(server),
+            authorized_headers(),
+            Path((
+                0,
+                format!("{:#x}", H256::from(held_claim.epoch_hash.inner())),
+            )),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(approvals.is_approved(
+            &dapp_address,
+            0,
+            &held_claim.epoch_hash
+        ));
+    }
+
+    #[test]
+    fn hold_drops_approval_of_replaced_claim() {
+        let approvals = ClaimApprovals::default();
+        let dapp_address = Address::default();
+        let claim = RollupsClaim::default();
+        let hold = |claim: &RollupsClaim| {
+            approvals.hold(
+                &dapp_address,
+                claim,
+                &HoldReason::AwaitingApproval,
+                Priority::Normal,
+                Duration::ZERO,
+            )
+        };
+        hold(&claim);
+        assert!(approvals.approve(&dapp_address, 0, &claim.epoch_hash));
+
+        let recomputed_claim = RollupsClaim {
+            epoch_hash: Hash::new([1; 32]),
+            ..Default::default()
+        };
+        hold(&recomputed_claim);
+        let held = approvals.held();
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].epoch_hash, recomputed_claim.epoch_hash);
+        assert!(!held[0].approved);
+        assert!(!approvals.is_approved(&dapp_address, 0, &claim.epoch_hash));
+        assert!(!approvals.is_approved(
+            &dapp_address,
+            0,
+            &recomputed_claim.epoch_hash
+        ));
+    }
+
+    fn authorized_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers
+            .insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        headers
+    }
+}
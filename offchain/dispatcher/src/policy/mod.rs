@@ -0,0 +1,534 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! This module exposes the `ClaimPolicy`, which decides when and how a claim
+//! received from the broker is handed to the `Sender`.
+//!
+//! A claim can be held back by the policy for two reasons: it is waiting for
+//! manual approval (see the `approval` module), or the estimated max fee per
+//! gas is above the configured ceiling. While a claim is held, its priority is
+//! escalated each time it exceeds the configured deadline. Once submitted, the
+//! ceiling is still enforced by the gas oracle of the `ClaimSender`, which caps
+//! the fees of every (re)submission.
+
+pub mod approval;
+
+use clap::Parser;
+use eth_state_fold_types::ethers::types::U256;
+use eth_tx_manager::Priority;
+use rollups_events::{DAppMetadata, RollupsClaim};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::{
+    metrics::DispatcherMetrics,
+    sender::{Sender, SenderError},
+};
+
+use self::approval::ClaimApprovals;
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "claim_policy_config")]
+#[command(about = "Configuration for the claim submission policy")]
+pub struct ClaimPolicyEnvCLIConfig {
+    /// Maximum fee per gas (in wei) paid for a claim; claims are held while the estimate is above it
+    #[arg(long, env)]
+    pub rd_claim_max_fee_per_gas: Option<u64>,
+
+    /// Time in seconds a claim may be pending before its priority is escalated (repeats until ASAP)
+    #[arg(long, env)]
+    pub rd_claim_escalation_deadline: Option<u64>,
+
+    /// Hold claims until they are approved through the claim approval HTTP endpoint, which requires `rd_admin_token`
+    #[arg(long, env, default_value = "false")]
+    pub rd_claim_manual_approval: bool,
+
+    /// Port of the claim approval HTTP endpoint, only used with manual approval
+    #[arg(long, env, default_value = "8081")]
+    pub rd_claim_approval_http_port: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClaimPolicyConfig {
+    pub priority: Priority,
+    pub max_fee_per_gas: Option<U256>,
+    pub escalation_deadline: Option<Duration>,
+    pub manual_approval: bool,
+    pub approval_http_port: u16,
+}
+
+impl ClaimPolicyConfig {
+    pub fn initialize(
+        cli: ClaimPolicyEnvCLIConfig,
+        priority: Priority,
+    ) -> Self {
+        Self {
+            priority,
+            max_fee_per_gas: cli.rd_claim_max_fee_per_gas.map(U256::from),
+            escalation_deadline: cli
+                .rd_claim_escalation_deadline
+                .map(Duration::from_secs),
+            manual_approval: cli.rd_claim_manual_approval,
+            approval_http_port: cli.rd_claim_approval_http_port,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HoldReason {
+    AwaitingApproval,
+    GasCeiling {
+        max_fee_per_gas: U256,
+        ceiling: U256,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimDecision {
+    Submit(Priority),
+    Hold(HoldReason),
+}
+
+/// A claim received from the broker that was not submitted yet.
+#[derive(Debug)]
+pub struct PendingClaim {
+    pub rollups_claim: RollupsClaim,
+    received_at: Instant,
+    priority: Priority,
+    last_decision: Option<ClaimDecision>,
+}
+
+impl PendingClaim {
+    pub fn new(rollups_claim: RollupsClaim, received_at: Instant) -> Self {
+        Self {
+            rollups_claim,
+            received_at,
+            priority: Priority::Low,
+            last_decision: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ClaimPolicy {
+    config: ClaimPolicyConfig,
+    approvals: ClaimApprovals,
+    dapp_metadata: DAppMetadata,
+    metrics: DispatcherMetrics,
+}
+
+impl ClaimPolicy {
+    pub fn new(
+        config: ClaimPolicyConfig,
+        approvals: ClaimApprovals,
+        dapp_metadata: DAppMetadata,
+        metrics: DispatcherMetrics,
+    ) -> Self {
+        Self {
+            config,
+            approvals,
+            dapp_metadata,
+            metrics,
+        }
+    }
+
+    /// Decides whether the pending claim should be submitted now, and with
+    /// which priority. The decision is logged and recorded in the metrics
+    /// whenever it changes.
+    pub async fn evaluate<S: Sender>(
+        &self,
+        pending: &mut PendingClaim,
+        sender: &S,
+        now: Instant,
+    ) -> Result<ClaimDecision, SenderError> {
+        let age = now.saturating_duration_since(pending.received_at);
+        let priority = self.priority(age);
+        let max_fee_per_gas = match self.config.max_fee_per_gas {
+            Some(_) if self.is_approved(&pending.rollups_claim) => {
+                Some(sender.max_fee_per_gas().await?)
+            }
+            _ => None,
+        };
+        let decision =
+            self.decide(&pending.rollups_claim, priority, max_fee_per_gas);
+
+        if pending.last_decision.is_some() && priority > pending.priority {
+            warn!(
+                epoch_index = pending.rollups_claim.epoch_index,
+                ?age,
+                ?priority,
+                "claim is overdue; escalating its priority"
+            );
+            self.metrics
+                .claims_escalated
+                .get_or_create(&self.dapp_metadata)
+                .inc();
+        }
+        pending.priority = priority;
+
+        if pending.last_decision.as_ref() != Some(&decision) {
+            self.record(&pending.rollups_claim, &decision);
+            pending.last_decision = Some(decision.clone());
+        }
+
+        match decision {
+            ClaimDecision::Submit(_) => self.approvals.release(
+                &self.dapp_metadata.dapp_address,
+                pending.rollups_claim.epoch_index,
+            ),
+            ClaimDecision::Hold(ref reason) => self.approvals.hold(
+                &self.dapp_metadata.dapp_address,
+                &pending.rollups_claim,
+                reason,
+                priority,
+                age,
+            ),
+        }
+
+        Ok(decision)
+    }
+
+    fn is_approved(&self, rollups_claim: &RollupsClaim) -> bool {
+        !self.config.manual_approval
+            || self.approvals.is_approved(
+                &self.dapp_metadata.dapp_address,
+                rollups_claim.epoch_index,
+                &rollups_claim.epoch_hash,
+            )
+    }
+
+    fn priority(&self, age: Duration) -> Priority {
+        let escalations = match self.config.escalation_deadline {
+            Some(deadline) if !deadline.is_zero() => {
+                age.as_secs() / deadline.as_secs().max(1)
+            }
+            _ => 0,
+        };
+        (0..escalations)
+            .fold(self.config.priority, |priority, _| escalate(priority))
+    }
+
+    fn decide(
+        &self,
+        rollups_claim: &RollupsClaim,
+        priority: Priority,
+        max_fee_per_gas: Option<U256>,
+    ) -> ClaimDecision {
+        if !self.is_approved(rollups_claim) {
+            return ClaimDecision::Hold(HoldReason::AwaitingApproval);
+        }
+        match (max_fee_per_gas, self.config.max_fee_per_gas) {
+            (Some(max_fee_per_gas), Some(ceiling))
+                if max_fee_per_gas > ceiling =>
+            {
+                ClaimDecision::Hold(HoldReason::GasCeiling {
+                    max_fee_per_gas,
+                    ceiling,
+                })
+            }
+            _ => ClaimDecision::Submit(priority),
+        }
+    }
+
+    fn record(&self, rollups_claim: &RollupsClaim, decision: &ClaimDecision) {
+        let epoch_index = rollups_claim.epoch_index;
+        match decision {
+            ClaimDecision::Submit(priority) => {
+                info!(epoch_index, ?priority, "claim cleared for submission")
+            }
+            ClaimDecision::Hold(HoldReason::AwaitingApproval) => {
+                info!(epoch_index, "holding claim until manual approval");
+                self.metrics
+                    .claims_held_for_approval
+                    .get_or_create(&self.dapp_metadata)
+                    .inc();
+            }
+            ClaimDecision::Hold(HoldReason::GasCeiling {
+                max_fee_per_gas,
+                ceiling,
+            }) => {
+                warn!(
+                    epoch_index,
+                    %max_fee_per_gas,
+                    %ceiling,
+                    "holding claim because the max fee per gas is above the ceiling"
+                );
+                self.metrics
+                    .claims_held_for_gas_ceiling
+                    .get_or_create(&self.dapp_metadata)
+                    .inc();
+            }
+        }
+    }
+}
+
+fn escalate(priority: Priority) -> Priority {
+    match priority {
+        Priority::Low => Priority::Normal,
+        Priority::Normal => Priority::High,
+        Priority::High | Priority::ASAP => Priority::ASAP,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_state_fold_types::ethers::types::{H160, U256};
+    use eth_tx_manager::Priority;
+    use rollups_events::{Address, DAppMetadata, RollupsClaim, HASH_SIZE};
+    use std::time::{Duration, Instant};
+
+    use crate::{drivers::mock, metrics::DispatcherMetrics};
+
+    use super::{
+        approval::ClaimApprovals, ClaimDecision, ClaimPolicy,
+        ClaimPolicyConfig, HoldReason, PendingClaim,
+    };
+
+    // --------------------------------------------------------------------------------------------
+    // priority
+    // --------------------------------------------------------------------------------------------
+
+    #[test]
+    fn priority_without_deadline() {
+        let policy = new_policy(config());
+        let age = Duration::from_secs(1_000_000);
+        assert_eq!(policy.priority(age), Priority::Normal);
+    }
+
+    #[test]
+    fn priority_escalates_after_each_deadline() {
+        let mut config = config();
+        config.escalation_deadline = Some(Duration::from_secs(10));
+        let policy = new_policy(config);
+        assert_eq!(policy.priority(Duration::from_secs(9)), Priority::Normal);
+        assert_eq!(policy.priority(Duration::from_secs(10)), Priority::High);
+        assert_eq!(policy.priority(Duration::from_secs(25)), Priority::ASAP);
+        assert_eq!(policy.priority(Duration::from_secs(99)), Priority::ASAP);
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // evaluate
+    // --------------------------------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn evaluate_submits_by_default() {
+        let policy = new_policy(config());
+        let sender = mock::Sender::new();
+        let mut pending = PendingClaim::new(new_claim(0), Instant::now());
+        let decision = policy
+            .evaluate(&mut pending, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Submit(Priority::Normal));
+    }
+
+    #[tokio::test]
+    async fn evaluate_holds_above_gas_ceiling() {
+        let mut config = config();
+        config.max_fee_per_gas = Some(U256::from(10));
+        let policy = new_policy(config);
+        let sender = mock::Sender::with_max_fee_per_gas(U256::from(11));
+        let mut pending = PendingClaim::new(new_claim(0), Instant::now());
+        let decision = policy
+            .evaluate(&mut pending, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(
+            decision,
+            ClaimDecision::Hold(HoldReason::GasCeiling {
+                max_fee_per_gas: U256::from(11),
+                ceiling: U256::from(10),
+            })
+        );
+        assert_eq!(policy.approvals.held().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn evaluate_submits_at_gas_ceiling() {
+        let mut config = config();
+        config.max_fee_per_gas = Some(U256::from(10));
+        let policy = new_policy(config);
+        let sender = mock::Sender::with_max_fee_per_gas(U256::from(10));
+        let mut pending = PendingClaim::new(new_claim(0), Instant::now());
+        let decision = policy
+            .evaluate(&mut pending, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Submit(Priority::Normal));
+    }
+
+    #[tokio::test]
+    async fn evaluate_holds_until_approved() {
+        let mut config = config();
+        config.manual_approval = true;
+        let policy = new_policy(config);
+        let sender = mock::Sender::new();
+        let mut pending = PendingClaim::new(new_claim(3), Instant::now());
+
+        let decision = policy
+            .evaluate(&mut pending, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Hold(HoldReason::AwaitingApproval));
+        let dapp_address = &policy.dapp_metadata.dapp_address;
+        let epoch_hash = &pending.rollups_claim.epoch_hash.clone();
+        assert!(!policy.approvals.approve(dapp_address, 4, epoch_hash));
+        assert!(!policy.approvals.approve(
+            dapp_address,
+            3,
+            &new_claim(4).epoch_hash
+        ));
+        assert!(policy.approvals.approve(dapp_address, 3, epoch_hash));
+
+        let decision = policy
+            .evaluate(&mut pending, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Submit(Priority::Normal));
+        assert!(policy.approvals.held().is_empty());
+    }
+
+    #[tokio::test]
+    async fn evaluate_holds_until_approved_per_dapp() {
+        let mut config = config();
+        config.manual_approval = true;
+        let approvals = ClaimApprovals::default();
+        let new_dapp_policy = || {
+            ClaimPolicy::new(
+                config.clone(),
+                approvals.clone(),
+                DAppMetadata {
+                    chain_id: 0,
+                    dapp_address: Address::new(H160::random().into()),
+                },
+                DispatcherMetrics::default(),
+            )
+        };
+        let policy1 = new_dapp_policy();
+        let policy2 = new_dapp_policy();
+        let sender = mock::Sender::new();
+        let mut pending1 = PendingClaim::new(new_claim(0), Instant::now());
+        let mut pending2 = PendingClaim::new(new_claim(0), Instant::now());
+        policy1
+            .evaluate(&mut pending1, &sender, Instant::now())
+            .await
+            .unwrap();
+        policy2
+            .evaluate(&mut pending2, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(approvals.held().len(), 2);
+        let epoch_hash = &pending1.rollups_claim.epoch_hash.clone();
+        assert_eq!(approvals.dapps_holding(0, epoch_hash).len(), 2);
+
+        assert!(approvals.approve(
+            &policy1.dapp_metadata.dapp_address,
+            0,
+            epoch_hash
+        ));
+        let decision = policy1
+            .evaluate(&mut pending1, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Submit(Priority::Normal));
+        let decision = policy2
+            .evaluate(&mut pending2, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Hold(HoldReason::AwaitingApproval));
+        assert_eq!(
+            approvals.dapps_holding(0, epoch_hash),
+            vec![policy2.dapp_metadata.dapp_address.clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn evaluate_holds_recomputed_claim_until_approved() {
+        let mut config = config();
+        config.manual_approval = true;
+        let policy = new_policy(config);
+        let sender = mock::Sender::new();
+        let dapp_address = &policy.dapp_metadata.dapp_address;
+        let mut pending = PendingClaim::new(new_claim(0), Instant::now());
+        policy
+            .evaluate(&mut pending, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert!(policy.approvals.approve(
+            dapp_address,
+            0,
+            &pending.rollups_claim.epoch_hash
+        ));
+
+        let mut recomputed_claim = new_claim(0);
+        recomputed_claim.epoch_hash = [0xff; HASH_SIZE].into();
+        let mut pending = PendingClaim::new(recomputed_claim, Instant::now());
+        let decision = policy
+            .evaluate(&mut pending, &sender, Instant::now())
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Hold(HoldReason::AwaitingApproval));
+        assert_eq!(policy.approvals.held().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn evaluate_escalates_held_claim() {
+        let mut config = config();
+        config.manual_approval = true;
+        config.escalation_deadline = Some(Duration::from_secs(60));
+        let policy = new_policy(config);
+        let sender = mock::Sender::new();
+        let received_at = Instant::now();
+        let mut pending = PendingClaim::new(new_claim(0), received_at);
+        policy
+            .evaluate(&mut pending, &sender, received_at)
+            .await
+            .unwrap();
+        policy.approvals.approve(
+            &policy.dapp_metadata.dapp_address,
+            0,
+            &pending.rollups_claim.epoch_hash,
+        );
+        let decision = policy
+            .evaluate(
+                &mut pending,
+                &sender,
+                received_at + Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+        assert_eq!(decision, ClaimDecision::Submit(Priority::High));
+    }
+
+    // --------------------------------------------------------------------------------------------
+    // auxiliary
+    // --------------------------------------------------------------------------------------------
+
+    fn config() -> ClaimPolicyConfig {
+        ClaimPolicyConfig {
+            priority: Priority::Normal,
+            max_fee_per_gas: None,
+            escalation_deadline: None,
+            manual_approval: false,
+            approval_http_port: 0,
+        }
+    }
+
+    fn new_policy(config: ClaimPolicyConfig) -> ClaimPolicy {
+        ClaimPolicy::new(
+            config,
+            ClaimApprovals::default(),
+            DAppMetadata::default(),
+            DispatcherMetrics::default(),
+        )
+    }
+
+    fn new_claim(epoch_index: u64) -> RollupsClaim {
+        RollupsClaim {
+            epoch_hash: [epoch_index as u8; HASH_SIZE].into(),
+            epoch_index,
+            first_index: epoch_index as u128,
+            last_index: epoch_index as u128,
+        }
+    }
+}
@@ -6,7 +6,8 @@
 //! A `Sender` is an object capable of submiting a claim.
 //!
 //! The `ClaimSender` encapsulates the logic of submiting a claim through the
-//! transaction manager.
+//! transaction manager. Its `CappedGasOracle` keeps the fees of each
+//! transaction sent by the tx-manager below the claim policy ceiling.
 
 use async_trait::async_trait;
 use contracts::{authority::Authority, history::Claim};
@@ -17,15 +18,18 @@ use eth_state_fold_types::{
         abi::AbiEncode,
         middleware::SignerMiddleware,
         providers::{
-            Http, HttpRateLimitRetryPolicy, MockProvider, Provider, RetryClient,
+            Http, HttpRateLimitRetryPolicy, Middleware as _, MockProvider,
+            Provider, ProviderError, RetryClient,
         },
         signers::Signer,
-        types::{Address, Bytes, NameOrAddress},
+        types::{Address, Bytes, NameOrAddress, U256},
     },
 };
 use eth_tx_manager::{
     database::FileSystemDatabase as Database,
-    gas_oracle::DefaultGasOracle as GasOracle,
+    gas_oracle::{
+        EIP1559GasInfo, GasInfo, GasOracle, GasOracleInfo, LegacyGasInfo,
+    },
     manager::Configuration,
     time::DefaultTime as Time,
     transaction::{Priority, Transaction, Value},
@@ -34,7 +38,7 @@ use eth_tx_manager::{
 use rollups_events::{DAppMetadata, RollupsClaim};
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::sync::Arc;
-use tracing::{info, instrument, trace};
+use tracing::{info, instrument, trace, warn};
 use url::{ParseError, Url};
 
 use crate::{
@@ -50,17 +54,26 @@ pub trait Sender: std::fmt::Debug + Sized {
         self,
         dapp_address: Address,
         rollups_claim: RollupsClaim,
+        priority: Priority,
     ) -> Result<Self, SenderError>;
+
+    /// Estimates the max fee per gas (or the gas price, for legacy chains)
+    /// that a transaction sent right now would pay.
+    async fn max_fee_per_gas(&self) -> Result<U256, SenderError>;
 }
 
 type Middleware =
     Arc<SignerMiddleware<Provider<RetryClient<Http>>, ConditionalSigner>>;
 
-type TransactionManager =
-    eth_tx_manager::TransactionManager<Middleware, GasOracle, Database, Time>;
+type TransactionManager = eth_tx_manager::TransactionManager<
+    Middleware,
+    CappedGasOracle,
+    Database,
+    Time,
+>;
 
 type TrasactionManagerError =
-    eth_tx_manager::Error<Middleware, GasOracle, Database>;
+    eth_tx_manager::Error<Middleware, CappedGasOracle, Database>;
 
 #[derive(Debug, Snafu)]
 pub enum SenderError {
@@ -73,6 +86,9 @@ pub enum SenderError {
     #[snafu(display("Transaction manager error"))]
     TransactionManager { source: TrasactionManagerError },
 
+    #[snafu(display("Failed to estimate the max fee per gas"))]
+    GasEstimation { source: ProviderError },
+
     #[snafu(display("Internal ethers-rs error: tx `to` should not be null"))]
     InternalEthers,
 
@@ -82,6 +98,83 @@ pub enum SenderError {
     InternalConfig,
 }
 
+#[derive(Debug, Snafu)]
+pub enum CappedGasOracleError {
+    #[snafu(display(
+        "No max fee per gas ceiling, defaulting to the provider"
+    ))]
+    Uncapped,
+
+    #[snafu(display("Failed to estimate the fees with the provider"))]
+    FeeEstimation { source: ProviderError },
+}
+
+/// Gas oracle that estimates the fees with the provider, like the tx-manager
+/// does by default, and caps them at the claim policy ceiling. Because the
+/// tx-manager queries the oracle before each (re)submission, the ceiling holds
+/// even after the claim was cleared by the `ClaimPolicy`.
+#[derive(Debug, Clone)]
+pub struct CappedGasOracle {
+    middleware: Middleware,
+    is_legacy: bool,
+    max_fee_per_gas: Option<U256>,
+}
+
+#[async_trait]
+impl GasOracle for CappedGasOracle {
+    type Error = CappedGasOracleError;
+
+    async fn get_info(
+        &self,
+        _: Priority,
+    ) -> Result<GasOracleInfo, Self::Error> {
+        let ceiling = self.max_fee_per_gas.context(UncappedSnafu)?;
+        let provider = self.middleware.inner();
+        let gas_info = if self.is_legacy {
+            let gas_price =
+                provider.get_gas_price().await.context(FeeEstimationSnafu)?;
+            GasInfo::Legacy(LegacyGasInfo { gas_price })
+        } else {
+            let (max_fee, max_priority_fee) = provider
+                .estimate_eip1559_fees(None)
+                .await
+                .context(FeeEstimationSnafu)?;
+            GasInfo::EIP1559(EIP1559GasInfo {
+                max_fee,
+                max_priority_fee: Some(max_priority_fee),
+            })
+        };
+        Ok(GasOracleInfo {
+            gas_info: cap_gas_info(gas_info, ceiling),
+            mining_time: None,
+            block_time: None,
+        })
+    }
+}
+
+/// Caps the gas price (legacy) or the max fee (EIP1559) at the ceiling; the
+/// max priority fee can't be above the max fee.
+fn cap_gas_info(gas_info: GasInfo, ceiling: U256) -> GasInfo {
+    match gas_info {
+        GasInfo::Legacy(LegacyGasInfo { gas_price }) if gas_price > ceiling => {
+            warn!(%gas_price, %ceiling, "capping the gas price at the ceiling");
+            GasInfo::Legacy(LegacyGasInfo { gas_price: ceiling })
+        }
+        GasInfo::EIP1559(EIP1559GasInfo {
+            max_fee,
+            max_priority_fee,
+        }) if max_fee > ceiling => {
+            warn!(%max_fee, %ceiling, "capping the max fee per gas at the ceiling");
+            GasInfo::EIP1559(EIP1559GasInfo {
+                max_fee: ceiling,
+                max_priority_fee: max_priority_fee
+                    .map(|max_priority_fee| max_priority_fee.min(ceiling)),
+            })
+        }
+        gas_info => gas_info,
+    }
+}
+
 struct SubmittableClaim(Address, RollupsClaim);
 
 impl From<SubmittableClaim> for Bytes {
@@ -103,11 +196,12 @@ impl From<SubmittableClaim> for Bytes {
 #[derive(Debug)]
 pub struct ClaimSender {
     tx_manager: TransactionManager,
+    middleware: Middleware,
+    is_legacy: bool,
     confirmations: usize,
-    priority: Priority,
     from: Address,
     authority: Authority<Provider<MockProvider>>,
-    dapp_metadata: DAppMetadata,
+    chain_id: u64,
     metrics: DispatcherMetrics,
 }
 
@@ -132,10 +226,10 @@ fn create_middleware(
 
 /// Instantiates the tx-manager calling `new` or `force_new`.
 macro_rules! tx_manager {
-    ($new: ident, $middleware: expr, $database_path: expr, $chain: expr) => {
+    ($new: ident, $middleware: expr, $gas_oracle: expr, $database_path: expr, $chain: expr) => {
         TransactionManager::$new(
             $middleware.clone(),
-            GasOracle::new(),
+            $gas_oracle.clone(),
             Database::new($database_path.clone()),
             $chain,
             Configuration::default(),
@@ -147,18 +241,16 @@ macro_rules! tx_manager {
 /// Creates the tx-manager instance.
 /// NOTE: tries to re-instantiate the tx-manager only once.
 async fn create_tx_manager(
-    conditional_signer: &ConditionalSigner,
-    provider_url: String,
+    middleware: &Middleware,
+    gas_oracle: CappedGasOracle,
     database_path: String,
     chain: Chain,
 ) -> Result<TransactionManager, SenderError> {
-    let middleware =
-        create_middleware(conditional_signer.clone(), provider_url)?;
-    let result = tx_manager!(new, middleware, database_path, chain);
+    let result = tx_manager!(new, middleware, gas_oracle, database_path, chain);
     let tx_manager =
         if let Err(TrasactionManagerError::NonceTooLow { .. }) = result {
             info!("Nonce too low! Clearing the tx-manager database.");
-            tx_manager!(force_new, middleware, database_path, chain)
+            tx_manager!(force_new, middleware, gas_oracle, database_path, chain)
                 .context(TransactionManagerSnafu)?
         } else {
             let (tx_manager, receipt) =
@@ -172,19 +264,30 @@ async fn create_tx_manager(
 impl ClaimSender {
     pub async fn new(
         config: &DispatcherConfig,
-        dapp_metadata: DAppMetadata,
         metrics: DispatcherMetrics,
     ) -> Result<Self, SenderError> {
         let chain: Chain = (&config.tx_config).into();
+        let is_legacy = chain.is_legacy;
 
         let conditional_signer =
             ConditionalSigner::new(chain.id, &config.auth_config)
                 .await
                 .context(SignerSnafu)?;
 
-        let tx_manager = create_tx_manager(
-            &conditional_signer,
+        let from = conditional_signer.address();
+        let middleware = create_middleware(
+            conditional_signer,
             config.tx_config.provider_http_endpoint.clone(),
+        )?;
+
+        let gas_oracle = CappedGasOracle {
+            middleware: middleware.clone(),
+            is_legacy,
+            max_fee_per_gas: config.claim_policy_config.max_fee_per_gas,
+        };
+        let tx_manager = create_tx_manager(
+            &middleware,
+            gas_oracle,
             config.tx_config.database_path.clone(),
             chain,
         )
@@ -201,11 +304,12 @@ impl ClaimSender {
 
         Ok(Self {
             tx_manager,
+            middleware,
+            is_legacy,
             confirmations: config.tx_config.default_confirmations,
-            priority: config.priority,
-            from: conditional_signer.address(),
+            from,
             authority,
-            dapp_metadata,
+            chain_id: config.tx_config.chain_id,
             metrics,
         })
     }
@@ -218,6 +322,7 @@ impl Sender for ClaimSender {
         self,
         dapp_address: Address,
         rollups_claim: RollupsClaim,
+        priority: Priority,
     ) -> Result<Self, SenderError> {
         let transaction = {
             let submittable_claim =
@@ -242,15 +347,74 @@ impl Sender for ClaimSender {
 
         let (tx_manager, receipt) = self
             .tx_manager
-            .send_transaction(transaction, self.confirmations, self.priority)
+            .send_transaction(transaction, self.confirmations, priority)
             .await
             .context(TransactionManagerSnafu)?;
-        self.metrics
-            .claims_sent
-            .get_or_create(&self.dapp_metadata)
-            .inc();
+        let dapp_metadata = DAppMetadata {
+            chain_id: self.chain_id,
+            dapp_address: rollups_events::Address::new(dapp_address.into()),
+        };
+        self.metrics.claims_sent.get_or_create(&dapp_metadata).inc();
         trace!("Claim transaction confirmed: `{:?}`", receipt);
 
         Ok(Self { tx_manager, ..self })
     }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn max_fee_per_gas(&self) -> Result<U256, SenderError> {
+        let provider = self.middleware.inner();
+        let max_fee_per_gas = if self.is_legacy {
+            provider.get_gas_price().await
+        } else {
+            provider
+                .estimate_eip1559_fees(None)
+                .await
+                .map(|(max_fee, _)| max_fee)
+        }
+        .context(GasEstimationSnafu)?;
+        trace!("Estimated max fee per gas: `{}`", max_fee_per_gas);
+        Ok(max_fee_per_gas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_state_fold_types::ethers::types::U256;
+    use eth_tx_manager::gas_oracle::{EIP1559GasInfo, GasInfo, LegacyGasInfo};
+
+    use super::cap_gas_info;
+
+    #[test]
+    fn cap_legacy_gas_price() {
+        let capped = |gas_price: u64| {
+            let gas_info = GasInfo::Legacy(LegacyGasInfo {
+                gas_price: gas_price.into(),
+            });
+            match cap_gas_info(gas_info, U256::from(10)) {
+                GasInfo::Legacy(gas_info) => gas_info.gas_price,
+                GasInfo::EIP1559(_) => panic!("expected legacy gas info"),
+            }
+        };
+        assert_eq!(capped(9), U256::from(9));
+        assert_eq!(capped(11), U256::from(10));
+    }
+
+    #[test]
+    fn cap_eip1559_max_fee() {
+        let capped = |max_fee: u64, max_priority_fee: u64| {
+            let gas_info = GasInfo::EIP1559(EIP1559GasInfo {
+                max_fee: max_fee.into(),
+                max_priority_fee: Some(max_priority_fee.into()),
+            });
+            match cap_gas_info(gas_info, U256::from(10)) {
+                GasInfo::EIP1559(gas_info) => {
+                    (gas_info.max_fee, gas_info.max_priority_fee.unwrap())
+                }
+                GasInfo::Legacy(_) => panic!("expected EIP1559 gas info"),
+            }
+        };
+        assert_eq!(capped(9, 2), (U256::from(9), U256::from(2)));
+        assert_eq!(capped(20, 2), (U256::from(10), U256::from(2)));
+        assert_eq!(capped(20, 15), (U256::from(10), U256::from(10)));
+    }
 }
@@ -6,19 +6,23 @@ use eth_state_client_lib::{
     GrpcStateFoldClient, StateServer,
 };
 use eth_state_fold_types::BlockStreamItem;
-use rollups_events::DAppMetadata;
+use rollups_events::{DAppMetadata, RollupsInput};
 use snafu::ResultExt;
 use tokio_stream::{Stream, StreamExt};
 use tonic::transport::Channel;
-use types::foldables::authority::{RollupsInitialState, RollupsState};
+use types::{
+    deployment_files::dapp_deployment::DappDeployment,
+    foldables::authority::{RollupsInitialState, RollupsState},
+};
 
 use crate::{
     config::DispatcherConfig,
-    drivers::Context,
+    drivers::{Context, EpochTracker},
     error::{
         BrokerSnafu, ChannelSnafu, ConnectSnafu, DispatcherError,
         StateServerSnafu,
     },
+    health::ChainHealth,
     machine::BrokerStatus,
     metrics::DispatcherMetrics,
 };
@@ -44,6 +48,7 @@ pub async fn create_state_server(
 pub async fn create_block_subscription(
     client: &impl BlockServer,
     confirmations: usize,
+    health: ChainHealth,
 ) -> Result<
     impl Stream<Item = Result<BlockStreamItem, StateServerError>>
         + Send
@@ -60,36 +65,43 @@ pub async fn create_block_subscription(
         s.ready_chunks(BUFFER_LEN)
     };
 
-    let s = s.filter_map(
-        |mut x| {
-            if x.len() == BUFFER_LEN {
-                None
-            } else {
-                x.pop()
-            }
-        },
-    );
+    let s = s.filter_map(move |mut x| {
+        let dropped = x.len() == BUFFER_LEN;
+        health.observe_block_chunk(x.len(), dropped);
+        if dropped {
+            None
+        } else {
+            x.pop()
+        }
+    });
 
     Ok(s)
 }
 
 pub async fn create_context(
     config: &DispatcherConfig,
+    dapp_deployment: &DappDeployment,
     block_server: &impl BlockServer,
     broker: &impl BrokerStatus,
+    input_events: &[RollupsInput],
     dapp_metadata: DAppMetadata,
     metrics: DispatcherMetrics,
 ) -> Result<Context, DispatcherError> {
-    let genesis_timestamp: u64 = block_server
-        .query_block(config.dapp_deployment.deploy_block_hash)
+    let genesis_block = block_server
+        .query_block(dapp_deployment.deploy_block_hash)
         .await
-        .context(StateServerSnafu)?
-        .timestamp
-        .as_u64();
+        .context(StateServerSnafu)?;
+    let genesis_timestamp: u64 = genesis_block.timestamp.as_u64();
     let epoch_length = config.epoch_duration;
+    let mut epoch_tracker = EpochTracker::new(
+        config.epoch_policy.clone(),
+        genesis_block.number.as_u64(),
+    );
+    epoch_tracker.restore(input_events);
     let context = Context::new(
         genesis_timestamp,
         epoch_length,
+        epoch_tracker,
         broker,
         dapp_metadata,
         metrics,
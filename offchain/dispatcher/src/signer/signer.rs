@@ -58,6 +58,13 @@ impl ConditionalSigner {
                     .with_chain_id(chain_id);
                 Ok(ConditionalSigner::LocalWallet(wallet))
             }
+            AuthConfig::Keystore { path, passphrase } => {
+                let wallet =
+                    LocalWallet::decrypt_keystore(path, passphrase.inner())
+                        .context(LocalWalletSnafu)?
+                        .with_chain_id(chain_id);
+                Ok(ConditionalSigner::LocalWallet(wallet))
+            }
             AuthConfig::Aws { key_id, region } => {
                 AwsSigner::new(key_id, chain_id, region)
                     .await
@@ -152,7 +159,8 @@ mod tests {
         transaction::{eip2718::TypedTransaction, eip2930::AccessList},
         Address, Eip1559TransactionRequest,
     };
-    use ethers_signers::Signer;
+    use ethers_signers::{LocalWallet, Signer};
+    use redacted::Redacted;
 
     use crate::{auth::AuthConfig, signer::ConditionalSigner};
 
@@ -169,6 +177,49 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn new_keystore_conditional_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let (wallet, _) = LocalWallet::new_keystore(
+            dir.path(),
+            &mut rand::thread_rng(),
+            PASSPHRASE,
+            Some("keystore.json"),
+        )
+        .unwrap();
+        let auth_config = AuthConfig::Keystore {
+            path: dir.path().join("keystore.json").display().to_string(),
+            passphrase: Redacted::new(PASSPHRASE.to_string()),
+        };
+        let conditional_signer = ConditionalSigner::new(CHAIN_ID, &auth_config)
+            .await
+            .unwrap();
+        assert!(matches!(
+            conditional_signer,
+            ConditionalSigner::LocalWallet(_)
+        ));
+        assert_eq!(conditional_signer.address(), wallet.address());
+        assert_eq!(conditional_signer.chain_id(), CHAIN_ID);
+    }
+
+    #[tokio::test]
+    async fn new_keystore_conditional_signer_with_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        LocalWallet::new_keystore(
+            dir.path(),
+            &mut rand::thread_rng(),
+            PASSPHRASE,
+            Some("keystore.json"),
+        )
+        .unwrap();
+        let auth_config = AuthConfig::Keystore {
+            path: dir.path().join("keystore.json").display().to_string(),
+            passphrase: Redacted::new("wrong passphrase".to_string()),
+        };
+        let result = ConditionalSigner::new(CHAIN_ID, &auth_config).await;
+        assert!(result.is_err());
+    }
+
     // --------------------------------------------------------------------------------------------
     // sign_transaction
     // --------------------------------------------------------------------------------------------
@@ -188,6 +239,7 @@ mod tests {
     const CHAIN_ID: u64 = 1;
     const MNEMONIC: &str =
         "indoor dish desk flag debris potato excuse depart ticket judge file exit";
+    const PASSPHRASE: &str = "correct horse battery staple";
 
     async fn local_wallet_conditional_signer() -> ConditionalSigner {
         let auth_config = AuthConfig::Mnemonic {
@@ -4,6 +4,12 @@
 use clap::Parser;
 use log::{LogConfig, LogEnvCliConfig};
 use rollups_data::{RepositoryCLIConfig, RepositoryConfig};
+use std::time::Duration;
+
+use crate::limits::{
+    QueryLimits, DEFAULT_MAX_BODY_SIZE, DEFAULT_MAX_COMPLEXITY,
+    DEFAULT_MAX_DEPTH, DEFAULT_REQUEST_TIMEOUT_MS,
+};
 
 #[derive(Debug)]
 pub struct GraphQLConfig {
@@ -12,6 +18,7 @@ pub struct GraphQLConfig {
     pub graphql_host: String,
     pub graphql_port: u16,
     pub healthcheck_port: u16,
+    pub query_limits: QueryLimits,
 }
 
 #[derive(Parser)]
@@ -33,6 +40,24 @@ pub struct CLIConfig {
     /// Port of health check
     #[arg(long, env = "GRAPHQL_HEALTHCHECK_PORT", default_value_t = 8080)]
     pub healthcheck_port: u16,
+
+    /// Max number of nested fields in a query
+    #[arg(long, env, default_value_t = DEFAULT_MAX_DEPTH)]
+    pub graphql_max_depth: usize,
+
+    /// Max complexity of a query, the estimated number of fields it
+    /// resolves, where each connection multiplies the fields of its nodes by
+    /// its page size
+    #[arg(long, env, default_value_t = DEFAULT_MAX_COMPLEXITY)]
+    pub graphql_max_complexity: u64,
+
+    /// Max size in bytes of the request body
+    #[arg(long, env, default_value_t = DEFAULT_MAX_BODY_SIZE)]
+    pub graphql_max_body_size: usize,
+
+    /// Duration in ms for a query to timeout
+    #[arg(long, env, default_value_t = DEFAULT_REQUEST_TIMEOUT_MS)]
+    pub graphql_request_timeout: u64,
 }
 
 impl From<CLIConfig> for GraphQLConfig {
@@ -43,6 +68,14 @@ impl From<CLIConfig> for GraphQLConfig {
             graphql_host: cli_config.graphql_host,
             graphql_port: cli_config.graphql_port,
             healthcheck_port: cli_config.healthcheck_port,
+            query_limits: QueryLimits {
+                max_depth: cli_config.graphql_max_depth,
+                max_complexity: cli_config.graphql_max_complexity,
+                max_body_size: cli_config.graphql_max_body_size,
+                request_timeout: Duration::from_millis(
+                    cli_config.graphql_request_timeout,
+                ),
+            },
         }
     }
 }
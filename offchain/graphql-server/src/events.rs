@@ -0,0 +1,44 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use rollups_data::{NotificationListener, RepositoryEvent};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Forward the events notified by the database to the subscriptions,
+/// reconnecting to the database when the connection fails; it never returns
+///
+/// The events sent while the listener is disconnected are lost.
+pub async fn forward_events(
+    endpoint: String,
+    events: broadcast::Sender<RepositoryEvent>,
+) {
+    loop {
+        let mut listener = match NotificationListener::connect(&endpoint).await
+        {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::warn!("failed to listen to database events ({})", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        loop {
+            match listener.recv().await {
+                // Fails only when there are no subscriptions
+                Ok(event) => {
+                    let _ = events.send(event);
+                }
+                Err(e @ rollups_data::Error::ParseNotificationError { .. }) => {
+                    tracing::warn!("ignoring database event ({})", e);
+                }
+                Err(e) => {
+                    tracing::warn!("stopped receiving database events ({})", e);
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,53 +1,132 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
-use crate::schema::{Context, Query, RollupsGraphQLScalarValue, Schema};
+use crate::limits::QueryLimits;
+use crate::rest;
+use crate::schema::{
+    Context, Query, RollupsGraphQLScalarValue, Schema, Subscription,
+};
+use crate::subscriptions::subscriptions_handler;
 use actix_cors::Cors;
 use actix_web::dev::Server;
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::guard::GuardContext;
+use actix_web::http::header;
 use actix_web::{
-    middleware::Logger, web, web::Data, App, HttpResponse, HttpServer,
-    Responder,
+    middleware::Logger, web, web::Data, App, HttpRequest, HttpResponse,
+    HttpResponseBuilder, HttpServer, Responder,
 };
 use juniper::http::playground::playground_source;
 use juniper::http::GraphQLRequest;
-use juniper::{EmptyMutation, EmptySubscription};
+use juniper::{EmptyMutation, InputValue, RuleError};
+use juniper_graphql_ws::ConnectionConfig;
+use serde::Deserialize;
 use std::sync::Arc;
+use std::time::Duration;
+
+const SUBSCRIPTIONS_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
 
 struct HttpContext {
     schema: Arc<Schema>,
     context: Context,
+    limits: QueryLimits,
+}
+
+/// Body of the GraphQL requests, read before creating the juniper request so
+/// the query can be checked against the limits
+#[derive(Deserialize)]
+struct GraphQLPayload {
+    query: String,
+    #[serde(rename = "operationName")]
+    operation_name: Option<String>,
+    variables: Option<InputValue<RollupsGraphQLScalarValue>>,
 }
 
 pub fn start_service(
     host: &str,
     port: u16,
     context: Context,
+    limits: QueryLimits,
 ) -> std::io::Result<Server> {
     Ok(HttpServer::new(move || {
         let schema = std::sync::Arc::new(Schema::new_with_scalar_value(
             Query,
             EmptyMutation::new(),
-            EmptySubscription::new(),
+            Subscription,
         ));
 
+        let json_config = web::JsonConfig::default()
+            .limit(limits.max_body_size)
+            .error_handler(|err, _| match err {
+                JsonPayloadError::OverflowKnownLength { limit, .. }
+                | JsonPayloadError::Overflow { limit } => {
+                    let error = RuleError::new(
+                        &format!(
+                            "Request body exceeds the maximum size of {} bytes",
+                            limit
+                        ),
+                        &[],
+                    );
+                    let response =
+                        error_response(HttpResponse::PayloadTooLarge(), error);
+                    InternalError::from_response(err, response).into()
+                }
+                err => err.into(),
+            });
+
+        let repository = context.repository().clone();
         let http_context = HttpContext {
             schema: schema.clone(),
             context: context.clone(),
+            limits: limits.clone(),
         };
 
         let cors = Cors::permissive();
 
         App::new()
             .app_data(Data::new(http_context))
+            .app_data(json_config)
             .wrap(Logger::default())
             .wrap(cors)
             .service(graphql)
+            .service(subscriptions)
             .service(juniper_playground)
+            .app_data(Data::new(repository))
+            .configure(|cfg| rest::configure(cfg, limits.request_timeout))
     })
     .bind((host, port))?
     .run())
 }
 
+/// Serve the subscriptions with the graphql-ws protocol when the client
+/// upgrades the connection to a WebSocket; the operations are checked
+/// against the same limits of the queries
+#[actix_web::get("/graphql", guard = "is_websocket_upgrade")]
+async fn subscriptions(
+    req: HttpRequest,
+    stream: web::Payload,
+    http_context: web::Data<HttpContext>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let config = ConnectionConfig::new(http_context.context.clone())
+        .with_keep_alive_interval(SUBSCRIPTIONS_KEEP_ALIVE_INTERVAL);
+    subscriptions_handler(
+        req,
+        stream,
+        http_context.schema.clone(),
+        config,
+        http_context.limits.clone(),
+    )
+    .await
+}
+
+fn is_websocket_upgrade(ctx: &GuardContext) -> bool {
+    ctx.head()
+        .headers()
+        .get(header::UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
 #[actix_web::get("/graphql")]
 async fn juniper_playground() -> impl Responder {
     let html = playground_source("", None);
@@ -58,38 +137,61 @@ async fn juniper_playground() -> impl Responder {
 
 #[actix_web::post("/graphql")]
 async fn graphql(
-    query: web::Json<GraphQLRequest<RollupsGraphQLScalarValue>>,
+    payload: web::Json<GraphQLPayload>,
     http_context: web::Data<HttpContext>,
 ) -> HttpResponse {
-    // Execute resolvers in blocking thread as there are lot of blocking diesel db operations
-    let query = Arc::new(query);
-    let return_value: HttpResponse = match tokio::task::spawn_blocking(
-        move || {
-            let res =
-                query.execute_sync(&http_context.schema, &http_context.context);
-            serde_json::to_string(&res)
-        },
+    let payload = payload.into_inner();
+    let limits = &http_context.limits;
+    if let Err(error) = limits.check(
+        &http_context.schema.schema,
+        &payload.query,
+        payload.variables.as_ref(),
+    ) {
+        return error_response(HttpResponse::Ok(), error);
+    }
+    let query = GraphQLRequest::new(
+        payload.query,
+        payload.operation_name,
+        payload.variables,
+    );
+    // Each request batches and caches the nested lookups on its own context
+    let context = http_context.context.for_request();
+    let res = match tokio::time::timeout(
+        limits.request_timeout,
+        query.execute(&http_context.schema, &context),
     )
     .await
     {
-        Ok(value) => match value {
-            Ok(value) => HttpResponse::Ok()
-                .content_type("application/json")
-                .body(value),
-            Err(err) => {
-                let error_message = format!(
-                            "unable to execute query, internal server error, details: {}", err
-                        );
-                return HttpResponse::BadRequest().body(error_message);
-            }
-        },
+        Ok(res) => res,
+        Err(_) => {
+            let error = RuleError::new(
+                &format!(
+                    "Query exceeded the timeout of {} ms",
+                    limits.request_timeout.as_millis()
+                ),
+                &[],
+            );
+            return error_response(HttpResponse::Ok(), error);
+        }
+    };
+    match serde_json::to_string(&res) {
+        Ok(value) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(value),
         Err(err) => {
             let error_message = format!(
                 "unable to execute query, internal server error, details: {}",
                 err
             );
-            return HttpResponse::BadRequest().body(error_message);
+            HttpResponse::BadRequest().body(error_message)
         }
-    };
-    return_value
+    }
+}
+
+/// Respond with the error in the format of the GraphQL errors
+fn error_response(
+    mut response: HttpResponseBuilder,
+    error: RuleError,
+) -> HttpResponse {
+    response.json(serde_json::json!({ "errors": [error] }))
 }
@@ -2,25 +2,43 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 use snafu::ResultExt;
+use tokio::sync::broadcast;
 
 pub use config::{CLIConfig, GraphQLConfig};
 pub use error::GraphQLServerError;
+pub use events::forward_events;
 pub use http::start_service;
+pub use limits::QueryLimits;
 pub use schema::Context;
 
 pub mod config;
 mod error;
+mod events;
 pub mod http;
+mod limits;
+pub mod rest;
 pub mod schema;
+mod subscriptions;
+
+/// Number of events buffered for each subscription
+const EVENTS_CHANNEL_CAPACITY: usize = 1024;
 
 #[tracing::instrument(level = "trace", skip_all)]
 pub async fn run(config: GraphQLConfig) -> Result<(), GraphQLServerError> {
+    let endpoint = config.repository_config.endpoint();
     let repository = rollups_data::Repository::new(config.repository_config)
+        .await
         .expect("failed to connect to database");
-    let context = Context::new(repository);
-    let service_handler =
-        start_service(&config.graphql_host, config.graphql_port, context)
-            .expect("failed to create server");
+    let (events_tx, _) = broadcast::channel(EVENTS_CHANNEL_CAPACITY);
+    let context = Context::new(repository, events_tx.clone());
+    tokio::spawn(forward_events(endpoint, events_tx));
+    let service_handler = start_service(
+        &config.graphql_host,
+        config.graphql_port,
+        context,
+        config.query_limits,
+    )
+    .expect("failed to create server");
 
     let health_handle = http_health_check::start(config.healthcheck_port);
 
@@ -0,0 +1,372 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use juniper::meta::MetaType;
+use juniper::parser::{parse_document_source, SourcePosition};
+use juniper::{
+    Definition, InputValue, OperationType, RuleError, ScalarValue, SchemaType,
+    Selection,
+};
+use rollups_data::DEFAULT_PAGINATION_LIMIT;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub const DEFAULT_MAX_DEPTH: usize = 15;
+pub const DEFAULT_MAX_COMPLEXITY: u64 = 10_000_000;
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+pub const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 30_000;
+
+/// Arguments that set the number of nodes returned by a connection
+const PAGE_SIZE_ARGUMENTS: [&str; 2] = ["first", "last"];
+
+/// Limits applied to each request of the GraphQL endpoint
+#[derive(Debug, Clone)]
+pub struct QueryLimits {
+    /// Max number of nested fields in a query
+    pub max_depth: usize,
+    /// Max estimate of the number of fields resolved by a query
+    pub max_complexity: u64,
+    /// Max size in bytes of the request body
+    pub max_body_size: usize,
+    /// Max time to execute a query
+    pub request_timeout: Duration,
+}
+
+impl Default for QueryLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_complexity: DEFAULT_MAX_COMPLEXITY,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            request_timeout: Duration::from_millis(DEFAULT_REQUEST_TIMEOUT_MS),
+        }
+    }
+}
+
+impl QueryLimits {
+    /// Check the depth and the complexity of the operations in the query
+    /// before executing it; the queries that can't be parsed are left for
+    /// the executor to report.
+    ///
+    /// The complexity of a field is one plus the complexity of its
+    /// selection, multiplied by the page size for connections, which is
+    /// given by the `first` and `last` arguments or defaults to the max
+    /// page size.
+    pub fn check<S: ScalarValue>(
+        &self,
+        schema: &SchemaType<S>,
+        query: &str,
+        variables: Option<&InputValue<S>>,
+    ) -> Result<(), RuleError> {
+        let document = match parse_document_source(query, schema) {
+            Ok(document) => document,
+            Err(_) => return Ok(()),
+        };
+        let analyzer = Analyzer {
+            schema,
+            fragments: document
+                .iter()
+                .filter_map(|definition| match definition {
+                    Definition::Fragment(fragment) => {
+                        let fragment = &fragment.item;
+                        Some((
+                            fragment.name.item,
+                            (
+                                fragment.type_condition.item,
+                                &fragment.selection_set[..],
+                            ),
+                        ))
+                    }
+                    Definition::Operation(_) => None,
+                })
+                .collect(),
+            variables: variables
+                .and_then(|variables| variables.to_object_value())
+                .map(|variables| variables.into_iter().collect())
+                .unwrap_or_default(),
+            fragment_costs: RefCell::new(HashMap::new()),
+        };
+        for definition in document.iter() {
+            if let Definition::Operation(operation) = definition {
+                let root = match operation.item.operation_type {
+                    OperationType::Query => Some(schema.concrete_query_type()),
+                    OperationType::Mutation => schema.concrete_mutation_type(),
+                    OperationType::Subscription => {
+                        schema.concrete_subscription_type()
+                    }
+                };
+                let cost = analyzer
+                    .selection_cost(&operation.item.selection_set, root);
+                if cost.depth > self.max_depth {
+                    return Err(RuleError::new(
+                        &format!(
+                            "Query has depth {}, which exceeds the maximum depth of {}",
+                            cost.depth, self.max_depth
+                        ),
+                        &[cost.deepest.unwrap_or(operation.start)],
+                    ));
+                }
+                if cost.complexity > self.max_complexity {
+                    return Err(RuleError::new(
+                        &format!(
+                            "Query has complexity {}, which exceeds the maximum complexity of {}; \
+                             reduce the nesting or the page sizes with the first and last arguments",
+                            cost.complexity, self.max_complexity
+                        ),
+                        &[operation.start],
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Cost {
+    depth: usize,
+    complexity: u64,
+    /// Position of the field at the max depth
+    deepest: Option<SourcePosition>,
+}
+
+impl Cost {
+    fn add(&mut self, other: Cost) {
+        if other.depth > self.depth {
+            self.depth = other.depth;
+            self.deepest = other.deepest;
+        }
+        self.complexity = self.complexity.saturating_add(other.complexity);
+    }
+}
+
+struct Analyzer<'a, S> {
+    schema: &'a SchemaType<'a, S>,
+    fragments: HashMap<&'a str, (&'a str, &'a [Selection<'a, S>])>,
+    variables: HashMap<&'a str, &'a InputValue<S>>,
+    /// Cost of the fragments already analyzed, or None while analyzing one,
+    /// so the fragment spreads are analyzed once and the cycles are broken
+    fragment_costs: RefCell<HashMap<&'a str, Option<Cost>>>,
+}
+
+impl<'a, S: ScalarValue> Analyzer<'a, S> {
+    fn selection_cost(
+        &self,
+        selection_set: &'a [Selection<'a, S>],
+        parent: Option<&'a MetaType<'a, S>>,
+    ) -> Cost {
+        let mut cost = Cost::default();
+        for selection in selection_set {
+            match selection {
+                Selection::Field(field) => cost.add(
+                    self.field_cost(
+                        field.item.name.item,
+                        field.start,
+                        field.item.selection_set.as_deref(),
+                        field
+                            .item
+                            .arguments
+                            .iter()
+                            .flat_map(|arguments| arguments.item.items.iter())
+                            .map(|(name, value)| (name.item, &value.item)),
+                        parent,
+                    ),
+                ),
+                Selection::FragmentSpread(spread) => {
+                    cost.add(self.fragment_cost(spread.item.name.item))
+                }
+                Selection::InlineFragment(fragment) => {
+                    let parent = match &fragment.item.type_condition {
+                        Some(type_condition) => self
+                            .schema
+                            .concrete_type_by_name(type_condition.item),
+                        None => parent,
+                    };
+                    cost.add(
+                        self.selection_cost(
+                            &fragment.item.selection_set,
+                            parent,
+                        ),
+                    )
+                }
+            }
+        }
+        cost
+    }
+
+    fn field_cost(
+        &self,
+        name: &str,
+        position: SourcePosition,
+        selection_set: Option<&'a [Selection<'a, S>]>,
+        arguments: impl Iterator<Item = (&'a str, &'a InputValue<S>)>,
+        parent: Option<&'a MetaType<'a, S>>,
+    ) -> Cost {
+        // The introspection fields resolve the static schema
+        if name.starts_with("__") {
+            return Cost::default();
+        }
+        let meta = parent.and_then(|parent| parent.field_by_name(name));
+        let field_type = meta.and_then(|meta| {
+            self.schema
+                .concrete_type_by_name(meta.field_type.innermost_name())
+        });
+        let selection = match selection_set {
+            Some(selection_set) => {
+                self.selection_cost(selection_set, field_type)
+            }
+            None => Cost::default(),
+        };
+        let is_connection = meta
+            .and_then(|meta| meta.arguments.as_ref())
+            .is_some_and(|arguments| {
+                arguments.iter().any(|argument| {
+                    PAGE_SIZE_ARGUMENTS.contains(&&*argument.name)
+                })
+            });
+        let multiplier = if is_connection {
+            self.page_size(arguments)
+        } else {
+            1
+        };
+        Cost {
+            depth: selection.depth + 1,
+            complexity: selection
+                .complexity
+                .saturating_mul(multiplier)
+                .saturating_add(1),
+            deepest: selection.deepest.or(Some(position)),
+        }
+    }
+
+    fn fragment_cost(&self, name: &'a str) -> Cost {
+        if let Some(cost) = self.fragment_costs.borrow().get(name) {
+            return cost.unwrap_or_default();
+        }
+        let (type_condition, selection_set) = match self.fragments.get(name) {
+            Some(&fragment) => fragment,
+            None => return Cost::default(),
+        };
+        self.fragment_costs.borrow_mut().insert(name, None);
+        let parent = self.schema.concrete_type_by_name(type_condition);
+        let cost = self.selection_cost(selection_set, parent);
+        self.fragment_costs.borrow_mut().insert(name, Some(cost));
+        cost
+    }
+
+    /// Number of nodes requested from a connection
+    fn page_size(
+        &self,
+        arguments: impl Iterator<Item = (&'a str, &'a InputValue<S>)>,
+    ) -> u64 {
+        arguments
+            .filter(|(name, _)| PAGE_SIZE_ARGUMENTS.contains(name))
+            .filter_map(|(_, value)| match value {
+                InputValue::Variable(variable) => self
+                    .variables
+                    .get(variable.as_str())
+                    .and_then(|value| value.as_int_value()),
+                value => value.as_int_value(),
+            })
+            .filter(|size| *size >= 0)
+            .map(|size| size.min(DEFAULT_PAGINATION_LIMIT))
+            .min()
+            .unwrap_or(DEFAULT_PAGINATION_LIMIT) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{
+        Query, RollupsGraphQLScalarValue, Schema, Subscription,
+    };
+    use juniper::EmptyMutation;
+
+    fn check(
+        query: &str,
+        variables: Option<&InputValue<RollupsGraphQLScalarValue>>,
+        max_depth: usize,
+        max_complexity: u64,
+    ) -> Result<(), String> {
+        let schema = Schema::new_with_scalar_value(
+            Query,
+            EmptyMutation::new(),
+            Subscription,
+        );
+        let limits = QueryLimits {
+            max_depth,
+            max_complexity,
+            ..Default::default()
+        };
+        limits
+            .check(&schema.schema, query, variables)
+            .map_err(|error| error.message().to_owned())
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let query = "{ inputs { edges { node { epoch { index } } } } }";
+        assert!(check(query, None, 5, u64::MAX).is_ok());
+        assert_eq!(
+            check(query, None, 4, u64::MAX).unwrap_err(),
+            "Query has depth 5, which exceeds the maximum depth of 4"
+        );
+    }
+
+    #[test]
+    fn test_max_complexity() {
+        // 1 + 10 * (edges: 1 + (node: 1 + index: 1))
+        let query = "{ inputs(first: 10) { edges { node { index } } } }";
+        assert!(check(query, None, usize::MAX, 31).is_ok());
+        assert!(check(query, None, usize::MAX, 30)
+            .unwrap_err()
+            .starts_with("Query has complexity 31"));
+    }
+
+    #[test]
+    fn test_complexity_of_nested_connections() {
+        // The connections without page size request the max page size
+        let query = "{
+            inputs { edges { node { vouchers(last: 2) { totalCount } } } }
+        }";
+        let complexity = 1 + DEFAULT_PAGINATION_LIMIT as u64 * (1 + 1 + 2 + 1);
+        assert!(check(query, None, usize::MAX, complexity).is_ok());
+        assert!(check(query, None, usize::MAX, complexity - 1).is_err());
+    }
+
+    #[test]
+    fn test_complexity_with_variables() {
+        let query = "query($first: Int) {
+            inputs(first: $first) { edges { node { index } } }
+        }";
+        let variables = InputValue::object(
+            vec![("first", InputValue::scalar(5))].into_iter().collect(),
+        );
+        assert!(check(query, Some(&variables), usize::MAX, 16).is_ok());
+        assert!(check(query, Some(&variables), usize::MAX, 15).is_err());
+    }
+
+    #[test]
+    fn test_fragments() {
+        let query = "
+            { inputs(first: 10) { edges { node { ...InputFields } } } }
+            fragment InputFields on Input { index epoch { index } }
+        ";
+        // 1 + 10 * (edges: 1 + (node: 1 + index: 1 + epoch: 2))
+        assert!(check(query, None, 5, 51).is_ok());
+        assert!(check(query, None, 4, u64::MAX).is_err());
+        assert!(check(query, None, usize::MAX, 50).is_err());
+    }
+
+    #[test]
+    fn test_fragment_cycles_and_introspection_are_ignored() {
+        let query = "
+            { __schema { types { name } } input(index: 0) { ...A } }
+            fragment A on Input { index ...B }
+            fragment B on Input { ...A }
+        ";
+        assert!(check(query, None, 2, 2).is_ok());
+    }
+}
@@ -0,0 +1,53 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use rollups_data::Error as DataError;
+use snafu::Snafu;
+
+use super::models::ErrorResponse;
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(super)))]
+pub enum RestError {
+    #[snafu(display("{}", source))]
+    DataError { source: DataError },
+
+    #[snafu(display("{}", message))]
+    InvalidParameterError { message: String },
+
+    #[snafu(display("Request exceeded the timeout of {} ms", timeout_ms))]
+    TimeoutError { timeout_ms: u128 },
+}
+
+impl ResponseError for RestError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            RestError::DataError { source } => match source {
+                DataError::ItemNotFound { .. } => StatusCode::NOT_FOUND,
+                DataError::DecodeUTF8CursorError { .. }
+                | DataError::DecodeBase64CursorError { .. }
+                | DataError::ParseCursorError { .. }
+                | DataError::MixedPaginationError { .. }
+                | DataError::PaginationCursorError { .. }
+                | DataError::PaginationLimitError { .. } => {
+                    StatusCode::BAD_REQUEST
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            RestError::InvalidParameterError { .. } => StatusCode::BAD_REQUEST,
+            RestError::TimeoutError { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            tracing::warn!("Got error during request: {:?}", self);
+        }
+        HttpResponse::build(status).json(ErrorResponse {
+            error: self.to_string(),
+        })
+    }
+}
@@ -0,0 +1,23 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use std::fs::File;
+use std::io::Write;
+use utoipa::OpenApi;
+
+use graphql_server::rest::ApiDoc;
+
+const OPENAPI_FILE: &str = "openapi.json";
+
+fn main() {
+    let openapi = ApiDoc::openapi()
+        .to_pretty_json()
+        .expect("failed to serialize OpenAPI document");
+    let mut openapi_file = File::create(OPENAPI_FILE).unwrap();
+    match write!(openapi_file, "{}", openapi) {
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error writing OpenAPI document to file {}", e);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use actix_web::{get, web};
+use rollups_data::{
+    InputQueryFilter, NoticeQueryFilter, ReportQueryFilter, Repository,
+    VoucherQueryFilter,
+};
+use snafu::ResultExt;
+
+use super::error::{DataSnafu, InvalidParameterSnafu, RestError};
+use super::models::{
+    Input, InputPage, InputParams, NoticePage, OutputParams, PageParams,
+    ReportPage, VoucherPage, VoucherParams,
+};
+use crate::schema::hex_decode;
+
+type RestResult<T> = Result<web::Json<T>, RestError>;
+
+/// List the inputs
+#[utoipa::path(
+    tag = "inputs",
+    params(PageParams, InputParams),
+    responses(
+        (status = 200, description = "Page of inputs", body = InputPage),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+    ),
+)]
+#[get("/inputs")]
+async fn get_inputs(
+    page: web::Query<PageParams>,
+    params: web::Query<InputParams>,
+    repository: web::Data<Repository>,
+) -> RestResult<InputPage> {
+    let params = params.into_inner();
+    let filter = InputQueryFilter {
+        msg_sender: params.msg_sender.as_deref().map(decode).transpose()?,
+        status: params.status.map(Into::into),
+        epoch_index: params.epoch_index,
+        ..Default::default()
+    };
+    let page = page.into_inner();
+    let connection = repository
+        .get_inputs(page.first, page.last, page.after, page.before, filter)
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(connection.into()))
+}
+
+/// Get an input by its index
+#[utoipa::path(
+    tag = "inputs",
+    params(("index" = i32, Path, description = "Input index")),
+    responses(
+        (status = 200, description = "Input with the given index", body = Input),
+        (status = 404, description = "Input not found", body = ErrorResponse),
+    ),
+)]
+#[get("/inputs/{index}")]
+async fn get_input(
+    index: web::Path<i32>,
+    repository: web::Data<Repository>,
+) -> RestResult<Input> {
+    let input = repository
+        .get_input(index.into_inner())
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(input.into()))
+}
+
+/// List the vouchers produced by an input
+#[utoipa::path(
+    tag = "inputs",
+    params(("index" = i32, Path, description = "Input index"), PageParams),
+    responses(
+        (status = 200, description = "Page of vouchers produced by the input", body = VoucherPage),
+        (status = 404, description = "Input not found", body = ErrorResponse),
+    ),
+)]
+#[get("/inputs/{index}/vouchers")]
+async fn get_input_vouchers(
+    index: web::Path<i32>,
+    page: web::Query<PageParams>,
+    repository: web::Data<Repository>,
+) -> RestResult<VoucherPage> {
+    let input = repository
+        .get_input(index.into_inner())
+        .await
+        .context(DataSnafu)?;
+    let filter = VoucherQueryFilter {
+        input_index: Some(input.index),
+        ..Default::default()
+    };
+    let page = page.into_inner();
+    let connection = repository
+        .get_vouchers(page.first, page.last, page.after, page.before, filter)
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(connection.into()))
+}
+
+/// List the notices produced by an input
+#[utoipa::path(
+    tag = "inputs",
+    params(("index" = i32, Path, description = "Input index"), PageParams),
+    responses(
+        (status = 200, description = "Page of notices produced by the input", body = NoticePage),
+        (status = 404, description = "Input not found", body = ErrorResponse),
+    ),
+)]
+#[get("/inputs/{index}/notices")]
+async fn get_input_notices(
+    index: web::Path<i32>,
+    page: web::Query<PageParams>,
+    repository: web::Data<Repository>,
+) -> RestResult<NoticePage> {
+    let input = repository
+        .get_input(index.into_inner())
+        .await
+        .context(DataSnafu)?;
+    let filter = NoticeQueryFilter {
+        input_index: Some(input.index),
+        ..Default::default()
+    };
+    let page = page.into_inner();
+    let connection = repository
+        .get_notices(page.first, page.last, page.after, page.before, filter)
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(connection.into()))
+}
+
+/// List the reports produced by an input
+#[utoipa::path(
+    tag = "inputs",
+    params(("index" = i32, Path, description = "Input index"), PageParams),
+    responses(
+        (status = 200, description = "Page of reports produced by the input", body = ReportPage),
+        (status = 404, description = "Input not found", body = ErrorResponse),
+    ),
+)]
+#[get("/inputs/{index}/reports")]
+async fn get_input_reports(
+    index: web::Path<i32>,
+    page: web::Query<PageParams>,
+    repository: web::Data<Repository>,
+) -> RestResult<ReportPage> {
+    let input = repository
+        .get_input(index.into_inner())
+        .await
+        .context(DataSnafu)?;
+    let filter = ReportQueryFilter {
+        input_index: Some(input.index),
+        ..Default::default()
+    };
+    let page = page.into_inner();
+    let connection = repository
+        .get_reports(page.first, page.last, page.after, page.before, filter)
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(connection.into()))
+}
+
+/// List the vouchers
+#[utoipa::path(
+    tag = "outputs",
+    params(PageParams, VoucherParams),
+    responses(
+        (status = 200, description = "Page of vouchers", body = VoucherPage),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+    ),
+)]
+#[get("/vouchers")]
+async fn get_vouchers(
+    page: web::Query<PageParams>,
+    params: web::Query<VoucherParams>,
+    repository: web::Data<Repository>,
+) -> RestResult<VoucherPage> {
+    let params = params.into_inner();
+    let filter = VoucherQueryFilter {
+        input_index: params.input_index,
+        epoch_index: params.epoch_index,
+        destination: params.destination.as_deref().map(decode).transpose()?,
+        executed: params.executed,
+        ..Default::default()
+    };
+    let page = page.into_inner();
+    let connection = repository
+        .get_vouchers(page.first, page.last, page.after, page.before, filter)
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(connection.into()))
+}
+
+/// List the notices
+#[utoipa::path(
+    tag = "outputs",
+    params(PageParams, OutputParams),
+    responses(
+        (status = 200, description = "Page of notices", body = NoticePage),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+    ),
+)]
+#[get("/notices")]
+async fn get_notices(
+    page: web::Query<PageParams>,
+    params: web::Query<OutputParams>,
+    repository: web::Data<Repository>,
+) -> RestResult<NoticePage> {
+    let filter = NoticeQueryFilter {
+        input_index: params.input_index,
+        epoch_index: params.epoch_index,
+        ..Default::default()
+    };
+    let page = page.into_inner();
+    let connection = repository
+        .get_notices(page.first, page.last, page.after, page.before, filter)
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(connection.into()))
+}
+
+/// List the reports
+#[utoipa::path(
+    tag = "outputs",
+    params(PageParams, OutputParams),
+    responses(
+        (status = 200, description = "Page of reports", body = ReportPage),
+        (status = 400, description = "Invalid parameters", body = ErrorResponse),
+    ),
+)]
+#[get("/reports")]
+async fn get_reports(
+    page: web::Query<PageParams>,
+    params: web::Query<OutputParams>,
+    repository: web::Data<Repository>,
+) -> RestResult<ReportPage> {
+    let filter = ReportQueryFilter {
+        input_index: params.input_index,
+        epoch_index: params.epoch_index,
+        ..Default::default()
+    };
+    let page = page.into_inner();
+    let connection = repository
+        .get_reports(page.first, page.last, page.after, page.before, filter)
+        .await
+        .context(DataSnafu)?;
+    Ok(web::Json(connection.into()))
+}
+
+fn decode(data: &str) -> Result<Vec<u8>, RestError> {
+    hex_decode(data).map_err(|e| {
+        InvalidParameterSnafu {
+            message: e.message().to_owned(),
+        }
+        .build()
+    })
+}
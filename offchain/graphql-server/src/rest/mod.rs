@@ -0,0 +1,109 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! REST API that serves the same data of the GraphQL schema as plain JSON
+
+use actix_web::dev::Service;
+use actix_web::error::InternalError;
+use actix_web::{get, web, HttpResponse, Responder, ResponseError};
+use std::time::Duration;
+use utoipa::OpenApi;
+
+use crate::schema::CompletionStatus;
+use error::RestError;
+use models::{
+    ErrorResponse, Input, InputPage, Notice, NoticePage, PageInfo, Report,
+    ReportPage, Voucher, VoucherPage,
+};
+
+mod error;
+mod handlers;
+mod models;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Cartesi Rollups REST API",
+        description = "Read-only access to the inputs and outputs of the DApp"
+    ),
+    paths(
+        handlers::get_inputs,
+        handlers::get_input,
+        handlers::get_input_vouchers,
+        handlers::get_input_notices,
+        handlers::get_input_reports,
+        handlers::get_vouchers,
+        handlers::get_notices,
+        handlers::get_reports,
+    ),
+    components(schemas(
+        CompletionStatus,
+        ErrorResponse,
+        Input,
+        InputPage,
+        Notice,
+        NoticePage,
+        PageInfo,
+        Report,
+        ReportPage,
+        Voucher,
+        VoucherPage,
+    ))
+)]
+pub struct ApiDoc;
+
+/// Register the REST routes, which expect the repository in the app data.
+///
+/// The requests that take longer than the timeout are answered with an
+/// error, as the GraphQL queries are.
+pub fn configure(cfg: &mut web::ServiceConfig, request_timeout: Duration) {
+    cfg.app_data(
+        web::QueryConfig::default()
+            .error_handler(|err, _| invalid_parameter(err)),
+    )
+    .app_data(
+        web::PathConfig::default()
+            .error_handler(|err, _| invalid_parameter(err)),
+    )
+    .service(
+        web::scope("")
+            .wrap_fn(move |req, srv| {
+                let response = srv.call(req);
+                async move {
+                    match tokio::time::timeout(request_timeout, response).await
+                    {
+                        Ok(response) => response,
+                        Err(_) => Err(RestError::TimeoutError {
+                            timeout_ms: request_timeout.as_millis(),
+                        }
+                        .into()),
+                    }
+                }
+            })
+            .service(handlers::get_inputs)
+            .service(handlers::get_input)
+            .service(handlers::get_input_vouchers)
+            .service(handlers::get_input_notices)
+            .service(handlers::get_input_reports)
+            .service(handlers::get_vouchers)
+            .service(handlers::get_notices)
+            .service(handlers::get_reports)
+            .service(get_openapi),
+    );
+}
+
+/// Serve the OpenAPI document of the REST API
+#[get("/openapi.json")]
+async fn get_openapi() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Respond to the requests with invalid parameters with the same body of the
+/// other errors
+fn invalid_parameter<E: ResponseError + 'static>(err: E) -> actix_web::Error {
+    let response = RestError::InvalidParameterError {
+        message: err.to_string(),
+    }
+    .error_response();
+    InternalError::from_response(err, response).into()
+}
@@ -0,0 +1,264 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use rollups_data::{Connection, PageInfo as DbPageInfo};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::schema::{hex_encode, timestamp_to_secs, CompletionStatus};
+
+/// Request submitted to the application to advance its state
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Input {
+    /// Input index starting from genesis
+    pub index: i32,
+    pub status: CompletionStatus,
+    /// Address responsible for submitting the input in Ethereum hex binary
+    /// format (20 bytes), starting with '0x'
+    pub msg_sender: String,
+    /// Timestamp in seconds of the base layer block in which the input was
+    /// recorded
+    pub timestamp: i64,
+    /// Number of the base layer block in which the input was recorded
+    pub block_number: i64,
+    /// Input payload in Ethereum hex binary format, starting with '0x'
+    pub payload: String,
+    /// Epoch of the input; inputs indexed before the epochs were stored have
+    /// no epoch
+    pub epoch_index: Option<i32>,
+}
+
+impl From<rollups_data::Input> for Input {
+    fn from(input: rollups_data::Input) -> Input {
+        Input {
+            index: input.index,
+            status: input.status.into(),
+            msg_sender: hex_encode(&input.msg_sender),
+            timestamp: timestamp_to_secs(input.timestamp),
+            block_number: input.block_number,
+            payload: hex_encode(&input.payload),
+            epoch_index: input.epoch_index,
+        }
+    }
+}
+
+/// Representation of a transaction that can be carried out on the base layer
+/// blockchain, such as a transfer of assets
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Voucher {
+    /// Index of the input whose processing produced the voucher
+    pub input_index: i32,
+    /// Voucher index within the context of the input that produced it
+    pub index: i32,
+    /// Transaction destination address in Ethereum hex binary format
+    /// (20 bytes), starting with '0x'
+    pub destination: String,
+    /// Transaction payload in Ethereum hex binary format, starting with '0x'
+    pub payload: String,
+    /// Amount of wei sent with the transaction as a big-endian uint256 in
+    /// Ethereum hex binary format, starting with '0x'; legacy vouchers have
+    /// no value
+    pub value: Option<String>,
+    /// Whether the voucher runs the payload in the context of the DApp
+    /// contract with DELEGATECALL
+    pub is_delegate_call: bool,
+    /// Whether the voucher was already executed in the DApp contract
+    pub executed: bool,
+    /// Hash of the transaction that executed the voucher in Ethereum hex
+    /// binary format (32 bytes), starting with '0x'
+    pub execution_tx_hash: Option<String>,
+}
+
+impl From<rollups_data::Voucher> for Voucher {
+    fn from(voucher: rollups_data::Voucher) -> Voucher {
+        Voucher {
+            input_index: voucher.input_index,
+            index: voucher.index,
+            destination: hex_encode(&voucher.destination),
+            payload: hex_encode(&voucher.payload),
+            value: voucher.value.as_deref().map(hex_encode),
+            is_delegate_call: voucher.is_delegate_call,
+            executed: voucher.execution_tx_hash.is_some(),
+            execution_tx_hash: voucher
+                .execution_tx_hash
+                .as_deref()
+                .map(hex_encode),
+        }
+    }
+}
+
+/// Informational statement that can be validated in the base layer
+/// blockchain
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Notice {
+    /// Index of the input whose processing produced the notice
+    pub input_index: i32,
+    /// Notice index within the context of the input that produced it
+    pub index: i32,
+    /// Notice data as a payload in Ethereum hex binary format, starting with
+    /// '0x'
+    pub payload: String,
+}
+
+impl From<rollups_data::Notice> for Notice {
+    fn from(notice: rollups_data::Notice) -> Notice {
+        Notice {
+            input_index: notice.input_index,
+            index: notice.index,
+            payload: hex_encode(&notice.payload),
+        }
+    }
+}
+
+/// Application log or diagnostic information
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    /// Index of the input whose processing produced the report
+    pub input_index: i32,
+    /// Report index within the context of the input that produced it
+    pub index: i32,
+    /// Report data as a payload in Ethereum hex binary format, starting with
+    /// '0x'
+    pub payload: String,
+}
+
+impl From<rollups_data::Report> for Report {
+    fn from(report: rollups_data::Report) -> Report {
+        Report {
+            input_index: report.input_index,
+            index: report.index,
+            payload: hex_encode(&report.payload),
+        }
+    }
+}
+
+/// Page of the entries that match the query
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[aliases(
+    InputPage = Page<Input>,
+    VoucherPage = Page<Voucher>,
+    NoticePage = Page<Notice>,
+    ReportPage = Page<Report>
+)]
+pub struct Page<T> {
+    /// Total number of entries that match the query
+    pub total_count: i32,
+    /// Entries of the page
+    pub nodes: Vec<T>,
+    pub page_info: PageInfo,
+}
+
+impl<T, N: Debug> From<Connection<N>> for Page<T>
+where
+    T: From<N>,
+{
+    fn from(connection: Connection<N>) -> Page<T> {
+        Page {
+            total_count: connection.total_count,
+            page_info: (&connection.page_info).into(),
+            nodes: connection
+                .edges
+                .into_iter()
+                .map(|edge| edge.node.into())
+                .collect(),
+        }
+    }
+}
+
+/// Page metadata for the cursor-based pagination
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    /// Cursor pointing to the first entry of the page
+    pub start_cursor: Option<String>,
+    /// Cursor pointing to the last entry of the page
+    pub end_cursor: Option<String>,
+    /// Indicates if there are additional entries after the end cursor
+    pub has_next_page: bool,
+    /// Indicates if there are additional entries before the start cursor
+    pub has_previous_page: bool,
+}
+
+impl From<&DbPageInfo> for PageInfo {
+    fn from(page_info: &DbPageInfo) -> PageInfo {
+        PageInfo {
+            start_cursor: page_info
+                .start_cursor
+                .as_ref()
+                .map(|cursor| cursor.encode()),
+            end_cursor: page_info
+                .end_cursor
+                .as_ref()
+                .map(|cursor| cursor.encode()),
+            has_next_page: page_info.has_next_page,
+            has_previous_page: page_info.has_previous_page,
+        }
+    }
+}
+
+/// Error returned by the failed requests
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+/// Pagination of the listed entries
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Get at most the first `n` entries (forward pagination)
+    pub first: Option<i32>,
+    /// Get at most the last `n` entries (backward pagination)
+    pub last: Option<i32>,
+    /// Get entries that come after the provided cursor (forward pagination)
+    pub after: Option<String>,
+    /// Get entries that come before the provided cursor (backward pagination)
+    pub before: Option<String>,
+}
+
+/// Filter of the listed inputs
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct InputParams {
+    /// Filter only inputs sent by a given address in Ethereum hex binary
+    /// format, starting with '0x'
+    pub msg_sender: Option<String>,
+    /// Filter only inputs with a given status
+    pub status: Option<CompletionStatus>,
+    /// Filter only inputs of a given epoch
+    pub epoch_index: Option<i32>,
+}
+
+/// Filter of the listed vouchers
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct VoucherParams {
+    /// Filter only vouchers produced by a given input
+    pub input_index: Option<i32>,
+    /// Filter only vouchers produced by inputs of a given epoch
+    pub epoch_index: Option<i32>,
+    /// Filter only vouchers with a given destination address in Ethereum hex
+    /// binary format, starting with '0x'
+    pub destination: Option<String>,
+    /// Filter only vouchers that were executed or only vouchers that were not
+    pub executed: Option<bool>,
+}
+
+/// Filter of the listed notices and reports
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct OutputParams {
+    /// Filter only outputs produced by a given input
+    pub input_index: Option<i32>,
+    /// Filter only outputs produced by inputs of a given epoch
+    pub epoch_index: Option<i32>,
+}
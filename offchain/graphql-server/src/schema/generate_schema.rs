@@ -5,11 +5,11 @@
 // because it is executed before crate is built, and many structures/entities
 // from graphql module must be used to generate schema
 
-use juniper::{EmptyMutation, EmptySubscription};
+use juniper::EmptyMutation;
 use std::fs::File;
 use std::io::Write;
 
-use graphql_server::schema::{Query, Schema};
+use graphql_server::schema::{Query, Schema, Subscription};
 
 const GRAPHQL_SCHEMA_FILE: &str = "schema.graphql";
 
@@ -17,7 +17,7 @@ fn main() {
     let schema = Schema::new_with_scalar_value(
         Query {},
         EmptyMutation::new(),
-        EmptySubscription::new(),
+        Subscription,
     );
     let graphql_schema = schema.as_schema_language();
     let mut graphql_schema_file = File::create(GRAPHQL_SCHEMA_FILE).unwrap();
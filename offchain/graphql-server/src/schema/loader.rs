@@ -0,0 +1,718 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use rollups_data::{
+    Claim, Connection, Epoch, Error, Input, Notice, OutputEnum, OutputPage,
+    Pagination, Proof, Report, Repository, SortDirection, Voucher,
+};
+
+/// Request-scoped batching and caching of the nested field lookups.
+///
+/// The resolvers of a connection prime the loader with the keys of the
+/// nested fields of its nodes. The first lookup of a key then fetches all the
+/// pending keys of the same kind with a single query, and the following
+/// lookups are served from the cache. The fields are resolved depth first,
+/// so the items fetched in a batch also prime the keys of their own nested
+/// fields. An uncached loader fetches every key on its own, which is what
+/// long-lived contexts such as the subscriptions need to avoid serving stale
+/// data.
+pub struct Loader {
+    repository: Repository,
+    inputs: BatchCache<i32, Option<Input>>,
+    epochs: BatchCache<i32, Option<Epoch>>,
+    claims: BatchCache<i32, Option<Claim>>,
+    vouchers: OutputCache<Voucher>,
+    notices: OutputCache<Notice>,
+    reports: OutputCache<Report>,
+    voucher_proofs: BatchCache<(i32, i32), Option<Proof>>,
+    notice_proofs: BatchCache<(i32, i32), Option<Proof>>,
+}
+
+impl Loader {
+    /// Create a loader that batches and caches the lookups
+    pub fn new(repository: Repository) -> Self {
+        Self::with_cache(repository, true)
+    }
+
+    /// Create a loader that sends every lookup to the repository
+    pub fn uncached(repository: Repository) -> Self {
+        Self::with_cache(repository, false)
+    }
+
+    fn with_cache(repository: Repository, enabled: bool) -> Self {
+        Self {
+            repository,
+            inputs: BatchCache::new(enabled),
+            epochs: BatchCache::new(enabled),
+            claims: BatchCache::new(enabled),
+            vouchers: OutputCache::new(enabled),
+            notices: OutputCache::new(enabled),
+            reports: OutputCache::new(enabled),
+            voucher_proofs: BatchCache::new(enabled),
+            notice_proofs: BatchCache::new(enabled),
+        }
+    }
+
+    /// Cache the given inputs and prime the lookups of their nested fields
+    pub fn prime_inputs<'a>(
+        &self,
+        inputs: impl IntoIterator<Item = &'a Input>,
+    ) {
+        for input in inputs {
+            self.inputs.insert(input.index, Some(input.clone()));
+            self.prime_input_fields(input);
+        }
+    }
+
+    fn prime_input_fields(&self, input: &Input) {
+        self.epochs.prime(input.epoch_index);
+        self.vouchers.prime(input.index);
+        self.notices.prime(input.index);
+        self.reports.prime(input.index);
+    }
+
+    pub fn prime_epochs<'a>(
+        &self,
+        epochs: impl IntoIterator<Item = &'a Epoch>,
+    ) {
+        for epoch in epochs {
+            self.epochs.insert(epoch.index, Some(epoch.clone()));
+            self.claims.prime([epoch.index]);
+        }
+    }
+
+    pub fn prime_vouchers<'a>(
+        &self,
+        vouchers: impl IntoIterator<Item = &'a Voucher>,
+    ) {
+        for voucher in vouchers {
+            self.inputs.prime([voucher.input_index]);
+            self.voucher_proofs
+                .prime([(voucher.input_index, voucher.index)]);
+        }
+    }
+
+    pub fn prime_notices<'a>(
+        &self,
+        notices: impl IntoIterator<Item = &'a Notice>,
+    ) {
+        for notice in notices {
+            self.inputs.prime([notice.input_index]);
+            self.notice_proofs
+                .prime([(notice.input_index, notice.index)]);
+        }
+    }
+
+    pub fn prime_reports<'a>(
+        &self,
+        reports: impl IntoIterator<Item = &'a Report>,
+    ) {
+        for report in reports {
+            self.inputs.prime([report.input_index]);
+        }
+    }
+
+    pub async fn input(&self, index: i32) -> Result<Input, Error> {
+        self.inputs
+            .load(index, |indices| async move {
+                let inputs =
+                    self.repository.get_inputs_by_indices(&indices).await?;
+                for input in &inputs {
+                    self.prime_input_fields(input);
+                }
+                Ok(inputs
+                    .into_iter()
+                    .map(|input| (input.index, Some(input)))
+                    .collect())
+            })
+            .await?
+            .ok_or(Error::ItemNotFound {
+                item_type: "input".to_owned(),
+            })
+    }
+
+    pub async fn epoch(&self, index: i32) -> Result<Epoch, Error> {
+        self.epochs
+            .load(index, |indices| async move {
+                let epochs =
+                    self.repository.get_epochs_by_indices(&indices).await?;
+                self.claims.prime(epochs.iter().map(|epoch| epoch.index));
+                Ok(epochs
+                    .into_iter()
+                    .map(|epoch| (epoch.index, Some(epoch)))
+                    .collect())
+            })
+            .await?
+            .ok_or(Error::ItemNotFound {
+                item_type: "epoch".to_owned(),
+            })
+    }
+
+    pub async fn claim(
+        &self,
+        epoch_index: i32,
+    ) -> Result<Option<Claim>, Error> {
+        self.claims
+            .load(epoch_index, |indices| async move {
+                let claims = self
+                    .repository
+                    .get_claims_by_epoch_indices(&indices)
+                    .await?;
+                Ok(claims
+                    .into_iter()
+                    .map(|claim| (claim.epoch_index, Some(claim)))
+                    .collect())
+            })
+            .await
+    }
+
+    /// Get the page of the vouchers of the input
+    pub async fn vouchers(
+        &self,
+        input_index: i32,
+        page: PageArgs,
+    ) -> Result<Connection<Voucher>, Error> {
+        self.vouchers
+            .load(input_index, page, |keys| {
+                fetch_output_pages(
+                    keys,
+                    |indices| async move {
+                        self.repository
+                            .count_vouchers_by_input_indices(&indices)
+                            .await
+                    },
+                    |pages| async move {
+                        let vouchers = self
+                            .repository
+                            .get_vouchers_by_input_pages(&pages)
+                            .await?;
+                        self.prime_vouchers(&vouchers);
+                        Ok(vouchers)
+                    },
+                    |voucher| voucher.input_index,
+                )
+            })
+            .await
+    }
+
+    /// Get the page of the notices of the input
+    pub async fn notices(
+        &self,
+        input_index: i32,
+        page: PageArgs,
+    ) -> Result<Connection<Notice>, Error> {
+        self.notices
+            .load(input_index, page, |keys| {
+                fetch_output_pages(
+                    keys,
+                    |indices| async move {
+                        self.repository
+                            .count_notices_by_input_indices(&indices)
+                            .await
+                    },
+                    |pages| async move {
+                        let notices = self
+                            .repository
+                            .get_notices_by_input_pages(&pages)
+                            .await?;
+                        self.prime_notices(&notices);
+                        Ok(notices)
+                    },
+                    |notice| notice.input_index,
+                )
+            })
+            .await
+    }
+
+    /// Get the page of the reports of the input
+    pub async fn reports(
+        &self,
+        input_index: i32,
+        page: PageArgs,
+    ) -> Result<Connection<Report>, Error> {
+        self.reports
+            .load(input_index, page, |keys| {
+                fetch_output_pages(
+                    keys,
+                    |indices| async move {
+                        self.repository
+                            .count_reports_by_input_indices(&indices)
+                            .await
+                    },
+                    |pages| async move {
+                        let reports = self
+                            .repository
+                            .get_reports_by_input_pages(&pages)
+                            .await?;
+                        self.prime_reports(&reports);
+                        Ok(reports)
+                    },
+                    |report| report.input_index,
+                )
+            })
+            .await
+    }
+
+    pub async fn voucher_proof(
+        &self,
+        input_index: i32,
+        index: i32,
+    ) -> Result<Option<Proof>, Error> {
+        self.voucher_proofs
+            .load((input_index, index), |keys| {
+                self.fetch_proofs(keys, OutputEnum::Voucher)
+            })
+            .await
+    }
+
+    pub async fn notice_proof(
+        &self,
+        input_index: i32,
+        index: i32,
+    ) -> Result<Option<Proof>, Error> {
+        self.notice_proofs
+            .load((input_index, index), |keys| {
+                self.fetch_proofs(keys, OutputEnum::Notice)
+            })
+            .await
+    }
+
+    async fn fetch_proofs(
+        &self,
+        keys: Vec<(i32, i32)>,
+        output_enum: OutputEnum,
+    ) -> Result<HashMap<(i32, i32), Option<Proof>>, Error> {
+        let input_indices: HashSet<i32> =
+            keys.iter().map(|(input_index, _)| *input_index).collect();
+        let input_indices: Vec<i32> = input_indices.into_iter().collect();
+        let proofs = self
+            .repository
+            .get_proofs_by_input_indices(&input_indices, output_enum)
+            .await?;
+        Ok(proofs
+            .into_iter()
+            .map(|proof| ((proof.input_index, proof.output_index), Some(proof)))
+            .collect())
+    }
+}
+
+/// Pagination arguments of the outputs of an input
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PageArgs {
+    pub first: Option<i32>,
+    pub last: Option<i32>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub order: SortDirection,
+}
+
+impl PageArgs {
+    fn pagination(&self, total_count: i32) -> Result<Pagination, Error> {
+        Pagination::new(
+            self.first,
+            self.last,
+            self.after.clone(),
+            self.before.clone(),
+            total_count,
+        )
+    }
+
+    /// Page of the outputs of the input, which are sorted in ascending order
+    /// in the database
+    fn output_page(
+        &self,
+        input_index: i32,
+        pagination: &Pagination,
+        total_count: i32,
+    ) -> OutputPage {
+        let offset = match self.order {
+            SortDirection::Ascending => pagination.offset(),
+            SortDirection::Descending => {
+                total_count - pagination.offset() - pagination.limit()
+            }
+        };
+        OutputPage {
+            input_index,
+            offset,
+            limit: pagination.limit(),
+        }
+    }
+
+    /// Create the connection of the fetched page, producing the same
+    /// connection as the paginated queries of the repository
+    fn create_connection<T: std::fmt::Debug>(
+        &self,
+        total_count: i32,
+        mut nodes: Vec<T>,
+    ) -> Result<Connection<T>, Error> {
+        let pagination = self.pagination(total_count)?;
+        if self.order == SortDirection::Descending {
+            nodes.reverse();
+        }
+        Ok(pagination.create_connection(nodes))
+    }
+}
+
+/// Total count and page of the outputs of an input
+type FetchedPage<T> = (i32, Vec<T>);
+
+/// Fetch the pages of the outputs of the given inputs: count the outputs of
+/// every input with one query, then fetch the pages with one query for each
+/// distinct pagination. The pages with invalid arguments are left empty, so
+/// their lookups fail when creating the connection.
+async fn fetch_output_pages<T, Count, CountFut, Fetch, FetchFut>(
+    keys: Vec<(i32, PageArgs)>,
+    count: Count,
+    fetch: Fetch,
+    input_index: impl Fn(&T) -> i32,
+) -> Result<HashMap<(i32, PageArgs), FetchedPage<T>>, Error>
+where
+    Count: FnOnce(Vec<i32>) -> CountFut,
+    CountFut: Future<Output = Result<Vec<(i32, i32)>, Error>>,
+    Fetch: Fn(Vec<OutputPage>) -> FetchFut,
+    FetchFut: Future<Output = Result<Vec<T>, Error>>,
+{
+    let input_indices: HashSet<i32> =
+        keys.iter().map(|(input_index, _)| *input_index).collect();
+    let counts: HashMap<i32, i32> = count(input_indices.into_iter().collect())
+        .await?
+        .into_iter()
+        .collect();
+    let mut keys_by_page: HashMap<PageArgs, Vec<i32>> = HashMap::new();
+    for (input_index, page) in keys {
+        keys_by_page.entry(page).or_default().push(input_index);
+    }
+    let mut values = HashMap::new();
+    for (page, input_indices) in keys_by_page {
+        let mut output_pages = vec![];
+        for &index in &input_indices {
+            let total_count = counts.get(&index).copied().unwrap_or_default();
+            if let Ok(pagination) = page.pagination(total_count) {
+                if pagination.limit() > 0 {
+                    output_pages.push(page.output_page(
+                        index,
+                        &pagination,
+                        total_count,
+                    ));
+                }
+            }
+        }
+        let mut nodes = if output_pages.is_empty() {
+            HashMap::new()
+        } else {
+            group_by(fetch(output_pages).await?, &input_index)
+        };
+        for index in input_indices {
+            let total_count = counts.get(&index).copied().unwrap_or_default();
+            let page_nodes = nodes.remove(&index).unwrap_or_default();
+            values.insert((index, page.clone()), (total_count, page_nodes));
+        }
+    }
+    Ok(values)
+}
+
+/// Group the items sorted by the given key
+fn group_by<T>(items: Vec<T>, key: impl Fn(&T) -> i32) -> HashMap<i32, Vec<T>> {
+    let mut groups: HashMap<i32, Vec<T>> = HashMap::new();
+    for item in items {
+        groups.entry(key(&item)).or_default().push(item);
+    }
+    groups
+}
+
+/// Cache of the pages of a kind of output. The inputs are primed without the
+/// pagination, which is only known by the lookups, so the first lookup of a
+/// page primes the same page for every primed input.
+struct OutputCache<T> {
+    inputs: Mutex<HashSet<i32>>,
+    pages: BatchCache<(i32, PageArgs), FetchedPage<T>>,
+}
+
+impl<T: Clone + std::fmt::Debug> OutputCache<T> {
+    fn new(enabled: bool) -> Self {
+        Self {
+            inputs: Mutex::new(HashSet::new()),
+            pages: BatchCache::new(enabled),
+        }
+    }
+
+    fn prime(&self, input_index: i32) {
+        if self.pages.enabled {
+            self.lock().insert(input_index);
+        }
+    }
+
+    async fn load<F, Fut>(
+        &self,
+        input_index: i32,
+        page: PageArgs,
+        fetch: F,
+    ) -> Result<Connection<T>, Error>
+    where
+        F: FnOnce(Vec<(i32, PageArgs)>) -> Fut,
+        Fut: Future<
+            Output = Result<HashMap<(i32, PageArgs), FetchedPage<T>>, Error>,
+        >,
+    {
+        let keys: Vec<(i32, PageArgs)> = self
+            .lock()
+            .iter()
+            .map(|input_index| (*input_index, page.clone()))
+            .collect();
+        self.pages.prime(keys);
+        let (total_count, nodes) =
+            self.pages.load((input_index, page.clone()), fetch).await?;
+        page.create_connection(total_count, nodes)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<i32>> {
+        self.inputs.lock().expect("Mutex should never be poisoned")
+    }
+}
+
+/// Cache of the values of a kind of lookup, along with the keys that are
+/// expected to be looked up and should be fetched in the next batch
+struct BatchCache<K, V> {
+    enabled: bool,
+    state: Mutex<BatchState<K, V>>,
+    // Held while fetching, so the concurrent lookups wait for the batch
+    // instead of fetching the same keys again
+    fetching: tokio::sync::Mutex<()>,
+}
+
+struct BatchState<K, V> {
+    pending: HashSet<K>,
+    loaded: HashMap<K, V>,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone + Default> BatchCache<K, V> {
+    fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            state: Mutex::new(BatchState {
+                pending: HashSet::new(),
+                loaded: HashMap::new(),
+            }),
+            fetching: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn prime(&self, keys: impl IntoIterator<Item = K>) {
+        if !self.enabled {
+            return;
+        }
+        let mut state = self.lock();
+        for key in keys {
+            if !state.loaded.contains_key(&key) {
+                state.pending.insert(key);
+            }
+        }
+    }
+
+    fn insert(&self, key: K, value: V) {
+        if !self.enabled {
+            return;
+        }
+        let mut state = self.lock();
+        state.pending.remove(&key);
+        state.loaded.insert(key, value);
+    }
+
+    /// Get the value of the key from the cache, or fetch it along with the
+    /// pending keys; the keys missing from the fetched values get the default
+    /// value. The state is not locked while fetching, so the fetch may prime
+    /// other caches.
+    async fn load<F, Fut>(&self, key: K, fetch: F) -> Result<V, Error>
+    where
+        F: FnOnce(Vec<K>) -> Fut,
+        Fut: Future<Output = Result<HashMap<K, V>, Error>>,
+    {
+        if !self.enabled {
+            let mut values = fetch(vec![key.clone()]).await?;
+            return Ok(values.remove(&key).unwrap_or_default());
+        }
+        let _fetching = self.fetching.lock().await;
+        let keys: Vec<K> = {
+            let mut state = self.lock();
+            if let Some(value) = state.loaded.get(&key) {
+                return Ok(value.clone());
+            }
+            state.pending.insert(key.clone());
+            state.pending.iter().cloned().collect()
+        };
+        let mut values = fetch(keys.clone()).await?;
+        let mut state = self.lock();
+        for key in keys {
+            state.pending.remove(&key);
+            let value = values.remove(&key).unwrap_or_default();
+            state.loaded.insert(key, value);
+        }
+        Ok(state.loaded[&key].clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BatchState<K, V>> {
+        self.state.lock().expect("Mutex should never be poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::future::ready;
+
+    #[tokio::test]
+    async fn test_load_fetches_pending_keys_once() {
+        let cache = BatchCache::<i32, Option<i32>>::new(true);
+        let fetches = RefCell::new(vec![]);
+        let fetch = |mut keys: Vec<i32>| {
+            keys.sort();
+            fetches.borrow_mut().push(keys.clone());
+            ready(Ok(keys
+                .into_iter()
+                .filter(|key| *key != 2)
+                .map(|key| (key, Some(key * 10)))
+                .collect()))
+        };
+        cache.prime([1, 2, 3]);
+        assert_eq!(cache.load(3, fetch).await.unwrap(), Some(30));
+        assert_eq!(cache.load(1, fetch).await.unwrap(), Some(10));
+        assert_eq!(cache.load(2, fetch).await.unwrap(), None);
+        assert_eq!(cache.load(4, fetch).await.unwrap(), Some(40));
+        assert_eq!(*fetches.borrow(), vec![vec![1, 2, 3], vec![4]]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_loads_share_the_batch() {
+        let cache = BatchCache::<i32, Option<i32>>::new(true);
+        let fetches = RefCell::new(0);
+        let fetch = |keys: Vec<i32>| {
+            let fetches = &fetches;
+            async move {
+                *fetches.borrow_mut() += 1;
+                tokio::task::yield_now().await;
+                Ok(keys.into_iter().map(|key| (key, Some(key))).collect())
+            }
+        };
+        cache.prime([1, 2]);
+        let (one, two) =
+            tokio::join!(cache.load(1, fetch), cache.load(2, fetch));
+        assert_eq!(one.unwrap(), Some(1));
+        assert_eq!(two.unwrap(), Some(2));
+        assert_eq!(*fetches.borrow(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_uncached_load_fetches_every_key() {
+        let cache = BatchCache::<i32, Option<i32>>::new(false);
+        let fetches = RefCell::new(0);
+        let fetch = |keys: Vec<i32>| {
+            *fetches.borrow_mut() += 1;
+            ready(Ok(keys.into_iter().map(|key| (key, Some(key))).collect()))
+        };
+        cache.prime([1, 2]);
+        cache.insert(1, Some(1));
+        assert_eq!(cache.load(1, fetch).await.unwrap(), Some(1));
+        assert_eq!(cache.load(1, fetch).await.unwrap(), Some(1));
+        assert_eq!(*fetches.borrow(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_output_pages() {
+        let first_two = PageArgs {
+            first: Some(2),
+            ..Default::default()
+        };
+        let last_three = PageArgs {
+            last: Some(3),
+            order: SortDirection::Descending,
+            ..Default::default()
+        };
+        let after_end = PageArgs {
+            // Cursor of the offset 3
+            after: Some("Mw==".to_owned()),
+            ..Default::default()
+        };
+        let fetches = RefCell::new(vec![]);
+        let mut pages = fetch_output_pages(
+            vec![
+                (0, first_two.clone()),
+                (1, first_two.clone()),
+                (0, last_three.clone()),
+                (1, after_end.clone()),
+            ],
+            |_| ready(Ok(vec![(0, 4), (1, 3)])),
+            |output_pages| {
+                fetches.borrow_mut().push(output_pages.clone());
+                // The outputs of an input are its positions times ten
+                ready(Ok(output_pages
+                    .iter()
+                    .flat_map(|page| {
+                        (page.offset..page.offset + page.limit)
+                            .map(|position| (page.input_index, position * 10))
+                    })
+                    .collect()))
+            },
+            |(input_index, _)| *input_index,
+        )
+        .await
+        .unwrap();
+        let mut fetches = fetches.into_inner();
+        fetches.sort_by_key(|output_pages| output_pages.len());
+        assert_eq!(
+            fetches,
+            vec![
+                vec![OutputPage {
+                    input_index: 0,
+                    offset: 0,
+                    limit: 3,
+                }],
+                vec![
+                    OutputPage {
+                        input_index: 0,
+                        offset: 0,
+                        limit: 2,
+                    },
+                    OutputPage {
+                        input_index: 1,
+                        offset: 0,
+                        limit: 2,
+                    },
+                ],
+            ]
+        );
+
+        let nodes = |connection: Connection<(i32, i32)>| {
+            connection
+                .edges
+                .into_iter()
+                .map(|edge| edge.node.1)
+                .collect::<Vec<_>>()
+        };
+        let (total_count, page) =
+            pages.remove(&(1, first_two.clone())).unwrap();
+        let connection =
+            first_two.create_connection(total_count, page).unwrap();
+        assert_eq!(connection.total_count, 3);
+        assert!(connection.page_info.has_next_page);
+        assert_eq!(nodes(connection), vec![0, 10]);
+
+        let (total_count, page) =
+            pages.remove(&(0, last_three.clone())).unwrap();
+        let connection =
+            last_three.create_connection(total_count, page).unwrap();
+        assert!(connection.page_info.has_previous_page);
+        assert!(!connection.page_info.has_next_page);
+        assert_eq!(nodes(connection), vec![20, 10, 0]);
+
+        let (total_count, page) =
+            pages.remove(&(1, after_end.clone())).unwrap();
+        assert!(page.is_empty());
+        assert!(after_end.create_connection(total_count, page).is_err());
+    }
+}
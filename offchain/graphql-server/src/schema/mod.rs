@@ -1,16 +1,20 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
+mod loader;
 mod resolvers;
 mod scalar;
 
-pub use resolvers::{Context, Query};
+pub(crate) use resolvers::{
+    hex_decode, hex_encode, timestamp_to_secs, CompletionStatus,
+};
+pub use resolvers::{Context, Query, Subscription};
 pub use scalar::RollupsGraphQLScalarValue;
 
 pub type Schema = juniper::RootNode<
     'static,
     Query,
     juniper::EmptyMutation<Context>,
-    juniper::EmptySubscription<Context>,
+    Subscription,
     RollupsGraphQLScalarValue,
 >;
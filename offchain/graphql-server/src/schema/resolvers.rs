@@ -1,30 +1,64 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
+use juniper::futures::{stream, Stream};
 use juniper::{
-    graphql_object, DefaultScalarValue, FieldError, FieldResult, GraphQLEnum,
-    GraphQLInputObject, GraphQLObject,
+    graphql_object, graphql_subscription, DefaultScalarValue, FieldError,
+    FieldResult, GraphQLEnum, GraphQLInputObject, GraphQLObject,
 };
-use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::ToSchema;
 
-use rollups_data::Repository;
 use rollups_data::{
-    CompletionStatus as DbCompletionStatus, Connection, Edge, Input,
-    InputQueryFilter, Notice, NoticeQueryFilter, OutputEnum,
-    PageInfo as DbPageInfo, Proof, Report, ReportQueryFilter, Voucher,
-    VoucherQueryFilter,
+    Claim, CompletionStatus as DbCompletionStatus, Connection, Edge, Epoch,
+    EpochQueryFilter, Input, InputQueryFilter, Notice, NoticeQueryFilter,
+    PageInfo as DbPageInfo, Proof, Report, ReportQueryFilter,
+    SortDirection as DbSortDirection, Voucher, VoucherQueryFilter,
 };
+use rollups_data::{Repository, RepositoryEvent, RepositoryEventKind};
 
+use super::loader::{Loader, PageArgs};
 use super::scalar::RollupsGraphQLScalarValue;
 
 #[derive(Clone)]
 pub struct Context {
     repository: Repository,
+    events: broadcast::Sender<RepositoryEvent>,
+    loader: Arc<Loader>,
 }
 
 impl Context {
-    pub fn new(repository: Repository) -> Self {
-        Self { repository }
+    /// Create the context; the subscriptions receive the events sent to the
+    /// given channel. The nested fields are not batched, so use a context for
+    /// each request to batch them.
+    pub fn new(
+        repository: Repository,
+        events: broadcast::Sender<RepositoryEvent>,
+    ) -> Self {
+        let loader = Arc::new(Loader::uncached(repository.clone()));
+        Self {
+            repository,
+            events,
+            loader,
+        }
+    }
+
+    /// Create a context for a single request, which batches and caches the
+    /// lookups of nested fields
+    pub fn for_request(&self) -> Self {
+        Self {
+            loader: Arc::new(Loader::new(self.repository.clone())),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn repository(&self) -> &Repository {
+        &self.repository
     }
 }
 
@@ -39,18 +73,19 @@ pub struct Query;
 )]
 impl Query {
     #[graphql(description = "Get input based on its identifier")]
-    fn input(
+    async fn input(
         #[graphql(description = "Input index")] index: i32,
     ) -> FieldResult<Input> {
         executor
             .context()
             .repository
             .get_input(index)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(description = "Get voucher based on its index")]
-    fn voucher(
+    async fn voucher(
         #[graphql(description = "Voucher index in input")] voucher_index: i32,
         #[graphql(description = "Input index")] input_index: i32,
     ) -> FieldResult<Voucher> {
@@ -58,11 +93,12 @@ impl Query {
             .context()
             .repository
             .get_voucher(voucher_index, input_index)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(description = "Get notice based on its index")]
-    fn notice(
+    async fn notice(
         #[graphql(description = "Notice index in input")] notice_index: i32,
         #[graphql(description = "Input index")] input_index: i32,
     ) -> FieldResult<Notice> {
@@ -70,11 +106,12 @@ impl Query {
             .context()
             .repository
             .get_notice(notice_index, input_index)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(description = "Get report based on its index")]
-    fn report(
+    async fn report(
         #[graphql(description = "Report index in input")] report_index: i32,
         #[graphql(description = "Input index")] input_index: i32,
     ) -> FieldResult<Report> {
@@ -82,11 +119,66 @@ impl Query {
             .context()
             .repository
             .get_report(report_index, input_index)
+            .await
+            .map_err(convert_error)
+    }
+
+    #[graphql(description = "Get epoch based on its index")]
+    async fn epoch(
+        #[graphql(description = "Epoch index")] index: i32,
+    ) -> FieldResult<Epoch> {
+        executor
+            .context()
+            .repository
+            .get_epoch(index)
+            .await
             .map_err(convert_error)
     }
 
+    #[graphql(description = "Get epochs with support for pagination")]
+    async fn epochs(
+        #[graphql(
+            description = "Get at most the first `n` entries (forward pagination)"
+        )]
+        first: Option<i32>,
+        #[graphql(
+            description = "Get at most the last `n` entries (backward pagination)"
+        )]
+        last: Option<i32>,
+        #[graphql(
+            description = "Get entries that come after the provided cursor (forward pagination)"
+        )]
+        after: Option<String>,
+        #[graphql(
+            description = "Get entries that come before the provided cursor (backward pagination)"
+        )]
+        before: Option<String>,
+        #[graphql(description = "Filter entries to retrieve")] r#where: Option<
+            EpochFilter,
+        >,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
+    ) -> FieldResult<Connection<Epoch>> {
+        let filter = EpochQueryFilter {
+            order: order.map(SortDirection::into).unwrap_or_default(),
+            ..query_filter(r#where)?
+        };
+        let connection = executor
+            .context()
+            .repository
+            .get_epochs(first, last, after, before, filter)
+            .await
+            .map_err(convert_error)?;
+        executor
+            .context()
+            .loader
+            .prime_epochs(connection.edges.iter().map(|edge| &edge.node));
+        Ok(connection)
+    }
+
     #[graphql(description = "Get inputs with support for pagination")]
-    fn inputs(
+    async fn inputs(
         #[graphql(
             description = "Get at most the first `n` entries (forward pagination)"
         )]
@@ -106,17 +198,29 @@ impl Query {
         #[graphql(description = "Filter entries to retrieve")] r#where: Option<
             InputFilter,
         >,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
     ) -> FieldResult<Connection<Input>> {
-        let filter = r#where.map(InputFilter::into).unwrap_or_default();
-        executor
+        let filter = InputQueryFilter {
+            order: order.map(SortDirection::into).unwrap_or_default(),
+            ..query_filter(r#where)?
+        };
+        let connection = executor
             .context()
             .repository
             .get_inputs(first, last, after, before, filter)
-            .map_err(convert_error)
+            .await
+            .map_err(convert_error)?;
+        executor
+            .context()
+            .loader
+            .prime_inputs(connection.edges.iter().map(|edge| &edge.node));
+        Ok(connection)
     }
 
     #[graphql(description = "Get vouchers with support for pagination")]
-    fn vouchers(
+    async fn vouchers(
         #[graphql(
             description = "Get at most the first `n` entries (forward pagination)"
         )]
@@ -133,16 +237,32 @@ impl Query {
             description = "Get entries that come before the provided cursor (backward pagination)"
         )]
         before: Option<String>,
+        #[graphql(description = "Filter entries to retrieve")] r#where: Option<
+            VoucherFilter,
+        >,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
     ) -> FieldResult<Connection<Voucher>> {
-        executor
+        let filter = VoucherQueryFilter {
+            order: order.map(SortDirection::into).unwrap_or_default(),
+            ..query_filter(r#where)?
+        };
+        let connection = executor
             .context()
             .repository
-            .get_vouchers(first, last, after, before, Default::default())
-            .map_err(convert_error)
+            .get_vouchers(first, last, after, before, filter)
+            .await
+            .map_err(convert_error)?;
+        executor
+            .context()
+            .loader
+            .prime_vouchers(connection.edges.iter().map(|edge| &edge.node));
+        Ok(connection)
     }
 
     #[graphql(description = "Get notices with support for pagination")]
-    fn notices(
+    async fn notices(
         #[graphql(
             description = "Get at most the first `n` entries (forward pagination)"
         )]
@@ -159,16 +279,32 @@ impl Query {
             description = "Get entries that come before the provided cursor (backward pagination)"
         )]
         before: Option<String>,
+        #[graphql(description = "Filter entries to retrieve")] r#where: Option<
+            NoticeFilter,
+        >,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
     ) -> FieldResult<Connection<Notice>> {
-        executor
+        let filter = NoticeQueryFilter {
+            order: order.map(SortDirection::into).unwrap_or_default(),
+            ..query_filter(r#where)?
+        };
+        let connection = executor
             .context()
             .repository
-            .get_notices(first, last, after, before, Default::default())
-            .map_err(convert_error)
+            .get_notices(first, last, after, before, filter)
+            .await
+            .map_err(convert_error)?;
+        executor
+            .context()
+            .loader
+            .prime_notices(connection.edges.iter().map(|edge| &edge.node));
+        Ok(connection)
     }
 
     #[graphql(description = "Get reports with support for pagination")]
-    fn reports(
+    async fn reports(
         #[graphql(
             description = "Get at most the first `n` entries (forward pagination)"
         )]
@@ -185,17 +321,181 @@ impl Query {
             description = "Get entries that come before the provided cursor (backward pagination)"
         )]
         before: Option<String>,
+        #[graphql(description = "Filter entries to retrieve")] r#where: Option<
+            ReportFilter,
+        >,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
     ) -> FieldResult<Connection<Report>> {
-        executor
+        let filter = ReportQueryFilter {
+            order: order.map(SortDirection::into).unwrap_or_default(),
+            ..query_filter(r#where)?
+        };
+        let connection = executor
             .context()
             .repository
-            .get_reports(first, last, after, before, Default::default())
-            .map_err(convert_error)
+            .get_reports(first, last, after, before, filter)
+            .await
+            .map_err(convert_error)?;
+        executor
+            .context()
+            .loader
+            .prime_reports(connection.edges.iter().map(|edge| &edge.node));
+        Ok(connection)
     }
 }
 
-#[derive(GraphQLEnum)]
-enum CompletionStatus {
+type EventStream<T> = Pin<Box<dyn Stream<Item = FieldResult<T>> + Send>>;
+
+pub struct Subscription;
+
+#[graphql_subscription(
+    context = Context,
+    Scalar = RollupsGraphQLScalarValue
+    description = "Top level subscriptions"
+)]
+impl Subscription {
+    #[graphql(description = "Get inputs as they are added")]
+    async fn input_added(
+        context: &Context,
+        #[graphql(description = "Filter entries to retrieve")] r#where: Option<
+            InputFilter,
+        >,
+    ) -> FieldResult<EventStream<Input>> {
+        subscribe(
+            context,
+            RepositoryEventKind::InputAdded,
+            r#where,
+            |repository, event| async move {
+                repository.get_input(event.input_index).await
+            },
+        )
+    }
+
+    #[graphql(
+        description = "Get inputs when their status changes after they are processed"
+    )]
+    async fn input_status_changed(
+        context: &Context,
+        #[graphql(description = "Filter entries to retrieve")] r#where: Option<
+            InputFilter,
+        >,
+    ) -> FieldResult<EventStream<Input>> {
+        subscribe(
+            context,
+            RepositoryEventKind::InputStatusChanged,
+            r#where,
+            |repository, event| async move {
+                repository.get_input(event.input_index).await
+            },
+        )
+    }
+
+    #[graphql(description = "Get vouchers as they are added")]
+    async fn voucher_added(
+        context: &Context,
+        #[graphql(description = "Filter entries to retrieve by their input")]
+        r#where: Option<InputFilter>,
+    ) -> FieldResult<EventStream<Voucher>> {
+        subscribe(
+            context,
+            RepositoryEventKind::VoucherAdded,
+            r#where,
+            |repository, event| async move {
+                repository.get_voucher(event.index, event.input_index).await
+            },
+        )
+    }
+
+    #[graphql(description = "Get notices as they are added")]
+    async fn notice_added(
+        context: &Context,
+        #[graphql(description = "Filter entries to retrieve by their input")]
+        r#where: Option<InputFilter>,
+    ) -> FieldResult<EventStream<Notice>> {
+        subscribe(
+            context,
+            RepositoryEventKind::NoticeAdded,
+            r#where,
+            |repository, event| async move {
+                repository.get_notice(event.index, event.input_index).await
+            },
+        )
+    }
+
+    #[graphql(description = "Get reports as they are added")]
+    async fn report_added(
+        context: &Context,
+        #[graphql(description = "Filter entries to retrieve by their input")]
+        r#where: Option<InputFilter>,
+    ) -> FieldResult<EventStream<Report>> {
+        subscribe(
+            context,
+            RepositoryEventKind::ReportAdded,
+            r#where,
+            |repository, event| async move {
+                repository.get_report(event.index, event.input_index).await
+            },
+        )
+    }
+}
+
+/// Stream the entries of the events of the given kind whose input matches
+/// the filter, fetching them from the repository
+fn subscribe<T, F, Fut>(
+    context: &Context,
+    kind: RepositoryEventKind,
+    filter: Option<InputFilter>,
+    fetch: F,
+) -> FieldResult<EventStream<T>>
+where
+    T: Send + 'static,
+    F: Fn(Repository, RepositoryEvent) -> Fut + Copy + Send + Sync + 'static,
+    Fut: Future<Output = Result<T, rollups_data::Error>> + Send,
+{
+    let filter: Option<InputQueryFilter> =
+        filter.map(TryInto::try_into).transpose()?;
+    let repository = context.repository.clone();
+    let events = context.events.subscribe();
+    Ok(Box::pin(stream::unfold(events, move |mut events| {
+        let repository = repository.clone();
+        let filter = filter.clone();
+        async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(count)) => {
+                        tracing::warn!(
+                            "subscription lagged behind, skipped {} events",
+                            count
+                        );
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+                if event.kind != kind {
+                    continue;
+                }
+                if let Some(filter) = &filter {
+                    match repository.get_input(event.input_index).await {
+                        Ok(input) if !filter.matches(&input) => continue,
+                        Ok(_) => {}
+                        Err(e) => return Some((Err(convert_error(e)), events)),
+                    }
+                }
+                let result = fetch(repository.clone(), event)
+                    .await
+                    .map_err(convert_error);
+                return Some((result, events));
+            }
+        }
+    })))
+}
+
+#[derive(GraphQLEnum, Debug, Clone, Copy, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CompletionStatus {
     Unprocessed,
     Accepted,
     Rejected,
@@ -229,6 +529,45 @@ impl From<DbCompletionStatus> for CompletionStatus {
     }
 }
 
+impl From<CompletionStatus> for DbCompletionStatus {
+    fn from(status: CompletionStatus) -> DbCompletionStatus {
+        match status {
+            CompletionStatus::Unprocessed => DbCompletionStatus::Unprocessed,
+            CompletionStatus::Accepted => DbCompletionStatus::Accepted,
+            CompletionStatus::Rejected => DbCompletionStatus::Rejected,
+            CompletionStatus::Exception => DbCompletionStatus::Exception,
+            CompletionStatus::MachineHalted => {
+                DbCompletionStatus::MachineHalted
+            }
+            CompletionStatus::CycleLimitExceeded => {
+                DbCompletionStatus::CycleLimitExceeded
+            }
+            CompletionStatus::TimeLimitExceeded => {
+                DbCompletionStatus::TimeLimitExceeded
+            }
+            CompletionStatus::PayloadLengthLimitExceeded => {
+                DbCompletionStatus::PayloadLengthLimitExceeded
+            }
+        }
+    }
+}
+
+#[derive(GraphQLEnum)]
+/// Order of the entries by index
+enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl From<SortDirection> for DbSortDirection {
+    fn from(order: SortDirection) -> DbSortDirection {
+        match order {
+            SortDirection::Ascending => DbSortDirection::Ascending,
+            SortDirection::Descending => DbSortDirection::Descending,
+        }
+    }
+}
+
 #[graphql_object(
     context = Context,
     Scalar = RollupsGraphQLScalarValue,
@@ -254,13 +593,7 @@ impl Input {
         description = "Timestamp associated with the input submission, as defined by the base layer's block in which it was recorded"
     )]
     fn timestamp(&self) -> i64 {
-        match self.timestamp.duration_since(UNIX_EPOCH) {
-            Ok(duration) => duration.as_secs() as i64,
-            Err(e) => {
-                tracing::warn!("failed to parse timestamp ({})", e);
-                0
-            }
-        }
+        timestamp_to_secs(self.timestamp)
     }
 
     #[graphql(
@@ -277,10 +610,26 @@ impl Input {
         hex_encode(&self.payload)
     }
 
+    #[graphql(
+        description = "Epoch of the input; inputs indexed before the epochs were stored have no epoch"
+    )]
+    async fn epoch(&self) -> FieldResult<Option<Epoch>> {
+        match self.epoch_index {
+            Some(index) => executor
+                .context()
+                .loader
+                .epoch(index)
+                .await
+                .map(Some)
+                .map_err(convert_error),
+            None => Ok(None),
+        }
+    }
+
     #[graphql(
         description = "Get voucher from this particular input given the voucher's index"
     )]
-    fn voucher(
+    async fn voucher(
         &self,
         #[graphql(description = "Voucher index in input")] index: i32,
     ) -> FieldResult<Voucher> {
@@ -288,13 +637,14 @@ impl Input {
             .context()
             .repository
             .get_voucher(index, self.index)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(
         description = "Get notice from this particular input given the notice's index"
     )]
-    fn notice(
+    async fn notice(
         &self,
         #[graphql(description = "Notice index in input")] index: i32,
     ) -> FieldResult<Notice> {
@@ -302,13 +652,14 @@ impl Input {
             .context()
             .repository
             .get_notice(index, self.index)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(
         description = "Get report from this particular input given the report's index"
     )]
-    fn report(
+    async fn report(
         &self,
         #[graphql(description = "Report index in input")] index: i32,
     ) -> FieldResult<Report> {
@@ -316,13 +667,14 @@ impl Input {
             .context()
             .repository
             .get_report(index, self.index)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(
         description = "Get vouchers from this particular input with support for pagination"
     )]
-    fn vouchers(
+    async fn vouchers(
         &self,
         #[graphql(
             description = "Get at most the first `n` entries (forward pagination)"
@@ -340,21 +692,29 @@ impl Input {
             description = "Get entries that come before the provided cursor (backward pagination)"
         )]
         before: Option<String>,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
     ) -> FieldResult<Connection<Voucher>> {
-        let filter = VoucherQueryFilter {
-            input_index: Some(self.index),
+        let page = PageArgs {
+            first,
+            last,
+            after,
+            before,
+            order: order.map(SortDirection::into).unwrap_or_default(),
         };
         executor
             .context()
-            .repository
-            .get_vouchers(first, last, after, before, filter)
+            .loader
+            .vouchers(self.index, page)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(
         description = "Get notices from this particular input with support for pagination"
     )]
-    fn notices(
+    async fn notices(
         &self,
         #[graphql(
             description = "Get at most the first `n` entries (forward pagination)"
@@ -372,21 +732,29 @@ impl Input {
             description = "Get entries that come before the provided cursor (backward pagination)"
         )]
         before: Option<String>,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
     ) -> FieldResult<Connection<Notice>> {
-        let filter = NoticeQueryFilter {
-            input_index: Some(self.index),
+        let page = PageArgs {
+            first,
+            last,
+            after,
+            before,
+            order: order.map(SortDirection::into).unwrap_or_default(),
         };
         executor
             .context()
-            .repository
-            .get_notices(first, last, after, before, filter)
+            .loader
+            .notices(self.index, page)
+            .await
             .map_err(convert_error)
     }
 
     #[graphql(
         description = "Get reports from this particular input with support for pagination"
     )]
-    fn reports(
+    async fn reports(
         &self,
         #[graphql(
             description = "Get at most the first `n` entries (forward pagination)"
@@ -404,16 +772,130 @@ impl Input {
             description = "Get entries that come before the provided cursor (backward pagination)"
         )]
         before: Option<String>,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
     ) -> FieldResult<Connection<Report>> {
-        let filter = ReportQueryFilter {
-            input_index: Some(self.index),
+        let page = PageArgs {
+            first,
+            last,
+            after,
+            before,
+            order: order.map(SortDirection::into).unwrap_or_default(),
         };
         executor
             .context()
-            .repository
-            .get_reports(first, last, after, before, filter)
+            .loader
+            .reports(self.index, page)
+            .await
+            .map_err(convert_error)
+    }
+}
+
+#[graphql_object(
+    context = Context,
+    Scalar = RollupsGraphQLScalarValue,
+    description = "Group of inputs whose resulting machine state is claimed on the base layer blockchain"
+)]
+impl Epoch {
+    #[graphql(description = "Epoch index starting from genesis")]
+    fn index(&self) -> i32 {
+        self.index
+    }
+
+    #[graphql(
+        description = "Whether the epoch is finished, so no more inputs are added to it"
+    )]
+    fn finished(&self) -> bool {
+        self.finished
+    }
+
+    #[graphql(
+        description = "Claim of the epoch, which exists after the epoch is finished and its inputs are processed"
+    )]
+    async fn claim(&self) -> FieldResult<Option<Claim>> {
+        executor
+            .context()
+            .loader
+            .claim(self.index)
+            .await
             .map_err(convert_error)
     }
+
+    #[graphql(
+        description = "Get inputs from this particular epoch with support for pagination"
+    )]
+    async fn inputs(
+        &self,
+        #[graphql(
+            description = "Get at most the first `n` entries (forward pagination)"
+        )]
+        first: Option<i32>,
+        #[graphql(
+            description = "Get at most the last `n` entries (backward pagination)"
+        )]
+        last: Option<i32>,
+        #[graphql(
+            description = "Get entries that come after the provided cursor (forward pagination)"
+        )]
+        after: Option<String>,
+        #[graphql(
+            description = "Get entries that come before the provided cursor (backward pagination)"
+        )]
+        before: Option<String>,
+        #[graphql(description = "Filter entries to retrieve")] r#where: Option<
+            InputFilter,
+        >,
+        #[graphql(description = "Order of the entries by index")] order: Option<
+            SortDirection,
+        >,
+    ) -> FieldResult<Connection<Input>> {
+        let filter = InputQueryFilter {
+            epoch_index: Some(self.index),
+            order: order.map(SortDirection::into).unwrap_or_default(),
+            ..query_filter(r#where)?
+        };
+        let connection = executor
+            .context()
+            .repository
+            .get_inputs(first, last, after, before, filter)
+            .await
+            .map_err(convert_error)?;
+        executor
+            .context()
+            .loader
+            .prime_inputs(connection.edges.iter().map(|edge| &edge.node));
+        Ok(connection)
+    }
+}
+
+#[graphql_object(
+    context = Context,
+    Scalar = RollupsGraphQLScalarValue,
+    description = "Claim of the machine state after processing the inputs of an epoch"
+)]
+impl Claim {
+    #[graphql(description = "Index of the claimed epoch")]
+    fn epoch_index(&self) -> i32 {
+        self.epoch_index
+    }
+
+    #[graphql(
+        description = "Claimed hash of the epoch in Ethereum hex binary format (32 bytes), starting with '0x'"
+    )]
+    fn epoch_hash(&self) -> String {
+        hex_encode(&self.epoch_hash)
+    }
+
+    #[graphql(description = "Index of the first input of the epoch")]
+    fn first_input_index(&self) -> i32 {
+        self.first_input_index
+    }
+
+    #[graphql(description = "Index of the last input of the epoch")]
+    fn last_input_index(&self) -> i32 {
+        self.last_input_index
+    }
 }
 
 #[graphql_object(
@@ -430,11 +912,12 @@ impl Voucher {
     }
 
     #[graphql(description = "Input whose processing produced the voucher")]
-    fn input(&self) -> FieldResult<Input> {
+    async fn input(&self) -> FieldResult<Input> {
         executor
             .context()
-            .repository
-            .get_input(self.input_index)
+            .loader
+            .input(self.input_index)
+            .await
             .map_err(convert_error)
     }
 
@@ -452,14 +935,43 @@ impl Voucher {
         hex_encode(&self.payload)
     }
 
+    #[graphql(
+        description = "Amount of wei sent with the transaction as a big-endian uint256 in Ethereum hex binary format, starting with '0x'; legacy vouchers have no value"
+    )]
+    fn value(&self) -> Option<String> {
+        self.value.as_deref().map(hex_encode)
+    }
+
+    #[graphql(
+        description = "Whether the voucher runs the payload in the context of the DApp contract with DELEGATECALL"
+    )]
+    fn is_delegate_call(&self) -> bool {
+        self.is_delegate_call
+    }
+
+    #[graphql(
+        description = "Whether the voucher was already executed in the DApp contract"
+    )]
+    fn executed(&self) -> bool {
+        self.execution_tx_hash.is_some()
+    }
+
+    #[graphql(
+        description = "Hash of the transaction that executed the voucher in Ethereum hex binary format (32 bytes), starting with '0x'; null if it was not executed"
+    )]
+    fn execution_tx_hash(&self) -> Option<String> {
+        self.execution_tx_hash.as_deref().map(hex_encode)
+    }
+
     #[graphql(
         description = "Proof object that allows this voucher to be validated and executed on the base layer blockchain"
     )]
-    fn proof(&self) -> FieldResult<Option<Proof>> {
+    async fn proof(&self) -> FieldResult<Option<Proof>> {
         executor
             .context()
-            .repository
-            .get_proof(self.input_index, self.index, OutputEnum::Voucher)
+            .loader
+            .voucher_proof(self.input_index, self.index)
+            .await
             .map_err(convert_error)
     }
 }
@@ -478,11 +990,12 @@ impl Notice {
     }
 
     #[graphql(description = "Input whose processing produced the notice")]
-    fn input(&self) -> FieldResult<Input> {
+    async fn input(&self) -> FieldResult<Input> {
         executor
             .context()
-            .repository
-            .get_input(self.input_index)
+            .loader
+            .input(self.input_index)
+            .await
             .map_err(convert_error)
     }
 
@@ -496,11 +1009,12 @@ impl Notice {
     #[graphql(
         description = "Proof object that allows this notice to be validated by the base layer blockchain"
     )]
-    fn proof(&self) -> FieldResult<Option<Proof>> {
+    async fn proof(&self) -> FieldResult<Option<Proof>> {
         executor
             .context()
-            .repository
-            .get_proof(self.input_index, self.index, OutputEnum::Notice)
+            .loader
+            .notice_proof(self.input_index, self.index)
+            .await
             .map_err(convert_error)
     }
 }
@@ -519,11 +1033,12 @@ impl Report {
     }
 
     #[graphql(description = "Input whose processing produced the report")]
-    fn input(&self) -> FieldResult<Input> {
+    async fn input(&self) -> FieldResult<Input> {
         executor
             .context()
-            .repository
-            .get_input(self.input_index)
+            .loader
+            .input(self.input_index)
+            .await
             .map_err(convert_error)
     }
 
@@ -633,17 +1148,178 @@ pub struct InputFilter {
 
     /// Filter only inputs with index greater than a given value
     pub index_greater_than: Option<i32>,
+
+    /// Filter only inputs submitted by a given address in Ethereum hex binary format, starting with '0x'
+    pub msg_sender: Option<String>,
+
+    /// Filter only inputs with block number lower than a given value
+    pub block_number_lower_than: Option<i64>,
+
+    /// Filter only inputs with block number greater than a given value
+    pub block_number_greater_than: Option<i64>,
+
+    /// Filter only inputs with timestamp lower than a given value, in seconds
+    pub timestamp_lower_than: Option<i64>,
+
+    /// Filter only inputs with timestamp greater than a given value, in seconds
+    pub timestamp_greater_than: Option<i64>,
+
+    /// Filter only inputs with a given status
+    pub status: Option<CompletionStatus>,
+
+    /// Filter only inputs of a given epoch
+    pub epoch_index: Option<i32>,
 }
 
-impl From<InputFilter> for InputQueryFilter {
-    fn from(filter: InputFilter) -> InputQueryFilter {
-        InputQueryFilter {
+impl TryFrom<InputFilter> for InputQueryFilter {
+    type Error = FieldError;
+
+    fn try_from(filter: InputFilter) -> FieldResult<InputQueryFilter> {
+        Ok(InputQueryFilter {
             index_lower_than: filter.index_lower_than,
             index_greater_than: filter.index_greater_than,
-        }
+            msg_sender: filter
+                .msg_sender
+                .as_deref()
+                .map(hex_decode)
+                .transpose()?,
+            block_number_lower_than: filter.block_number_lower_than,
+            block_number_greater_than: filter.block_number_greater_than,
+            timestamp_lower_than: filter
+                .timestamp_lower_than
+                .map(timestamp_from_secs)
+                .transpose()?,
+            timestamp_greater_than: filter
+                .timestamp_greater_than
+                .map(timestamp_from_secs)
+                .transpose()?,
+            status: filter.status.map(CompletionStatus::into),
+            epoch_index: filter.epoch_index,
+            order: Default::default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, GraphQLInputObject)]
+#[graphql(scalar = RollupsGraphQLScalarValue)]
+/// Filter object to restrict results depending on voucher properties
+pub struct VoucherFilter {
+    /// Filter only vouchers produced by a given input
+    pub input_index: Option<i32>,
+
+    /// Filter only vouchers produced by inputs of a given epoch
+    pub epoch_index: Option<i32>,
+
+    /// Filter only vouchers with a given destination address in Ethereum hex binary format, starting with '0x'
+    pub destination: Option<String>,
+
+    /// Filter only vouchers that have a proof or only vouchers that do not
+    pub has_proof: Option<bool>,
+
+    /// Filter only vouchers that were executed or only vouchers that were not
+    pub executed: Option<bool>,
+}
+
+impl TryFrom<VoucherFilter> for VoucherQueryFilter {
+    type Error = FieldError;
+
+    fn try_from(filter: VoucherFilter) -> FieldResult<VoucherQueryFilter> {
+        Ok(VoucherQueryFilter {
+            input_index: filter.input_index,
+            epoch_index: filter.epoch_index,
+            destination: filter
+                .destination
+                .as_deref()
+                .map(hex_decode)
+                .transpose()?,
+            has_proof: filter.has_proof,
+            executed: filter.executed,
+            order: Default::default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, GraphQLInputObject)]
+#[graphql(scalar = RollupsGraphQLScalarValue)]
+/// Filter object to restrict results depending on notice properties
+pub struct NoticeFilter {
+    /// Filter only notices produced by a given input
+    pub input_index: Option<i32>,
+
+    /// Filter only notices produced by inputs of a given epoch
+    pub epoch_index: Option<i32>,
+
+    /// Filter only notices that have a proof or only notices that do not
+    pub has_proof: Option<bool>,
+}
+
+impl TryFrom<NoticeFilter> for NoticeQueryFilter {
+    type Error = FieldError;
+
+    fn try_from(filter: NoticeFilter) -> FieldResult<NoticeQueryFilter> {
+        Ok(NoticeQueryFilter {
+            input_index: filter.input_index,
+            epoch_index: filter.epoch_index,
+            has_proof: filter.has_proof,
+            order: Default::default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, GraphQLInputObject)]
+#[graphql(scalar = RollupsGraphQLScalarValue)]
+/// Filter object to restrict results depending on report properties
+pub struct ReportFilter {
+    /// Filter only reports produced by a given input
+    pub input_index: Option<i32>,
+
+    /// Filter only reports produced by inputs of a given epoch
+    pub epoch_index: Option<i32>,
+}
+
+impl TryFrom<ReportFilter> for ReportQueryFilter {
+    type Error = FieldError;
+
+    fn try_from(filter: ReportFilter) -> FieldResult<ReportQueryFilter> {
+        Ok(ReportQueryFilter {
+            input_index: filter.input_index,
+            epoch_index: filter.epoch_index,
+            order: Default::default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, GraphQLInputObject)]
+#[graphql(scalar = RollupsGraphQLScalarValue)]
+/// Filter object to restrict results depending on epoch properties
+pub struct EpochFilter {
+    /// Filter only epochs with index lower than a given value
+    pub index_lower_than: Option<i32>,
+
+    /// Filter only epochs with index greater than a given value
+    pub index_greater_than: Option<i32>,
+}
+
+impl TryFrom<EpochFilter> for EpochQueryFilter {
+    type Error = FieldError;
+
+    fn try_from(filter: EpochFilter) -> FieldResult<EpochQueryFilter> {
+        Ok(EpochQueryFilter {
+            index_lower_than: filter.index_lower_than,
+            index_greater_than: filter.index_greater_than,
+            order: Default::default(),
+        })
     }
 }
 
+/// Convert the optional filter of a query into the repository filter
+fn query_filter<F, Q>(filter: Option<F>) -> FieldResult<Q>
+where
+    Q: TryFrom<F, Error = FieldError> + Default,
+{
+    Ok(filter.map(Q::try_from).transpose()?.unwrap_or_default())
+}
+
 #[derive(Debug, Clone, GraphQLObject)]
 /// Page metadata for the cursor-based Connection pagination pattern
 struct PageInfo {
@@ -731,6 +1407,7 @@ impl_connection!("InputConnection", "InputEdge", Input);
 impl_connection!("VoucherConnection", "VoucherEdge", Voucher);
 impl_connection!("NoticeConnection", "NoticeEdge", Notice);
 impl_connection!("ReportConnection", "ReportEdge", Report);
+impl_connection!("EpochConnection", "EpochEdge", Epoch);
 
 fn convert_error(e: rollups_data::Error) -> FieldError<DefaultScalarValue> {
     tracing::warn!("Got error during query: {:?}", e);
@@ -740,3 +1417,26 @@ fn convert_error(e: rollups_data::Error) -> FieldError<DefaultScalarValue> {
 pub fn hex_encode(data: &[u8]) -> String {
     format!("0x{}", hex::encode(data))
 }
+
+pub fn hex_decode(data: &str) -> FieldResult<Vec<u8>> {
+    let digits = data.strip_prefix("0x").unwrap_or(data);
+    hex::decode(digits).map_err(|e| {
+        FieldError::from(format!("invalid hex string {} ({})", data, e))
+    })
+}
+
+pub fn timestamp_to_secs(timestamp: SystemTime) -> i64 {
+    match timestamp.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(e) => {
+            tracing::warn!("failed to parse timestamp ({})", e);
+            0
+        }
+    }
+}
+
+fn timestamp_from_secs(secs: i64) -> FieldResult<SystemTime> {
+    let secs = u64::try_from(secs)
+        .map_err(|_| FieldError::from(format!("invalid timestamp {}", secs)))?;
+    Ok(UNIX_EPOCH + Duration::from_secs(secs))
+}
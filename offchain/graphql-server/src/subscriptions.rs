@@ -0,0 +1,219 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Subscriptions with the graphql-ws protocol over WebSockets.
+//!
+//! The messages of the client go through an actor that checks the operations
+//! against the query limits before handing them to the juniper connection,
+//! which would run them without any limit.
+
+use actix::{
+    Actor, ActorContext, ActorFutureExt, AsyncContext, Handler, Message,
+    StreamHandler, WrapFuture,
+};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use juniper::futures::{SinkExt, StreamExt};
+use juniper::{EmptyMutation, InputValue, RuleError};
+use juniper_graphql_ws::{
+    ArcSchema, ClientMessage, Connection, ConnectionConfig, ServerMessage,
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::limits::QueryLimits;
+use crate::schema::{
+    Context, Query, RollupsGraphQLScalarValue, Schema, Subscription,
+};
+
+type GraphQLConnection = Connection<
+    ArcSchema<
+        Query,
+        EmptyMutation<Context>,
+        Subscription,
+        Context,
+        RollupsGraphQLScalarValue,
+    >,
+    ConnectionConfig<Context>,
+>;
+
+/// Serve the graphql-ws protocol over the WebSocket connection
+pub async fn subscriptions_handler(
+    req: HttpRequest,
+    stream: web::Payload,
+    schema: Arc<Schema>,
+    config: ConnectionConfig<Context>,
+    limits: QueryLimits,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (client_tx, client_rx) = mpsc::unbounded_channel();
+    let actor = SubscriptionActor {
+        connection: Some(Connection::new(ArcSchema(schema.clone()), config)),
+        client_tx,
+        client_rx: Some(client_rx),
+        schema,
+        limits,
+    };
+    let mut response = ws::start(actor, &req, stream)?;
+    response.headers_mut().insert(
+        HeaderName::from_static("sec-websocket-protocol"),
+        HeaderValue::from_static("graphql-ws"),
+    );
+    Ok(response)
+}
+
+struct SubscriptionActor {
+    /// Taken when the actor starts to drive the connection
+    connection: Option<GraphQLConnection>,
+    client_tx: mpsc::UnboundedSender<ClientMessage<RollupsGraphQLScalarValue>>,
+    client_rx: Option<
+        mpsc::UnboundedReceiver<ClientMessage<RollupsGraphQLScalarValue>>,
+    >,
+    schema: Arc<Schema>,
+    limits: QueryLimits,
+}
+
+impl SubscriptionActor {
+    /// Check the operation of a start message, replying with the error and
+    /// completing the operation when it exceeds the limits
+    fn check_start(
+        &self,
+        message: &ClientMessage<RollupsGraphQLScalarValue>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) -> bool {
+        let (id, payload) = match message {
+            ClientMessage::Start { id, payload } => (id, payload),
+            _ => return true,
+        };
+        let variables =
+            InputValue::object(payload.variables.clone().into_iter().collect());
+        match self.limits.check(
+            &self.schema.schema,
+            &payload.query,
+            Some(&variables),
+        ) {
+            Ok(()) => true,
+            Err(error) => {
+                send_operation_error(ctx, id, error);
+                false
+            }
+        }
+    }
+}
+
+impl Actor for SubscriptionActor {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let connection = self.connection.take().expect("started once");
+        let mut client_rx = self.client_rx.take().expect("started once");
+        let (mut connection_tx, mut connection_rx) = connection.split();
+        ctx.spawn(
+            async move {
+                while let Some(message) = client_rx.recv().await {
+                    // Sending to the connection is infallible
+                    let _ = connection_tx.send(message).await;
+                }
+            }
+            .into_actor(self),
+        );
+        let addr = ctx.address();
+        ctx.spawn(
+            async move {
+                while let Some(message) = connection_rx.next().await {
+                    addr.do_send(ServerMessageWrapper(message));
+                }
+            }
+            .into_actor(self)
+            // The connection ends when the client terminates it
+            .map(|_, _, ctx| ctx.stop()),
+        );
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>>
+    for SubscriptionActor
+{
+    fn handle(
+        &mut self,
+        message: Result<ws::Message, ws::ProtocolError>,
+        ctx: &mut Self::Context,
+    ) {
+        let message = match message {
+            Ok(ws::Message::Text(text)) => {
+                match serde_json::from_str::<
+                    ClientMessage<RollupsGraphQLScalarValue>,
+                >(&text)
+                {
+                    Ok(message) => message,
+                    Err(e) => {
+                        let error = serde_json::json!({
+                            "type": "connection_error",
+                            "payload": { "message": e.to_string() },
+                        });
+                        ctx.text(error.to_string());
+                        return;
+                    }
+                }
+            }
+            Ok(ws::Message::Ping(bytes)) => {
+                ctx.pong(&bytes);
+                return;
+            }
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+                return;
+            }
+            Ok(_) => return,
+            Err(e) => {
+                tracing::warn!("subscription protocol error ({})", e);
+                ctx.stop();
+                return;
+            }
+        };
+        if self.check_start(&message, ctx) {
+            // The receiver lives as long as the actor
+            let _ = self.client_tx.send(message);
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct ServerMessageWrapper(ServerMessage<RollupsGraphQLScalarValue>);
+
+impl Handler<ServerMessageWrapper> for SubscriptionActor {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        message: ServerMessageWrapper,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        match serde_json::to_string(&message.0) {
+            Ok(text) => ctx.text(text),
+            Err(e) => ctx.close(Some(ws::CloseReason {
+                code: ws::CloseCode::Error,
+                description: Some(format!("error serializing response: {}", e)),
+            })),
+        }
+    }
+}
+
+/// Reply to the start message with the error in the format of the GraphQL
+/// errors, as juniper does for the validation errors
+fn send_operation_error(
+    ctx: &mut ws::WebsocketContext<SubscriptionActor>,
+    id: &str,
+    error: RuleError,
+) {
+    let error = serde_json::json!({
+        "type": "error",
+        "id": id,
+        "payload": [error],
+    });
+    ctx.text(error.to_string());
+    let complete = serde_json::json!({ "type": "complete", "id": id });
+    ctx.text(complete.to_string());
+}
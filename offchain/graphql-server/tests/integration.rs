@@ -3,19 +3,23 @@
 
 use actix_web::dev::ServerHandle;
 use actix_web::rt::spawn;
-use awc::{Client, ClientRequest};
-use graphql_server::{http, schema::Context};
+use awc::{ws, BoxedSocket, Client, ClientRequest};
+use futures_util::{SinkExt, StreamExt};
+use graphql_server::{forward_events, http, schema::Context, QueryLimits};
 use rollups_data::{
-    CompletionStatus, Input, Notice, Proof, Report, Repository, Voucher,
+    Claim, CompletionStatus, Epoch, Input, Notice, Proof, Report, Repository,
+    Voucher,
 };
 use std::fs::read_to_string;
 use std::str::from_utf8;
 use std::time::{Duration, UNIX_EPOCH};
 use test_fixtures::RepositoryFixture;
 use testcontainers::clients::Cli;
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
 use tokio::task::JoinHandle;
 
+type Subscription = actix_codec::Framed<BoxedSocket, ws::Codec>;
+
 const QUERY_PATH: &str = "tests/queries/";
 const RESPONSE_PATH: &str = "tests/responses/";
 const HOST: &str = "127.0.0.1";
@@ -28,14 +32,28 @@ struct TestState<'d> {
 
 impl TestState<'_> {
     async fn setup(docker: &Cli) -> TestState<'_> {
-        let repository = RepositoryFixture::setup(docker);
-        let server =
-            GraphQLServerWrapper::spawn_server(repository.repository().clone())
-                .await;
+        let repository = RepositoryFixture::setup(docker).await;
+        let server = GraphQLServerWrapper::spawn_server(
+            repository.repository().clone(),
+            repository.config().endpoint(),
+        )
+        .await;
         TestState { repository, server }
     }
 
     async fn populate_database(&self) {
+        let epoch = Epoch {
+            index: 0,
+            finished: true,
+        };
+
+        let claim = Claim {
+            epoch_index: 0,
+            epoch_hash: "<hash>".as_bytes().to_vec(),
+            first_input_index: 0,
+            last_input_index: 0,
+        };
+
         let input = Input {
             index: 0,
             msg_sender: "msg-sender".as_bytes().to_vec(),
@@ -44,6 +62,7 @@ impl TestState<'_> {
             timestamp: UNIX_EPOCH + Duration::from_secs(1676489717),
             payload: "input-0".as_bytes().to_vec(),
             status: CompletionStatus::Accepted,
+            epoch_index: Some(0),
         };
 
         let notice = Notice {
@@ -57,6 +76,9 @@ impl TestState<'_> {
             index: 0,
             destination: "destination".as_bytes().to_vec(),
             payload: "voucher-0-0".as_bytes().to_vec(),
+            value: None,
+            is_delegate_call: false,
+            execution_tx_hash: None,
         };
 
         let report = Report {
@@ -107,26 +129,45 @@ impl TestState<'_> {
 
         let repo = self.repository.repository();
 
+        repo.insert_epoch(epoch.clone())
+            .await
+            .expect("Failed to insert epoch");
+
         repo.insert_input(input.clone())
+            .await
             .expect("Failed to insert input");
 
         repo.insert_notice(notice.clone())
+            .await
             .expect("Failed to insert notice");
 
         repo.insert_voucher(voucher.clone())
+            .await
             .expect("Failed to insert voucher");
 
         repo.insert_report(report.clone())
+            .await
             .expect("Failed to insert report");
 
         repo.insert_proof(proof_notice.clone())
+            .await
             .expect("Failed to insert notice type proof");
 
         repo.insert_proof(proof_voucher.clone())
+            .await
             .expect("Failed to insert voucher type proof");
+
+        repo.insert_claim(claim.clone())
+            .await
+            .expect("Failed to insert claim");
     }
 
     async fn populate_for_pagination(&self) {
+        let epoch = Epoch {
+            index: 0,
+            finished: false,
+        };
+
         let input = Input {
             index: 0,
             msg_sender: "msg-sender".as_bytes().to_vec(),
@@ -135,6 +176,7 @@ impl TestState<'_> {
             timestamp: UNIX_EPOCH + Duration::from_secs(1676489717),
             payload: "input-0".as_bytes().to_vec(),
             status: CompletionStatus::Accepted,
+            epoch_index: Some(0),
         };
 
         let notice0 = Notice {
@@ -169,40 +211,142 @@ impl TestState<'_> {
 
         let repo = self.repository.repository();
 
+        repo.insert_epoch(epoch.clone())
+            .await
+            .expect("Failed to insert epoch");
+
         repo.insert_input(input.clone())
+            .await
             .expect("Failed to insert input");
 
         repo.insert_notice(notice0.clone())
+            .await
             .expect("Failed to insert notice");
 
         repo.insert_notice(notice1.clone())
+            .await
             .expect("Failed to insert notice");
 
         repo.insert_notice(notice2.clone())
+            .await
             .expect("Failed to insert notice");
 
         repo.insert_notice(notice3.clone())
+            .await
             .expect("Failed to insert notice");
 
         repo.insert_notice(notice4.clone())
+            .await
             .expect("Failed to insert notice");
     }
+
+    async fn populate_for_batching(&self) {
+        let repo = self.repository.repository();
+
+        repo.insert_epoch(Epoch {
+            index: 0,
+            finished: false,
+        })
+        .await
+        .expect("Failed to insert epoch");
+
+        for input_index in 0..3 {
+            repo.insert_input(Input {
+                index: input_index,
+                msg_sender: "msg-sender".as_bytes().to_vec(),
+                tx_hash: "tx-hash".as_bytes().to_vec(),
+                block_number: 0,
+                timestamp: UNIX_EPOCH + Duration::from_secs(1676489717),
+                payload: format!("input-{}", input_index).as_bytes().to_vec(),
+                status: CompletionStatus::Accepted,
+                epoch_index: Some(0),
+            })
+            .await
+            .expect("Failed to insert input");
+
+            for index in 0..2 {
+                repo.insert_voucher(Voucher {
+                    input_index,
+                    index,
+                    destination: "destination".as_bytes().to_vec(),
+                    payload: format!("voucher-{}-{}", input_index, index)
+                        .as_bytes()
+                        .to_vec(),
+                    value: None,
+                    is_delegate_call: false,
+                    execution_tx_hash: None,
+                })
+                .await
+                .expect("Failed to insert voucher");
+
+                repo.insert_notice(Notice {
+                    input_index,
+                    index,
+                    payload: format!("notice-{}-{}", input_index, index)
+                        .as_bytes()
+                        .to_vec(),
+                })
+                .await
+                .expect("Failed to insert notice");
+
+                for output_enum in [
+                    rollups_data::OutputEnum::Voucher,
+                    rollups_data::OutputEnum::Notice,
+                ] {
+                    repo.insert_proof(Proof {
+                        input_index,
+                        output_index: index,
+                        output_enum,
+                        validity_input_index_within_epoch: input_index,
+                        validity_output_index_within_input: index,
+                        validity_output_hashes_root_hash: "<hash>"
+                            .as_bytes()
+                            .to_vec(),
+                        validity_vouchers_epoch_root_hash: "<hash>"
+                            .as_bytes()
+                            .to_vec(),
+                        validity_notices_epoch_root_hash: "<hash>"
+                            .as_bytes()
+                            .to_vec(),
+                        validity_machine_state_hash: "<hash>"
+                            .as_bytes()
+                            .to_vec(),
+                        validity_output_hash_in_output_hashes_siblings: vec![],
+                        validity_output_hashes_in_epoch_siblings: vec![],
+                        context: format!("context-{}-{}", input_index, index)
+                            .as_bytes()
+                            .to_vec(),
+                    })
+                    .await
+                    .expect("Failed to insert proof");
+                }
+            }
+        }
+    }
 }
 
 pub struct GraphQLServerWrapper {
     server_handle: ServerHandle,
     join_handle: JoinHandle<Result<(), std::io::Error>>,
+    events_handle: JoinHandle<()>,
 }
 
 impl GraphQLServerWrapper {
-    async fn spawn_server(repository: Repository) -> Self {
-        let context = Context::new(repository);
+    async fn spawn_server(repository: Repository, endpoint: String) -> Self {
+        let (events_tx, _) = broadcast::channel(16);
+        let context = Context::new(repository, events_tx.clone());
+        let events_handle = spawn(forward_events(endpoint, events_tx));
         let (tx, rx) = oneshot::channel();
 
         let join_handle = spawn(
             async {
-                let service_handler = http::start_service(HOST, PORT, context)
-                    .expect("failed to create server");
+                let service_handler = http::start_service(
+                    HOST,
+                    PORT,
+                    context,
+                    QueryLimits::default(),
+                )
+                .expect("failed to create server");
                 tx.send(service_handler.handle())
                     .expect("failed to send server handle");
                 service_handler
@@ -213,10 +357,12 @@ impl GraphQLServerWrapper {
         Self {
             server_handle,
             join_handle,
+            events_handle,
         }
     }
 
     pub async fn stop(self) {
+        self.events_handle.abort();
         self.server_handle.stop(true).await;
         self.join_handle
             .await
@@ -503,6 +649,42 @@ async fn query_inputs() {
     test.server.stop().await;
 }
 
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_input_with_epoch() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("input_with_epoch.json").await;
+    assert_from_body(body, "input_with_epoch.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_epoch() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("epoch.json").await;
+    assert_from_body(body, "epoch.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_epochs() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_for_pagination().await;
+
+    let body = post_query_request("epochs.json").await;
+    assert_from_body(body, "epochs.json");
+    test.server.stop().await;
+}
+
 #[actix_web::test]
 #[serial_test::serial]
 async fn query_next_page() {
@@ -527,6 +709,91 @@ async fn query_previous_page() {
     test.server.stop().await;
 }
 
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_inputs_with_filter() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("inputs_with_filter.json").await;
+    assert_from_body(body, "inputs_with_filter.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_vouchers_with_filter() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("vouchers_with_filter.json").await;
+    assert_from_body(body, "vouchers_with_filter.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_unexecuted_vouchers() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("unexecuted_vouchers.json").await;
+    assert_from_body(body, "unexecuted_vouchers.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_notices_without_proof() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("notices_without_proof.json").await;
+    assert_from_body(body, "notices_without_proof.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_descending_page() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_for_pagination().await;
+
+    let body = post_query_request("descending_page.json").await;
+    assert_from_body(body, "descending_page.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_nested_fields_in_batches() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_for_batching().await;
+    let repo = test.repository.repository();
+
+    // Inputs (count and page), epochs, vouchers (count and page), voucher
+    // proofs, notices (count and page) and notice proofs; the inputs of the
+    // vouchers are already cached
+    let query_count = repo.query_count();
+    let body = post_query_request("inputs_with_nested_outputs.json").await;
+    assert_eq!(repo.query_count() - query_count, 9);
+    assert_from_body(body, "inputs_with_nested_outputs.json");
+
+    // Vouchers (count and page), inputs and proofs
+    let query_count = repo.query_count();
+    let body = post_query_request("vouchers_with_input_and_proof.json").await;
+    assert_eq!(repo.query_count() - query_count, 4);
+    assert_from_body(body, "vouchers_with_input_and_proof.json");
+
+    test.server.stop().await;
+}
+
 #[actix_web::test]
 #[serial_test::serial]
 async fn query_error_missing_argument() {
@@ -563,6 +830,191 @@ async fn query_error_unknown_field() {
     test.server.stop().await;
 }
 
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_error_invalid_filter() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("error_invalid_filter.json").await;
+    assert_from_body(body, "error_invalid_filter.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_error_max_depth() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("error_max_depth.json").await;
+    assert_from_body(body, "error_max_depth.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_error_max_complexity() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let body = post_query_request("error_max_complexity.json").await;
+    assert_from_body(body, "error_max_complexity.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn query_error_max_body_size() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+
+    let limit = QueryLimits::default().max_body_size;
+    let query = serde_json::json!({
+        "query": format!("{{inputs {{totalCount}}}}{}", " ".repeat(limit))
+    });
+    let client = Client::builder().timeout(Duration::from_secs(5)).finish();
+    let mut response = client
+        .post(format!("http://localhost:{}/graphql", PORT))
+        .send_json(&query)
+        .await
+        .expect("Should query server");
+    assert_eq!(response.status(), 413);
+    let body = response.body().await.expect("Should be body");
+    assert_eq!(
+        from_utf8(&body).expect("Should contain response body"),
+        format!(
+            r#"{{"errors":[{{"message":"Request body exceeds the maximum size of {} bytes","locations":[]}}]}}"#,
+            limit
+        )
+    );
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn rest_get_input() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let (status, body) = get_rest_request("inputs/0").await;
+    assert_eq!(status, 200);
+    assert_from_body(body, "rest_input.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn rest_get_input_notices() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let (status, body) = get_rest_request("inputs/0/notices?first=10").await;
+    assert_eq!(status, 200);
+    assert_from_body(body, "rest_input_notices.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn rest_get_vouchers_with_destination() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let (status, body) =
+        get_rest_request("vouchers?destination=0x64657374696e6174696f6e").await;
+    assert_eq!(status, 200);
+    assert_from_body(body, "rest_vouchers_with_destination.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn rest_error_not_found() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+
+    let (status, body) = get_rest_request("inputs/1/notices").await;
+    assert_eq!(status, 404);
+    assert_from_body(body, "rest_error_not_found.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn rest_get_openapi() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+
+    let (status, body) = get_rest_request("openapi.json").await;
+    assert_eq!(status, 200);
+    let openapi: serde_json::Value =
+        serde_json::from_slice(&body).expect("Should be JSON document");
+    for path in ["/inputs/{index}", "/inputs/{index}/notices", "/vouchers"] {
+        assert!(openapi["paths"][path]["get"].is_object(), "{}", path);
+    }
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn subscribe_notice_added() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    let mut subscription = start_subscription("notice_added.json").await;
+    test.populate_database().await;
+
+    let payload = receive_subscription_data(&mut subscription).await;
+    assert_from_payload(payload, "notice_added.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn subscribe_input_status_changed() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    test.populate_database().await;
+    let mut subscription =
+        start_subscription("input_status_changed.json").await;
+    let repo = test.repository.repository();
+    repo.update_input_status(0, CompletionStatus::Rejected)
+        .await
+        .expect("Failed to update input status");
+
+    let payload = receive_subscription_data(&mut subscription).await;
+    assert_from_payload(payload, "input_status_changed.json");
+    test.server.stop().await;
+}
+
+#[actix_web::test]
+#[serial_test::serial]
+async fn subscribe_error_max_depth() {
+    let docker = Cli::default();
+    let test = TestState::setup(&docker).await;
+    let mut subscription =
+        start_subscription("error_subscription_max_depth.json").await;
+
+    let mut message = receive_message(&mut subscription).await;
+    assert_eq!(message["type"], "error");
+    assert_eq!(message["id"], "1");
+    assert_from_payload(
+        message["payload"].take(),
+        "error_subscription_max_depth.json",
+    );
+    let complete = receive_message(&mut subscription).await;
+    assert_eq!(complete["type"], "complete");
+    assert_eq!(complete["id"], "1");
+    test.server.stop().await;
+}
+
 fn create_get_request(endpoint: &str) -> ClientRequest {
     let client = Client::default();
 
@@ -586,6 +1038,21 @@ async fn post_query_request(query_file: &str) -> actix_web::web::Bytes {
     body
 }
 
+async fn get_rest_request(
+    path: &str,
+) -> (actix_web::http::StatusCode, actix_web::web::Bytes) {
+    let client = Client::builder().timeout(Duration::from_secs(5)).finish();
+    let mut response = client
+        .get(format!("http://localhost:{}/{}", PORT, path))
+        .send()
+        .await
+        .expect("Should query server");
+
+    let body = response.body().await.expect("Should be body");
+
+    (response.status(), body)
+}
+
 fn assert_from_body(body: actix_web::web::Bytes, res_file: &str) {
     let response = String::from(RESPONSE_PATH) + res_file;
     assert_eq!(
@@ -593,3 +1060,69 @@ fn assert_from_body(body: actix_web::web::Bytes, res_file: &str) {
         read_to_string(response).expect("Should read response file")
     );
 }
+
+/// Start a subscription with the graphql-ws protocol
+async fn start_subscription(query_file: &str) -> Subscription {
+    let query = String::from(QUERY_PATH) + query_file;
+    let (_, mut subscription) = Client::new()
+        .ws(format!("ws://localhost:{}/graphql", PORT))
+        .protocols(["graphql-ws"])
+        .connect()
+        .await
+        .expect("Should connect to server");
+    send_message(&mut subscription, r#"{"type":"connection_init"}"#).await;
+    let ack = receive_message(&mut subscription).await;
+    assert_eq!(ack["type"], "connection_ack");
+    let start = format!(
+        r#"{{"type":"start","id":"1","payload":{}}}"#,
+        read_to_string(query).expect("Should read request file")
+    );
+    send_message(&mut subscription, &start).await;
+    // The protocol does not acknowledge the start of the subscription, so
+    // give the server some time to set it up
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    subscription
+}
+
+async fn send_message(subscription: &mut Subscription, message: &str) {
+    subscription
+        .send(ws::Message::Text(message.to_owned().into()))
+        .await
+        .expect("Should send message");
+}
+
+async fn receive_message(subscription: &mut Subscription) -> serde_json::Value {
+    loop {
+        let frame =
+            tokio::time::timeout(Duration::from_secs(5), subscription.next())
+                .await
+                .expect("Should receive message in time")
+                .expect("Should not close subscription")
+                .expect("Should receive valid frame");
+        if let ws::Frame::Text(text) = frame {
+            let message: serde_json::Value =
+                serde_json::from_slice(&text).expect("Should be JSON message");
+            if message["type"] != "ka" {
+                return message;
+            }
+        }
+    }
+}
+
+async fn receive_subscription_data(
+    subscription: &mut Subscription,
+) -> serde_json::Value {
+    let mut message = receive_message(subscription).await;
+    assert_eq!(message["type"], "data");
+    assert_eq!(message["id"], "1");
+    message["payload"].take()
+}
+
+fn assert_from_payload(payload: serde_json::Value, res_file: &str) {
+    let response = String::from(RESPONSE_PATH) + res_file;
+    let expected: serde_json::Value = serde_json::from_str(
+        &read_to_string(response).expect("Should read response file"),
+    )
+    .expect("Should parse response file");
+    assert_eq!(payload, expected);
+}
@@ -3,6 +3,10 @@
 
 use clap::Parser;
 use log::{LogConfig, LogEnvCliConfig};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::limits::ResourceLimits;
 
 const DEFAULT_ADDRESS: &str = "0.0.0.0";
 #[derive(Debug, Clone)]
@@ -16,6 +20,10 @@ pub struct Config {
     pub http_rollup_server_port: u16,
     pub finish_timeout: u64,
     pub healthcheck_port: u16,
+    pub record_file: Option<PathBuf>,
+    pub replay_file: Option<PathBuf>,
+    pub resource_limits: ResourceLimits,
+    pub session_ports: Vec<(String, u16)>,
 }
 
 #[derive(Parser)]
@@ -57,6 +65,51 @@ pub struct CLIConfig {
     /// Port of health check
     #[arg(long, env = "HOST_RUNNER_HEALTHCHECK_PORT", default_value_t = 8080)]
     pub healthcheck_port: u16,
+
+    /// Path to the session log where the rollup requests and the DApp outputs are recorded
+    #[arg(long, env)]
+    pub record_file: Option<PathBuf>,
+
+    /// Path to a session log to replay to the DApp instead of serving the Server Manager; the outputs are compared with the recorded ones
+    #[arg(long, env, conflicts_with = "record_file")]
+    pub replay_file: Option<PathBuf>,
+
+    /// Duration in ms for the DApp to finish each input before it ends with time limit exceeded
+    #[arg(long, env)]
+    pub input_time_limit: Option<u64>,
+
+    /// Duration in ms that emulates the cycle limit; inputs that take longer end with cycle limit exceeded
+    #[arg(long, env)]
+    pub input_cycle_limit: Option<u64>,
+
+    /// Max length in bytes of the input payload; longer inputs end with payload length limit exceeded
+    #[arg(long, env)]
+    pub max_payload_length: Option<usize>,
+
+    /// Max number of vouchers, notices or reports of each input; the machine halts when it is exceeded
+    #[arg(long, env)]
+    pub max_output_count: Option<usize>,
+
+    /// Max length in bytes of the payload of each voucher, notice or report; the machine halts when it is exceeded
+    #[arg(long, env)]
+    pub max_output_size: Option<usize>,
+
+    /// Comma-separated list of session_id=port; each listed session gets its own Rollup Server endpoint at that port
+    #[arg(long, env, value_delimiter = ',', value_parser = parse_session_port)]
+    pub session_ports: Vec<(String, u16)>,
+}
+
+fn parse_session_port(value: &str) -> Result<(String, u16), String> {
+    let (session_id, port) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected session_id=port, got {}", value))?;
+    if session_id.is_empty() {
+        return Err(String::from("session id is empty"));
+    }
+    let port = port
+        .parse()
+        .map_err(|e| format!("invalid port {} ({})", port, e))?;
+    Ok((session_id.to_owned(), port))
 }
 
 impl From<CLIConfig> for Config {
@@ -71,6 +124,20 @@ impl From<CLIConfig> for Config {
             http_rollup_server_port: cli_config.http_rollup_server_port,
             finish_timeout: cli_config.finish_timeout,
             healthcheck_port: cli_config.healthcheck_port,
+            record_file: cli_config.record_file,
+            replay_file: cli_config.replay_file,
+            resource_limits: ResourceLimits {
+                time_limit: cli_config
+                    .input_time_limit
+                    .map(Duration::from_millis),
+                cycle_limit: cli_config
+                    .input_cycle_limit
+                    .map(Duration::from_millis),
+                max_payload_length: cli_config.max_payload_length,
+                max_output_count: cli_config.max_output_count,
+                max_output_size: cli_config.max_output_size,
+            },
+            session_ports: cli_config.session_ports,
         }
     }
 }
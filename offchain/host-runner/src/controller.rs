@@ -5,8 +5,12 @@ use async_trait::async_trait;
 use snafu::Snafu;
 use std::time::Duration;
 use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
 
+use crate::limits::ResourceLimits;
 use crate::model::*;
+use crate::monitor::ControllerMonitor;
+use crate::recorder::{RecordedRequest, RecordedResult, Recorder};
 
 const MPSC_BUFFER_SIZE: usize = 1000;
 
@@ -21,10 +25,17 @@ pub struct Controller {
     report_tx: mpsc::Sender<SyncReportRequest>,
     exception_tx: mpsc::Sender<SyncExceptionRequest>,
     shutdown_tx: mpsc::Sender<SyncShutdownRequest>,
+    monitor: ControllerMonitor,
 }
 
 impl Controller {
-    pub fn new(finish_timeout: Duration) -> Self {
+    /// When a recorder is given, every request handled by the controller is
+    /// written to its session log along with the outputs of the DApp.
+    pub fn new(
+        finish_timeout: Duration,
+        limits: ResourceLimits,
+        recorder: Option<Recorder>,
+    ) -> Self {
         let (advance_tx, advance_rx) = mpsc::channel(MPSC_BUFFER_SIZE);
         let (inspect_tx, inspect_rx) = mpsc::channel(MPSC_BUFFER_SIZE);
         let (voucher_tx, voucher_rx) = mpsc::channel(MPSC_BUFFER_SIZE);
@@ -33,6 +44,7 @@ impl Controller {
         let (finish_tx, finish_rx) = mpsc::channel(MPSC_BUFFER_SIZE);
         let (exception_tx, exception_rx) = mpsc::channel(MPSC_BUFFER_SIZE);
         let (shutdown_tx, shutdown_rx) = mpsc::channel(MPSC_BUFFER_SIZE);
+        let monitor = ControllerMonitor::new(IDLE_STATE_NAME.into());
         let data = SharedStateData {
             advance_rx,
             inspect_rx,
@@ -43,6 +55,9 @@ impl Controller {
             exception_rx,
             shutdown_rx,
             finish_timeout,
+            limits,
+            recorder,
+            monitor: monitor.clone(),
         };
         let service = Service::new(data);
         tokio::spawn(service.run());
@@ -55,13 +70,20 @@ impl Controller {
             finish_tx,
             exception_tx,
             shutdown_tx,
+            monitor,
         }
     }
 
+    /// Read-only view of the state machine, for debugging the DApp.
+    pub fn monitor(&self) -> &ControllerMonitor {
+        &self.monitor
+    }
+
     pub async fn advance(
         &self,
         request: AdvanceStateRequest,
     ) -> oneshot::Receiver<AdvanceResult> {
+        self.monitor.push_request((&request).into());
         SyncRequest::send(&self.advance_tx, request).await
     }
 
@@ -69,6 +91,7 @@ impl Controller {
         &self,
         request: InspectStateRequest,
     ) -> oneshot::Receiver<InspectResult> {
+        self.monitor.push_request((&request).into());
         SyncRequest::send(&self.inspect_tx, request).await
     }
 
@@ -125,6 +148,8 @@ pub enum ControllerError {
         request_name: String,
         state_name: String,
     },
+    #[snafu(display("{} exceeds the output limits; halting", output_name))]
+    OutputLimitExceeded { output_name: String },
 }
 
 struct Service {
@@ -140,7 +165,9 @@ impl Service {
 
     async fn run(mut self) {
         loop {
+            let monitor = self.state.data().monitor.clone();
             if let Some(state) = self.state.process().await {
+                monitor.set_state(state.name());
                 self.state = state;
             } else {
                 tracing::info!("controller service terminated successfully");
@@ -168,8 +195,18 @@ impl Service {
         Some(state)
     }
 
-    fn shutdown(request: SyncShutdownRequest) -> Option<Box<dyn State>> {
+    /// Writes the pending entries of the session log before acknowledging
+    /// the shutdown.
+    async fn shutdown(
+        request: SyncShutdownRequest,
+        data: &mut SharedStateData,
+    ) -> Option<Box<dyn State>> {
         tracing::info!("processing shutdown request");
+        if let Some(recorder) = data.recorder.take() {
+            if let Err(e) = recorder.close().await {
+                tracing::error!("failed to close session log ({})", e);
+            }
+        }
         request.process(|_| ());
         None
     }
@@ -256,13 +293,29 @@ struct SharedStateData {
     exception_rx: mpsc::Receiver<SyncExceptionRequest>,
     shutdown_rx: mpsc::Receiver<SyncShutdownRequest>,
     finish_timeout: Duration,
+    limits: ResourceLimits,
+    recorder: Option<Recorder>,
+    monitor: ControllerMonitor,
 }
 
+impl SharedStateData {
+    fn record(&self, request: RecordedRequest, result: RecordedResult) {
+        if let Some(recorder) = &self.recorder {
+            if let Err(e) = recorder.record(request, result) {
+                tracing::error!("failed to record request ({})", e);
+            }
+        }
+    }
+}
+
+const IDLE_STATE_NAME: &str = "idle";
+
 /// OOP state design-pattern
 #[async_trait]
 trait State: Send + Sync {
     async fn process(self: Box<Self>) -> Option<Box<dyn State>>;
     fn name(&self) -> String;
+    fn data(&self) -> &SharedStateData;
 }
 
 /// The controller waits for finish request from the DApp
@@ -300,13 +353,17 @@ impl State for IdleState {
                 Service::handle_invalid(request, self, "exception")
             }
             Some(request) = self.data.shutdown_rx.recv() => {
-                Service::shutdown(request)
+                Service::shutdown(request, &mut self.data).await
             }
         }
     }
 
     fn name(&self) -> String {
-        "idle".into()
+        IDLE_STATE_NAME.into()
+    }
+
+    fn data(&self) -> &SharedStateData {
+        &self.data
     }
 }
 
@@ -338,6 +395,7 @@ impl State for FetchRequestState {
             _ = tokio::time::sleep(self.data.finish_timeout) => {
                 tracing::debug!("fetch request timed out; setting state to idle");
                 let timeout_err = ControllerError::FetchRequestTimeout;
+                self.data.monitor.push_timeout(timeout_err.to_string());
                 send_response(self.finish_response_tx, Err(timeout_err));
                 Some(Box::new(IdleState::new(self.data)))
             }
@@ -345,17 +403,26 @@ impl State for FetchRequestState {
                 tracing::debug!("received inspect request; setting state to inspect");
                 tracing::debug!("request: {:?}", request);
                 let (inspect_request, inspect_response_tx) = request.into_inner();
-                let rollup_request = RollupRequest::InspectState(inspect_request);
+                self.data.monitor.pop_request(&(&inspect_request).into());
+                let rollup_request = RollupRequest::InspectState(inspect_request.clone());
                 send_response(self.finish_response_tx, Ok(rollup_request));
-                Some(Box::new(InspectState::new(self.data, inspect_response_tx)))
+                Some(Box::new(InspectState::new(self.data, inspect_request, inspect_response_tx)))
             }
             Some(request) = self.data.advance_rx.recv() => {
                 tracing::debug!("received advance request; setting state to advance");
                 tracing::debug!("request: {:?}", request);
                 let (advance_request, advance_response_tx) = request.into_inner();
-                let rollup_request = RollupRequest::AdvanceState(advance_request);
+                self.data.monitor.pop_request(&(&advance_request).into());
+                if let Some(status) = self.data.limits.check_payload(&advance_request.payload) {
+                    tracing::warn!("advance request exceeds the payload length limit; skipping");
+                    let result = AdvanceResult::limit_exceeded(status, vec![]);
+                    self.data.record((&advance_request).into(), (&result).into());
+                    send_response(advance_response_tx, result);
+                    return Some(self);
+                }
+                let rollup_request = RollupRequest::AdvanceState(advance_request.clone());
                 send_response(self.finish_response_tx, Ok(rollup_request));
-                Some(Box::new(AdvanceState::new(self.data, advance_response_tx)))
+                Some(Box::new(AdvanceState::new(self.data, advance_request, advance_response_tx)))
             }
             Some(request) = self.data.finish_rx.recv() => {
                 tracing::debug!("received finish request; terminating previous finish request");
@@ -378,7 +445,7 @@ impl State for FetchRequestState {
                 Service::handle_invalid(request, self, "exception")
             }
             Some(request) = self.data.shutdown_rx.recv() => {
-                Service::shutdown(request)
+                Service::shutdown(request, &mut self.data).await
             }
         }
     }
@@ -386,11 +453,16 @@ impl State for FetchRequestState {
     fn name(&self) -> String {
         "fetch request".into()
     }
+
+    fn data(&self) -> &SharedStateData {
+        &self.data
+    }
 }
 
 /// The controller wait for reports, exception, and finish
 struct InspectState {
     data: SharedStateData,
+    request: InspectStateRequest,
     inspect_response_tx: oneshot::Sender<InspectResult>,
     reports: Vec<Report>,
 }
@@ -398,10 +470,12 @@ struct InspectState {
 impl InspectState {
     fn new(
         data: SharedStateData,
+        request: InspectStateRequest,
         inspect_response_tx: oneshot::Sender<InspectResult>,
     ) -> Self {
         Self {
             data,
+            request,
             inspect_response_tx,
             reports: vec![],
         }
@@ -421,6 +495,7 @@ impl State for InspectState {
                     FinishStatus::Accept => InspectResult::accepted(self.reports),
                     FinishStatus::Reject => InspectResult::rejected(self.reports),
                 };
+                self.data.record((&self.request).into(), (&result).into());
                 send_response(self.inspect_response_tx, result);
                 Some(Box::new(FetchRequestState::new(self.data, response_tx)))
             }
@@ -438,6 +513,7 @@ impl State for InspectState {
                 tracing::debug!("request: {:?}", request);
                 let (exception, exception_response_tx) = request.into_inner();
                 let result = InspectResult::exception(self.reports, exception);
+                self.data.record((&self.request).into(), (&result).into());
                 send_response(self.inspect_response_tx, result);
                 send_response(exception_response_tx, Ok(()));
                 Some(Box::new(IdleState::new(self.data)))
@@ -449,7 +525,7 @@ impl State for InspectState {
                 Service::handle_invalid(request, self, "notice")
             }
             Some(request) = self.data.shutdown_rx.recv() => {
-                Service::shutdown(request)
+                Service::shutdown(request, &mut self.data).await
             }
         }
     }
@@ -457,29 +533,90 @@ impl State for InspectState {
     fn name(&self) -> String {
         "inspect".into()
     }
+
+    fn data(&self) -> &SharedStateData {
+        &self.data
+    }
 }
 
 /// The controller waits for vouchers, notices, reports, exception, and finish
 struct AdvanceState {
     data: SharedStateData,
+    request: AdvanceStateRequest,
     advance_response_tx: oneshot::Sender<AdvanceResult>,
-    vouchers: Vec<Voucher>,
-    notices: Vec<Notice>,
+    outputs: AdvanceOutputs,
     reports: Vec<Report>,
+    deadline: Option<(Instant, CompletionStatus)>,
 }
 
 impl AdvanceState {
     fn new(
         data: SharedStateData,
+        request: AdvanceStateRequest,
         advance_response_tx: oneshot::Sender<AdvanceResult>,
     ) -> Self {
+        let deadline = data.limits.deadline(Instant::now());
         Self {
             data,
+            request,
             advance_response_tx,
-            vouchers: vec![],
-            notices: vec![],
+            outputs: AdvanceOutputs::default(),
             reports: vec![],
+            deadline,
+        }
+    }
+
+    /// Ends the input with the given limit-exceeded status; the DApp must
+    /// call finish again to receive the next request.
+    fn halt(
+        self: Box<Self>,
+        status: CompletionStatus,
+    ) -> Option<Box<dyn State>> {
+        tracing::warn!(
+            "advance request ended with {:?}; setting state to idle",
+            status
+        );
+        if matches!(
+            status,
+            CompletionStatus::TimeLimitExceeded
+                | CompletionStatus::CycleLimitExceeded
+        ) {
+            self.data.monitor.push_timeout(format!("{:?}", status));
         }
+        let result = AdvanceResult::limit_exceeded(status, self.reports);
+        self.data.record((&self.request).into(), (&result).into());
+        send_response(self.advance_response_tx, result);
+        Some(Box::new(IdleState::new(self.data)))
+    }
+
+    fn halt_on_output<U>(
+        self: Box<Self>,
+        response_tx: oneshot::Sender<Result<U, ControllerError>>,
+        output_name: &str,
+    ) -> Option<Box<dyn State>>
+    where
+        U: std::fmt::Debug + Send + Sync,
+    {
+        let err = ControllerError::OutputLimitExceeded {
+            output_name: output_name.into(),
+        };
+        tracing::warn!("{}", err.to_string());
+        send_response(response_tx, Err(err));
+        self.halt(CompletionStatus::MachineHalted)
+    }
+}
+
+/// Waits until the deadline and returns its status, or forever if there is
+/// no deadline.
+async fn wait_deadline(
+    deadline: Option<(Instant, CompletionStatus)>,
+) -> CompletionStatus {
+    match deadline {
+        Some((instant, status)) => {
+            tokio::time::sleep_until(instant).await;
+            status
+        }
+        None => std::future::pending().await,
     }
 }
 
@@ -494,11 +631,7 @@ impl State for AdvanceState {
                 let (status, response_tx) = request.into_inner();
                 let result = match status {
                     FinishStatus::Accept => {
-                        AdvanceResult::accepted(
-                            self.vouchers,
-                            self.notices,
-                            self.reports,
-                        )
+                        AdvanceResult::accepted(self.outputs, self.reports)
                     },
                     FinishStatus::Reject => {
                         AdvanceResult::rejected(
@@ -506,34 +639,49 @@ impl State for AdvanceState {
                         )
                     },
                 };
+                self.data.record((&self.request).into(), (&result).into());
                 send_response(self.advance_response_tx, result);
                 Some(Box::new(FetchRequestState::new(self.data, response_tx)))
             }
+            status = wait_deadline(self.deadline.clone()) => {
+                self.halt(status)
+            }
             Some(request) = self.data.voucher_rx.recv() => {
                 tracing::debug!("received voucher request");
                 tracing::debug!("request: {:?}", request);
-                request.process(|voucher| {
-                    self.vouchers.push(voucher);
-                    Ok(self.vouchers.len() - 1)
-                });
+                let (voucher, response_tx) = request.into_inner();
+                let count = self.outputs.vouchers.len();
+                if !self.data.limits.allows_output(count, voucher.payload.len())
+                    || self.outputs.push_voucher(voucher).is_err()
+                {
+                    return self.halt_on_output(response_tx, "voucher");
+                }
+                send_response(response_tx, Ok(count));
                 Some(self)
             }
             Some(request) = self.data.notice_rx.recv() => {
                 tracing::debug!("received notice request");
                 tracing::debug!("request: {:?}", request);
-                request.process(|notice| {
-                    self.notices.push(notice);
-                    Ok(self.notices.len() - 1)
-                });
+                let (notice, response_tx) = request.into_inner();
+                let count = self.outputs.notices.len();
+                if !self.data.limits.allows_output(count, notice.payload.len())
+                    || self.outputs.push_notice(notice).is_err()
+                {
+                    return self.halt_on_output(response_tx, "notice");
+                }
+                send_response(response_tx, Ok(count));
                 Some(self)
             }
             Some(request) = self.data.report_rx.recv() => {
                 tracing::debug!("received report request");
                 tracing::debug!("request: {:?}", request);
-                request.process(|report| {
-                    self.reports.push(report);
-                    Ok(())
-                });
+                let (report, response_tx) = request.into_inner();
+                let count = self.reports.len();
+                if !self.data.limits.allows_output(count, report.payload.len()) {
+                    return self.halt_on_output(response_tx, "report");
+                }
+                self.reports.push(report);
+                send_response(response_tx, Ok(()));
                 Some(self)
             }
             Some(request) = self.data.exception_rx.recv() => {
@@ -544,12 +692,13 @@ impl State for AdvanceState {
                     exception,
                     self.reports,
                 );
+                self.data.record((&self.request).into(), (&result).into());
                 send_response(self.advance_response_tx, result);
                 send_response(exception_response_tx, Ok(()));
                 Some(Box::new(IdleState::new(self.data)))
             }
             Some(request) = self.data.shutdown_rx.recv() => {
-                Service::shutdown(request)
+                Service::shutdown(request, &mut self.data).await
             }
         }
     }
@@ -557,6 +706,10 @@ impl State for AdvanceState {
     fn name(&self) -> String {
         "advance".into()
     }
+
+    fn data(&self) -> &SharedStateData {
+        &self.data
+    }
 }
 
 #[cfg(test)]
@@ -566,7 +719,7 @@ mod tests {
     const TEST_FINISH_TIMEOUT: Duration = Duration::from_millis(100);
 
     fn setup() -> Controller {
-        Controller::new(TEST_FINISH_TIMEOUT)
+        Controller::new(TEST_FINISH_TIMEOUT, ResourceLimits::default(), None)
     }
 
     #[tokio::test]
@@ -721,7 +874,7 @@ mod tests {
         let advance_result = advance_rx.await.unwrap();
         assert_eq!(
             advance_result,
-            AdvanceResult::accepted(vec![], vec![], vec![])
+            AdvanceResult::accepted(AdvanceOutputs::default(), vec![])
         );
         controller.shutdown().await;
     }
@@ -747,7 +900,7 @@ mod tests {
         let advance_result = advance_rx.await.unwrap();
         assert_eq!(
             advance_result,
-            AdvanceResult::accepted(vec![], vec![], vec![])
+            AdvanceResult::accepted(AdvanceOutputs::default(), vec![])
         );
         controller.shutdown().await;
     }
@@ -846,6 +999,30 @@ mod tests {
         controller.shutdown().await;
     }
 
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_builds_output_trees_during_advance() {
+        let controller = setup();
+        let advance_rx = controller.advance(mock_advance_request()).await;
+        let finish_rx = controller.finish(FinishStatus::Accept).await;
+        let _ = finish_rx.await.unwrap().unwrap();
+        let vouchers = vec![mock_voucher(), mock_voucher()];
+        for voucher in vouchers.iter() {
+            let voucher_rx = controller.insert_voucher(voucher.clone()).await;
+            voucher_rx.await.unwrap().unwrap();
+        }
+        let notice = mock_notice();
+        let notice_rx = controller.insert_notice(notice.clone()).await;
+        notice_rx.await.unwrap().unwrap();
+        let _ = controller.finish(FinishStatus::Accept).await;
+        let result = advance_rx.await.unwrap();
+        let outputs = AdvanceOutputs::new(vouchers, vec![notice]).unwrap();
+        assert_eq!(result.voucher_tree.len(), 2);
+        assert_eq!(result.notice_tree.len(), 1);
+        assert_eq!(result, AdvanceResult::accepted(outputs, vec![]));
+        controller.shutdown().await;
+    }
+
     #[tokio::test]
     #[tracing_test::traced_test]
     async fn test_it_sends_reports_during_inspect() {
@@ -900,6 +1077,236 @@ mod tests {
         controller.shutdown().await;
     }
 
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_records_requests_and_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let recorder = Recorder::create(&path).unwrap();
+        let controller = Controller::new(
+            TEST_FINISH_TIMEOUT,
+            ResourceLimits::default(),
+            Some(recorder),
+        );
+        // Advance with a notice
+        let advance_request = mock_advance_request();
+        let advance_rx = controller.advance(advance_request.clone()).await;
+        let _ = controller.finish(FinishStatus::Accept).await.await.unwrap();
+        let notice = mock_notice();
+        let _ = controller
+            .insert_notice(notice.clone())
+            .await
+            .await
+            .unwrap();
+        let _ = controller.finish(FinishStatus::Accept).await;
+        let advance_result = advance_rx.await.unwrap();
+        // Inspect with an exception
+        let inspect_request = mock_inspect_request();
+        let inspect_rx = controller.inspect(inspect_request.clone()).await;
+        let exception = mock_exception();
+        let _ = controller.notify_exception(exception).await.await.unwrap();
+        let inspect_result = inspect_rx.await.unwrap();
+        controller.shutdown().await.await.unwrap();
+
+        let entries = crate::recorder::read_session_log(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].request, (&advance_request).into());
+        assert_eq!(entries[0].result, (&advance_result).into());
+        assert_eq!(entries[1].request, (&inspect_request).into());
+        assert_eq!(entries[1].result, (&inspect_result).into());
+    }
+
+    fn setup_with_limits(limits: ResourceLimits) -> Controller {
+        Controller::new(TEST_FINISH_TIMEOUT, limits, None)
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_skips_advance_with_payload_over_limit() {
+        let controller = setup_with_limits(ResourceLimits {
+            max_payload_length: Some(8),
+            ..Default::default()
+        });
+        let finish_rx = controller.finish(FinishStatus::Accept).await;
+        // The payload of the mock request has 32 bytes
+        let advance_rx = controller.advance(mock_advance_request()).await;
+        assert_eq!(
+            advance_rx.await.unwrap(),
+            AdvanceResult::limit_exceeded(
+                CompletionStatus::PayloadLengthLimitExceeded,
+                vec![]
+            )
+        );
+        // The DApp keeps waiting for a request within the limit
+        let request = AdvanceStateRequest {
+            payload: vec![0; 8],
+            ..mock_advance_request()
+        };
+        let _ = controller.advance(request.clone()).await;
+        let rollup_request = finish_rx.await.unwrap().unwrap();
+        assert_eq!(rollup_request, RollupRequest::AdvanceState(request));
+        controller.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_halts_advance_after_time_limit() {
+        let controller = setup_with_limits(ResourceLimits {
+            time_limit: Some(Duration::from_millis(10)),
+            ..Default::default()
+        });
+        let advance_rx = controller.advance(mock_advance_request()).await;
+        let _ = controller.finish(FinishStatus::Accept).await.await.unwrap();
+        let report = mock_report();
+        let _ = controller
+            .insert_report(report.clone())
+            .await
+            .await
+            .unwrap();
+        assert_eq!(
+            advance_rx.await.unwrap(),
+            AdvanceResult::limit_exceeded(
+                CompletionStatus::TimeLimitExceeded,
+                vec![report]
+            )
+        );
+        // The controller is idle after the input ends
+        let rx = controller.insert_report(mock_report()).await;
+        assert_eq!(
+            rx.await.unwrap().unwrap_err(),
+            ControllerError::InvalidRequest {
+                request_name: String::from("report"),
+                state_name: String::from("idle")
+            }
+        );
+        controller.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_halts_advance_after_cycle_limit() {
+        let controller = setup_with_limits(ResourceLimits {
+            time_limit: Some(Duration::from_secs(10)),
+            cycle_limit: Some(Duration::from_millis(10)),
+            ..Default::default()
+        });
+        let advance_rx = controller.advance(mock_advance_request()).await;
+        let _ = controller.finish(FinishStatus::Accept).await.await.unwrap();
+        assert_eq!(
+            advance_rx.await.unwrap(),
+            AdvanceResult::limit_exceeded(
+                CompletionStatus::CycleLimitExceeded,
+                vec![]
+            )
+        );
+        controller.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_halts_advance_over_output_count() {
+        let controller = setup_with_limits(ResourceLimits {
+            max_output_count: Some(1),
+            ..Default::default()
+        });
+        let advance_rx = controller.advance(mock_advance_request()).await;
+        let _ = controller.finish(FinishStatus::Accept).await.await.unwrap();
+        let rx = controller.insert_notice(mock_notice()).await;
+        assert_eq!(rx.await.unwrap().unwrap(), 0);
+        let report = mock_report();
+        let _ = controller
+            .insert_report(report.clone())
+            .await
+            .await
+            .unwrap();
+        let rx = controller.insert_notice(mock_notice()).await;
+        assert_eq!(
+            rx.await.unwrap().unwrap_err(),
+            ControllerError::OutputLimitExceeded {
+                output_name: String::from("notice")
+            }
+        );
+        assert_eq!(
+            advance_rx.await.unwrap(),
+            AdvanceResult::limit_exceeded(
+                CompletionStatus::MachineHalted,
+                vec![report]
+            )
+        );
+        controller.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_halts_advance_over_output_size() {
+        let controller = setup_with_limits(ResourceLimits {
+            max_output_size: Some(16),
+            ..Default::default()
+        });
+        let advance_rx = controller.advance(mock_advance_request()).await;
+        let _ = controller.finish(FinishStatus::Accept).await.await.unwrap();
+        // The payload of the mock voucher has 32 bytes
+        let rx = controller.insert_voucher(mock_voucher()).await;
+        assert_eq!(
+            rx.await.unwrap().unwrap_err(),
+            ControllerError::OutputLimitExceeded {
+                output_name: String::from("voucher")
+            }
+        );
+        assert_eq!(
+            advance_rx.await.unwrap(),
+            AdvanceResult::limit_exceeded(
+                CompletionStatus::MachineHalted,
+                vec![]
+            )
+        );
+        controller.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_reports_state_and_pending_requests_to_monitor() {
+        let controller = setup();
+        let mut transitions = controller.monitor().subscribe();
+        let advance_request = mock_advance_request();
+        let inspect_request = mock_inspect_request();
+        let _ = controller.advance(advance_request.clone()).await;
+        let _ = controller.inspect(inspect_request.clone()).await;
+        let status = controller.monitor().status();
+        assert_eq!(status.state, "idle");
+        assert_eq!(
+            status.pending_requests,
+            vec![(&advance_request).into(), (&inspect_request).into()]
+        );
+        // The inspect takes priority over the advance
+        let _ = controller.finish(FinishStatus::Accept).await.await.unwrap();
+        let transition = transitions.recv().await.unwrap();
+        assert_eq!(transition.from, "idle");
+        assert_eq!(transition.to, "fetch request");
+        let transition = transitions.recv().await.unwrap();
+        assert_eq!(transition.from, "fetch request");
+        assert_eq!(transition.to, "inspect");
+        let status = controller.monitor().status();
+        assert_eq!(status.state, "inspect");
+        assert_eq!(status.pending_requests, vec![(&advance_request).into()]);
+        controller.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_reports_timeouts_to_monitor() {
+        let controller = setup();
+        let _ = controller.finish(FinishStatus::Accept).await.await.unwrap();
+        let timeouts = controller.monitor().status().recent_timeouts;
+        assert_eq!(timeouts.len(), 1);
+        assert_eq!(timeouts[0].state, "fetch request");
+        assert_eq!(
+            timeouts[0].reason,
+            ControllerError::FetchRequestTimeout.to_string()
+        );
+        controller.shutdown().await;
+    }
+
     fn mock_voucher() -> Voucher {
         Voucher::new(rand::random(), rand::random::<[u8; 32]>().into())
     }
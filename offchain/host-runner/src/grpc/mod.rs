@@ -12,7 +12,7 @@ use grpc_interfaces::cartesi_server_manager::server_manager_server::ServerManage
 use server_manager::ServerManagerService;
 
 use crate::config::Config;
-use crate::controller::Controller;
+use crate::sessions::SessionControllers;
 
 /// Create the grpc healthcheck for the host-runner
 ///
@@ -29,7 +29,7 @@ async fn create_health_service() -> HealthServer<impl Health> {
 
 pub async fn start_service<F: Future<Output = ()>>(
     config: &Config,
-    controller: Controller,
+    sessions: SessionControllers,
     signal: F,
 ) -> Result<(), tonic::transport::Error> {
     let addr = format!(
@@ -38,7 +38,7 @@ pub async fn start_service<F: Future<Output = ()>>(
     )
     .parse()
     .expect("invalid config");
-    let service = ServerManagerService::new(controller);
+    let service = ServerManagerService::new(sessions);
     Server::builder()
         .add_service(create_health_service().await)
         .add_service(ServerManagerServer::new(service))
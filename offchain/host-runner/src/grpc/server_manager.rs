@@ -2,20 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 use crate::controller::Controller;
-use crate::hash::{Hash, HASH_SIZE};
-use crate::merkle_tree::{
-    complete::Tree, proof::Proof, Error as MerkleTreeError,
-};
 use crate::model::{
     AdvanceMetadata, AdvanceResult, AdvanceStateRequest, CompletionStatus,
-    InspectStateRequest, InspectStatus, Notice, Report, Voucher,
+    InspectStateRequest, InspectStatus, Notice, Report, Voucher, VoucherKind,
 };
-use crate::proofs::compute_proofs;
+use crate::monitor::EpochStatus;
+use crate::recorder::RecordedResult;
+use crate::sessions::SessionControllers;
+use crate::storage::{self, RestoredEpoch, StoredEpoch, StoredSession};
 use ethabi::ethereum_types::U256;
 use ethabi::Token;
-use grpc_interfaces::cartesi_machine::{
-    Hash as GrpcHash, MerkleTreeProof as GrpcMerkleTreeProof, Void,
-};
+use grpc_interfaces::cartesi_machine::{Hash as GrpcHash, Void};
 use grpc_interfaces::cartesi_server_manager::{
     processed_input::ProcessedInputOneOf, server_manager_server::ServerManager,
     AcceptedData, Address, AdvanceStateRequest as GrpcAdvanceStateRequest,
@@ -27,23 +24,27 @@ use grpc_interfaces::cartesi_server_manager::{
     InspectStateResponse, Notice as GrpcNotice, OutputEnum,
     OutputValidityProof, ProcessedInput, Proof as GrpcProof,
     Report as GrpcReport, StartSessionRequest, StartSessionResponse,
-    TaintStatus, Voucher as GrpcVoucher,
+    TaintStatus, Voucher as GrpcVoucher, VoucherKind as GrpcVoucherKind,
 };
 use grpc_interfaces::versioning::{GetVersionResponse, SemanticVersion};
-use std::{collections::HashMap, sync::Arc};
+use output_proofs::hash::{Hash, HASH_SIZE};
+use output_proofs::merkle_tree::{incremental::Tree, Error as MerkleTreeError};
+use output_proofs::outputs::{
+    new_epoch_tree, OutputsTree, LOG2_EPOCH_SIZE,
+    LOG2_HASH_SIZE as LOG2_KECCAK_SIZE,
+};
+use std::{collections::HashMap, path::Path, sync::Arc};
 use tokio::sync::Mutex;
 use tonic::{Request, Response, Status};
 
 pub struct ServerManagerService {
-    controller: Controller,
     sessions: SessionManager,
 }
 
 impl ServerManagerService {
-    pub fn new(controller: Controller) -> Self {
+    pub fn new(controllers: SessionControllers) -> Self {
         Self {
-            controller,
-            sessions: SessionManager::new(),
+            sessions: SessionManager::new(controllers),
         }
     }
 }
@@ -76,9 +77,9 @@ impl ServerManager for ServerManagerService {
         self.sessions
             .try_set_session(
                 request.session_id,
+                request.machine_directory,
                 request.active_epoch_index,
                 request.processed_input_count,
-                self.controller.clone(),
             )
             .await?;
         let response = StartSessionResponse { config: None };
@@ -152,9 +153,6 @@ impl ServerManager for ServerManagerService {
     ) -> Result<Response<FinishEpochResponse>, Status> {
         let request = request.into_inner();
         tracing::info!("received finish_epoch with id={}", request.session_id);
-        if !request.storage_directory.is_empty() {
-            tracing::warn!("ignoring storage_directory parameter");
-        }
         let response = self
             .sessions
             .try_get_session(&request.session_id)
@@ -164,6 +162,7 @@ impl ServerManager for ServerManagerService {
             .try_finish_epoch(
                 request.active_epoch_index,
                 request.processed_input_count_within_epoch,
+                &request.storage_directory,
             )
             .await?;
         Ok(Response::new(response))
@@ -252,112 +251,120 @@ impl ServerManager for ServerManagerService {
 }
 
 struct SessionManager {
-    entry: Mutex<Option<SessionEntry>>,
+    controllers: SessionControllers,
+    entries: Mutex<HashMap<String, Arc<Mutex<Session>>>>,
 }
 
 impl SessionManager {
-    fn new() -> Self {
+    fn new(controllers: SessionControllers) -> Self {
         Self {
-            entry: Mutex::new(None),
+            controllers,
+            entries: Mutex::new(HashMap::new()),
         }
     }
 
     async fn try_set_session(
         &self,
         session_id: String,
+        machine_directory: String,
         active_epoch_index: u64,
         processed_input_count: u64,
-        controller: Controller,
     ) -> Result<(), Status> {
         if session_id.is_empty() {
             return Err(Status::invalid_argument("session id is empty"));
         }
-        let mut entry = self.entry.lock().await;
-        match *entry {
-            Some(_) => {
-                tracing::warn!(
-                    "the host-runner only supports a single session"
-                );
-                Err(Status::already_exists("session id is taken"))
-            }
-            None => {
-                *entry = Some(SessionEntry::new(
-                    session_id,
-                    active_epoch_index,
-                    processed_input_count,
-                    controller,
-                ));
-                Ok(())
-            }
+        let mut entries = self.entries.lock().await;
+        if entries.contains_key(&session_id) {
+            return Err(Status::already_exists("session id is taken"));
         }
+        let epochs = try_load_epochs(
+            &machine_directory,
+            active_epoch_index,
+            processed_input_count,
+        )?;
+        let controller = self.controllers.acquire(&session_id);
+        let mut session =
+            Session::new(active_epoch_index, processed_input_count, controller);
+        for (epoch_index, epoch) in epochs {
+            session
+                .epochs
+                .insert(epoch_index, Arc::new(Mutex::new(epoch)));
+        }
+        session.publish_epoch().await;
+        entries.insert(session_id, Arc::new(Mutex::new(session)));
+        Ok(())
     }
 
     async fn try_get_session(
         &self,
         request_id: &String,
     ) -> Result<Arc<Mutex<Session>>, Status> {
-        self.entry
+        self.entries
             .lock()
             .await
-            .as_ref()
-            .and_then(|entry| entry.get_session(request_id))
+            .get(request_id)
+            .cloned()
             .ok_or(Status::invalid_argument("session id not found"))
     }
 
     async fn try_del_session(&self, request_id: &String) -> Result<(), Status> {
-        self.try_get_session(request_id)
-            .await?
+        let session = self.try_get_session(request_id).await?;
+        let session = session
             .try_lock()
-            .or(Err(Status::aborted("concurrent call in session")))?
-            .check_endable()
-            .await?;
-        let mut entry = self.entry.lock().await;
-        *entry = None;
+            .or(Err(Status::aborted("concurrent call in session")))?;
+        session.check_endable().await?;
+        session.controller.monitor().set_epoch(None);
+        self.entries.lock().await.remove(request_id);
+        self.controllers.release(request_id).await;
         Ok(())
     }
 
     async fn get_sessions(&self) -> Vec<String> {
-        let mut sessions = Vec::new();
-        if let Some(entry) = self.entry.lock().await.as_ref() {
-            sessions.push(entry.get_id());
-        }
+        let mut sessions: Vec<_> =
+            self.entries.lock().await.keys().cloned().collect();
+        sessions.sort();
         sessions
     }
 }
 
-struct SessionEntry {
-    id: String,
-    session: Arc<Mutex<Session>>,
-}
-
-impl SessionEntry {
-    fn new(
-        id: String,
-        active_epoch_index: u64,
-        processed_input_count: u64,
-        controller: Controller,
-    ) -> Self {
-        Self {
-            id,
-            session: Arc::new(Mutex::new(Session::new(
-                active_epoch_index,
-                processed_input_count,
-                controller,
-            ))),
-        }
+/// Loads the finished epochs stored in the machine directory, which must
+/// match the epoch the session starts at.
+fn try_load_epochs(
+    machine_directory: &str,
+    active_epoch_index: u64,
+    processed_input_count: u64,
+) -> Result<Vec<(u64, Epoch)>, Status> {
+    if machine_directory.is_empty() {
+        return Ok(vec![]);
     }
-
-    fn get_session(&self, request_id: &String) -> Option<Arc<Mutex<Session>>> {
-        if &self.id == request_id {
-            Some(self.session.clone())
-        } else {
-            None
+    let restored = storage::load(Path::new(machine_directory))
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let restored = match restored {
+        Some(restored) => restored,
+        None => {
+            tracing::warn!(
+                "no session state in machine directory; starting a new session"
+            );
+            return Ok(vec![]);
         }
+    };
+    if restored.active_epoch_index != active_epoch_index
+        || restored.processed_input_count != processed_input_count
+    {
+        return Err(Status::invalid_argument(format!(
+            "stored session state does not match (expected epoch {} with {} \
+            processed inputs, got epoch {} with {} processed inputs)",
+            restored.active_epoch_index,
+            restored.processed_input_count,
+            active_epoch_index,
+            processed_input_count
+        )));
     }
-
-    fn get_id(&self) -> String {
-        self.id.clone()
-    }
+    restored
+        .epochs
+        .into_iter()
+        .map(|epoch| Ok((epoch.epoch_index, Epoch::try_restore(epoch)?)))
+        .collect()
 }
 
 struct Session {
@@ -398,20 +405,33 @@ impl Session {
             .lock()
             .await
             .try_add_pending_input(current_input_index)?;
+        let pending_input_count = epoch.lock().await.pending_inputs;
+        self.controller
+            .monitor()
+            .set_pending_input_count(active_epoch_index, pending_input_count);
         let rx = self.controller.advance(advance_request).await;
         let epoch = epoch.clone();
         let tainted = self.tainted.clone();
+        let monitor = self.controller.monitor().clone();
         // Handle the advance response in another thread
         tokio::spawn(async move {
             match rx.await {
                 Ok(result) => {
-                    if let Err(e) =
-                        epoch.lock().await.add_processed_input(result)
-                    {
-                        tracing::error!(
-                            "failed to add processed input; tainting session"
-                        );
-                        *tainted.lock().await = Some(e);
+                    let mut epoch = epoch.lock().await;
+                    let recorded = RecordedResult::from(&result);
+                    match epoch.add_processed_input(result) {
+                        // Only publish the new input to keep updates cheap
+                        Ok(()) => monitor.push_processed_input(
+                            active_epoch_index,
+                            epoch.pending_inputs,
+                            recorded,
+                        ),
+                        Err(e) => {
+                            tracing::error!(
+                                "failed to add processed input; tainting session"
+                            );
+                            *tainted.lock().await = Some(e);
+                        }
                     }
                 }
                 Err(_) => {
@@ -459,6 +479,7 @@ impl Session {
         &mut self,
         active_epoch_index: u64,
         processed_input_count_within_epoch: u64,
+        storage_directory: &str,
     ) -> Result<FinishEpochResponse, Status> {
         self.check_epoch_index_overflow()?;
         self.check_tainted().await?;
@@ -480,9 +501,52 @@ impl Session {
                 + processed_input_count_within_epoch,
         )));
         self.epochs.insert(self.active_epoch_index, epoch);
+        self.publish_epoch().await;
+        if !storage_directory.is_empty() {
+            if let Err(e) = self.try_store(Path::new(storage_directory)).await {
+                tracing::error!("failed to store session; tainting session");
+                *self.tainted.lock().await = Some(e.clone());
+                return Err(e);
+            }
+        }
         Ok(response)
     }
 
+    /// Stores the state of the session right after an epoch is finished.
+    async fn try_store(&self, directory: &Path) -> Result<(), Status> {
+        let mut epochs = vec![];
+        for (epoch_index, epoch) in &self.epochs {
+            if *epoch_index != self.active_epoch_index {
+                epochs.push(epoch.lock().await.store(*epoch_index));
+            }
+        }
+        epochs.sort_by_key(|epoch| epoch.epoch_index);
+        let processed_input_count = self
+            .try_get_epoch(self.active_epoch_index)?
+            .lock()
+            .await
+            .get_num_processed_inputs_since_genesis();
+        let session = StoredSession {
+            active_epoch_index: self.active_epoch_index,
+            processed_input_count,
+            epochs,
+        };
+        storage::save(directory, &session)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+
+    /// Reports the whole active epoch to the monitor of the controller.
+    ///
+    /// This copies every processed input, so it is only used when the active
+    /// epoch changes; advances update the monitor incrementally.
+    async fn publish_epoch(&self) {
+        if let Some(epoch) = self.epochs.get(&self.active_epoch_index) {
+            let status =
+                epoch.lock().await.monitor_status(self.active_epoch_index);
+            self.controller.monitor().set_epoch(Some(status));
+        }
+    }
+
     async fn try_delete_epoch(
         &mut self,
         epoch_index: u64,
@@ -582,14 +646,8 @@ impl Session {
     }
 }
 
-/// The keccak output has 32 bytes
-const LOG2_KECCAK_SIZE: usize = 5;
-
-/// The epoch tree has 2^32 leafs
-const LOG2_ROOT_SIZE: usize = 32 + LOG2_KECCAK_SIZE;
-
 /// The max number of inputs in an epoch is limited by the size of the merkle tree
-const MAX_INPUTS_IN_EPOCH: usize = 1 << (LOG2_ROOT_SIZE - LOG2_KECCAK_SIZE);
+const MAX_INPUTS_IN_EPOCH: usize = 1 << (LOG2_EPOCH_SIZE - LOG2_KECCAK_SIZE);
 
 #[derive(Debug)]
 struct Epoch {
@@ -598,6 +656,10 @@ struct Epoch {
     processed_inputs: Vec<AdvanceResult>,
     vouchers_tree: Tree,
     notices_tree: Tree,
+    /// Output-hashes trees of each processed input, used to prove the outputs
+    /// when the epoch finishes
+    voucher_trees: Vec<OutputsTree>,
+    notice_trees: Vec<OutputsTree>,
     processed_input_count_since_genesis: u64,
 }
 
@@ -607,22 +669,39 @@ impl Epoch {
             state: EpochState::Active,
             pending_inputs: 0,
             processed_inputs: vec![],
-            vouchers_tree: Tree::new(
-                LOG2_ROOT_SIZE,
-                LOG2_KECCAK_SIZE,
-                LOG2_KECCAK_SIZE,
-            )
-            .expect("cannot fail"),
-            notices_tree: Tree::new(
-                LOG2_ROOT_SIZE,
-                LOG2_KECCAK_SIZE,
-                LOG2_KECCAK_SIZE,
-            )
-            .expect("cannot fail"),
+            vouchers_tree: new_epoch_tree(),
+            notices_tree: new_epoch_tree(),
+            voucher_trees: vec![],
+            notice_trees: vec![],
             processed_input_count_since_genesis,
         }
     }
 
+    /// Rebuilds a finished epoch, computing the output trees of its inputs
+    /// again.
+    fn try_restore(restored: RestoredEpoch) -> Result<Self, Status> {
+        let mut epoch = Self::new(restored.processed_input_count_since_genesis);
+        for result in restored.processed_inputs {
+            epoch.pending_inputs += 1;
+            epoch.add_processed_input(result)?;
+        }
+        epoch.state = EpochState::Finished;
+        Ok(epoch)
+    }
+
+    fn store(&self, epoch_index: u64) -> StoredEpoch {
+        StoredEpoch {
+            epoch_index,
+            processed_input_count_since_genesis: self
+                .processed_input_count_since_genesis,
+            processed_inputs: self
+                .processed_inputs
+                .iter()
+                .map(RecordedResult::from)
+                .collect(),
+        }
+    }
+
     fn try_add_pending_input(
         &mut self,
         current_input_index: u64,
@@ -638,26 +717,25 @@ impl Epoch {
         &mut self,
         mut result: AdvanceResult,
     ) -> Result<(), Status> {
-        // Compute proofs and update vouchers and notices trees
-        if let CompletionStatus::Accepted { vouchers, notices } =
-            &mut result.status
-        {
-            let voucher_root = compute_proofs(vouchers)?;
-            result.voucher_root = Some(voucher_root.clone());
-            self.vouchers_tree.push(voucher_root)?;
-            let notice_root = compute_proofs(notices)?;
-            result.notice_root = Some(notice_root.clone());
-            self.notices_tree.push(notice_root)?;
-        } else {
-            self.vouchers_tree.push(Hash::default())?;
-            self.notices_tree.push(Hash::default())?;
-        }
-        // Setup proofs for the current result
-        let address = (self.vouchers_tree.len() - 1) << LOG2_KECCAK_SIZE;
-        result.voucher_hashes_in_epoch =
-            Some(self.vouchers_tree.get_proof(address, LOG2_KECCAK_SIZE)?);
-        result.notice_hashes_in_epoch =
-            Some(self.notices_tree.get_proof(address, LOG2_KECCAK_SIZE)?);
+        // The controller builds the output trees as the outputs arrive, so
+        // only their roots go to the epoch trees; the proofs are only
+        // computed when the epoch finishes
+        let voucher_tree = std::mem::take(&mut result.voucher_tree);
+        let notice_tree = std::mem::take(&mut result.notice_tree);
+        let (voucher_root, notice_root) =
+            if let CompletionStatus::Accepted { .. } = &result.status {
+                (voucher_tree.get_root_hash(), notice_tree.get_root_hash())
+            } else {
+                (Hash::default(), Hash::default())
+            };
+        self.vouchers_tree
+            .push(voucher_root)
+            .map_err(convert_merkle_tree_error)?;
+        self.notices_tree
+            .push(notice_root)
+            .map_err(convert_merkle_tree_error)?;
+        self.voucher_trees.push(voucher_tree);
+        self.notice_trees.push(notice_tree);
         // Add result to processed inputs
         self.pending_inputs -= 1;
         self.processed_inputs.push(result);
@@ -681,22 +759,36 @@ impl Epoch {
         let index = Token::Int(U256::from(epoch_index));
         let context = ethabi::encode(&[index]);
 
+        let vouchers_epoch_root_hash = self.vouchers_tree.get_root_hash();
+        let notices_epoch_root_hash = self.notices_tree.get_root_hash();
         for (local_input_index, result) in
-            self.processed_inputs.iter_mut().enumerate()
+            self.processed_inputs.iter().enumerate()
         {
             let address = local_input_index << LOG2_KECCAK_SIZE;
-            let voucher_hashes_in_epoch =
-                self.vouchers_tree.get_proof(address, LOG2_KECCAK_SIZE)?;
-            let notice_hashes_in_epoch =
-                self.notices_tree.get_proof(address, LOG2_KECCAK_SIZE)?;
+            let voucher_hashes_in_epoch = self
+                .vouchers_tree
+                .get_proof(address, LOG2_KECCAK_SIZE)
+                .map_err(convert_merkle_tree_error)?;
+            let notice_hashes_in_epoch = self
+                .notices_tree
+                .get_proof(address, LOG2_KECCAK_SIZE)
+                .map_err(convert_merkle_tree_error)?;
             let global_input_index = self.processed_input_count_since_genesis
                 + local_input_index as u64;
 
             if let CompletionStatus::Accepted { vouchers, notices } =
-                &mut result.status
+                &result.status
             {
+                let voucher_root =
+                    self.voucher_trees[local_input_index].get_root_hash();
+                let notice_root =
+                    self.notice_trees[local_input_index].get_root_hash();
                 // Create GrpcProof for each voucher
-                for (output_index, voucher) in vouchers.iter().enumerate() {
+                for output_index in 0..vouchers.len() {
+                    let voucher_hash_in_voucher_hashes = self.voucher_trees
+                        [local_input_index]
+                        .get_proof(output_index)
+                        .map_err(convert_merkle_tree_error)?;
                     proofs.push(GrpcProof {
                         input_index: global_input_index,
                         output_index: output_index as u64,
@@ -705,41 +797,41 @@ impl Epoch {
                         validity: Some(OutputValidityProof {
                             input_index_within_epoch: local_input_index as u64,
                             output_index_within_input: output_index as u64,
-                            output_hashes_root_hash: Some(GrpcHash::from(
-                                result.voucher_root.clone().expect(
-                                    "expected voucher's root hash to exist",
-                                ),
+                            output_hashes_root_hash: Some(convert_hash(
+                                voucher_root.clone(),
                             )),
-                            vouchers_epoch_root_hash: Some(GrpcHash::from(
-                                self.vouchers_tree.get_root_hash().clone(),
+                            vouchers_epoch_root_hash: Some(convert_hash(
+                                vouchers_epoch_root_hash.clone(),
                             )),
-                            notices_epoch_root_hash: Some(GrpcHash::from(
-                                self.notices_tree.get_root_hash().clone(),
+                            notices_epoch_root_hash: Some(convert_hash(
+                                notices_epoch_root_hash.clone(),
                             )),
                             machine_state_hash: Some(
                                 machine_state_hash.clone(),
                             ),
-                            output_hash_in_output_hashes_siblings: voucher
-                                .keccak_in_voucher_hashes
-                                .clone()
-                                .expect("expected voucher proof to exist")
-                                .sibling_hashes
-                                .into_iter()
-                                .map(GrpcHash::from)
-                                .collect(),
+                            output_hash_in_output_hashes_siblings:
+                                voucher_hash_in_voucher_hashes
+                                    .sibling_hashes
+                                    .into_iter()
+                                    .map(convert_hash)
+                                    .collect(),
                             output_hashes_in_epoch_siblings:
                                 voucher_hashes_in_epoch
                                     .clone()
                                     .sibling_hashes
                                     .into_iter()
-                                    .map(GrpcHash::from)
+                                    .map(convert_hash)
                                     .collect(),
                         }),
                         context: context.clone(),
                     })
                 }
                 // Create GrpcProof for each notice
-                for (output_index, notice) in notices.iter().enumerate() {
+                for output_index in 0..notices.len() {
+                    let notice_hash_in_notice_hashes = self.notice_trees
+                        [local_input_index]
+                        .get_proof(output_index)
+                        .map_err(convert_merkle_tree_error)?;
                     proofs.push(GrpcProof {
                         input_index: global_input_index,
                         output_index: output_index as u64,
@@ -748,34 +840,30 @@ impl Epoch {
                         validity: Some(OutputValidityProof {
                             input_index_within_epoch: local_input_index as u64,
                             output_index_within_input: output_index as u64,
-                            output_hashes_root_hash: Some(GrpcHash::from(
-                                result.notice_root.clone().expect(
-                                    "expected notice's root hash to exist",
-                                ),
+                            output_hashes_root_hash: Some(convert_hash(
+                                notice_root.clone(),
                             )),
-                            vouchers_epoch_root_hash: Some(GrpcHash::from(
-                                self.vouchers_tree.get_root_hash().clone(),
+                            vouchers_epoch_root_hash: Some(convert_hash(
+                                vouchers_epoch_root_hash.clone(),
                             )),
-                            notices_epoch_root_hash: Some(GrpcHash::from(
-                                self.notices_tree.get_root_hash().clone(),
+                            notices_epoch_root_hash: Some(convert_hash(
+                                notices_epoch_root_hash.clone(),
                             )),
                             machine_state_hash: Some(
                                 machine_state_hash.clone(),
                             ),
-                            output_hash_in_output_hashes_siblings: notice
-                                .keccak_in_notice_hashes
-                                .clone()
-                                .expect("expected notice proof to exist")
-                                .sibling_hashes
-                                .into_iter()
-                                .map(GrpcHash::from)
-                                .collect(),
+                            output_hash_in_output_hashes_siblings:
+                                notice_hash_in_notice_hashes
+                                    .sibling_hashes
+                                    .into_iter()
+                                    .map(convert_hash)
+                                    .collect(),
                             output_hashes_in_epoch_siblings:
                                 notice_hashes_in_epoch
                                     .clone()
                                     .sibling_hashes
                                     .into_iter()
-                                    .map(GrpcHash::from)
+                                    .map(convert_hash)
                                     .collect(),
                         }),
                         context: context.clone(),
@@ -786,11 +874,11 @@ impl Epoch {
 
         Ok(FinishEpochResponse {
             machine_hash: Some(machine_state_hash.clone()),
-            vouchers_epoch_root_hash: Some(GrpcHash::from(
-                self.vouchers_tree.get_root_hash().clone(),
+            vouchers_epoch_root_hash: Some(convert_hash(
+                vouchers_epoch_root_hash,
             )),
-            notices_epoch_root_hash: Some(GrpcHash::from(
-                self.notices_tree.get_root_hash().clone(),
+            notices_epoch_root_hash: Some(convert_hash(
+                notices_epoch_root_hash,
             )),
             proofs,
         })
@@ -832,6 +920,20 @@ impl Epoch {
         }
     }
 
+    fn monitor_status(&self, epoch_index: u64) -> EpochStatus {
+        EpochStatus {
+            epoch_index,
+            pending_input_count: self.pending_inputs,
+            processed_input_count_since_genesis: self
+                .processed_input_count_since_genesis,
+            processed_inputs: self
+                .processed_inputs
+                .iter()
+                .map(RecordedResult::from)
+                .collect(),
+        }
+    }
+
     fn get_num_processed_inputs_within_epoch(&self) -> u64 {
         self.processed_inputs.len() as u64
     }
@@ -937,6 +1039,18 @@ impl From<&CompletionStatus> for i32 {
             CompletionStatus::Exception { .. } => {
                 GrpcCompletionStatus::Exception
             }
+            CompletionStatus::MachineHalted => {
+                GrpcCompletionStatus::MachineHalted
+            }
+            CompletionStatus::CycleLimitExceeded => {
+                GrpcCompletionStatus::CycleLimitExceeded
+            }
+            CompletionStatus::TimeLimitExceeded => {
+                GrpcCompletionStatus::TimeLimitExceeded
+            }
+            CompletionStatus::PayloadLengthLimitExceeded => {
+                GrpcCompletionStatus::PayloadLengthLimitExceeded
+            }
         };
         status as i32
     }
@@ -968,7 +1082,11 @@ impl From<CompletionStatus> for Option<ProcessedInputOneOf> {
                         .collect(),
                 }))
             }
-            CompletionStatus::Rejected => None,
+            CompletionStatus::Rejected
+            | CompletionStatus::MachineHalted
+            | CompletionStatus::CycleLimitExceeded
+            | CompletionStatus::TimeLimitExceeded
+            | CompletionStatus::PayloadLengthLimitExceeded => None,
             CompletionStatus::Exception { exception } => {
                 Some(ProcessedInputOneOf::ExceptionData(exception.payload))
             }
@@ -978,11 +1096,20 @@ impl From<CompletionStatus> for Option<ProcessedInputOneOf> {
 
 impl From<Voucher> for GrpcVoucher {
     fn from(voucher: Voucher) -> GrpcVoucher {
+        let (kind, value) = match voucher.kind {
+            VoucherKind::Legacy => (GrpcVoucherKind::Legacy, None),
+            VoucherKind::Call { value } => {
+                (GrpcVoucherKind::Call, Some(GrpcHash { data: value.into() }))
+            }
+            VoucherKind::DelegateCall => (GrpcVoucherKind::DelegateCall, None),
+        };
         GrpcVoucher {
             destination: Some(Address {
                 data: voucher.destination.into(),
             }),
             payload: voucher.payload,
+            kind: kind.into(),
+            value,
         }
     }
 }
@@ -1003,34 +1130,13 @@ impl From<Report> for GrpcReport {
     }
 }
 
-impl From<Hash> for GrpcHash {
-    fn from(hash: Hash) -> GrpcHash {
-        GrpcHash { data: hash.into() }
-    }
+fn convert_hash(hash: Hash) -> GrpcHash {
+    GrpcHash { data: hash.into() }
 }
 
-impl From<Proof> for GrpcMerkleTreeProof {
-    fn from(proof: Proof) -> GrpcMerkleTreeProof {
-        GrpcMerkleTreeProof {
-            target_address: proof.target_address as u64,
-            log2_target_size: proof.log2_target_size as u64,
-            target_hash: Some(proof.target_hash.into()),
-            log2_root_size: proof.log2_root_size as u64,
-            root_hash: Some(proof.root_hash.into()),
-            sibling_hashes: proof
-                .sibling_hashes
-                .into_iter()
-                .map(GrpcHash::from)
-                .collect(),
-        }
-    }
-}
-
-impl From<MerkleTreeError> for Status {
-    fn from(e: MerkleTreeError) -> Status {
-        Status::internal(format!(
-            "unexpected error when updating merkle tree ({})",
-            e
-        ))
-    }
+fn convert_merkle_tree_error(e: MerkleTreeError) -> Status {
+    Status::internal(format!(
+        "unexpected error when updating merkle tree ({})",
+        e
+    ))
 }
@@ -0,0 +1,71 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Read-only endpoints to debug the DApp: a snapshot of the controller
+//! status, and a server-sent-events stream of its state transitions.
+
+use actix_web::{http::header, web::Bytes, web::Json, HttpResponse, Responder};
+use futures_util::stream;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::monitor::StateTransition;
+
+use super::rollup_server::SessionController;
+
+#[actix_web::get("/dashboard")]
+async fn dashboard(controller: SessionController) -> impl Responder {
+    Json(controller.monitor().status())
+}
+
+#[actix_web::get("/dashboard/events")]
+async fn dashboard_events(controller: SessionController) -> HttpResponse {
+    let rx = controller.monitor().subscribe();
+    let events = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(transition) => {
+                    let event = encode_event(&transition);
+                    return Some((Ok::<_, actix_web::Error>(event), rx));
+                }
+                Err(RecvError::Lagged(count)) => {
+                    tracing::warn!(
+                        "dashboard subscriber skipped {} transitions",
+                        count
+                    );
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(events)
+}
+
+fn encode_event(transition: &StateTransition) -> Bytes {
+    let data = serde_json::to_string(transition)
+        .expect("failed to serialize state transition");
+    Bytes::from(format!("event: transition\ndata: {}\n\n", data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_it_encodes_transition_event() {
+        let transition = StateTransition {
+            from: String::from("idle"),
+            to: String::from("fetch request"),
+            timestamp: 10,
+        };
+        assert_eq!(
+            encode_event(&transition),
+            Bytes::from(
+                "event: transition\n\
+                 data: {\"from\":\"idle\",\"to\":\"fetch request\",\"timestamp\":10}\n\n"
+            )
+        );
+    }
+}
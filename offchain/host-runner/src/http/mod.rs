@@ -1,17 +1,36 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
+mod dashboard;
 pub mod errors;
 pub mod model;
 mod rollup_server;
 
+use futures_util::future::try_join_all;
+
 use crate::config::Config;
-use crate::controller::Controller;
+use crate::sessions::SessionControllers;
 
-/// Setup the HTTP server that receives requests from the DApp backend
+/// Setup the HTTP servers that receive requests from the DApp backends: the
+/// main rollup server, and one server for each session bound to a port
 pub async fn start_services(
     config: &Config,
-    controller: Controller,
+    sessions: SessionControllers,
 ) -> std::io::Result<()> {
-    rollup_server::start_service(config, controller.clone()).await
+    let address = config.http_rollup_server_address.clone();
+    let mut services = vec![rollup_server::start_service(
+        address.clone(),
+        config.http_rollup_server_port,
+        sessions.root(),
+        Some(sessions.clone()),
+    )];
+    for (port, controller) in sessions.port_bindings() {
+        services.push(rollup_server::start_service(
+            address.clone(),
+            port,
+            controller,
+            None,
+        ));
+    }
+    try_join_all(services).await.map(|_| ())
 }
@@ -87,20 +87,62 @@ impl From<RollupRequest> for HttpRollupRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HttpVoucher {
     pub destination: String,
+    /// Amount of wei sent with the call, as a big-endian uint256 in hex; the
+    /// voucher is encoded as a legacy one when it is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
     pub payload: String,
 }
 
 impl TryFrom<HttpVoucher> for Voucher {
     type Error = VoucherDecodeError;
     fn try_from(voucher: HttpVoucher) -> Result<Voucher, VoucherDecodeError> {
-        Ok(Voucher::new(
+        let kind = match voucher.value {
+            Some(value) => VoucherKind::Call {
+                value: decode_value(&value)?,
+            },
+            None => VoucherKind::Legacy,
+        };
+        Ok(Voucher::with_kind(
             conversions::decode_ethereum_binary(&voucher.destination)?
                 .try_into()?,
+            kind,
             conversions::decode_ethereum_binary(&voucher.payload)?,
         ))
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpDelegateCallVoucher {
+    pub destination: String,
+    pub payload: String,
+}
+
+impl TryFrom<HttpDelegateCallVoucher> for Voucher {
+    type Error = VoucherDecodeError;
+    fn try_from(
+        voucher: HttpDelegateCallVoucher,
+    ) -> Result<Voucher, VoucherDecodeError> {
+        Ok(Voucher::with_kind(
+            conversions::decode_ethereum_binary(&voucher.destination)?
+                .try_into()?,
+            VoucherKind::DelegateCall,
+            conversions::decode_ethereum_binary(&voucher.payload)?,
+        ))
+    }
+}
+
+/// Decodes a uint256 value, left-padding values shorter than 32 bytes
+fn decode_value(value: &str) -> Result<[u8; 32], VoucherDecodeError> {
+    let bytes = conversions::decode_ethereum_binary(value)?;
+    if bytes.len() > 32 {
+        return Err(VoucherDecodeError::InvalidValueSize { got: bytes.len() });
+    }
+    let mut value = [0; 32];
+    value[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(value)
+}
+
 #[derive(Debug, Snafu)]
 pub enum VoucherDecodeError {
     #[snafu(display(
@@ -108,6 +150,11 @@ pub enum VoucherDecodeError {
         got
     ))]
     InvalidAddressSize { got: usize },
+    #[snafu(display(
+        "Invalid voucher value size (got {} bytes, expected up to 32 bytes)",
+        got
+    ))]
+    InvalidValueSize { got: usize },
     #[snafu(display("{}", e))]
     HexDecodeError { e: DecodeError },
 }
@@ -2,48 +2,129 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 use actix_web::{
-    error, error::Result as HttpResult, middleware::Logger, web::Data,
-    web::Json, App, HttpResponse, HttpServer, Responder,
+    dev::Payload, error, error::Result as HttpResult, middleware::Logger, web,
+    web::Data, web::Json, App, FromRequest, HttpRequest, HttpResponse,
+    HttpServer, Responder,
 };
+use futures_util::future::{ready, Ready};
 
-use crate::config::Config;
 use crate::controller::{Controller, ControllerError};
 use crate::model::{FinishStatus, Notice, Report, RollupException, Voucher};
+use crate::sessions::SessionControllers;
 
+use super::dashboard::{dashboard, dashboard_events};
 use super::model::{
-    HttpFinishRequest, HttpIndexResponse, HttpNotice, HttpReport,
-    HttpRollupException, HttpRollupRequest, HttpVoucher,
+    HttpDelegateCallVoucher, HttpFinishRequest, HttpIndexResponse, HttpNotice,
+    HttpReport, HttpRollupException, HttpRollupRequest, HttpVoucher,
 };
 
+/// Starts a rollup HTTP server whose root paths reach `controller`; when
+/// `sessions` is set, the controller of each session is also reachable at
+/// `/sessions/<session_id>/...`.
 pub async fn start_service(
-    config: &Config,
+    address: String,
+    port: u16,
     controller: Controller,
+    sessions: Option<SessionControllers>,
 ) -> std::io::Result<()> {
     HttpServer::new(move || {
-        App::new()
+        let mut app = App::new()
             .app_data(Data::new(controller.clone()))
-            .wrap(Logger::default())
             .service(voucher)
+            .service(delegate_call_voucher)
             .service(notice)
             .service(report)
             .service(exception)
             .service(finish)
+            .service(dashboard)
+            .service(dashboard_events);
+        if let Some(sessions) = &sessions {
+            app = app.service(
+                web::scope("/sessions/{session_id}")
+                    .app_data(Data::new(sessions.clone()))
+                    .service(voucher)
+                    .service(delegate_call_voucher)
+                    .service(notice)
+                    .service(report)
+                    .service(exception)
+                    .service(finish)
+                    .service(dashboard)
+                    .service(dashboard_events),
+            );
+        }
+        app.wrap(Logger::default())
     })
-    .bind((
-        config.http_rollup_server_address.as_str(),
-        config.http_rollup_server_port,
-    ))?
+    .bind((address.as_str(), port))?
     .run()
     .await
 }
 
+/// Controller of the session addressed by the request path.
+pub(super) struct SessionController(Controller);
+
+impl FromRequest for SessionController {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let controller = match req.match_info().get("session_id") {
+            Some(session_id) => {
+                match req.app_data::<Data<SessionControllers>>() {
+                    Some(sessions) => {
+                        sessions.get(session_id).ok_or_else(|| {
+                            error::ErrorNotFound(format!(
+                                "unknown session {}",
+                                session_id
+                            ))
+                        })
+                    }
+                    None => Err(error::ErrorInternalServerError(
+                        "missing session controllers",
+                    )),
+                }
+            }
+            None => req
+                .app_data::<Data<Controller>>()
+                .map(|controller| controller.get_ref().clone())
+                .ok_or_else(|| {
+                    error::ErrorInternalServerError("missing controller")
+                }),
+        };
+        ready(controller.map(SessionController))
+    }
+}
+
+impl std::ops::Deref for SessionController {
+    type Target = Controller;
+
+    fn deref(&self) -> &Controller {
+        &self.0
+    }
+}
+
 #[actix_web::post("/voucher")]
 async fn voucher(
     voucher: Json<HttpVoucher>,
-    controller: Data<Controller>,
+    controller: SessionController,
 ) -> HttpResult<impl Responder> {
     let voucher: Voucher = voucher.into_inner().try_into()?;
-    let rx = controller.insert_voucher(voucher).await;
+    insert_voucher(&controller, voucher).await
+}
+
+#[actix_web::post("/delegate-call-voucher")]
+async fn delegate_call_voucher(
+    request: Json<HttpDelegateCallVoucher>,
+    controller: SessionController,
+) -> HttpResult<impl Responder> {
+    let delegate_call_voucher: Voucher = request.into_inner().try_into()?;
+    insert_voucher(&controller, delegate_call_voucher).await
+}
+
+async fn insert_voucher(
+    controller: &Controller,
+    new_voucher: Voucher,
+) -> HttpResult<HttpResponse> {
+    let rx = controller.insert_voucher(new_voucher).await;
     let index = rx.await.map_err(|_| {
         tracing::error!("sender dropped the channel");
         error::ErrorInternalServerError("failed to insert voucher")
@@ -57,7 +138,7 @@ async fn voucher(
 #[actix_web::post("/notice")]
 async fn notice(
     notice: Json<HttpNotice>,
-    controller: Data<Controller>,
+    controller: SessionController,
 ) -> HttpResult<impl Responder> {
     let notice: Notice = notice.into_inner().try_into()?;
     let rx = controller.insert_notice(notice).await;
@@ -74,7 +155,7 @@ async fn notice(
 #[actix_web::post("/report")]
 async fn report(
     report: Json<HttpReport>,
-    controller: Data<Controller>,
+    controller: SessionController,
 ) -> HttpResult<impl Responder> {
     let report: Report = report.into_inner().try_into()?;
     let rx = controller.insert_report(report).await;
@@ -88,7 +169,7 @@ async fn report(
 #[actix_web::post("/exception")]
 async fn exception(
     exception: Json<HttpRollupException>,
-    controller: Data<Controller>,
+    controller: SessionController,
 ) -> HttpResult<impl Responder> {
     let exception: RollupException = exception.into_inner().try_into()?;
     let rx = controller.notify_exception(exception).await;
@@ -102,7 +183,7 @@ async fn exception(
 #[actix_web::post("/finish")]
 async fn finish(
     body: Json<HttpFinishRequest>,
-    controller: Data<Controller>,
+    controller: SessionController,
 ) -> HttpResult<impl Responder> {
     let status: FinishStatus = body.into_inner().try_into()?;
     let rx = controller.finish(status).await;
@@ -118,7 +199,8 @@ async fn finish(
             ControllerError::FetchRequestTimeout => {
                 HttpResponse::Accepted().body(e.to_string())
             }
-            ControllerError::InvalidRequest { .. } => {
+            ControllerError::InvalidRequest { .. }
+            | ControllerError::OutputLimitExceeded { .. } => {
                 HttpResponse::BadRequest().body(e.to_string())
             }
         },
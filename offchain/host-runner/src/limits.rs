@@ -0,0 +1,127 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Emulation of the resource limits enforced by the server-manager.
+//!
+//! The limits only apply to advance requests, which are the ones that can end
+//! with a limit-exceeded status in the server-manager. Host mode has no cycle
+//! counter, so the cycle limit is emulated with a wall-clock budget as well.
+
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::model::CompletionStatus;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Wall-clock deadline for the DApp to finish each input
+    pub time_limit: Option<Duration>,
+    /// Wall-clock budget that emulates the cycle limit of each input
+    pub cycle_limit: Option<Duration>,
+    /// Max length of the input payload
+    pub max_payload_length: Option<usize>,
+    /// Max number of vouchers, notices or reports of each input
+    pub max_output_count: Option<usize>,
+    /// Max payload length of each voucher, notice or report
+    pub max_output_size: Option<usize>,
+}
+
+impl ResourceLimits {
+    /// Returns the status of an input whose payload exceeds the limit.
+    pub fn check_payload(&self, payload: &[u8]) -> Option<CompletionStatus> {
+        match self.max_payload_length {
+            Some(max) if payload.len() > max => {
+                Some(CompletionStatus::PayloadLengthLimitExceeded)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns whether the DApp may emit an output of `size` bytes after
+    /// emitting `count` outputs of the same kind for the current input.
+    pub fn allows_output(&self, count: usize, size: usize) -> bool {
+        let count_ok = self.max_output_count.is_none_or(|max| count < max);
+        let size_ok = self.max_output_size.is_none_or(|max| size <= max);
+        count_ok && size_ok
+    }
+
+    /// Returns the earliest deadline of an input that started at `start`,
+    /// with the status the input ends with when it is reached.
+    pub fn deadline(
+        &self,
+        start: Instant,
+    ) -> Option<(Instant, CompletionStatus)> {
+        let time = self
+            .time_limit
+            .map(|limit| (start + limit, CompletionStatus::TimeLimitExceeded));
+        let cycle = self
+            .cycle_limit
+            .map(|limit| (start + limit, CompletionStatus::CycleLimitExceeded));
+        match (time, cycle) {
+            (Some(time), Some(cycle)) if cycle.0 < time.0 => Some(cycle),
+            (Some(time), _) => Some(time),
+            (None, cycle) => cycle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_it_checks_payload_length() {
+        let limits = ResourceLimits {
+            max_payload_length: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(limits.check_payload(&[0, 1]), None);
+        assert_eq!(
+            limits.check_payload(&[0, 1, 2]),
+            Some(CompletionStatus::PayloadLengthLimitExceeded)
+        );
+        assert_eq!(ResourceLimits::default().check_payload(&[0; 1024]), None);
+    }
+
+    #[test]
+    fn test_it_checks_outputs() {
+        let limits = ResourceLimits {
+            max_output_count: Some(2),
+            max_output_size: Some(4),
+            ..Default::default()
+        };
+        assert!(limits.allows_output(1, 4));
+        assert!(!limits.allows_output(2, 4));
+        assert!(!limits.allows_output(0, 5));
+        assert!(ResourceLimits::default().allows_output(usize::MAX, 1024));
+    }
+
+    #[test]
+    fn test_it_picks_earliest_deadline() {
+        let start = Instant::now();
+        let limits = ResourceLimits {
+            time_limit: Some(Duration::from_millis(20)),
+            cycle_limit: Some(Duration::from_millis(10)),
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.deadline(start),
+            Some((
+                start + Duration::from_millis(10),
+                CompletionStatus::CycleLimitExceeded
+            ))
+        );
+        let limits = ResourceLimits {
+            time_limit: Some(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.deadline(start),
+            Some((
+                start + Duration::from_millis(20),
+                CompletionStatus::TimeLimitExceeded
+            ))
+        );
+        assert_eq!(ResourceLimits::default().deadline(start), None);
+    }
+}
@@ -4,15 +4,17 @@
 mod config;
 mod controller;
 mod conversions;
-mod driver;
 mod grpc;
-mod hash;
 mod http;
-mod merkle_tree;
+mod limits;
 mod model;
-mod proofs;
+mod monitor;
+mod recorder;
+mod sessions;
+mod storage;
 
 use futures_util::FutureExt;
+use std::path::Path;
 use std::sync::{atomic::AtomicBool, atomic::Ordering, Arc};
 use std::time::Duration;
 use tokio::sync::oneshot;
@@ -21,6 +23,8 @@ use tracing::info;
 use clap::Parser;
 use config::{CLIConfig, Config};
 use controller::Controller;
+use recorder::Recorder;
+use sessions::SessionControllers;
 
 fn log_result<T, E: std::error::Error>(name: &str, result: Result<T, E>) {
     let prefix = format!("http {} terminated ", name);
@@ -38,20 +42,44 @@ async fn main() {
 
     info!(?config, "Starting Host Runner");
 
-    let controller =
-        Controller::new(Duration::from_millis(config.finish_timeout));
+    if let Some(replay_file) = &config.replay_file {
+        std::process::exit(replay(&config, replay_file).await);
+    }
+
+    let recorder = match config
+        .record_file
+        .as_deref()
+        .map(Recorder::create)
+        .transpose()
+    {
+        Ok(recorder) => recorder,
+        Err(e) => {
+            tracing::error!("failed to create session log ({})", e);
+            std::process::exit(1);
+        }
+    };
+    let controller = Controller::new(
+        Duration::from_millis(config.finish_timeout),
+        config.resource_limits.clone(),
+        recorder,
+    );
+    let sessions = SessionControllers::new(
+        controller,
+        Duration::from_millis(config.finish_timeout),
+        config.resource_limits.clone(),
+        config.session_ports.clone(),
+    );
     let http_service_running = Arc::new(AtomicBool::new(true));
     let (grpc_shutdown_tx, grpc_shutdown_rx) = oneshot::channel::<()>();
     let grpc_service = {
-        let controller = controller.clone();
+        let sessions = sessions.clone();
         let config = config.clone();
         let shutdown = grpc_shutdown_rx.map(|_| ());
         let http_service_running = http_service_running.clone();
         tokio::spawn(async move {
             log_result(
                 "gRPC service",
-                grpc::start_service(&config, controller.clone(), shutdown)
-                    .await,
+                grpc::start_service(&config, sessions, shutdown).await,
             );
             if http_service_running.load(Ordering::Relaxed) {
                 panic!("gRPC service terminated before shutdown signal");
@@ -60,7 +88,7 @@ async fn main() {
     };
 
     // We run the actix-web in the main thread because it handles the SIGINT
-    let host_runner_handle = http::start_services(&config, controller.clone());
+    let host_runner_handle = http::start_services(&config, sessions.clone());
     let health_handle = http_health_check::start(config.healthcheck_port);
     tokio::select! {
         result = health_handle => {
@@ -73,9 +101,7 @@ async fn main() {
     http_service_running.store(false, Ordering::Relaxed);
 
     // Shutdown the other services
-    if let Err(e) = controller.shutdown().await.await {
-        tracing::error!("failed to shutdown controller ({})", e);
-    }
+    sessions.shutdown().await;
     if grpc_shutdown_tx.send(()).is_err() {
         tracing::error!("failed to send the shutdown signal to grpc");
     }
@@ -83,3 +109,65 @@ async fn main() {
         tracing::error!("failed to shutdown the grpc service ({})", e);
     }
 }
+
+/// Replays the session log to the DApp and returns the exit code, which is
+/// non-zero when the outputs diverge from the recorded ones.
+async fn replay(config: &Config, replay_file: &Path) -> i32 {
+    let entries = match recorder::read_session_log(replay_file) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::error!("failed to read session log ({})", e);
+            return 1;
+        }
+    };
+    info!(
+        "Replaying {} requests from {:?}",
+        entries.len(),
+        replay_file
+    );
+
+    let controller = Controller::new(
+        Duration::from_millis(config.finish_timeout),
+        config.resource_limits.clone(),
+        None,
+    );
+    let sessions = SessionControllers::new(
+        controller.clone(),
+        Duration::from_millis(config.finish_timeout),
+        config.resource_limits.clone(),
+        vec![],
+    );
+    let host_runner_handle = http::start_services(config, sessions);
+    let exit_code = tokio::select! {
+        result = host_runner_handle => {
+            log_result("http service", result);
+            1
+        }
+        result = recorder::replay(&controller, entries) => match result {
+            Ok(mismatches) if mismatches.is_empty() => {
+                info!("Replay finished; all outputs match the session log");
+                0
+            }
+            Ok(mismatches) => {
+                for mismatch in mismatches {
+                    tracing::error!(
+                        diffs = ?mismatch.diffs,
+                        "request {} ({:?}) diverged from the session log",
+                        mismatch.index,
+                        mismatch.request
+                    );
+                }
+                1
+            }
+            Err(e) => {
+                tracing::error!("failed to replay session log ({})", e);
+                1
+            }
+        }
+    };
+
+    if let Err(e) = controller.shutdown().await.await {
+        tracing::error!("failed to shutdown controller ({})", e);
+    }
+    exit_code
+}
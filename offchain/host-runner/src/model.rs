@@ -1,11 +1,15 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
+use output_proofs::encoding::{
+    compute_notice_hash, compute_output_hash, compute_voucher_hash,
+    encode_call_voucher, encode_delegate_call_voucher,
+};
+use output_proofs::hash::{Hash, HASH_SIZE};
+use output_proofs::merkle_tree;
+use output_proofs::outputs::OutputsTree;
+
 use crate::conversions;
-use crate::driver::{compute_notice_hash, compute_voucher_hash};
-use crate::hash::Hash;
-use crate::merkle_tree::proof::Proof;
-use crate::proofs::Proofable;
 
 const ADDRESS_SIZE: usize = 20;
 
@@ -28,20 +32,24 @@ pub struct AdvanceMetadata {
 pub struct AdvanceResult {
     pub status: CompletionStatus,
     pub reports: Vec<Report>,
-    pub voucher_hashes_in_epoch: Option<Proof>,
-    pub voucher_root: Option<Hash>,
-    pub notice_hashes_in_epoch: Option<Proof>,
-    pub notice_root: Option<Hash>,
+    /// Output-hashes trees of the vouchers and notices, which are empty
+    /// unless the input was accepted
+    pub voucher_tree: OutputsTree,
+    pub notice_tree: OutputsTree,
 }
 
 impl AdvanceResult {
-    pub fn accepted(
-        vouchers: Vec<Voucher>,
-        notices: Vec<Notice>,
-        reports: Vec<Report>,
-    ) -> Self {
-        let status = CompletionStatus::Accepted { vouchers, notices };
-        Self::new(status, reports)
+    pub fn accepted(outputs: AdvanceOutputs, reports: Vec<Report>) -> Self {
+        let status = CompletionStatus::Accepted {
+            vouchers: outputs.vouchers,
+            notices: outputs.notices,
+        };
+        Self {
+            status,
+            reports,
+            voucher_tree: outputs.voucher_tree,
+            notice_tree: outputs.notice_tree,
+        }
     }
 
     pub fn rejected(reports: Vec<Report>) -> Self {
@@ -53,15 +61,65 @@ impl AdvanceResult {
         Self::new(status, reports)
     }
 
+    /// Result of an input that ended because it exceeded a resource limit.
+    pub fn limit_exceeded(
+        status: CompletionStatus,
+        reports: Vec<Report>,
+    ) -> Self {
+        Self::new(status, reports)
+    }
+
     fn new(status: CompletionStatus, reports: Vec<Report>) -> Self {
         Self {
             status,
             reports,
-            voucher_hashes_in_epoch: None,
-            voucher_root: None,
-            notice_hashes_in_epoch: None,
-            notice_root: None,
+            voucher_tree: OutputsTree::new(),
+            notice_tree: OutputsTree::new(),
+        }
+    }
+}
+
+/// Vouchers and notices of an input, whose output-hashes trees are updated
+/// as each output arrives so finishing the epoch only has to prove them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdvanceOutputs {
+    pub vouchers: Vec<Voucher>,
+    pub notices: Vec<Notice>,
+    pub voucher_tree: OutputsTree,
+    pub notice_tree: OutputsTree,
+}
+
+impl AdvanceOutputs {
+    pub fn new(
+        vouchers: Vec<Voucher>,
+        notices: Vec<Notice>,
+    ) -> Result<Self, merkle_tree::Error> {
+        let mut outputs = Self::default();
+        for voucher in vouchers {
+            outputs.push_voucher(voucher)?;
         }
+        for notice in notices {
+            outputs.push_notice(notice)?;
+        }
+        Ok(outputs)
+    }
+
+    pub fn push_voucher(
+        &mut self,
+        voucher: Voucher,
+    ) -> Result<(), merkle_tree::Error> {
+        self.voucher_tree.push(&voucher.keccak)?;
+        self.vouchers.push(voucher);
+        Ok(())
+    }
+
+    pub fn push_notice(
+        &mut self,
+        notice: Notice,
+    ) -> Result<(), merkle_tree::Error> {
+        self.notice_tree.push(&notice.keccak)?;
+        self.notices.push(notice);
+        Ok(())
     }
 }
 
@@ -75,6 +133,10 @@ pub enum CompletionStatus {
     Exception {
         exception: RollupException,
     },
+    MachineHalted,
+    CycleLimitExceeded,
+    TimeLimitExceeded,
+    PayloadLengthLimitExceeded,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -130,33 +192,65 @@ pub enum RollupRequest {
     InspectState(InspectStateRequest),
 }
 
+/// Kind of voucher, which defines how the voucher is encoded and executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoucherKind {
+    /// Voucher of the 1.x contracts, which calls the destination
+    Legacy,
+    /// Voucher of the newer contracts, which calls the destination
+    /// transferring `value` wei (big-endian uint256)
+    Call { value: [u8; HASH_SIZE] },
+    /// Voucher of the newer contracts, which runs the payload in the context
+    /// of the DApp contract with DELEGATECALL
+    DelegateCall,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Voucher {
     pub destination: [u8; ADDRESS_SIZE],
+    pub kind: VoucherKind,
     pub payload: Vec<u8>,
     pub keccak: Hash,
-    pub keccak_in_voucher_hashes: Option<Proof>,
 }
 
 impl Voucher {
     pub fn new(destination: [u8; ADDRESS_SIZE], payload: Vec<u8>) -> Self {
-        let keccak = compute_voucher_hash(&destination, &payload);
+        Self::with_kind(destination, VoucherKind::Legacy, payload)
+    }
+
+    pub fn with_kind(
+        destination: [u8; ADDRESS_SIZE],
+        kind: VoucherKind,
+        payload: Vec<u8>,
+    ) -> Self {
+        let keccak = match encode_voucher(&destination, &kind, &payload) {
+            Some(output) => compute_output_hash(&output),
+            None => compute_voucher_hash(&destination, &payload),
+        };
         Self {
             destination,
+            kind,
             payload,
             keccak,
-            keccak_in_voucher_hashes: None,
         }
     }
 }
 
-impl Proofable for Voucher {
-    fn get_hash(&self) -> &Hash {
-        &self.keccak
-    }
-
-    fn set_proof(&mut self, proof: Proof) {
-        self.keccak_in_voucher_hashes = Some(proof);
+/// Output encoding of the vouchers of the newer contracts; legacy vouchers
+/// have none.
+fn encode_voucher(
+    destination: &[u8; ADDRESS_SIZE],
+    kind: &VoucherKind,
+    payload: &[u8],
+) -> Option<Vec<u8>> {
+    match kind {
+        VoucherKind::Legacy => None,
+        VoucherKind::Call { value } => {
+            Some(encode_call_voucher(destination, value, payload))
+        }
+        VoucherKind::DelegateCall => {
+            Some(encode_delegate_call_voucher(destination, payload))
+        }
     }
 }
 
@@ -164,27 +258,12 @@ impl Proofable for Voucher {
 pub struct Notice {
     pub payload: Vec<u8>,
     pub keccak: Hash,
-    pub keccak_in_notice_hashes: Option<Proof>,
 }
 
 impl Notice {
     pub fn new(payload: Vec<u8>) -> Self {
         let keccak = compute_notice_hash(&payload);
-        Self {
-            payload,
-            keccak,
-            keccak_in_notice_hashes: None,
-        }
-    }
-}
-
-impl Proofable for Notice {
-    fn get_hash(&self) -> &Hash {
-        &self.keccak
-    }
-
-    fn set_proof(&mut self, proof: Proof) {
-        self.keccak_in_notice_hashes = Some(proof);
+        Self { payload, keccak }
     }
 }
 
@@ -0,0 +1,279 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Read-only view of the controller for debugging DApps in host mode.
+//!
+//! The controller reports its state transitions, the requests waiting for the
+//! DApp, and the timeouts it hits; the server-manager session reports its
+//! active epoch. The rollup HTTP server exposes a snapshot of this status and
+//! a stream of the state transitions.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+use crate::recorder::{RecordedRequest, RecordedResult};
+
+/// Number of timeouts kept in the status
+const RECENT_TIMEOUTS_LIMIT: usize = 16;
+
+/// Number of transitions buffered for slow subscribers
+const TRANSITIONS_BUFFER_SIZE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ControllerStatus {
+    /// Name of the current state of the controller
+    pub state: String,
+    /// Time when the controller entered the current state, in unix millis
+    pub state_since: u64,
+    /// Requests sent to the controller that the DApp did not fetch yet
+    pub pending_requests: Vec<RecordedRequest>,
+    /// Active epoch of the session bound to the controller
+    pub epoch: Option<EpochStatus>,
+    /// Latest timeouts, from the oldest to the newest
+    pub recent_timeouts: VecDeque<TimeoutEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EpochStatus {
+    pub epoch_index: u64,
+    pub pending_input_count: u64,
+    pub processed_input_count_since_genesis: u64,
+    pub processed_inputs: Vec<RecordedResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimeoutEvent {
+    /// State in which the timeout happened
+    pub state: String,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateTransition {
+    pub from: String,
+    pub to: String,
+    pub timestamp: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ControllerMonitor {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    status: Mutex<ControllerStatus>,
+    transitions_tx: broadcast::Sender<StateTransition>,
+}
+
+impl ControllerMonitor {
+    pub fn new(state: String) -> Self {
+        let (transitions_tx, _) = broadcast::channel(TRANSITIONS_BUFFER_SIZE);
+        let status = ControllerStatus {
+            state,
+            state_since: now(),
+            pending_requests: vec![],
+            epoch: None,
+            recent_timeouts: VecDeque::new(),
+        };
+        Self {
+            inner: Arc::new(Inner {
+                status: Mutex::new(status),
+                transitions_tx,
+            }),
+        }
+    }
+
+    pub fn status(&self) -> ControllerStatus {
+        self.inner.status.lock().unwrap().clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StateTransition> {
+        self.inner.transitions_tx.subscribe()
+    }
+
+    /// Updates the current state, notifying the subscribers when it changes.
+    pub fn set_state(&self, state: String) {
+        let mut status = self.inner.status.lock().unwrap();
+        if status.state == state {
+            return;
+        }
+        let transition = StateTransition {
+            from: std::mem::replace(&mut status.state, state.clone()),
+            to: state,
+            timestamp: now(),
+        };
+        status.state_since = transition.timestamp;
+        // Sending only fails when nobody is subscribed
+        let _ = self.inner.transitions_tx.send(transition);
+    }
+
+    pub fn push_request(&self, request: RecordedRequest) {
+        let mut status = self.inner.status.lock().unwrap();
+        status.pending_requests.push(request);
+    }
+
+    /// Removes the oldest pending request of the same type as `request`, since
+    /// each type has its own queue in the controller.
+    pub fn pop_request(&self, request: &RecordedRequest) {
+        let mut status = self.inner.status.lock().unwrap();
+        let position = status.pending_requests.iter().position(|pending| {
+            std::mem::discriminant(pending) == std::mem::discriminant(request)
+        });
+        if let Some(position) = position {
+            status.pending_requests.remove(position);
+        }
+    }
+
+    pub fn push_timeout(&self, reason: String) {
+        let mut status = self.inner.status.lock().unwrap();
+        let timeout = TimeoutEvent {
+            state: status.state.clone(),
+            reason,
+            timestamp: now(),
+        };
+        if status.recent_timeouts.len() == RECENT_TIMEOUTS_LIMIT {
+            status.recent_timeouts.pop_front();
+        }
+        status.recent_timeouts.push_back(timeout);
+    }
+
+    pub fn set_epoch(&self, epoch: Option<EpochStatus>) {
+        self.inner.status.lock().unwrap().epoch = epoch;
+    }
+
+    /// Updates the pending input count of the given epoch if it is still the
+    /// one reported.
+    pub fn set_pending_input_count(&self, epoch_index: u64, count: u64) {
+        let mut status = self.inner.status.lock().unwrap();
+        match status.epoch.as_mut() {
+            Some(epoch) if epoch.epoch_index == epoch_index => {
+                epoch.pending_input_count = count;
+            }
+            _ => {}
+        }
+    }
+
+    /// Appends a processed input to the given epoch if it is still the one
+    /// reported, so the status is not rebuilt after every advance.
+    pub fn push_processed_input(
+        &self,
+        epoch_index: u64,
+        pending_input_count: u64,
+        result: RecordedResult,
+    ) {
+        let mut status = self.inner.status.lock().unwrap();
+        match status.epoch.as_mut() {
+            Some(epoch) if epoch.epoch_index == epoch_index => {
+                epoch.pending_input_count = pending_input_count;
+                epoch.processed_inputs.push(result);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::RecordedStatus;
+
+    fn inspect(payload: &str) -> RecordedRequest {
+        RecordedRequest::InspectState {
+            payload: payload.into(),
+        }
+    }
+
+    fn advance(payload: &str) -> RecordedRequest {
+        RecordedRequest::AdvanceState {
+            msg_sender: String::from("0x00"),
+            epoch_index: 0,
+            input_index: 0,
+            block_number: 0,
+            timestamp: 0,
+            payload: payload.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_it_notifies_state_transitions() {
+        let monitor = ControllerMonitor::new("idle".into());
+        let mut rx = monitor.subscribe();
+        monitor.set_state("idle".into());
+        monitor.set_state("fetch request".into());
+        assert_eq!(monitor.status().state, "fetch request");
+        let transition = rx.recv().await.unwrap();
+        assert_eq!(transition.from, "idle");
+        assert_eq!(transition.to, "fetch request");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_it_pops_oldest_request_of_same_type() {
+        let monitor = ControllerMonitor::new("idle".into());
+        monitor.push_request(advance("0x01"));
+        monitor.push_request(inspect("0x02"));
+        monitor.push_request(inspect("0x03"));
+        monitor.pop_request(&inspect("0x03"));
+        assert_eq!(
+            monitor.status().pending_requests,
+            vec![advance("0x01"), inspect("0x03")]
+        );
+        monitor.pop_request(&advance("0x01"));
+        monitor.pop_request(&advance("0x01"));
+        assert_eq!(monitor.status().pending_requests, vec![inspect("0x03")]);
+    }
+
+    #[test]
+    fn test_it_keeps_recent_timeouts() {
+        let monitor = ControllerMonitor::new("fetch request".into());
+        for i in 0..RECENT_TIMEOUTS_LIMIT + 2 {
+            monitor.push_timeout(i.to_string());
+        }
+        let timeouts = monitor.status().recent_timeouts;
+        assert_eq!(timeouts.len(), RECENT_TIMEOUTS_LIMIT);
+        assert_eq!(timeouts.front().unwrap().reason, "2");
+        assert_eq!(timeouts.front().unwrap().state, "fetch request");
+        assert_eq!(
+            timeouts.back().unwrap().reason,
+            (RECENT_TIMEOUTS_LIMIT + 1).to_string()
+        );
+    }
+
+    #[test]
+    fn test_it_appends_processed_inputs_to_reported_epoch() {
+        let monitor = ControllerMonitor::new("idle".into());
+        monitor.set_epoch(Some(EpochStatus {
+            epoch_index: 1,
+            pending_input_count: 0,
+            processed_input_count_since_genesis: 3,
+            processed_inputs: vec![],
+        }));
+        let result = RecordedResult {
+            status: RecordedStatus::Accepted,
+            vouchers: vec![],
+            notices: vec![],
+            reports: vec![],
+            exception: None,
+        };
+        monitor.set_pending_input_count(1, 2);
+        monitor.push_processed_input(1, 1, result.clone());
+        // Late updates of previous epochs are ignored
+        monitor.set_pending_input_count(0, 5);
+        monitor.push_processed_input(0, 4, result.clone());
+        let epoch = monitor.status().epoch.unwrap();
+        assert_eq!(epoch.pending_input_count, 1);
+        assert_eq!(epoch.processed_inputs, vec![result]);
+    }
+}
@@ -0,0 +1,724 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Record and replay of the rollup requests handled by the controller.
+//!
+//! The session log is a JSON-lines file with one entry per request, holding
+//! the request and the outputs produced by the DApp while handling it. The
+//! replay feeds the requests of a session log back to the DApp, in the same
+//! order, and compares the new outputs with the recorded ones.
+//!
+//! The recorder writes the session log in a dedicated thread, so the
+//! controller never waits for the disk while handling requests.
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+
+use crate::controller::Controller;
+use crate::conversions::{decode_ethereum_binary, encode_ethereum_binary};
+use crate::model::*;
+
+#[derive(Debug, Snafu)]
+pub enum RecorderError {
+    #[snafu(display("failed to open session log ({})", path.display()))]
+    OpenLogError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("failed to write session log"))]
+    WriteLogError { source: std::io::Error },
+    #[snafu(display("failed to start session log writer"))]
+    SpawnWriterError { source: std::io::Error },
+    #[snafu(display("session log writer stopped"))]
+    WriterStoppedError,
+    #[snafu(display("failed to read session log"))]
+    ReadLogError { source: std::io::Error },
+    #[snafu(display("failed to parse entry {} of session log", index))]
+    ParseLogError {
+        index: usize,
+        source: serde_json::Error,
+    },
+    #[snafu(display("invalid binary in entry {} of session log", index))]
+    DecodeLogError {
+        index: usize,
+        source: crate::conversions::DecodeError,
+    },
+    #[snafu(display("invalid address in entry {} of session log", index))]
+    InvalidAddressError { index: usize },
+    #[snafu(display(
+        "invalid voucher value in entry {} of session log",
+        index
+    ))]
+    InvalidValueError { index: usize },
+    #[snafu(display("too many outputs in entry {} of session log", index))]
+    OutputsTreeError {
+        index: usize,
+        source: output_proofs::merkle_tree::Error,
+    },
+    #[snafu(display("controller dropped request {} during replay", index))]
+    ReplayDroppedError { index: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedRequest {
+    AdvanceState {
+        msg_sender: String,
+        epoch_index: u64,
+        input_index: u64,
+        block_number: u64,
+        timestamp: u64,
+        payload: String,
+    },
+    InspectState {
+        payload: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedStatus {
+    Accepted,
+    Rejected,
+    Exception,
+    MachineHalted,
+    CycleLimitExceeded,
+    TimeLimitExceeded,
+    PayloadLengthLimitExceeded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedVoucher {
+    pub destination: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_delegate_call: bool,
+    pub payload: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResult {
+    pub status: RecordedStatus,
+    pub vouchers: Vec<RecordedVoucher>,
+    pub notices: Vec<String>,
+    pub reports: Vec<String>,
+    pub exception: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionLogEntry {
+    pub request: RecordedRequest,
+    pub result: RecordedResult,
+}
+
+impl From<&AdvanceStateRequest> for RecordedRequest {
+    fn from(request: &AdvanceStateRequest) -> Self {
+        let metadata = &request.metadata;
+        Self::AdvanceState {
+            msg_sender: encode_ethereum_binary(&metadata.msg_sender),
+            epoch_index: metadata.epoch_index,
+            input_index: metadata.input_index,
+            block_number: metadata.block_number,
+            timestamp: metadata.timestamp,
+            payload: encode_ethereum_binary(&request.payload),
+        }
+    }
+}
+
+impl From<&InspectStateRequest> for RecordedRequest {
+    fn from(request: &InspectStateRequest) -> Self {
+        Self::InspectState {
+            payload: encode_ethereum_binary(&request.payload),
+        }
+    }
+}
+
+impl RecordedRequest {
+    /// Decodes the request of the entry `index` of the session log.
+    pub fn decode(&self, index: usize) -> Result<RollupRequest, RecorderError> {
+        let decode = |s: &str| {
+            decode_ethereum_binary(s).context(DecodeLogSnafu { index })
+        };
+        match self {
+            Self::AdvanceState {
+                msg_sender,
+                epoch_index,
+                input_index,
+                block_number,
+                timestamp,
+                payload,
+            } => {
+                let msg_sender =
+                    decode(msg_sender)?.try_into().map_err(|_| {
+                        RecorderError::InvalidAddressError { index }
+                    })?;
+                Ok(RollupRequest::AdvanceState(AdvanceStateRequest {
+                    metadata: AdvanceMetadata {
+                        msg_sender,
+                        epoch_index: *epoch_index,
+                        input_index: *input_index,
+                        block_number: *block_number,
+                        timestamp: *timestamp,
+                    },
+                    payload: decode(payload)?,
+                }))
+            }
+            Self::InspectState { payload } => {
+                Ok(RollupRequest::InspectState(InspectStateRequest {
+                    payload: decode(payload)?,
+                }))
+            }
+        }
+    }
+}
+
+impl From<&AdvanceResult> for RecordedResult {
+    fn from(result: &AdvanceResult) -> Self {
+        let reports = encode_reports(&result.reports);
+        match &result.status {
+            CompletionStatus::Accepted { vouchers, notices } => Self {
+                status: RecordedStatus::Accepted,
+                vouchers: vouchers
+                    .iter()
+                    .map(|voucher| RecordedVoucher {
+                        destination: encode_ethereum_binary(
+                            &voucher.destination,
+                        ),
+                        value: match &voucher.kind {
+                            VoucherKind::Call { value } => {
+                                Some(encode_ethereum_binary(value))
+                            }
+                            _ => None,
+                        },
+                        is_delegate_call: voucher.kind
+                            == VoucherKind::DelegateCall,
+                        payload: encode_ethereum_binary(&voucher.payload),
+                    })
+                    .collect(),
+                notices: notices
+                    .iter()
+                    .map(|notice| encode_ethereum_binary(&notice.payload))
+                    .collect(),
+                reports,
+                exception: None,
+            },
+            CompletionStatus::Rejected => {
+                Self::new(RecordedStatus::Rejected, reports, None)
+            }
+            CompletionStatus::Exception { exception } => {
+                Self::new(RecordedStatus::Exception, reports, Some(exception))
+            }
+            CompletionStatus::MachineHalted => {
+                Self::new(RecordedStatus::MachineHalted, reports, None)
+            }
+            CompletionStatus::CycleLimitExceeded => {
+                Self::new(RecordedStatus::CycleLimitExceeded, reports, None)
+            }
+            CompletionStatus::TimeLimitExceeded => {
+                Self::new(RecordedStatus::TimeLimitExceeded, reports, None)
+            }
+            CompletionStatus::PayloadLengthLimitExceeded => Self::new(
+                RecordedStatus::PayloadLengthLimitExceeded,
+                reports,
+                None,
+            ),
+        }
+    }
+}
+
+impl From<&InspectResult> for RecordedResult {
+    fn from(result: &InspectResult) -> Self {
+        let reports = encode_reports(&result.reports);
+        match &result.status {
+            InspectStatus::Accepted => {
+                Self::new(RecordedStatus::Accepted, reports, None)
+            }
+            InspectStatus::Rejected => {
+                Self::new(RecordedStatus::Rejected, reports, None)
+            }
+            InspectStatus::Exception { exception } => {
+                Self::new(RecordedStatus::Exception, reports, Some(exception))
+            }
+        }
+    }
+}
+
+impl RecordedResult {
+    fn new(
+        status: RecordedStatus,
+        reports: Vec<String>,
+        exception: Option<&RollupException>,
+    ) -> Self {
+        Self {
+            status,
+            vouchers: vec![],
+            notices: vec![],
+            reports,
+            exception: exception.map(|e| encode_ethereum_binary(&e.payload)),
+        }
+    }
+
+    /// Decodes the result of the advance request in the entry `index`.
+    pub fn decode_advance(
+        &self,
+        index: usize,
+    ) -> Result<AdvanceResult, RecorderError> {
+        let decode = |s: &str| {
+            decode_ethereum_binary(s).context(DecodeLogSnafu { index })
+        };
+        let reports = self
+            .reports
+            .iter()
+            .map(|payload| {
+                Ok(Report {
+                    payload: decode(payload)?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let result = match self.status {
+            RecordedStatus::Accepted => {
+                let vouchers = self
+                    .vouchers
+                    .iter()
+                    .map(|voucher| {
+                        let destination = decode(&voucher.destination)?
+                            .try_into()
+                            .map_err(|_| {
+                                RecorderError::InvalidAddressError { index }
+                            })?;
+                        let kind = match &voucher.value {
+                            _ if voucher.is_delegate_call => {
+                                VoucherKind::DelegateCall
+                            }
+                            Some(value) => VoucherKind::Call {
+                                value: decode(value)?.try_into().map_err(
+                                    |_| RecorderError::InvalidValueError {
+                                        index,
+                                    },
+                                )?,
+                            },
+                            None => VoucherKind::Legacy,
+                        };
+                        Ok(Voucher::with_kind(
+                            destination,
+                            kind,
+                            decode(&voucher.payload)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let notices = self
+                    .notices
+                    .iter()
+                    .map(|payload| Ok(Notice::new(decode(payload)?)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let outputs = AdvanceOutputs::new(vouchers, notices)
+                    .context(OutputsTreeSnafu { index })?;
+                AdvanceResult::accepted(outputs, reports)
+            }
+            RecordedStatus::Rejected => AdvanceResult::rejected(reports),
+            RecordedStatus::Exception => {
+                let payload = self.exception.as_deref().unwrap_or("0x");
+                let exception = RollupException {
+                    payload: decode(payload)?,
+                };
+                AdvanceResult::exception(exception, reports)
+            }
+            RecordedStatus::MachineHalted => AdvanceResult::limit_exceeded(
+                CompletionStatus::MachineHalted,
+                reports,
+            ),
+            RecordedStatus::CycleLimitExceeded => {
+                AdvanceResult::limit_exceeded(
+                    CompletionStatus::CycleLimitExceeded,
+                    reports,
+                )
+            }
+            RecordedStatus::TimeLimitExceeded => AdvanceResult::limit_exceeded(
+                CompletionStatus::TimeLimitExceeded,
+                reports,
+            ),
+            RecordedStatus::PayloadLengthLimitExceeded => {
+                AdvanceResult::limit_exceeded(
+                    CompletionStatus::PayloadLengthLimitExceeded,
+                    reports,
+                )
+            }
+        };
+        Ok(result)
+    }
+
+    /// Describes each difference between the expected and the actual result.
+    pub fn diff(&self, actual: &RecordedResult) -> Vec<String> {
+        let mut diffs = vec![];
+        if self.status != actual.status {
+            diffs.push(format!(
+                "status: expected {:?}, got {:?}",
+                self.status, actual.status
+            ));
+        }
+        if self.exception != actual.exception {
+            diffs.push(format!(
+                "exception: expected {:?}, got {:?}",
+                self.exception, actual.exception
+            ));
+        }
+        diff_outputs("voucher", &self.vouchers, &actual.vouchers, &mut diffs);
+        diff_outputs("notice", &self.notices, &actual.notices, &mut diffs);
+        diff_outputs("report", &self.reports, &actual.reports, &mut diffs);
+        diffs
+    }
+}
+
+fn encode_reports(reports: &[Report]) -> Vec<String> {
+    reports
+        .iter()
+        .map(|report| encode_ethereum_binary(&report.payload))
+        .collect()
+}
+
+fn diff_outputs<T: PartialEq + std::fmt::Debug>(
+    name: &str,
+    expected: &[T],
+    actual: &[T],
+    diffs: &mut Vec<String>,
+) {
+    if expected.len() != actual.len() {
+        diffs.push(format!(
+            "{} count: expected {}, got {}",
+            name,
+            expected.len(),
+            actual.len()
+        ));
+    }
+    for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        if expected != actual {
+            diffs.push(format!(
+                "{} {}: expected {:?}, got {:?}",
+                name, i, expected, actual
+            ));
+        }
+    }
+}
+
+/// Writes the requests handled by the controller to the session log.
+#[derive(Debug)]
+pub struct Recorder {
+    entry_tx: mpsc::Sender<SessionLogEntry>,
+    closed_rx: oneshot::Receiver<Result<(), RecorderError>>,
+}
+
+impl Recorder {
+    /// Creates the session log, truncating any previous one, and starts the
+    /// thread that writes to it.
+    pub fn create(path: &Path) -> Result<Self, RecorderError> {
+        let file = File::create(path).context(OpenLogSnafu { path })?;
+        let (entry_tx, entry_rx) = mpsc::channel();
+        let (closed_tx, closed_rx) = oneshot::channel();
+        std::thread::Builder::new()
+            .name("session-log-writer".into())
+            .spawn(move || {
+                let result = write_entries(BufWriter::new(file), entry_rx);
+                if let Err(e) = &result {
+                    tracing::error!("failed to write session log ({})", e);
+                }
+                let _ = closed_tx.send(result);
+            })
+            .context(SpawnWriterSnafu)?;
+        Ok(Self {
+            entry_tx,
+            closed_rx,
+        })
+    }
+
+    /// Queues the entry to be written; it doesn't wait for the disk.
+    pub fn record(
+        &self,
+        request: RecordedRequest,
+        result: RecordedResult,
+    ) -> Result<(), RecorderError> {
+        self.entry_tx
+            .send(SessionLogEntry { request, result })
+            .map_err(|_| RecorderError::WriterStoppedError)
+    }
+
+    /// Waits until the queued entries are written to the session log.
+    pub async fn close(self) -> Result<(), RecorderError> {
+        drop(self.entry_tx);
+        self.closed_rx
+            .await
+            .unwrap_or(Err(RecorderError::WriterStoppedError))
+    }
+}
+
+fn write_entries(
+    mut writer: BufWriter<File>,
+    entry_rx: mpsc::Receiver<SessionLogEntry>,
+) -> Result<(), RecorderError> {
+    for entry in entry_rx {
+        let line = serde_json::to_string(&entry)
+            .expect("failed to serialize session log entry");
+        writeln!(writer, "{}", line).context(WriteLogSnafu)?;
+        // Flush every entry, so the log is complete if the process crashes
+        writer.flush().context(WriteLogSnafu)?;
+    }
+    Ok(())
+}
+
+pub fn read_session_log(
+    path: &Path,
+) -> Result<Vec<SessionLogEntry>, RecorderError> {
+    let file = File::open(path).context(OpenLogSnafu { path })?;
+    let mut entries = vec![];
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.context(ReadLogSnafu)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry =
+            serde_json::from_str(&line).context(ParseLogSnafu { index })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayMismatch {
+    pub index: usize,
+    pub request: RecordedRequest,
+    pub diffs: Vec<String>,
+}
+
+/// Feeds the requests of the session log to the DApp through the controller
+/// and returns the requests whose outputs differ from the recorded ones.
+pub async fn replay(
+    controller: &Controller,
+    entries: Vec<SessionLogEntry>,
+) -> Result<Vec<ReplayMismatch>, RecorderError> {
+    let mut mismatches = vec![];
+    for (index, entry) in entries.into_iter().enumerate() {
+        tracing::info!("replaying request {} ({:?})", index, entry.request);
+        let result = match entry.request.decode(index)? {
+            RollupRequest::AdvanceState(request) => {
+                let rx = controller.advance(request).await;
+                let result = rx
+                    .await
+                    .map_err(|_| RecorderError::ReplayDroppedError { index })?;
+                RecordedResult::from(&result)
+            }
+            RollupRequest::InspectState(request) => {
+                let rx = controller.inspect(request).await;
+                let result = rx
+                    .await
+                    .map_err(|_| RecorderError::ReplayDroppedError { index })?;
+                RecordedResult::from(&result)
+            }
+        };
+
+        let diffs = entry.result.diff(&result);
+        if !diffs.is_empty() {
+            tracing::warn!(?diffs, "request {} diverged from the log", index);
+            mismatches.push(ReplayMismatch {
+                index,
+                request: entry.request,
+                diffs,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::ResourceLimits;
+    use std::time::Duration;
+
+    const TEST_FINISH_TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[tokio::test]
+    async fn test_it_reads_recorded_session_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let entries = vec![mock_advance_entry(), mock_inspect_entry()];
+        let recorder = Recorder::create(&path).unwrap();
+        for entry in entries.iter().cloned() {
+            recorder.record(entry.request, entry.result).unwrap();
+        }
+        recorder.close().await.unwrap();
+        assert_eq!(read_session_log(&path).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_it_fails_to_read_invalid_session_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, "{}\n").unwrap();
+        let err = read_session_log(&path).unwrap_err();
+        assert_eq!(err.to_string(), "failed to parse entry 0 of session log");
+    }
+
+    #[test]
+    fn test_it_decodes_recorded_result_with_voucher_kinds() {
+        let outputs = AdvanceOutputs::new(
+            vec![
+                Voucher::new([1; 20], vec![2]),
+                Voucher::with_kind(
+                    [1; 20],
+                    VoucherKind::Call { value: [3; 32] },
+                    vec![2],
+                ),
+                Voucher::with_kind([1; 20], VoucherKind::DelegateCall, vec![2]),
+            ],
+            vec![],
+        )
+        .unwrap();
+        let result = AdvanceResult::accepted(outputs, vec![]);
+        let recorded = RecordedResult::from(&result);
+        assert_eq!(recorded.vouchers[0].value, None);
+        assert!(recorded.vouchers[2].is_delegate_call);
+        assert_eq!(recorded.decode_advance(0).unwrap(), result);
+    }
+
+    #[test]
+    fn test_it_decodes_recorded_request() {
+        let request = mock_advance_entry().request;
+        let decoded = request.decode(0).unwrap();
+        match &decoded {
+            RollupRequest::AdvanceState(advance) => {
+                assert_eq!(advance.metadata.msg_sender, [0xaa; 20]);
+                assert_eq!(advance.metadata.input_index, 1);
+                assert_eq!(advance.payload, vec![0x01, 0x02]);
+                assert_eq!(RecordedRequest::from(advance), request);
+            }
+            _ => panic!("expected advance request"),
+        }
+    }
+
+    #[test]
+    fn test_it_fails_to_decode_invalid_address() {
+        let request = RecordedRequest::AdvanceState {
+            msg_sender: String::from("0xaa"),
+            epoch_index: 0,
+            input_index: 0,
+            block_number: 0,
+            timestamp: 0,
+            payload: String::from("0x"),
+        };
+        let err = request.decode(3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid address in entry 3 of session log"
+        );
+    }
+
+    #[test]
+    fn test_it_diffs_results() {
+        let expected = mock_advance_entry().result;
+        assert!(expected.diff(&expected).is_empty());
+        let mut actual = expected.clone();
+        actual.notices.push(String::from("0x03"));
+        actual.reports[0] = String::from("0x04");
+        assert_eq!(
+            expected.diff(&actual),
+            vec![
+                String::from("notice count: expected 1, got 2"),
+                String::from("report 0: expected \"0x05\", got \"0x04\""),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_replays_session_log() {
+        let controller = Controller::new(
+            TEST_FINISH_TIMEOUT,
+            ResourceLimits::default(),
+            None,
+        );
+        let entries = vec![mock_advance_entry(), mock_inspect_entry()];
+        let dapp = {
+            let controller = controller.clone();
+            tokio::spawn(async move {
+                // Advance: emit the recorded notice and a different report
+                fetch_request(&controller, FinishStatus::Accept).await;
+                let notice = Notice::new(vec![0x03]);
+                let _ = controller.insert_notice(notice).await.await;
+                let report = Report {
+                    payload: vec![0x06],
+                };
+                let _ = controller.insert_report(report).await.await;
+                // Inspect: reject it
+                fetch_request(&controller, FinishStatus::Accept).await;
+                let _ = controller.finish(FinishStatus::Reject).await;
+            })
+        };
+        let mismatches = replay(&controller, entries).await.unwrap();
+        dapp.await.unwrap();
+        controller.shutdown().await.await.unwrap();
+        assert_eq!(
+            mismatches,
+            vec![ReplayMismatch {
+                index: 0,
+                request: mock_advance_entry().request,
+                diffs: vec![String::from(
+                    "report 0: expected \"0x05\", got \"0x06\""
+                )],
+            }]
+        );
+    }
+
+    /// Finishes the current request like a DApp, retrying on timeouts until
+    /// the next request arrives.
+    async fn fetch_request(
+        controller: &Controller,
+        status: FinishStatus,
+    ) -> RollupRequest {
+        loop {
+            let rx = controller.finish(status.clone()).await;
+            if let Ok(request) = rx.await.unwrap() {
+                return request;
+            }
+        }
+    }
+
+    fn mock_advance_entry() -> SessionLogEntry {
+        SessionLogEntry {
+            request: RecordedRequest::AdvanceState {
+                msg_sender: encode_ethereum_binary(&[0xaa; 20]),
+                epoch_index: 0,
+                input_index: 1,
+                block_number: 2,
+                timestamp: 3,
+                payload: String::from("0x0102"),
+            },
+            result: RecordedResult {
+                status: RecordedStatus::Accepted,
+                vouchers: vec![],
+                notices: vec![String::from("0x03")],
+                reports: vec![String::from("0x05")],
+                exception: None,
+            },
+        }
+    }
+
+    fn mock_inspect_entry() -> SessionLogEntry {
+        SessionLogEntry {
+            request: RecordedRequest::InspectState {
+                payload: String::from("0x"),
+            },
+            result: RecordedResult {
+                status: RecordedStatus::Rejected,
+                vouchers: vec![],
+                notices: vec![],
+                reports: vec![],
+                exception: None,
+            },
+        }
+    }
+}
@@ -0,0 +1,294 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Binding between the server-manager sessions and the controllers that
+//! talk to the DApps.
+//!
+//! Each session has its own controller, and its DApp reaches it through the
+//! rollup HTTP server at `/sessions/<session_id>/...`, or at the root of its
+//! own port when the session is bound to one. The root paths of the rollup
+//! HTTP server belong to the first session started without its own port, so
+//! a single DApp works as before.
+//!
+//! The controller of a session exists while the session is running; the
+//! controllers of the sessions bound to their own port live as long as the
+//! host-runner, because their HTTP servers hold them.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::controller::Controller;
+use crate::limits::ResourceLimits;
+
+#[derive(Clone, Debug)]
+pub struct SessionControllers {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    finish_timeout: Duration,
+    limits: ResourceLimits,
+    root: Controller,
+    session_ports: Vec<(String, u16)>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    root_session: Option<String>,
+    controllers: HashMap<String, Controller>,
+}
+
+impl SessionControllers {
+    pub fn new(
+        root: Controller,
+        finish_timeout: Duration,
+        limits: ResourceLimits,
+        session_ports: Vec<(String, u16)>,
+    ) -> Self {
+        let controllers = session_ports
+            .iter()
+            .map(|(session_id, _)| {
+                let controller =
+                    Controller::new(finish_timeout, limits.clone(), None);
+                (session_id.clone(), controller)
+            })
+            .collect();
+        Self {
+            inner: Arc::new(Inner {
+                finish_timeout,
+                limits,
+                root,
+                session_ports,
+                state: Mutex::new(State {
+                    root_session: None,
+                    controllers,
+                }),
+            }),
+        }
+    }
+
+    /// Controller behind the root paths of the rollup HTTP server.
+    pub fn root(&self) -> Controller {
+        self.inner.root.clone()
+    }
+
+    /// Controllers of the sessions bound to their own rollup HTTP port.
+    pub fn port_bindings(&self) -> Vec<(u16, Controller)> {
+        let state = self.inner.state.lock().unwrap();
+        self.inner
+            .session_ports
+            .iter()
+            .filter_map(|(session_id, port)| {
+                let controller = state.controllers.get(session_id)?;
+                Some((*port, controller.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns the controller of a running session, or of a session bound
+    /// to its own port; `None` for unknown sessions.
+    pub fn get(&self, session_id: &str) -> Option<Controller> {
+        let state = self.inner.state.lock().unwrap();
+        state.controllers.get(session_id).cloned()
+    }
+
+    /// Returns the controller of a starting session; the first session
+    /// without a controller of its own takes the root controller.
+    pub fn acquire(&self, session_id: &str) -> Controller {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.root_session.is_none()
+            && !state.controllers.contains_key(session_id)
+        {
+            tracing::info!("binding session {} to the root paths", session_id);
+            state.root_session = Some(session_id.to_owned());
+            state
+                .controllers
+                .insert(session_id.to_owned(), self.inner.root.clone());
+        }
+        state
+            .controllers
+            .entry(session_id.to_owned())
+            .or_insert_with(|| {
+                Controller::new(
+                    self.inner.finish_timeout,
+                    self.inner.limits.clone(),
+                    None,
+                )
+            })
+            .clone()
+    }
+
+    /// Unbinds the ending session. The root controller is freed for the
+    /// next session, and the controller created for the session is shut
+    /// down; the controllers bound to a port are kept.
+    pub async fn release(&self, session_id: &str) {
+        let controller =
+            {
+                let mut state = self.inner.state.lock().unwrap();
+                if state.root_session.as_deref() == Some(session_id) {
+                    state.root_session = None;
+                    state.controllers.remove(session_id);
+                    return;
+                }
+                let port_bound = self.inner.session_ports.iter().any(
+                    |(bound_session_id, _)| bound_session_id == session_id,
+                );
+                if port_bound {
+                    return;
+                }
+                match state.controllers.remove(session_id) {
+                    Some(controller) => controller,
+                    None => return,
+                }
+            };
+        tracing::info!("shutting down controller of session {}", session_id);
+        if let Err(e) = controller.shutdown().await.await {
+            tracing::error!("failed to shutdown controller ({})", e);
+        }
+    }
+
+    pub async fn shutdown(&self) {
+        let controllers: Vec<_> = {
+            let state = self.inner.state.lock().unwrap();
+            state
+                .controllers
+                .iter()
+                .filter(|(session_id, _)| {
+                    state.root_session.as_ref() != Some(*session_id)
+                })
+                .map(|(_, controller)| controller.clone())
+                .chain(std::iter::once(self.inner.root.clone()))
+                .collect()
+        };
+        for controller in controllers {
+            if let Err(e) = controller.shutdown().await.await {
+                tracing::error!("failed to shutdown controller ({})", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FinishStatus, InspectStateRequest, RollupRequest};
+
+    const TEST_FINISH_TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn setup(session_ports: Vec<(String, u16)>) -> SessionControllers {
+        let limits = ResourceLimits::default();
+        let root = Controller::new(TEST_FINISH_TIMEOUT, limits.clone(), None);
+        SessionControllers::new(
+            root,
+            TEST_FINISH_TIMEOUT,
+            limits,
+            session_ports,
+        )
+    }
+
+    fn root_session(sessions: &SessionControllers) -> Option<String> {
+        sessions.inner.state.lock().unwrap().root_session.clone()
+    }
+
+    /// Checks whether both controllers share the same state machine by
+    /// sending an inspect through one and fetching it through the other.
+    async fn same_controller(a: &Controller, b: &Controller) -> bool {
+        let request = InspectStateRequest {
+            payload: rand::random::<[u8; 32]>().into(),
+        };
+        let _ = a.inspect(request.clone()).await;
+        let result = b.finish(FinishStatus::Accept).await.await.unwrap();
+        let same = result == Ok(RollupRequest::InspectState(request));
+        let owner = if same {
+            b
+        } else {
+            // Fetch the inspect from the controller that received it
+            let _ = a.finish(FinishStatus::Accept).await.await.unwrap();
+            a
+        };
+        // Finish the inspect and wait for the fetch of the next request to
+        // time out, so the state machine is ready again
+        let _ = owner.finish(FinishStatus::Accept).await.await;
+        same
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_binds_first_session_to_root() {
+        let sessions = setup(vec![]);
+        let first = sessions.acquire("first");
+        let second = sessions.acquire("second");
+        assert_eq!(root_session(&sessions), Some(String::from("first")));
+        assert!(same_controller(&first, &sessions.root()).await);
+        assert!(same_controller(&sessions.get("first").unwrap(), &first).await);
+        assert!(!same_controller(&second, &sessions.root()).await);
+        assert!(
+            same_controller(&sessions.get("second").unwrap(), &second).await
+        );
+        sessions.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_releases_root_session() {
+        let sessions = setup(vec![]);
+        sessions.acquire("first");
+        sessions.release("second").await;
+        assert_eq!(root_session(&sessions), Some(String::from("first")));
+        sessions.release("first").await;
+        assert_eq!(root_session(&sessions), None);
+        assert!(sessions.get("first").is_none());
+        let second = sessions.acquire("second");
+        assert_eq!(root_session(&sessions), Some(String::from("second")));
+        assert!(same_controller(&second, &sessions.root()).await);
+        sessions.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_keeps_port_bound_sessions_off_root() {
+        let sessions = setup(vec![(String::from("bound"), 5010)]);
+        let bound = sessions.acquire("bound");
+        assert_eq!(root_session(&sessions), None);
+        let bindings = sessions.port_bindings();
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0].0, 5010);
+        assert!(same_controller(&bound, &bindings[0].1).await);
+        assert!(!same_controller(&bound, &sessions.root()).await);
+        sessions.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_shuts_down_released_session_controller() {
+        let sessions = setup(vec![]);
+        sessions.acquire("first");
+        let second = sessions.acquire("second");
+        sessions.release("second").await;
+        assert!(sessions.get("second").is_none());
+        assert!(second.finish(FinishStatus::Accept).await.await.is_err());
+        sessions.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_keeps_port_bound_session_controller() {
+        let sessions = setup(vec![(String::from("bound"), 5010)]);
+        let bound = sessions.acquire("bound");
+        sessions.release("bound").await;
+        let kept = sessions.get("bound").unwrap();
+        assert!(same_controller(&bound, &kept).await);
+        sessions.shutdown().await;
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_it_does_not_find_unknown_session() {
+        let sessions = setup(vec![]);
+        assert!(sessions.get("unknown").is_none());
+        sessions.shutdown().await;
+    }
+}
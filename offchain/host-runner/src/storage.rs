@@ -0,0 +1,204 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Persistence of the server-manager session state.
+//!
+//! The server-manager stores a machine snapshot in the `storage_directory` of
+//! the finish epoch request, and loads it back from the `machine_directory` of
+//! the start session request. Host mode has no machine to store, so the
+//! host-runner writes its epoch state to that directory instead. A restarted
+//! host-runner then resumes the session from the epoch where it was stored.
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::model::AdvanceResult;
+use crate::recorder::{RecordedResult, RecorderError};
+
+/// Name of the file with the session state inside the storage directory
+pub const SESSION_STATE_FILE: &str = "host-runner-session.json";
+
+#[derive(Debug, Snafu)]
+pub enum StorageError {
+    #[snafu(display("failed to create storage directory ({})", path.display()))]
+    CreateDirectoryError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("failed to write session state ({})", path.display()))]
+    WriteStateError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("failed to read session state ({})", path.display()))]
+    ReadStateError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("failed to parse session state ({})", path.display()))]
+    ParseStateError {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[snafu(display("failed to decode session state ({})", path.display()))]
+    DecodeStateError {
+        path: PathBuf,
+        source: RecorderError,
+    },
+}
+
+/// Session state right after an epoch is finished.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredSession {
+    /// Index of the epoch that was opened when the previous one finished
+    pub active_epoch_index: u64,
+    /// Number of inputs processed before the active epoch
+    pub processed_input_count: u64,
+    /// Finished epochs that were not deleted yet
+    pub epochs: Vec<StoredEpoch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredEpoch {
+    pub epoch_index: u64,
+    pub processed_input_count_since_genesis: u64,
+    pub processed_inputs: Vec<RecordedResult>,
+}
+
+/// Stored session with the processed inputs decoded back into results, whose
+/// proofs must be computed again.
+#[derive(Debug)]
+pub struct RestoredSession {
+    pub active_epoch_index: u64,
+    pub processed_input_count: u64,
+    pub epochs: Vec<RestoredEpoch>,
+}
+
+#[derive(Debug)]
+pub struct RestoredEpoch {
+    pub epoch_index: u64,
+    pub processed_input_count_since_genesis: u64,
+    pub processed_inputs: Vec<AdvanceResult>,
+}
+
+/// Writes the session state to the storage directory, creating it if needed.
+pub fn save(
+    directory: &Path,
+    session: &StoredSession,
+) -> Result<(), StorageError> {
+    fs::create_dir_all(directory)
+        .context(CreateDirectorySnafu { path: directory })?;
+    let path = directory.join(SESSION_STATE_FILE);
+    let contents = serde_json::to_string(session)
+        .expect("failed to serialize session state");
+    // Write to a temporary file first, so a crash never leaves a partial state
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)
+        .and_then(|_| fs::rename(&tmp_path, &path))
+        .context(WriteStateSnafu { path })
+}
+
+/// Reads the session state from the machine directory; returns `None` when
+/// the directory has no state stored by the host-runner.
+pub fn load(directory: &Path) -> Result<Option<RestoredSession>, StorageError> {
+    let path = directory.join(SESSION_STATE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents =
+        fs::read_to_string(&path).context(ReadStateSnafu { path: &path })?;
+    let session: StoredSession = serde_json::from_str(&contents)
+        .context(ParseStateSnafu { path: &path })?;
+    let epochs = session
+        .epochs
+        .iter()
+        .map(|epoch| {
+            let processed_inputs = epoch
+                .processed_inputs
+                .iter()
+                .enumerate()
+                .map(|(index, result)| result.decode_advance(index))
+                .collect::<Result<Vec<_>, _>>()
+                .context(DecodeStateSnafu { path: &path })?;
+            Ok(RestoredEpoch {
+                epoch_index: epoch.epoch_index,
+                processed_input_count_since_genesis: epoch
+                    .processed_input_count_since_genesis,
+                processed_inputs,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(RestoredSession {
+        active_epoch_index: session.active_epoch_index,
+        processed_input_count: session.processed_input_count,
+        epochs,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{AdvanceOutputs, Notice, Report, Voucher};
+
+    fn mock_accepted_result() -> AdvanceResult {
+        let outputs = AdvanceOutputs::new(
+            vec![Voucher::new([1; 20], vec![2, 3])],
+            vec![Notice::new(vec![4])],
+        )
+        .unwrap();
+        AdvanceResult::accepted(outputs, vec![Report { payload: vec![5] }])
+    }
+
+    fn mock_session() -> StoredSession {
+        let result = mock_accepted_result();
+        StoredSession {
+            active_epoch_index: 2,
+            processed_input_count: 3,
+            epochs: vec![StoredEpoch {
+                epoch_index: 1,
+                processed_input_count_since_genesis: 1,
+                processed_inputs: vec![
+                    RecordedResult::from(&result),
+                    RecordedResult::from(&AdvanceResult::rejected(vec![])),
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_it_saves_and_loads_session_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let directory = dir.path().join("epoch-1");
+        let session = mock_session();
+        save(&directory, &session).unwrap();
+        let restored = load(&directory).unwrap().unwrap();
+        assert_eq!(restored.active_epoch_index, 2);
+        assert_eq!(restored.processed_input_count, 3);
+        let epochs = restored.epochs;
+        assert_eq!(epochs.len(), 1);
+        assert_eq!(epochs[0].epoch_index, 1);
+        assert_eq!(epochs[0].processed_input_count_since_genesis, 1);
+        assert_eq!(
+            epochs[0].processed_inputs,
+            vec![mock_accepted_result(), AdvanceResult::rejected(vec![]),]
+        );
+    }
+
+    #[test]
+    fn test_it_loads_nothing_from_directory_without_state() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_it_fails_to_load_invalid_state() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(SESSION_STATE_FILE), "not json").unwrap();
+        assert!(matches!(
+            load(dir.path()),
+            Err(StorageError::ParseStateError { .. })
+        ));
+    }
+}
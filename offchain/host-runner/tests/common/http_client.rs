@@ -47,7 +47,16 @@ pub fn create_payload() -> String {
 }
 
 pub async fn finish(status: String) -> Result<RollupHttpRequest, HttpError> {
-    let url = format!("{}/finish", config::get_http_rollup_server_address());
+    finish_at(&config::get_http_rollup_server_address(), status).await
+}
+
+/// Sends the finish request to the rollup server endpoint at `address`,
+/// which may point to a session prefix or to a session port
+pub async fn finish_at(
+    address: &str,
+    status: String,
+) -> Result<RollupHttpRequest, HttpError> {
+    let url = format!("{}/finish", address);
     let mut request = HashMap::new();
     request.insert("status", status);
     let client = reqwest::Client::new();
@@ -68,6 +77,37 @@ pub async fn insert_voucher(
     handle_json_response(response).await
 }
 
+pub async fn insert_voucher_with_value(
+    destination: String,
+    value: String,
+    payload: String,
+) -> Result<IndexResponse, HttpError> {
+    let url = format!("{}/voucher", config::get_http_rollup_server_address());
+    let mut request = HashMap::new();
+    request.insert("destination", destination);
+    request.insert("value", value);
+    request.insert("payload", payload);
+    let client = reqwest::Client::new();
+    let response = client.post(url).json(&request).send().await.unwrap();
+    handle_json_response(response).await
+}
+
+pub async fn insert_delegate_call_voucher(
+    destination: String,
+    payload: String,
+) -> Result<IndexResponse, HttpError> {
+    let url = format!(
+        "{}/delegate-call-voucher",
+        config::get_http_rollup_server_address()
+    );
+    let mut request = HashMap::new();
+    request.insert("destination", destination);
+    request.insert("payload", payload);
+    let client = reqwest::Client::new();
+    let response = client.post(url).json(&request).send().await.unwrap();
+    handle_json_response(response).await
+}
+
 pub async fn insert_notice(
     payload: String,
 ) -> Result<IndexResponse, HttpError> {
@@ -97,6 +137,24 @@ pub async fn notify_exception(payload: String) -> Result<(), HttpError> {
     handle_response(response).await.map(|_| ())
 }
 
+/// Returns the status of the controller behind the rollup server
+pub async fn dashboard() -> serde_json::Value {
+    let url = format!("{}/dashboard", config::get_http_rollup_server_address());
+    let response = reqwest::get(url).await.unwrap();
+    handle_json_response(response).await.unwrap()
+}
+
+/// Subscribes to the stream of state transitions of the controller
+pub async fn dashboard_events() -> Response {
+    let url = format!(
+        "{}/dashboard/events",
+        config::get_http_rollup_server_address()
+    );
+    handle_response(reqwest::get(url).await.unwrap())
+        .await
+        .unwrap()
+}
+
 async fn handle_response(response: Response) -> Result<Response, HttpError> {
     if response.status() == reqwest::StatusCode::OK {
         Ok(response)
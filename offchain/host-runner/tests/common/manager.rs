@@ -14,6 +14,11 @@ pub struct Wrapper {
 impl Wrapper {
     /// Start the manager and waits until it is ready to answer
     pub async fn new() -> Self {
+        Self::new_with_args(&[]).await
+    }
+
+    /// Start the manager with extra command line arguments
+    pub async fn new_with_args(args: &[&str]) -> Self {
         let mut command = Command::new(config::get_host_runner_path());
         command
             .env("RUST_LOG", "host_runner=debug,info")
@@ -24,7 +29,8 @@ impl Wrapper {
             .arg("--http-rollup-server-port")
             .arg(config::HTTP_ROLLUP_SERVER_PORT.to_string())
             .arg("--finish-timeout")
-            .arg(config::FINISH_TIMEOUT.to_string());
+            .arg(config::FINISH_TIMEOUT.to_string())
+            .args(args);
         if !config::get_test_verbose() {
             command.stdout(Stdio::null()).stderr(Stdio::null());
         }
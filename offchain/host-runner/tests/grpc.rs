@@ -13,5 +13,8 @@ mod grpc_tests {
     mod get_status;
     mod get_version;
     mod inspect_state;
+    mod resource_limits;
+    mod session_storage;
+    mod sessions;
     mod start_session;
 }
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 use crate::common::{grpc_client::FinishEpochResponse, *};
+use output_proofs::{encoding, hash::Hash, validity};
 
 #[tokio::test]
 #[serial_test::serial]
@@ -305,6 +306,95 @@ async fn test_it_updates_input_index_after_finishing_epoch() {
     assert_eq!(epoch_status.processed_inputs[0].input_index, 1);
 }
 
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_finishes_epoch_with_proofs_that_validate() {
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    setup_advance_state(&mut grpc_client, "rollup session").await;
+    let destination = [0xfa; 20];
+    let destination_hex = String::from("0x") + &hex::encode(destination);
+    http_client::insert_voucher(destination_hex.clone(), "0xdeadbeef".into())
+        .await
+        .unwrap();
+    http_client::insert_voucher_with_value(
+        destination_hex,
+        "0x01".into(),
+        "0xbeef".into(),
+    )
+    .await
+    .unwrap();
+    http_client::insert_notice("0xdeadbeef".into())
+        .await
+        .unwrap();
+    finish_advance_state(&mut grpc_client, "rollup session").await;
+
+    let result = grpc_client
+        .finish_epoch(grpc_client::FinishEpochRequest {
+            session_id: "rollup session".into(),
+            active_epoch_index: 0,
+            processed_input_count_within_epoch: 1,
+            storage_directory: "".into(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let convert = |hash: Option<grpc_client::Hash>| -> Hash {
+        hash.unwrap().data.try_into().unwrap()
+    };
+    let epoch_hash = validity::compute_epoch_hash(
+        &convert(result.vouchers_epoch_root_hash),
+        &convert(result.notices_epoch_root_hash),
+        &convert(result.machine_hash),
+    );
+    let mut value = [0; 32];
+    value[31] = 1;
+    let outputs = [
+        (
+            validity::OutputKind::Voucher,
+            encoding::compute_voucher_hash(
+                &destination,
+                &[0xde, 0xad, 0xbe, 0xef],
+            ),
+        ),
+        (
+            validity::OutputKind::Voucher,
+            encoding::compute_output_hash(&encoding::encode_call_voucher(
+                &destination,
+                &value,
+                &[0xbe, 0xef],
+            )),
+        ),
+        (
+            validity::OutputKind::Notice,
+            encoding::compute_notice_hash(&[0xde, 0xad, 0xbe, 0xef]),
+        ),
+    ];
+    assert_eq!(result.proofs.len(), outputs.len());
+    for (proof, (kind, hash)) in result.proofs.into_iter().zip(outputs) {
+        let proof = proof.validity.unwrap();
+        let convert_all = |hashes: Vec<grpc_client::Hash>| -> Vec<Hash> {
+            hashes.into_iter().map(|hash| convert(Some(hash))).collect()
+        };
+        let proof = validity::OutputValidityProof {
+            input_index_within_epoch: proof.input_index_within_epoch,
+            output_index_within_input: proof.output_index_within_input,
+            output_hashes_root_hash: convert(proof.output_hashes_root_hash),
+            vouchers_epoch_root_hash: convert(proof.vouchers_epoch_root_hash),
+            notices_epoch_root_hash: convert(proof.notices_epoch_root_hash),
+            machine_state_hash: convert(proof.machine_state_hash),
+            output_hash_in_output_hashes_siblings: convert_all(
+                proof.output_hash_in_output_hashes_siblings,
+            ),
+            output_hashes_in_epoch_siblings: convert_all(
+                proof.output_hashes_in_epoch_siblings,
+            ),
+        };
+        validity::validate_output(kind, &hash, &proof, &epoch_hash).unwrap();
+    }
+}
+
 fn assert_eq_finish_epoch_response(
     lhs: FinishEpochResponse,
     rhs: FinishEpochResponse,
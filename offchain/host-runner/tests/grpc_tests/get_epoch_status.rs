@@ -82,6 +82,8 @@ async fn test_it_get_epoch_status_of_epoch_with_voucher_notice_and_report() {
                                 ],
                             }),
                             payload: vec![222, 173, 190, 239],
+                            kind: grpc_client::VoucherKind::Legacy as i32,
+                            value: None,
                         }],
                         notices: vec![grpc_client::Notice {
                             payload: vec![222, 173, 190, 239],
@@ -96,6 +98,55 @@ async fn test_it_get_epoch_status_of_epoch_with_voucher_notice_and_report() {
     assert_eq!(response, expected);
 }
 
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_get_epoch_status_with_voucher_kinds() {
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    setup_advance_state(&mut grpc_client, "rollup session").await;
+    let destination = String::from("0x") + &"fa".repeat(20);
+    http_client::insert_voucher_with_value(
+        destination.clone(),
+        "0x01".into(),
+        "0xbeef".into(),
+    )
+    .await
+    .unwrap();
+    http_client::insert_delegate_call_voucher(destination, "0xbeef".into())
+        .await
+        .unwrap();
+    finish_advance_state(&mut grpc_client, "rollup session").await;
+    let response = grpc_client
+        .get_epoch_status(grpc_client::GetEpochStatusRequest {
+            session_id: "rollup session".into(),
+            epoch_index: 0,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let vouchers =
+        match response.processed_inputs[0].processed_input_one_of.clone() {
+            Some(
+                grpc_client::processed_input::ProcessedInputOneOf::AcceptedData(
+                    data,
+                ),
+            ) => data.vouchers,
+            _ => panic!("expected accepted input"),
+        };
+    let mut value = vec![0; 32];
+    value[31] = 1;
+    assert_eq!(vouchers.len(), 2);
+    assert_eq!(vouchers[0].kind, grpc_client::VoucherKind::Call as i32);
+    assert_eq!(vouchers[0].value, Some(grpc_client::Hash { data: value }));
+    assert_eq!(vouchers[0].payload, vec![0xbe, 0xef]);
+    assert_eq!(
+        vouchers[1].kind,
+        grpc_client::VoucherKind::DelegateCall as i32
+    );
+    assert_eq!(vouchers[1].value, None);
+    assert_eq!(vouchers[1].payload, vec![0xbe, 0xef]);
+}
+
 #[tokio::test]
 #[serial_test::serial]
 async fn test_it_fails_to_get_non_existent_epoch_status() {
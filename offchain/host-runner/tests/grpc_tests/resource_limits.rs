@@ -0,0 +1,113 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use crate::common::*;
+use std::time::Duration;
+
+async fn get_processed_input(
+    grpc_client: &mut grpc_client::ServerManagerClient,
+) -> Option<grpc_client::ProcessedInput> {
+    const RETRIES: i32 = 100;
+    for _ in 0..RETRIES {
+        let processed = grpc_client
+            .get_epoch_status(grpc_client::GetEpochStatusRequest {
+                session_id: "rollup session".into(),
+                epoch_index: 0,
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .processed_inputs
+            .pop();
+        if processed.is_some() {
+            return processed;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    None
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_skips_input_over_payload_length_limit() {
+    let _manager =
+        manager::Wrapper::new_with_args(&["--max-payload-length", "8"]).await;
+    let mut grpc_client = grpc_client::connect().await;
+    grpc_client
+        .start_session(grpc_client::create_start_session_request(
+            "rollup session",
+        ))
+        .await
+        .unwrap();
+    // The input is skipped once the DApp asks for it
+    let handle = tokio::spawn(http_client::finish("accept".into()));
+    grpc_client
+        .advance_state(grpc_client::create_advance_state_request(
+            "rollup session",
+            0,
+            0,
+        ))
+        .await
+        .unwrap();
+    let processed = get_processed_input(&mut grpc_client).await.unwrap();
+    assert_eq!(
+        processed,
+        grpc_client::ProcessedInput {
+            input_index: 0,
+            status: grpc_client::CompletionStatus::PayloadLengthLimitExceeded
+                as i32,
+            processed_input_one_of: None,
+            reports: vec![],
+        }
+    );
+    handle
+        .await
+        .expect("tokio spawn failed")
+        .expect_err("finish should return error");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_ends_input_after_time_limit() {
+    let _manager =
+        manager::Wrapper::new_with_args(&["--input-time-limit", "500"]).await;
+    let mut grpc_client = grpc_client::connect().await;
+    setup_advance_state(&mut grpc_client, "rollup session").await;
+    http_client::insert_report("0xdeadbeef".into())
+        .await
+        .unwrap();
+    let processed = get_processed_input(&mut grpc_client).await.unwrap();
+    assert_eq!(
+        processed,
+        grpc_client::ProcessedInput {
+            input_index: 0,
+            status: grpc_client::CompletionStatus::TimeLimitExceeded as i32,
+            processed_input_one_of: None,
+            reports: vec![grpc_client::Report {
+                payload: vec![222, 173, 190, 239],
+            }],
+        }
+    );
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_halts_machine_over_output_limits() {
+    let _manager =
+        manager::Wrapper::new_with_args(&["--max-output-size", "2"]).await;
+    let mut grpc_client = grpc_client::connect().await;
+    setup_advance_state(&mut grpc_client, "rollup session").await;
+    http_client::insert_notice("0xdeadbeef".into())
+        .await
+        .expect_err("notice should exceed the output limits");
+    let processed = get_processed_input(&mut grpc_client).await.unwrap();
+    assert_eq!(
+        processed,
+        grpc_client::ProcessedInput {
+            input_index: 0,
+            status: grpc_client::CompletionStatus::MachineHalted as i32,
+            processed_input_one_of: None,
+            reports: vec![],
+        }
+    );
+}
@@ -0,0 +1,125 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use crate::common::*;
+
+fn create_restart_session_request(
+    machine_directory: &std::path::Path,
+    active_epoch_index: u64,
+    processed_input_count: u64,
+) -> grpc_client::StartSessionRequest {
+    grpc_client::StartSessionRequest {
+        machine_directory: machine_directory.to_string_lossy().into(),
+        active_epoch_index,
+        processed_input_count,
+        ..grpc_client::create_start_session_request("rollup session")
+    }
+}
+
+async fn get_epoch_status(
+    grpc_client: &mut grpc_client::ServerManagerClient,
+    epoch_index: u64,
+) -> grpc_client::GetEpochStatusResponse {
+    grpc_client
+        .get_epoch_status(grpc_client::GetEpochStatusRequest {
+            session_id: "rollup session".into(),
+            epoch_index,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_restores_session_after_restart() {
+    let storage = tempfile::tempdir().unwrap();
+    let storage_directory = storage.path().join("epoch-1");
+    let (finish_response, epoch_status) = {
+        let _manager = manager::Wrapper::new().await;
+        let mut grpc_client = grpc_client::connect().await;
+        setup_advance_state(&mut grpc_client, "rollup session").await;
+        http_client::insert_notice("0xdeadbeef".into())
+            .await
+            .unwrap();
+        finish_advance_state(&mut grpc_client, "rollup session")
+            .await
+            .unwrap();
+        let finish_response = grpc_client
+            .finish_epoch(grpc_client::FinishEpochRequest {
+                session_id: "rollup session".into(),
+                active_epoch_index: 0,
+                processed_input_count_within_epoch: 1,
+                storage_directory: storage_directory.to_string_lossy().into(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        (finish_response, get_epoch_status(&mut grpc_client, 0).await)
+    };
+
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    grpc_client
+        .start_session(create_restart_session_request(&storage_directory, 1, 1))
+        .await
+        .unwrap();
+    let session_status = grpc_client
+        .get_session_status(grpc_client::GetSessionStatusRequest {
+            session_id: "rollup session".into(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(session_status.active_epoch_index, 1);
+    assert_eq!(session_status.epoch_index, vec![0, 1]);
+    assert_eq!(get_epoch_status(&mut grpc_client, 0).await, epoch_status);
+    assert!(!finish_response.proofs.is_empty());
+    // The session continues from the stored epoch
+    grpc_client
+        .advance_state(grpc_client::create_advance_state_request(
+            "rollup session",
+            1,
+            1,
+        ))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_fails_to_restore_session_at_another_epoch() {
+    let storage = tempfile::tempdir().unwrap();
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    grpc_client
+        .start_session(grpc_client::create_start_session_request(
+            "rollup session",
+        ))
+        .await
+        .unwrap();
+    grpc_client
+        .finish_epoch(grpc_client::FinishEpochRequest {
+            session_id: "rollup session".into(),
+            active_epoch_index: 0,
+            processed_input_count_within_epoch: 0,
+            storage_directory: storage.path().to_string_lossy().into(),
+        })
+        .await
+        .unwrap();
+    grpc_client
+        .end_session(grpc_client::EndSessionRequest {
+            session_id: "rollup session".into(),
+        })
+        .await
+        .unwrap();
+    let err = grpc_client
+        .start_session(create_restart_session_request(storage.path(), 2, 0))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    grpc_client
+        .start_session(create_restart_session_request(storage.path(), 1, 0))
+        .await
+        .unwrap();
+}
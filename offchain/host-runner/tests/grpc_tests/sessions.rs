@@ -0,0 +1,127 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use crate::common::*;
+
+const SESSION_PORT: u16 = 50005;
+
+async fn start_sessions(
+    grpc_client: &mut grpc_client::ServerManagerClient,
+    session_ids: &[&str],
+) {
+    for session_id in session_ids {
+        grpc_client
+            .start_session(grpc_client::create_start_session_request(
+                session_id,
+            ))
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_advances_sessions_independently() {
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    start_sessions(&mut grpc_client, &["session-1", "session-2"]).await;
+    grpc_client
+        .advance_state(grpc_client::create_advance_state_request(
+            "session-2",
+            0,
+            0,
+        ))
+        .await
+        .unwrap();
+    // The root paths belong to the first session, which has no input
+    let err = http_client::finish("accept".into()).await.unwrap_err();
+    assert_eq!(err.status, 202);
+    // The second session is reachable through its path prefix
+    let address = format!(
+        "{}/sessions/session-2",
+        config::get_http_rollup_server_address()
+    );
+    let response = http_client::finish_at(&address, "accept".into())
+        .await
+        .unwrap();
+    assert!(matches!(
+        response,
+        http_client::RollupHttpRequest::Advance { .. }
+    ));
+    // The first session still accepts inputs
+    grpc_client
+        .advance_state(grpc_client::create_advance_state_request(
+            "session-1",
+            0,
+            0,
+        ))
+        .await
+        .unwrap();
+    let response = http_client::finish("accept".into()).await.unwrap();
+    assert!(matches!(
+        response,
+        http_client::RollupHttpRequest::Advance { .. }
+    ));
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_serves_session_bound_to_port() {
+    let port_binding = format!("bound={}", SESSION_PORT);
+    let _manager =
+        manager::Wrapper::new_with_args(&["--session-ports", &port_binding])
+            .await;
+    let mut grpc_client = grpc_client::connect().await;
+    start_sessions(&mut grpc_client, &["bound", "rollup session"]).await;
+    grpc_client
+        .advance_state(grpc_client::create_advance_state_request("bound", 0, 0))
+        .await
+        .unwrap();
+    let address = format!("http://127.0.0.1:{}", SESSION_PORT);
+    let response = http_client::finish_at(&address, "accept".into())
+        .await
+        .unwrap();
+    assert!(matches!(
+        response,
+        http_client::RollupHttpRequest::Advance { .. }
+    ));
+    // The root paths went to the first session without a port
+    grpc_client
+        .advance_state(grpc_client::create_advance_state_request(
+            "rollup session",
+            0,
+            0,
+        ))
+        .await
+        .unwrap();
+    let response = http_client::finish("accept".into()).await.unwrap();
+    assert!(matches!(
+        response,
+        http_client::RollupHttpRequest::Advance { .. }
+    ));
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_rejects_unknown_session_path() {
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    start_sessions(&mut grpc_client, &["session-1", "session-2"]).await;
+    grpc_client
+        .end_session(grpc_client::EndSessionRequest {
+            session_id: "session-2".into(),
+        })
+        .await
+        .unwrap();
+    for session_id in ["session-2", "unknown"] {
+        let address = format!(
+            "{}/sessions/{}",
+            config::get_http_rollup_server_address(),
+            session_id
+        );
+        let err = http_client::finish_at(&address, "accept".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, 404);
+    }
+}
@@ -40,21 +40,29 @@ async fn test_it_fails_to_start_session_with_same_name() {
 
 #[tokio::test]
 #[serial_test::serial]
-async fn test_it_fails_to_start_multiple_sessions_because_it_supports_only_one()
-{
+async fn test_it_starts_multiple_sessions() {
     let _manager = manager::Wrapper::new().await;
     let mut grpc_client = grpc_client::connect().await;
-    grpc_client
-        .start_session(grpc_client::create_start_session_request(
-            "rollup session 1",
-        ))
-        .await
-        .unwrap();
-    let err = grpc_client
-        .start_session(grpc_client::create_start_session_request(
-            "rollup session 2",
-        ))
+    for session_id in ["rollup session 1", "rollup session 2"] {
+        grpc_client
+            .start_session(grpc_client::create_start_session_request(
+                session_id,
+            ))
+            .await
+            .unwrap();
+    }
+    let response = grpc_client
+        .get_status(grpc_client::Void {})
         .await
-        .unwrap_err();
-    assert_eq!(err.code(), tonic::Code::AlreadyExists);
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        response,
+        grpc_client::GetStatusResponse {
+            session_id: vec![
+                String::from("rollup session 1"),
+                String::from("rollup session 2")
+            ]
+        }
+    );
 }
@@ -4,6 +4,7 @@
 mod common;
 
 mod http_tests {
+    mod dashboard;
     mod exception;
     mod finish;
     mod notice;
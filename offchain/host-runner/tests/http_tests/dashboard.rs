@@ -0,0 +1,90 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use crate::common::*;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Polls the dashboard until the controller status matches the predicate
+async fn wait_dashboard(predicate: impl Fn(&Value) -> bool) -> Value {
+    for _ in 0..100 {
+        let status = http_client::dashboard().await;
+        if predicate(&status) {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("dashboard did not reach the expected status");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_shows_controller_status_in_dashboard() {
+    let _manager = manager::Wrapper::new().await;
+    let status = http_client::dashboard().await;
+    assert_eq!(status["state"], "idle");
+    assert_eq!(status["epoch"], Value::Null);
+    let mut grpc_client = grpc_client::connect().await;
+    grpc_client
+        .start_session(grpc_client::create_start_session_request(
+            "rollup session",
+        ))
+        .await
+        .unwrap();
+    grpc_client
+        .advance_state(grpc_client::create_advance_state_request(
+            "rollup session",
+            0,
+            0,
+        ))
+        .await
+        .unwrap();
+    // The advance waits for the DApp
+    let status = http_client::dashboard().await;
+    assert_eq!(status["pending_requests"][0]["type"], "advance_state");
+    assert_eq!(status["epoch"]["epoch_index"], 0);
+    assert_eq!(status["epoch"]["pending_input_count"], 1);
+    // The DApp fetches the advance
+    http_client::finish("accept".into()).await.unwrap();
+    let status = wait_dashboard(|status| status["state"] == "advance").await;
+    assert_eq!(status["pending_requests"], Value::Array(vec![]));
+    // The DApp emits a notice and accepts the advance
+    http_client::insert_notice(http_client::create_payload())
+        .await
+        .unwrap();
+    let _ = http_client::finish("accept".into()).await;
+    let status = wait_dashboard(|status| {
+        status["epoch"]["processed_inputs"]
+            .as_array()
+            .is_some_and(|inputs| inputs.len() == 1)
+    })
+    .await;
+    let processed_input = &status["epoch"]["processed_inputs"][0];
+    assert_eq!(processed_input["status"], "accepted");
+    assert_eq!(processed_input["notices"].as_array().unwrap().len(), 1);
+    assert_eq!(status["epoch"]["pending_input_count"], 0);
+    // The last finish timed out without new requests
+    let timeouts = status["recent_timeouts"].as_array().unwrap();
+    assert_eq!(timeouts[0]["state"], "fetch request");
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_streams_state_transitions() {
+    let _manager = manager::Wrapper::new().await;
+    let mut events = http_client::dashboard_events().await;
+    assert_eq!(
+        events.headers()["content-type"].to_str().unwrap(),
+        "text/event-stream"
+    );
+    tokio::spawn(http_client::finish("accept".into()));
+    let chunk = events.chunk().await.unwrap().unwrap();
+    let chunk = String::from_utf8(chunk.to_vec()).unwrap();
+    let data = chunk
+        .strip_prefix("event: transition\ndata: ")
+        .and_then(|data| data.strip_suffix("\n\n"))
+        .unwrap();
+    let transition: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(transition["from"], "idle");
+    assert_eq!(transition["to"], "fetch request");
+}
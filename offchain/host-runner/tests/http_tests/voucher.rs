@@ -35,6 +35,90 @@ async fn test_it_insert_voucher_during_advance_state() {
     }
 }
 
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_insert_vouchers_with_value_and_delegate_call() {
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    setup_advance_state(&mut grpc_client, "rollup session").await;
+    let destination = http_client::create_address();
+    let result =
+        http_client::insert_voucher(destination.clone(), "0xdeadbeef".into())
+            .await
+            .unwrap();
+    assert_eq!(result.index, 0);
+    let result = http_client::insert_voucher_with_value(
+        destination.clone(),
+        "0x01".into(),
+        "0xdeadbeef".into(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.index, 1);
+    let result = http_client::insert_delegate_call_voucher(
+        destination,
+        "0xdeadbeef".into(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.index, 2);
+    // The payload is kept as is; the kind and value go in their own fields
+    let processed = finish_advance_state(&mut grpc_client, "rollup session")
+        .await
+        .unwrap();
+    match processed.processed_input_one_of.unwrap() {
+        grpc_client::processed_input::ProcessedInputOneOf::AcceptedData(
+            result,
+        ) => {
+            let mut value = vec![0; 32];
+            value[31] = 1;
+            let vouchers = result.vouchers;
+            assert_eq!(
+                vouchers[0].kind,
+                grpc_client::VoucherKind::Legacy as i32
+            );
+            assert_eq!(vouchers[0].value, None);
+            assert_eq!(vouchers[1].kind, grpc_client::VoucherKind::Call as i32);
+            assert_eq!(
+                vouchers[1].value,
+                Some(grpc_client::Hash { data: value })
+            );
+            assert_eq!(
+                vouchers[2].kind,
+                grpc_client::VoucherKind::DelegateCall as i32
+            );
+            assert_eq!(vouchers[2].value, None);
+            for voucher in vouchers {
+                assert_eq!(voucher.payload, vec![0xde, 0xad, 0xbe, 0xef]);
+            }
+        }
+        grpc_client::processed_input::ProcessedInputOneOf::ExceptionData(_) => {
+            panic!("unexpected advance result");
+        }
+    }
+}
+
+#[tokio::test]
+#[serial_test::serial]
+async fn test_it_fails_to_insert_voucher_with_invalid_value() {
+    let _manager = manager::Wrapper::new().await;
+    let mut grpc_client = grpc_client::connect().await;
+    setup_advance_state(&mut grpc_client, "rollup session").await;
+    let response = http_client::insert_voucher_with_value(
+        http_client::create_address(),
+        String::from("0x") + &"01".repeat(33),
+        "0xdeadbeef".into(),
+    )
+    .await;
+    assert_eq!(
+        response,
+        Err(http_client::HttpError {
+            status: 400,
+            message: "Invalid voucher value size (got 33 bytes, expected up to 32 bytes)".into(),
+        })
+    );
+}
+
 #[tokio::test]
 #[serial_test::serial]
 async fn test_it_fails_to_insert_voucher_with_incorrect_data() {
@@ -11,6 +11,7 @@ pub use prometheus_client::registry::Registry;
 // Add any other metrics to re-export here.
 pub use prometheus_client::metrics::counter::Counter as CounterRef;
 pub use prometheus_client::metrics::family::Family as FamilyRef;
+pub use prometheus_client::metrics::gauge::Gauge as GaugeRef;
 // End of metrics to re-export.
 
 // Re-exporting hyper error.
@@ -7,12 +7,13 @@
 use std::time::{Duration, UNIX_EPOCH};
 
 use rollups_events::{
-    RollupsAdvanceStateInput, RollupsCompletionStatus, RollupsNotice,
-    RollupsOutputEnum, RollupsProof, RollupsReport, RollupsVoucher,
+    RollupsAdvanceStateInput, RollupsClaim, RollupsCompletionStatus,
+    RollupsNotice, RollupsOutputEnum, RollupsProof, RollupsRejectedInput,
+    RollupsReport, RollupsVoucher,
 };
 
 use rollups_data::{
-    CompletionStatus, Input, Notice, OutputEnum, Proof, Report, Voucher,
+    Claim, CompletionStatus, Input, Notice, OutputEnum, Proof, Report, Voucher,
 };
 
 pub fn convert_status(status: RollupsCompletionStatus) -> CompletionStatus {
@@ -35,7 +36,12 @@ pub fn convert_status(status: RollupsCompletionStatus) -> CompletionStatus {
     }
 }
 
-pub fn convert_input(input: RollupsAdvanceStateInput) -> Input {
+/// The epoch index comes from the rollups input because the input metadata
+/// does not have it
+pub fn convert_input(
+    input: RollupsAdvanceStateInput,
+    epoch_index: u64,
+) -> Input {
     let timestamp = UNIX_EPOCH + Duration::from_secs(input.metadata.timestamp);
     Input {
         index: input.metadata.input_index as i32,
@@ -45,15 +51,35 @@ pub fn convert_input(input: RollupsAdvanceStateInput) -> Input {
         timestamp,
         payload: input.payload.into_inner(),
         status: CompletionStatus::Unprocessed,
+        epoch_index: Some(epoch_index as i32),
     }
 }
 
+/// Rejected inputs are stored without payload, because the dispatcher never
+/// sends it
+pub fn convert_rejected_input(
+    input: RollupsRejectedInput,
+    epoch_index: u64,
+) -> Input {
+    convert_input(
+        RollupsAdvanceStateInput {
+            metadata: input.metadata,
+            payload: Default::default(),
+            tx_hash: input.tx_hash,
+        },
+        epoch_index,
+    )
+}
+
 pub fn convert_voucher(voucher: RollupsVoucher) -> Voucher {
     Voucher {
         input_index: voucher.input_index as i32,
         index: voucher.index as i32,
         destination: voucher.destination.into_inner().into(),
         payload: voucher.payload.into_inner(),
+        value: voucher.value.map(|value| value.into_inner()),
+        is_delegate_call: voucher.is_delegate_call,
+        execution_tx_hash: None,
     }
 }
 
@@ -124,3 +150,12 @@ pub fn convert_proof(proof: RollupsProof) -> Proof {
         context: proof.context.into_inner(),
     }
 }
+
+pub fn convert_claim(claim: RollupsClaim) -> Claim {
+    Claim {
+        epoch_index: claim.epoch_index as i32,
+        epoch_hash: claim.epoch_hash.into_inner().into(),
+        first_input_index: claim.first_index as i32,
+        last_input_index: claim.last_index as i32,
+    }
+}
@@ -21,7 +21,4 @@ pub enum IndexerError {
 
     #[snafu(display("repository error"))]
     RepositoryError { source: rollups_data::Error },
-
-    #[snafu(display("join error"))]
-    JoinError { source: tokio::task::JoinError },
 }
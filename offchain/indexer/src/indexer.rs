@@ -1,16 +1,17 @@
 // (c) Cartesi and individual authors (see AUTHORS)
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
-use rollups_data::Repository;
+use rollups_data::{Epoch, Repository};
 use rollups_events::indexer::{IndexerEvent, IndexerState};
 use rollups_events::{
-    Broker, BrokerError, RollupsData, RollupsInput, RollupsOutput,
+    Broker, BrokerError, RollupsClaim, RollupsData, RollupsInput,
+    RollupsOutput, RollupsVoucherExecution,
 };
 use snafu::ResultExt;
 
 use crate::conversions::*;
 use crate::error::{
-    BrokerSnafu, IndexerError, JoinSnafu, MigrationsSnafu, RepositorySnafu,
+    BrokerSnafu, IndexerError, MigrationsSnafu, RepositorySnafu,
 };
 use crate::IndexerConfig;
 
@@ -28,12 +29,9 @@ impl Indexer {
         rollups_data::run_migrations(&endpoint).context(MigrationsSnafu)?;
 
         tracing::info!("runned migrations; connecting to DB");
-        let repository = tokio::task::spawn_blocking(|| {
-            Repository::new(config.repository_config)
-        })
-        .await
-        .context(JoinSnafu)?
-        .context(RepositorySnafu)?;
+        let repository = Repository::new(config.repository_config)
+            .await
+            .context(RepositorySnafu)?;
 
         tracing::info!("connected to database; connecting to broker");
         let broker = Broker::new(config.broker_config)
@@ -50,17 +48,21 @@ impl Indexer {
         tracing::info!("connected to broker; starting main loop");
         loop {
             let event = indexer.consume_event().await?;
-            let repository = indexer.repository.clone();
-            tokio::task::spawn_blocking(move || match event {
+            let repository = &indexer.repository;
+            match event {
                 IndexerEvent::Input(input) => {
-                    store_input(&repository, input.payload)
+                    store_input(repository, input.payload).await
                 }
                 IndexerEvent::Output(output) => {
-                    store_output(&repository, output.payload)
+                    store_output(repository, output.payload).await
                 }
-            })
-            .await
-            .context(JoinSnafu)?
+                IndexerEvent::Claim(claim) => {
+                    store_claim(repository, claim.payload).await
+                }
+                IndexerEvent::VoucherExecution(execution) => {
+                    store_voucher_execution(repository, execution.payload).await
+                }
+            }
             .context(RepositorySnafu)?;
         }
     }
@@ -89,42 +91,88 @@ impl Indexer {
 }
 
 #[tracing::instrument(level = "trace", skip_all)]
-fn store_input(
+async fn store_input(
     repository: &Repository,
     input: RollupsInput,
 ) -> Result<(), rollups_data::Error> {
+    let epoch_index = input.epoch_index;
     match input.data {
         RollupsData::AdvanceStateInput(input) => {
-            repository.insert_input(convert_input(input))
+            repository
+                .insert_epoch(Epoch {
+                    index: epoch_index as i32,
+                    finished: false,
+                })
+                .await?;
+            repository
+                .insert_input(convert_input(input, epoch_index))
+                .await
+        }
+        RollupsData::RejectedInput(input) => {
+            tracing::info!(rejection = ?input.rejection, "storing rejected input");
+            repository
+                .insert_epoch(Epoch {
+                    index: epoch_index as i32,
+                    finished: false,
+                })
+                .await?;
+            repository
+                .insert_input(convert_rejected_input(input, epoch_index))
+                .await
         }
         RollupsData::FinishEpoch {} => {
-            tracing::trace!("ignoring finish epoch");
-            Ok(())
+            repository.finish_epoch(epoch_index as i32).await
         }
     }
 }
 
 #[tracing::instrument(level = "trace", skip_all)]
-fn store_output(
+async fn store_output(
     repository: &Repository,
     output: RollupsOutput,
 ) -> Result<(), rollups_data::Error> {
     match output {
-        RollupsOutput::AdvanceResult(result) => repository.update_input_status(
-            result.input_index as i32,
-            convert_status(result.status),
-        ),
+        RollupsOutput::AdvanceResult(result) => {
+            repository
+                .update_input_status(
+                    result.input_index as i32,
+                    convert_status(result.status),
+                )
+                .await
+        }
         RollupsOutput::Voucher(voucher) => {
-            repository.insert_voucher(convert_voucher(voucher))
+            repository.insert_voucher(convert_voucher(voucher)).await
         }
         RollupsOutput::Notice(notice) => {
-            repository.insert_notice(convert_notice(notice))
+            repository.insert_notice(convert_notice(notice)).await
         }
         RollupsOutput::Report(report) => {
-            repository.insert_report(convert_report(report))
+            repository.insert_report(convert_report(report)).await
         }
         RollupsOutput::Proof(proof) => {
-            repository.insert_proof(convert_proof(proof))
+            repository.insert_proof(convert_proof(proof)).await
         }
     }
 }
+
+#[tracing::instrument(level = "trace", skip_all)]
+async fn store_claim(
+    repository: &Repository,
+    claim: RollupsClaim,
+) -> Result<(), rollups_data::Error> {
+    repository.insert_claim(convert_claim(claim)).await
+}
+
+#[tracing::instrument(level = "trace", skip_all)]
+async fn store_voucher_execution(
+    repository: &Repository,
+    execution: RollupsVoucherExecution,
+) -> Result<(), rollups_data::Error> {
+    repository
+        .execute_voucher(
+            execution.input_index as i32,
+            execution.index as i32,
+            execution.tx_hash.into_inner().into(),
+        )
+        .await
+}
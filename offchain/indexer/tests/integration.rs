@@ -5,13 +5,13 @@ use indexer::IndexerError;
 use log::LogConfig;
 use rand::Rng;
 use rollups_data::{
-    Input, Notice, OutputEnum, Proof, Report, RepositoryConfig, Voucher,
+    Claim, Input, Notice, OutputEnum, Proof, Report, RepositoryConfig, Voucher,
 };
 use rollups_events::{
     BrokerConfig, BrokerEndpoint, DAppMetadata, InputMetadata,
-    RollupsAdvanceStateInput, RollupsData, RollupsNotice, RollupsOutput,
-    RollupsOutputEnum, RollupsOutputValidityProof, RollupsProof, RollupsReport,
-    RollupsVoucher,
+    RollupsAdvanceStateInput, RollupsClaim, RollupsData, RollupsNotice,
+    RollupsOutput, RollupsOutputEnum, RollupsOutputValidityProof, RollupsProof,
+    RollupsReport, RollupsVoucher, RollupsVoucherExecution,
 };
 use serial_test::serial;
 use std::time::UNIX_EPOCH;
@@ -148,7 +148,7 @@ async fn indexer_inserts_proofs() {
 
 #[test_log::test(tokio::test)]
 #[serial]
-async fn indexer_ignores_finish_epoch_and_insert_input_after() {
+async fn indexer_inserts_input_after_finish_epoch() {
     let docker = Cli::default();
     let state = TestState::setup(&docker).await;
 
@@ -161,6 +161,101 @@ async fn indexer_ignores_finish_epoch_and_insert_input_after() {
     assert_input_eq(&input_sent, &input_read);
 }
 
+#[test_log::test(tokio::test)]
+#[serial]
+async fn indexer_inserts_epochs_and_claims() {
+    let docker = Cli::default();
+    let state = TestState::setup(&docker).await;
+
+    let input0_sent = state.produce_input_in_broker(0).await;
+    state
+        .broker
+        .produce_input_event(RollupsData::FinishEpoch {})
+        .await;
+    let input1_sent = state.produce_input_in_broker(1).await;
+    let claim_sent = RollupsClaim {
+        epoch_index: 0,
+        epoch_hash: random_array().into(),
+        first_index: 0,
+        last_index: 0,
+    };
+    state.broker.produce_rollups_claim(claim_sent.clone()).await;
+
+    let claim_read = state.get_claim_from_database(0).await;
+    assert_eq!(claim_read.epoch_index, 0);
+    assert_eq!(&claim_read.epoch_hash, claim_sent.epoch_hash.inner());
+    assert_eq!(claim_read.first_input_index, 0);
+    assert_eq!(claim_read.last_input_index, 0);
+
+    let input0_read = state.get_input_from_database(&input0_sent).await;
+    assert_eq!(input0_read.epoch_index, Some(0));
+    let input1_read = state.get_input_from_database(&input1_sent).await;
+    assert_eq!(input1_read.epoch_index, Some(1));
+
+    let repository = state.repository.repository();
+    let epoch0 = repository.get_epoch(0).await.expect("failed to get epoch");
+    assert!(epoch0.finished);
+    let epoch1 = repository.get_epoch(1).await.expect("failed to get epoch");
+    assert!(!epoch1.finished);
+}
+
+#[test_log::test(tokio::test)]
+#[serial]
+async fn indexer_marks_executed_vouchers() {
+    let docker = Cli::default();
+    let state = TestState::setup(&docker).await;
+
+    state.produce_input_in_broker(0).await;
+    let voucher_sent = state.produce_voucher_in_broker(0, 1).await;
+    let voucher_read = state.get_voucher_from_database(&voucher_sent).await;
+    assert_eq!(voucher_read.execution_tx_hash, None);
+
+    let execution_sent = RollupsVoucherExecution {
+        executions_sent_count: 1,
+        input_index: 0,
+        index: 1,
+        tx_hash: random_array().into(),
+        block_number: 10,
+    };
+    state
+        .broker
+        .produce_voucher_execution(execution_sent.clone())
+        .await;
+
+    let voucher_read = state.get_executed_voucher_from_database(0, 1).await;
+    assert_eq!(
+        voucher_read.execution_tx_hash.as_deref(),
+        Some(execution_sent.tx_hash.inner().as_slice())
+    );
+}
+
+#[test_log::test(tokio::test)]
+#[serial]
+async fn indexer_marks_vouchers_executed_before_being_indexed() {
+    let docker = Cli::default();
+    let state = TestState::setup(&docker).await;
+
+    state.produce_input_in_broker(0).await;
+    let execution_sent = RollupsVoucherExecution {
+        executions_sent_count: 1,
+        input_index: 0,
+        index: 1,
+        tx_hash: random_array().into(),
+        block_number: 10,
+    };
+    state
+        .broker
+        .produce_voucher_execution(execution_sent.clone())
+        .await;
+    state.produce_voucher_in_broker(0, 1).await;
+
+    let voucher_read = state.get_executed_voucher_from_database(0, 1).await;
+    assert_eq!(
+        voucher_read.execution_tx_hash.as_deref(),
+        Some(execution_sent.tx_hash.inner().as_slice())
+    );
+}
+
 #[test_log::test(tokio::test)]
 #[serial]
 async fn indexer_does_not_override_existing_input() {
@@ -204,7 +299,7 @@ async fn indexer_fails_to_insert_output_when_input_does_not_exist() {
 impl TestState<'_> {
     async fn setup(docker: &Cli) -> TestState<'_> {
         let broker = BrokerFixture::setup(docker).await;
-        let repository = RepositoryFixture::setup(docker);
+        let repository = RepositoryFixture::setup(docker).await;
         let indexer = spawn_indexer(
             repository.config(),
             broker.redis_endpoint().to_owned(),
@@ -269,7 +364,27 @@ impl TestState<'_> {
     ) -> Input {
         tracing::info!("waiting for input in database");
         let index = input_sent.metadata.input_index as i32;
-        self.repository.retry(move |r| r.get_input(index)).await
+        self.repository
+            .retry(move |r| async move { r.get_input(index).await })
+            .await
+    }
+
+    async fn get_claim_from_database(&self, epoch_index: i32) -> Claim {
+        tracing::info!("waiting for claim in database");
+        self.repository
+            .retry(move |r| async move {
+                match r.get_claim(epoch_index).await {
+                    // The retry only works properly if the query returns item
+                    // not found
+                    Ok(option_claim) => {
+                        option_claim.ok_or(rollups_data::Error::ItemNotFound {
+                            item_type: "claim".to_owned(),
+                        })
+                    }
+                    Err(e) => Err(e),
+                }
+            })
+            .await
     }
 
     async fn produce_voucher_in_broker(
@@ -281,7 +396,9 @@ impl TestState<'_> {
             index,
             input_index,
             destination: random_array().into(),
+            value: Some(random_array::<32>().to_vec().into()),
             payload: random_array::<32>().to_vec().into(),
+            is_delegate_call: false,
         };
         let output = RollupsOutput::Voucher(voucher.clone());
 
@@ -298,7 +415,30 @@ impl TestState<'_> {
         let input_index = voucher_sent.input_index as i32;
         let index = voucher_sent.index as i32;
         self.repository
-            .retry(move |r| r.get_voucher(index, input_index))
+            .retry(
+                move |r| async move { r.get_voucher(index, input_index).await },
+            )
+            .await
+    }
+
+    async fn get_executed_voucher_from_database(
+        &self,
+        input_index: i32,
+        index: i32,
+    ) -> Voucher {
+        tracing::info!("waiting for executed voucher in database");
+        self.repository
+            .retry(move |r| async move {
+                // The retry only works properly if the query returns item
+                // not found
+                let voucher = r.get_voucher(index, input_index).await?;
+                match voucher.execution_tx_hash {
+                    Some(_) => Ok(voucher),
+                    None => Err(rollups_data::Error::ItemNotFound {
+                        item_type: "executed voucher".to_owned(),
+                    }),
+                }
+            })
             .await
     }
 
@@ -327,7 +467,9 @@ impl TestState<'_> {
         let input_index = notice_sent.input_index as i32;
         let index = notice_sent.index as i32;
         self.repository
-            .retry(move |r| r.get_notice(index, input_index))
+            .retry(
+                move |r| async move { r.get_notice(index, input_index).await },
+            )
             .await
     }
 
@@ -356,7 +498,9 @@ impl TestState<'_> {
         let input_index = report_sent.input_index as i32;
         let index = report_sent.index as i32;
         self.repository
-            .retry(move |r| r.get_report(index, input_index))
+            .retry(
+                move |r| async move { r.get_report(index, input_index).await },
+            )
             .await
     }
 
@@ -402,8 +546,9 @@ impl TestState<'_> {
             RollupsOutputEnum::Notice => OutputEnum::Notice,
         };
         self.repository
-            .retry(move |r| {
-                match r.get_proof(input_index, output_index, output_enum) {
+            .retry(move |r| async move {
+                match r.get_proof(input_index, output_index, output_enum).await
+                {
                     Ok(option_proof) => {
                         // The retry only works properly if the query
                         // returns item not found
@@ -479,6 +624,14 @@ fn assert_voucher_eq(voucher_sent: &RollupsVoucher, voucher_read: &Voucher) {
     assert_eq!(voucher_read.input_index as u64, voucher_sent.input_index);
     assert_eq!(&voucher_read.destination, voucher_sent.destination.inner());
     assert_eq!(&voucher_read.payload, voucher_sent.payload.inner());
+    assert_eq!(
+        voucher_read.value,
+        voucher_sent
+            .value
+            .as_ref()
+            .map(|value| value.inner().to_vec())
+    );
+    assert_eq!(voucher_read.is_delegate_call, voucher_sent.is_delegate_call);
 }
 
 fn assert_notice_eq(notice_sent: &RollupsNotice, notice_read: &Notice) {
@@ -0,0 +1,163 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Comparison of the complete and incremental merkle trees for the epoch
+//! tree, which is updated after each input and proven when the epoch finishes,
+//! and for the output-hashes tree of each input, which is updated after each
+//! output.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use output_proofs::hash::{Hash, HASH_SIZE};
+use output_proofs::merkle_tree::{complete, incremental, proof::Proof};
+use output_proofs::outputs::{
+    compute_proofs, OutputsTree, Proofable, LOG2_EPOCH_SIZE, LOG2_HASH_SIZE,
+};
+
+const LEAF_COUNTS: [usize; 3] = [16, 256, 1024];
+
+struct Output {
+    hash: Hash,
+    proof: Option<Proof>,
+}
+
+impl Proofable for Output {
+    fn get_hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    fn set_proof(&mut self, proof: Proof) {
+        self.proof = Some(proof);
+    }
+}
+
+fn leaves(n: usize) -> Vec<Hash> {
+    (0..n)
+        .map(|i| {
+            let mut data = [0; HASH_SIZE];
+            data[..8].copy_from_slice(&(i as u64).to_be_bytes());
+            Hash::from(data)
+        })
+        .collect()
+}
+
+fn new_complete() -> complete::Tree {
+    complete::Tree::new(LOG2_EPOCH_SIZE, LOG2_HASH_SIZE, LOG2_HASH_SIZE)
+        .unwrap()
+}
+
+fn new_incremental() -> incremental::Tree {
+    incremental::Tree::new(LOG2_EPOCH_SIZE, LOG2_HASH_SIZE, LOG2_HASH_SIZE)
+        .unwrap()
+}
+
+/// Push the leaves one by one, getting the root hash after each push
+fn bench_push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push");
+    for n in LEAF_COUNTS {
+        let leaves = leaves(n);
+        group.bench_with_input(BenchmarkId::new("complete", n), &n, |b, _| {
+            b.iter(|| {
+                let mut tree = new_complete();
+                for leaf in leaves.iter() {
+                    tree.push(leaf.clone()).unwrap();
+                    criterion::black_box(tree.get_root_hash());
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("incremental", n),
+            &n,
+            |b, _| {
+                b.iter(|| {
+                    let mut tree = new_incremental();
+                    for leaf in leaves.iter() {
+                        tree.push(leaf.clone()).unwrap();
+                        criterion::black_box(tree.get_root_hash());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Build the tree and get the proofs of every leaf
+fn bench_proofs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("proofs");
+    for n in LEAF_COUNTS {
+        let leaves = leaves(n);
+        group.bench_with_input(BenchmarkId::new("complete", n), &n, |b, _| {
+            b.iter(|| {
+                let mut tree = new_complete();
+                for leaf in leaves.iter() {
+                    tree.push(leaf.clone()).unwrap();
+                }
+                for i in 0..n {
+                    let address = i << LOG2_HASH_SIZE;
+                    criterion::black_box(
+                        tree.get_proof(address, LOG2_HASH_SIZE).unwrap(),
+                    );
+                }
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("incremental", n),
+            &n,
+            |b, _| {
+                b.iter(|| {
+                    let mut tree = new_incremental();
+                    for leaf in leaves.iter() {
+                        tree.push(leaf.clone()).unwrap();
+                    }
+                    for i in 0..n {
+                        let address = i << LOG2_HASH_SIZE;
+                        criterion::black_box(
+                            tree.get_proof(address, LOG2_HASH_SIZE).unwrap(),
+                        );
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Prove every output of an input, either building the complete tree from all
+/// the outputs or pushing each output to the outputs tree as it is emitted
+fn bench_outputs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("outputs");
+    for n in LEAF_COUNTS {
+        let hashes = leaves(n);
+        group.bench_with_input(BenchmarkId::new("complete", n), &n, |b, _| {
+            b.iter(|| {
+                let mut outputs: Vec<_> = hashes
+                    .iter()
+                    .map(|hash| Output {
+                        hash: hash.clone(),
+                        proof: None,
+                    })
+                    .collect();
+                criterion::black_box(compute_proofs(&mut outputs).unwrap());
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("incremental", n),
+            &n,
+            |b, _| {
+                b.iter(|| {
+                    let mut tree = OutputsTree::new();
+                    for hash in hashes.iter() {
+                        tree.push(hash).unwrap();
+                    }
+                    for i in 0..n {
+                        criterion::black_box(tree.get_proof(i).unwrap());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_push, bench_proofs, bench_outputs);
+criterion_main!(benches);
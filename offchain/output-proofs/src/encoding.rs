@@ -0,0 +1,152 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Encoding and hashing of the outputs, as done by the rollups contracts.
+
+use byteorder::{BigEndian, WriteBytesExt};
+use ethabi::{ethereum_types::U256, Address, ParamType, Token};
+use std::mem::size_of;
+
+use crate::hash::{Digest, Hash, Hasher, HASH_SIZE};
+
+pub fn compute_voucher_hash(destination: &[u8], payload: &[u8]) -> Hash {
+    let mut hasher = Hasher::new();
+    write_data(&mut hasher, destination);
+    write_u64(&mut hasher, 0x40);
+    write_payload(&mut hasher, payload);
+    hasher.finalize().into()
+}
+
+pub fn compute_notice_hash(payload: &[u8]) -> Hash {
+    let mut hasher = Hasher::new();
+    write_u64(&mut hasher, 0x20);
+    write_payload(&mut hasher, payload);
+    hasher.finalize().into()
+}
+
+/// Encodes a voucher of the newer rollups contracts, which calls the
+/// destination transferring `value` wei.
+pub fn encode_call_voucher(
+    destination: &[u8],
+    value: &[u8; HASH_SIZE],
+    payload: &[u8],
+) -> Vec<u8> {
+    encode_output(
+        "Voucher",
+        &[ParamType::Address, ParamType::Uint(256), ParamType::Bytes],
+        &[
+            Token::Address(Address::from_slice(destination)),
+            Token::Uint(U256::from_big_endian(value)),
+            Token::Bytes(payload.to_vec()),
+        ],
+    )
+}
+
+/// Encodes a voucher of the newer rollups contracts, which runs the payload
+/// in the context of the DApp contract with DELEGATECALL.
+pub fn encode_delegate_call_voucher(
+    destination: &[u8],
+    payload: &[u8],
+) -> Vec<u8> {
+    encode_output(
+        "DelegateCallVoucher",
+        &[ParamType::Address, ParamType::Bytes],
+        &[
+            Token::Address(Address::from_slice(destination)),
+            Token::Bytes(payload.to_vec()),
+        ],
+    )
+}
+
+/// Outputs of the newer rollups contracts are ABI-encoded calls to the
+/// function named after the output kind, and are hashed as a whole.
+fn encode_output(
+    name: &str,
+    params: &[ParamType],
+    tokens: &[Token],
+) -> Vec<u8> {
+    let mut output = ethabi::short_signature(name, params).to_vec();
+    output.extend(ethabi::encode(tokens));
+    output
+}
+
+pub fn compute_output_hash(output: &[u8]) -> Hash {
+    let mut hasher = Hasher::new();
+    hasher.update(output);
+    hasher.finalize().into()
+}
+
+fn write_padding(hasher: &mut Hasher, n: usize) {
+    let alignment = n % HASH_SIZE;
+    if alignment != 0 {
+        for _ in alignment..HASH_SIZE {
+            hasher.write_u8(0).expect("cannot fail");
+        }
+    }
+}
+
+fn write_u64(hasher: &mut Hasher, value: u64) {
+    write_padding(hasher, size_of::<u64>());
+    hasher.write_u64::<BigEndian>(value).expect("cannot fail");
+}
+
+fn write_data(hasher: &mut Hasher, data: &[u8]) {
+    write_padding(hasher, data.len());
+    hasher.update(data);
+}
+
+fn write_payload(hasher: &mut Hasher, payload: &[u8]) {
+    write_u64(hasher, payload.len() as u64);
+    hasher.update(payload);
+    write_padding(hasher, payload.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_voucher_hash() {
+        let destination =
+            hex::decode("5555555555555555555555555555555555555555").unwrap();
+        let payload: Vec<u8> = "hello world".as_bytes().into();
+        let hash = compute_voucher_hash(&destination, &payload);
+        let expected_hash = Hash::decode(
+            "61a61380d2a3b5e2b09a5ff259a2e1048da1989bdd6d6ecc69594cfbedc01278",
+        );
+        assert_eq!(&hash, &expected_hash);
+    }
+
+    #[test]
+    fn test_encode_call_voucher() {
+        let destination =
+            hex::decode("5555555555555555555555555555555555555555").unwrap();
+        let mut value = [0; HASH_SIZE];
+        value[HASH_SIZE - 1] = 1;
+        let output = encode_call_voucher(&destination, &value, &[0xab]);
+        assert_eq!(hex::encode(&output[..4]), "237a816f");
+        assert_eq!(&output[16..36], &destination[..]);
+        assert_eq!(output[67], 1);
+        assert_eq!(output.len(), 4 + 5 * HASH_SIZE);
+    }
+
+    #[test]
+    fn test_encode_delegate_call_voucher() {
+        let destination =
+            hex::decode("5555555555555555555555555555555555555555").unwrap();
+        let output = encode_delegate_call_voucher(&destination, &[0xab]);
+        assert_eq!(hex::encode(&output[..4]), "10321e8b");
+        assert_eq!(&output[16..36], &destination[..]);
+        assert_eq!(output.len(), 4 + 4 * HASH_SIZE);
+    }
+
+    #[test]
+    fn test_update_notice_hash() {
+        let payload: Vec<u8> = "hello world".as_bytes().into();
+        let hash = compute_notice_hash(&payload);
+        let expected_hash = Hash::decode(
+            "d9f29a4e347ad89dc70490124ee6975fbc0693c7e72d6bc383673bfd0e8841f2",
+        );
+        assert_eq!(&hash, &expected_hash);
+    }
+}
@@ -0,0 +1,288 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Parsing of the vouchers and notices fetched from the GraphQL API.
+//!
+//! The JSON may be the whole GraphQL response of a `voucher` or `notice`
+//! query, or just the output object. It must have the payload, the
+//! destination and value of vouchers, and the validity of the proof.
+
+use serde::Deserialize;
+use serde_json::Value;
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::encoding::{
+    compute_notice_hash, compute_output_hash, compute_voucher_hash,
+    encode_call_voucher, encode_delegate_call_voucher,
+};
+use crate::hash::{Hash, HASH_SIZE};
+use crate::validity::{OutputKind, OutputValidityProof};
+
+const ADDRESS_SIZE: usize = 20;
+
+#[derive(Debug, Snafu)]
+pub enum GraphQLError {
+    #[snafu(display("failed to parse GraphQL output"))]
+    ParseError { source: serde_json::Error },
+    #[snafu(display("GraphQL output has no proof"))]
+    MissingProofError,
+    #[snafu(display("invalid hex in field {}", field))]
+    InvalidHexError {
+        field: String,
+        source: hex::FromHexError,
+    },
+    #[snafu(display("invalid size of field {}", field))]
+    InvalidSizeError { field: String },
+}
+
+/// Output with its hash and validity proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenOutput {
+    pub kind: OutputKind,
+    pub hash: Hash,
+    pub proof: OutputValidityProof,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphQLOutput {
+    destination: Option<String>,
+    payload: String,
+    value: Option<String>,
+    #[serde(default)]
+    is_delegate_call: bool,
+    proof: Option<GraphQLProof>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLProof {
+    validity: GraphQLValidityProof,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphQLValidityProof {
+    input_index_within_epoch: u64,
+    output_index_within_input: u64,
+    output_hashes_root_hash: String,
+    vouchers_epoch_root_hash: String,
+    notices_epoch_root_hash: String,
+    machine_state_hash: String,
+    output_hash_in_output_hashes_siblings: Vec<String>,
+    output_hashes_in_epoch_siblings: Vec<String>,
+}
+
+/// Parse a voucher or notice fetched from GraphQL
+pub fn parse_output(json: &str) -> Result<ProvenOutput, GraphQLError> {
+    let mut value: Value = serde_json::from_str(json).context(ParseSnafu)?;
+    if let Some(data) = value.get_mut("data") {
+        value = data.take();
+    }
+    let mut kind = None;
+    for (name, output_kind) in [
+        ("voucher", OutputKind::Voucher),
+        ("notice", OutputKind::Notice),
+    ] {
+        if let Some(output) = value.get_mut(name) {
+            kind = Some(output_kind);
+            value = output.take();
+            break;
+        }
+    }
+    let output: GraphQLOutput =
+        serde_json::from_value(value).context(ParseSnafu)?;
+    let kind = kind.unwrap_or(match output.destination {
+        Some(_) => OutputKind::Voucher,
+        None => OutputKind::Notice,
+    });
+    let payload = decode("payload", &output.payload)?;
+    let hash = match kind {
+        OutputKind::Voucher => {
+            let destination = decode_sized::<ADDRESS_SIZE>(
+                "destination",
+                output.destination.as_deref().unwrap_or_default(),
+            )?;
+            if output.is_delegate_call {
+                let encoded =
+                    encode_delegate_call_voucher(&destination, &payload);
+                compute_output_hash(&encoded)
+            } else if let Some(value) = &output.value {
+                let value = decode_value(value)?;
+                let encoded =
+                    encode_call_voucher(&destination, &value, &payload);
+                compute_output_hash(&encoded)
+            } else {
+                compute_voucher_hash(&destination, &payload)
+            }
+        }
+        OutputKind::Notice => compute_notice_hash(&payload),
+    };
+    let validity = output.proof.context(MissingProofSnafu)?.validity;
+    let proof = OutputValidityProof {
+        input_index_within_epoch: validity.input_index_within_epoch,
+        output_index_within_input: validity.output_index_within_input,
+        output_hashes_root_hash: decode_hash(
+            "outputHashesRootHash",
+            &validity.output_hashes_root_hash,
+        )?,
+        vouchers_epoch_root_hash: decode_hash(
+            "vouchersEpochRootHash",
+            &validity.vouchers_epoch_root_hash,
+        )?,
+        notices_epoch_root_hash: decode_hash(
+            "noticesEpochRootHash",
+            &validity.notices_epoch_root_hash,
+        )?,
+        machine_state_hash: decode_hash(
+            "machineStateHash",
+            &validity.machine_state_hash,
+        )?,
+        output_hash_in_output_hashes_siblings: decode_hashes(
+            "outputHashInOutputHashesSiblings",
+            &validity.output_hash_in_output_hashes_siblings,
+        )?,
+        output_hashes_in_epoch_siblings: decode_hashes(
+            "outputHashesInEpochSiblings",
+            &validity.output_hashes_in_epoch_siblings,
+        )?,
+    };
+    Ok(ProvenOutput { kind, hash, proof })
+}
+
+/// Decode a hash in Ethereum hex binary format, with or without the 0x prefix
+pub fn decode_hash(field: &str, s: &str) -> Result<Hash, GraphQLError> {
+    decode_sized::<HASH_SIZE>(field, s).map(Hash::from)
+}
+
+fn decode_hashes(
+    field: &str,
+    hashes: &[String],
+) -> Result<Vec<Hash>, GraphQLError> {
+    hashes.iter().map(|hash| decode_hash(field, hash)).collect()
+}
+
+fn decode(field: &str, s: &str) -> Result<Vec<u8>, GraphQLError> {
+    hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .context(InvalidHexSnafu { field })
+}
+
+fn decode_sized<const N: usize>(
+    field: &str,
+    s: &str,
+) -> Result<[u8; N], GraphQLError> {
+    decode(field, s)?
+        .try_into()
+        .ok()
+        .context(InvalidSizeSnafu { field })
+}
+
+/// Decode the voucher value, left-padding it to an uint256
+fn decode_value(s: &str) -> Result<[u8; HASH_SIZE], GraphQLError> {
+    let bytes = decode("value", s)?;
+    snafu::ensure!(
+        bytes.len() <= HASH_SIZE,
+        InvalidSizeSnafu { field: "value" }
+    );
+    let mut value = [0; HASH_SIZE];
+    value[HASH_SIZE - bytes.len()..].copy_from_slice(&bytes);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validity_json() -> Value {
+        let hash = format!("0x{}", "11".repeat(HASH_SIZE));
+        serde_json::json!({
+            "inputIndexWithinEpoch": 1,
+            "outputIndexWithinInput": 2,
+            "outputHashesRootHash": hash,
+            "vouchersEpochRootHash": hash,
+            "noticesEpochRootHash": hash,
+            "machineStateHash": hash,
+            "outputHashInOutputHashesSiblings": [hash],
+            "outputHashesInEpochSiblings": [hash, hash],
+        })
+    }
+
+    #[test]
+    fn test_it_parses_voucher_response() {
+        let json = serde_json::json!({
+            "data": {
+                "voucher": {
+                    "index": 2,
+                    "destination": format!("0x{}", "55".repeat(20)),
+                    "payload": "0x68656c6c6f20776f726c64",
+                    "value": null,
+                    "isDelegateCall": false,
+                    "proof": { "validity": validity_json(), "context": "0x" },
+                }
+            }
+        });
+        let output = parse_output(&json.to_string()).unwrap();
+        assert_eq!(output.kind, OutputKind::Voucher);
+        assert_eq!(
+            output.hash,
+            compute_voucher_hash(&[0x55; 20], b"hello world")
+        );
+        assert_eq!(output.proof.input_index_within_epoch, 1);
+        assert_eq!(output.proof.output_index_within_input, 2);
+        assert_eq!(output.proof.output_hash_in_output_hashes_siblings.len(), 1);
+        assert_eq!(output.proof.output_hashes_in_epoch_siblings.len(), 2);
+    }
+
+    #[test]
+    fn test_it_parses_voucher_with_value() {
+        let json = serde_json::json!({
+            "destination": format!("0x{}", "55".repeat(20)),
+            "payload": "0xab",
+            "value": "0x01",
+            "proof": { "validity": validity_json() },
+        });
+        let output = parse_output(&json.to_string()).unwrap();
+        let mut value = [0; HASH_SIZE];
+        value[HASH_SIZE - 1] = 1;
+        let encoded = encode_call_voucher(&[0x55; 20], &value, &[0xab]);
+        assert_eq!(output.kind, OutputKind::Voucher);
+        assert_eq!(output.hash, compute_output_hash(&encoded));
+    }
+
+    #[test]
+    fn test_it_parses_notice() {
+        let json = serde_json::json!({
+            "notice": {
+                "payload": "0x68656c6c6f20776f726c64",
+                "proof": { "validity": validity_json() },
+            }
+        });
+        let output = parse_output(&json.to_string()).unwrap();
+        assert_eq!(output.kind, OutputKind::Notice);
+        assert_eq!(output.hash, compute_notice_hash(b"hello world"));
+    }
+
+    #[test]
+    fn test_it_fails_to_parse_output_without_proof() {
+        let json = serde_json::json!({ "payload": "0x", "proof": null });
+        assert!(matches!(
+            parse_output(&json.to_string()),
+            Err(GraphQLError::MissingProofError)
+        ));
+    }
+
+    #[test]
+    fn test_it_fails_to_parse_invalid_hash() {
+        let mut validity = validity_json();
+        validity["machineStateHash"] = Value::from("0x1234");
+        let json = serde_json::json!({
+            "payload": "0x",
+            "proof": { "validity": validity },
+        });
+        match parse_output(&json.to_string()) {
+            Err(GraphQLError::InvalidSizeError { field }) => {
+                assert_eq!(field, "machineStateHash")
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Cartesi-compatible merkle trees and output proofs.
+//!
+//! This crate builds the merkle trees of the outputs of an epoch, generates
+//! the proofs of vouchers and notices, and validates these proofs up to the
+//! epoch hash claimed on-chain.
+
+pub mod encoding;
+pub mod graphql;
+pub mod hash;
+pub mod merkle_tree;
+pub mod outputs;
+pub mod validity;
@@ -0,0 +1,50 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use clap::Parser;
+use std::path::PathBuf;
+
+use output_proofs::{graphql, validity};
+
+/// Verify a voucher or notice fetched from GraphQL against the epoch hash
+/// claimed on-chain
+#[derive(Parser, Debug)]
+#[command(name = "cartesi-rollups-verify-output")]
+pub struct CLIConfig {
+    /// JSON file with the voucher or notice and its proof, as returned by
+    /// the GraphQL API; reads from stdin when set to -
+    #[arg(long)]
+    pub output_file: PathBuf,
+
+    /// Epoch hash claimed on-chain, in Ethereum hex binary format
+    #[arg(long)]
+    pub epoch_hash: String,
+}
+
+fn main() {
+    let config = CLIConfig::parse();
+    match verify(&config) {
+        Ok(()) => println!("output is valid"),
+        Err(e) => {
+            eprintln!("output is invalid: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn verify(config: &CLIConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let json = if config.output_file.as_os_str() == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(&config.output_file)?
+    };
+    let output = graphql::parse_output(&json)?;
+    let epoch_hash = graphql::decode_hash("epoch hash", &config.epoch_hash)?;
+    validity::validate_output(
+        output.kind,
+        &output.hash,
+        &output.proof,
+        &epoch_hash,
+    )?;
+    Ok(())
+}
@@ -139,6 +139,11 @@ impl Tree {
             .len()
     }
 
+    /// Return whether the tree has no leafs
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Return the hash of a node at a given address
     ///
     /// For more information regarding the other parameters, see Tree::get_proof().
@@ -436,6 +441,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_it_gets_proofs_that_verify() {
+        let leaves = (0..5).map(|i| Hash::from([i; HASH_SIZE])).collect();
+        let tree = Tree::new_from_leaves(3, 0, 0, leaves).unwrap();
+        for address in 0..8 {
+            let mut proof = tree.get_proof(address, 0).unwrap();
+            assert!(proof.verify());
+            proof.sibling_hashes[1] = Hash::from([0xFF; HASH_SIZE]);
+            assert!(!proof.verify());
+        }
+        let proof = tree.get_proof(4, 2).unwrap();
+        assert_eq!(&proof.compute_root_hash(), tree.get_root_hash());
+    }
+
     #[test]
     fn test_it_pushes_leaf_in_empty_tree() {
         let mut tree = Tree::new(3, 0, 0).unwrap();
@@ -0,0 +1,317 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Incremental append-only merkle tree
+//!
+//! Unlike the complete tree, appending a leaf only updates the frontier of the
+//! tree, which holds the last complete left subtree at each level. The root
+//! hash is computed from the frontier, and the proofs are computed on request
+//! from the leaves, caching the hashes of the complete subtrees, which never
+//! change after they are filled.
+
+use std::collections::HashMap;
+
+use super::{
+    get_concat_hash, pristine, proof::Proof, Error,
+    LeafSizeGreaterThanRootSizeSnafu, MisalignedAddressSnafu,
+    SizeOutOfRangeSnafu, TreeIsFullSnafu, TreeTooLargeSnafu,
+    WordSizeGreaterThanLeafSizeSnafu,
+};
+use crate::hash::{Digest, Hash, Hasher};
+
+#[derive(Debug, Clone)]
+pub struct Tree {
+    log2_root_size: usize,
+    log2_leaf_size: usize,
+    pristine: pristine::Tree,
+    leaves: Vec<Hash>,
+    /// Last complete left subtree at each level, from the leaves up
+    frontier: Vec<Hash>,
+    /// Hashes of complete subtrees computed for proofs, by log2 size and index
+    cache: HashMap<(usize, usize), Hash>,
+}
+
+impl Tree {
+    /// Create a new incremental merkle tree
+    ///
+    /// The parameters are the same of complete::Tree::new().
+    pub fn new(
+        log2_root_size: usize,
+        log2_leaf_size: usize,
+        log2_word_size: usize,
+    ) -> Result<Self, Error> {
+        snafu::ensure!(
+            log2_leaf_size <= log2_root_size,
+            LeafSizeGreaterThanRootSizeSnafu
+        );
+        snafu::ensure!(
+            log2_word_size <= log2_leaf_size,
+            WordSizeGreaterThanLeafSizeSnafu
+        );
+        snafu::ensure!(
+            log2_root_size < std::mem::size_of::<usize>() * 8,
+            TreeTooLargeSnafu
+        );
+        Ok(Self {
+            log2_root_size,
+            log2_leaf_size,
+            pristine: pristine::Tree::new(log2_root_size, log2_word_size)?,
+            leaves: vec![],
+            frontier: vec![
+                Hash::default();
+                log2_root_size - log2_leaf_size + 1
+            ],
+            cache: HashMap::new(),
+        })
+    }
+
+    /// Append a new leaf hash to the tree
+    pub fn push(&mut self, leaf: Hash) -> Result<(), Error> {
+        let height = self.log2_root_size - self.log2_leaf_size;
+        let len = self.leaves.len();
+        snafu::ensure!(len < 1 << height, TreeIsFullSnafu);
+        let mut hasher = Hasher::new();
+        let mut hash = leaf.clone();
+        for level in 0..=height {
+            // The new node is a left child, so it waits for its sibling
+            if (len >> level) & 1 == 0 {
+                self.frontier[level] = hash;
+                break;
+            }
+            hash = get_concat_hash(&mut hasher, &self.frontier[level], &hash);
+        }
+        self.leaves.push(leaf);
+        Ok(())
+    }
+
+    /// Return the number of leafs
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Return whether the tree has no leafs
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Return the tree's root hash
+    pub fn get_root_hash(&self) -> Hash {
+        let height = self.log2_root_size - self.log2_leaf_size;
+        let len = self.leaves.len();
+        if len == 1 << height {
+            return self.frontier[height].clone();
+        }
+        // Go up from the first pristine leaf, whose left siblings are in the
+        // frontier and whose right siblings are pristine
+        let mut hasher = Hasher::new();
+        let mut hash = self.pristine_hash(self.log2_leaf_size);
+        for level in 0..height {
+            hash = if (len >> level) & 1 == 1 {
+                get_concat_hash(&mut hasher, &self.frontier[level], &hash)
+            } else {
+                let log2_size = self.log2_leaf_size + level;
+                get_concat_hash(
+                    &mut hasher,
+                    &hash,
+                    self.pristine_hash_ref(log2_size),
+                )
+            };
+        }
+        hash
+    }
+
+    /// Return proof for a given node
+    ///
+    /// The parameters are the same of complete::Tree::get_proof().
+    pub fn get_proof(
+        &mut self,
+        address: usize,
+        log2_size: usize,
+    ) -> Result<Proof, Error> {
+        snafu::ensure!(
+            log2_size >= self.log2_leaf_size
+                && log2_size <= self.log2_root_size,
+            SizeOutOfRangeSnafu
+        );
+        let aligned_address = (address >> log2_size) << log2_size;
+        snafu::ensure!(address == aligned_address, MisalignedAddressSnafu);
+        let index = address >> log2_size;
+        snafu::ensure!(
+            index < 1 << (self.log2_root_size - log2_size),
+            SizeOutOfRangeSnafu
+        );
+        let mut hasher = Hasher::new();
+        let target_hash = self.get_node_hash(&mut hasher, index, log2_size);
+        let mut proof = Proof::new(
+            address,
+            log2_size,
+            target_hash,
+            self.log2_root_size,
+            self.get_root_hash(),
+        )?;
+        for log2_sibling_size in log2_size..self.log2_root_size {
+            let sibling_index = (address >> log2_sibling_size) ^ 1;
+            let hash = self.get_node_hash(
+                &mut hasher,
+                sibling_index,
+                log2_sibling_size,
+            );
+            proof.set_sibling_hash(hash, log2_sibling_size)?;
+        }
+        Ok(proof)
+    }
+
+    /// Return the hash of the node with the given index at the level of the
+    /// given size, caching it when its subtree is complete
+    fn get_node_hash(
+        &mut self,
+        hasher: &mut Hasher,
+        index: usize,
+        log2_size: usize,
+    ) -> Hash {
+        let level = log2_size - self.log2_leaf_size;
+        let len = self.leaves.len();
+        if index << level >= len {
+            return self.pristine_hash(log2_size);
+        }
+        if level == 0 {
+            return self.leaves[index].clone();
+        }
+        let complete = (index + 1) << level <= len;
+        if complete {
+            if let Some(hash) = self.cache.get(&(log2_size, index)) {
+                return hash.clone();
+            }
+        }
+        let left = self.get_node_hash(hasher, 2 * index, log2_size - 1);
+        let right = self.get_node_hash(hasher, 2 * index + 1, log2_size - 1);
+        let hash = get_concat_hash(hasher, &left, &right);
+        if complete {
+            self.cache.insert((log2_size, index), hash.clone());
+        }
+        hash
+    }
+
+    fn pristine_hash(&self, log2_size: usize) -> Hash {
+        self.pristine_hash_ref(log2_size).clone()
+    }
+
+    fn pristine_hash_ref(&self, log2_size: usize) -> &Hash {
+        self.pristine.get_hash(log2_size).expect("cannot fail")
+    }
+}
+
+/// Trees are equal when they have the same shape and leaves, since the
+/// frontier and the cache are computed from them.
+impl PartialEq for Tree {
+    fn eq(&self, other: &Self) -> bool {
+        self.log2_root_size == other.log2_root_size
+            && self.log2_leaf_size == other.log2_leaf_size
+            && self.leaves == other.leaves
+    }
+}
+
+impl Eq for Tree {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::HASH_SIZE;
+    use crate::merkle_tree::complete;
+
+    fn leaf(i: usize) -> Hash {
+        Hash::from([i as u8; HASH_SIZE])
+    }
+
+    #[test]
+    fn test_it_fails_to_create_a_tree_with_leaf_size_greater_than_root_size() {
+        let err = Tree::new(2, 3, 0).unwrap_err();
+        assert_eq!(err, Error::LeafSizeGreaterThanRootSize);
+    }
+
+    #[test]
+    fn test_it_is_equals_to_pristine_tree_when_empty() {
+        let tree = Tree::new(8, 3, 0).unwrap();
+        let pristine = pristine::Tree::new(8, 0).unwrap();
+        assert_eq!(&tree.get_root_hash(), pristine.get_hash(8).unwrap());
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn test_it_matches_complete_tree_after_each_push() {
+        let mut tree = Tree::new(4, 0, 0).unwrap();
+        let mut complete = complete::Tree::new(4, 0, 0).unwrap();
+        for i in 0..16 {
+            tree.push(leaf(i)).unwrap();
+            complete.push(leaf(i)).unwrap();
+            assert_eq!(&tree.get_root_hash(), complete.get_root_hash());
+            assert_eq!(tree.len(), i + 1);
+        }
+    }
+
+    #[test]
+    fn test_it_gets_same_proofs_of_complete_tree() {
+        let mut tree = Tree::new(5, 1, 0).unwrap();
+        let mut complete = complete::Tree::new(5, 1, 0).unwrap();
+        for i in 0..11 {
+            tree.push(leaf(i)).unwrap();
+            complete.push(leaf(i)).unwrap();
+        }
+        for log2_size in 1..=5 {
+            for address in (0..32).step_by(1 << log2_size) {
+                let proof = tree.get_proof(address, log2_size).unwrap();
+                assert_eq!(
+                    proof,
+                    complete.get_proof(address, log2_size).unwrap()
+                );
+                assert!(proof.verify());
+            }
+        }
+        // The proofs are still right after the cached subtrees grow
+        tree.push(leaf(11)).unwrap();
+        complete.push(leaf(11)).unwrap();
+        for address in (0..32).step_by(2) {
+            assert_eq!(
+                tree.get_proof(address, 1).unwrap(),
+                complete.get_proof(address, 1).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_it_fails_to_get_proof_when_address_is_invalid() {
+        let mut tree = Tree::new(3, 2, 1).unwrap();
+        assert_eq!(tree.get_proof(0, 4).unwrap_err(), Error::SizeOutOfRange);
+        assert_eq!(tree.get_proof(0, 1).unwrap_err(), Error::SizeOutOfRange);
+        assert_eq!(tree.get_proof(1, 2).unwrap_err(), Error::MisalignedAddress);
+        assert_eq!(
+            tree.get_proof(2 << 2, 2).unwrap_err(),
+            Error::SizeOutOfRange
+        );
+    }
+
+    #[test]
+    fn test_it_fails_to_push_leaf_in_full_tree() {
+        let mut tree = Tree::new(3, 0, 0).unwrap();
+        let mut complete = complete::Tree::new(3, 0, 0).unwrap();
+        for i in 0..8 {
+            tree.push(leaf(i)).unwrap();
+            complete.push(leaf(i)).unwrap();
+        }
+        assert_eq!(&tree.get_root_hash(), complete.get_root_hash());
+        assert_eq!(tree.push(Hash::default()).unwrap_err(), Error::TreeIsFull);
+    }
+
+    #[test]
+    fn test_it_compares_leaves_regardless_of_cached_proofs() {
+        let mut tree = Tree::new(3, 0, 0).unwrap();
+        for i in 0..4 {
+            tree.push(leaf(i)).unwrap();
+        }
+        let other = tree.clone();
+        tree.get_proof(0, 0).unwrap();
+        assert_eq!(tree, other);
+        tree.push(leaf(4)).unwrap();
+        assert_ne!(tree, other);
+    }
+}
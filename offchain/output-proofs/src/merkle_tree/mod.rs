@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 pub mod complete;
+pub mod incremental;
 pub mod pristine;
 pub mod proof;
 
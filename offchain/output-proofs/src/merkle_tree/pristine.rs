@@ -10,7 +10,7 @@ use super::{
 use crate::hash::{Digest, Hash, Hasher};
 
 /// Merkle tree where all leaves are zero
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Tree {
     log2_root_size: usize,
     log2_word_size: usize,
@@ -3,9 +3,12 @@
 
 //! Merkle tree proof based on Cartesi machine-emulator implementation
 
-use super::{Error, SizeOutOfRangeSnafu, TargetSizeGreaterThanRootSizeSnafu};
+use super::{
+    get_concat_hash, Error, SizeOutOfRangeSnafu,
+    TargetSizeGreaterThanRootSizeSnafu,
+};
 
-use crate::hash::Hash;
+use crate::hash::{Digest, Hash, Hasher};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Proof {
@@ -58,6 +61,28 @@ impl Proof {
         Ok(())
     }
 
+    /// Compute the root hash from the target hash and the sibling hashes
+    pub fn compute_root_hash(&self) -> Hash {
+        let mut hasher = Hasher::new();
+        let mut hash = self.target_hash.clone();
+        for (index, sibling) in self.sibling_hashes.iter().enumerate() {
+            let log2_size = self.log2_target_size + index;
+            hash = if (self.target_address >> log2_size) & 1 == 0 {
+                get_concat_hash(&mut hasher, &hash, sibling)
+            } else {
+                get_concat_hash(&mut hasher, sibling, &hash)
+            };
+        }
+        hash
+    }
+
+    /// Check whether the sibling hashes lead from the target hash to the
+    /// root hash
+    pub fn verify(&self) -> bool {
+        self.sibling_hashes.len() == self.log2_root_size - self.log2_target_size
+            && self.compute_root_hash() == self.root_hash
+    }
+
     /// Converts log2_size to index into siblings array
     fn log2_size_to_index(&self, log2_size: usize) -> Result<usize, Error> {
         snafu::ensure!(log2_size < self.log2_root_size, SizeOutOfRangeSnafu);
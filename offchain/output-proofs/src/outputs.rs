@@ -0,0 +1,210 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Merkle trees of the outputs, laid out as in the Cartesi machine.
+//!
+//! The output hashes of each input are stored in a memory range whose leaves
+//! are 8-byte words. The roots of these ranges are the leaves of the epoch
+//! tree, which has one leaf for each input of the epoch.
+
+use crate::hash::{Digest, Hash, Hasher};
+use crate::merkle_tree::{self, complete::Tree, incremental, proof::Proof};
+
+/// Log2 of the size of a keccak hash
+pub const LOG2_HASH_SIZE: usize = 5;
+/// Log2 of the size of the memory range with the output hashes of an input
+pub const LOG2_OUTPUT_HASHES_SIZE: usize = 16 + LOG2_HASH_SIZE;
+/// Log2 of the size of the epoch tree, which has 2^32 leaves
+pub const LOG2_EPOCH_SIZE: usize = 32 + LOG2_HASH_SIZE;
+
+const LOG2_WORD_SIZE: usize = 3;
+const WORD_SIZE: usize = 1 << LOG2_WORD_SIZE;
+const WORDS_PER_HASH: usize = 1 << (LOG2_HASH_SIZE - LOG2_WORD_SIZE);
+
+/// Trait to be implemented by vouchers and notices
+pub trait Proofable {
+    fn get_hash(&self) -> &Hash;
+    fn set_proof(&mut self, proof: Proof);
+}
+
+/// Update the merkle proofs of every proofable in the array and return the merkle-tree's root hash
+pub fn compute_proofs(
+    proofables: &mut [impl Proofable],
+) -> Result<Hash, merkle_tree::Error> {
+    let mut hasher = Hasher::new();
+    let mut leaves: Vec<Hash> = vec![];
+    for proofable in proofables.iter() {
+        leaves.extend(hash_words(&mut hasher, proofable.get_hash()));
+    }
+    let tree = Tree::new_from_leaves(
+        LOG2_OUTPUT_HASHES_SIZE,
+        LOG2_WORD_SIZE,
+        LOG2_WORD_SIZE,
+        leaves,
+    )?;
+    for (i, proofable) in proofables.iter_mut().enumerate() {
+        let proof =
+            tree.get_proof(i * (1 << LOG2_HASH_SIZE), LOG2_HASH_SIZE)?;
+        proofable.set_proof(proof);
+    }
+    Ok(tree.get_root_hash().clone())
+}
+
+/// Return the hash of the node that holds an output hash in the memory range
+/// of the output hashes, which is the merkle root of its words.
+pub fn compute_output_node_hash(output_hash: &Hash) -> Hash {
+    let mut hasher = Hasher::new();
+    let words = hash_words(&mut hasher, output_hash);
+    Tree::new_from_leaves(LOG2_HASH_SIZE, LOG2_WORD_SIZE, LOG2_WORD_SIZE, words)
+        .expect("cannot fail")
+        .get_root_hash()
+        .clone()
+}
+
+/// Create an empty epoch tree, whose leaves are the output-hashes root hashes
+/// of the inputs.
+pub fn new_epoch_tree() -> incremental::Tree {
+    incremental::Tree::new(LOG2_EPOCH_SIZE, LOG2_HASH_SIZE, LOG2_HASH_SIZE)
+        .expect("cannot fail")
+}
+
+/// Memory range with the output hashes of an input, which is built as the
+/// outputs are emitted and proves them on request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputsTree {
+    tree: incremental::Tree,
+}
+
+impl OutputsTree {
+    pub fn new() -> Self {
+        Self {
+            tree: incremental::Tree::new(
+                LOG2_OUTPUT_HASHES_SIZE,
+                LOG2_HASH_SIZE,
+                LOG2_WORD_SIZE,
+            )
+            .expect("cannot fail"),
+        }
+    }
+
+    /// Append the hash of an output
+    pub fn push(
+        &mut self,
+        output_hash: &Hash,
+    ) -> Result<(), merkle_tree::Error> {
+        self.tree.push(compute_output_node_hash(output_hash))
+    }
+
+    /// Return the number of outputs
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// Return whether there are no outputs
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Return the output-hashes root hash
+    pub fn get_root_hash(&self) -> Hash {
+        self.tree.get_root_hash()
+    }
+
+    /// Return the proof of the output node hash with the given index
+    pub fn get_proof(
+        &mut self,
+        output_index: usize,
+    ) -> Result<Proof, merkle_tree::Error> {
+        self.tree
+            .get_proof(output_index << LOG2_HASH_SIZE, LOG2_HASH_SIZE)
+    }
+}
+
+impl Default for OutputsTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hash_words(hasher: &mut Hasher, hash: &Hash) -> Vec<Hash> {
+    (0..WORDS_PER_HASH)
+        .map(|word| {
+            let start = word * WORD_SIZE;
+            let end = start + WORD_SIZE;
+            hasher.update(&hash.data()[start..end]);
+            hasher.finalize_reset().into()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::HASH_SIZE;
+
+    struct Output {
+        hash: Hash,
+        proof: Option<Proof>,
+    }
+
+    impl Proofable for Output {
+        fn get_hash(&self) -> &Hash {
+            &self.hash
+        }
+
+        fn set_proof(&mut self, proof: Proof) {
+            self.proof = Some(proof);
+        }
+    }
+
+    #[test]
+    fn test_it_proves_output_node_hashes() {
+        let mut outputs: Vec<_> = (0..3)
+            .map(|i| Output {
+                hash: Hash::from([i; HASH_SIZE]),
+                proof: None,
+            })
+            .collect();
+        let root = compute_proofs(&mut outputs).unwrap();
+        for (i, output) in outputs.iter().enumerate() {
+            let proof = output.proof.as_ref().unwrap();
+            assert_eq!(proof.target_address, i << LOG2_HASH_SIZE);
+            assert_eq!(
+                proof.target_hash,
+                compute_output_node_hash(&output.hash)
+            );
+            assert_eq!(proof.root_hash, root);
+            assert!(proof.verify());
+        }
+    }
+
+    #[test]
+    fn test_it_proves_outputs_incrementally() {
+        let mut outputs: Vec<_> = (0..5)
+            .map(|i| Output {
+                hash: Hash::from([i; HASH_SIZE]),
+                proof: None,
+            })
+            .collect();
+        let root = compute_proofs(&mut outputs).unwrap();
+        let mut tree = OutputsTree::new();
+        for output in outputs.iter() {
+            tree.push(&output.hash).unwrap();
+        }
+        assert_eq!(tree.len(), outputs.len());
+        assert_eq!(tree.get_root_hash(), root);
+        for (i, output) in outputs.iter().enumerate() {
+            assert_eq!(
+                &tree.get_proof(i).unwrap(),
+                output.proof.as_ref().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_it_gets_pristine_root_hash_without_outputs() {
+        let root = compute_proofs(&mut Vec::<Output>::new()).unwrap();
+        assert!(OutputsTree::new().is_empty());
+        assert_eq!(OutputsTree::new().get_root_hash(), root);
+    }
+}
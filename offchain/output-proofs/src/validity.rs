@@ -0,0 +1,280 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+//! Validation of outputs against the claimed epoch hash, following the
+//! checks of `LibOutputValidation` in the rollups contracts.
+
+use snafu::{ensure, Snafu};
+
+use crate::hash::{Digest, Hash, Hasher};
+use crate::merkle_tree::proof::Proof;
+use crate::outputs::{
+    compute_output_node_hash, LOG2_EPOCH_SIZE, LOG2_HASH_SIZE,
+    LOG2_OUTPUT_HASHES_SIZE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputKind {
+    Voucher,
+    Notice,
+}
+
+/// Validity proof for an output, as the `RollupsOutputValidityProof` of the
+/// server-manager and the GraphQL API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputValidityProof {
+    pub input_index_within_epoch: u64,
+    pub output_index_within_input: u64,
+    pub output_hashes_root_hash: Hash,
+    pub vouchers_epoch_root_hash: Hash,
+    pub notices_epoch_root_hash: Hash,
+    pub machine_state_hash: Hash,
+    pub output_hash_in_output_hashes_siblings: Vec<Hash>,
+    pub output_hashes_in_epoch_siblings: Vec<Hash>,
+}
+
+#[derive(Debug, Snafu, PartialEq)]
+pub enum ValidityError {
+    #[snafu(display("incorrect epoch hash"))]
+    IncorrectEpochHash,
+    #[snafu(display("incorrect outputs epoch root hash"))]
+    IncorrectOutputsEpochRootHash,
+    #[snafu(display("incorrect output hashes root hash"))]
+    IncorrectOutputHashesRootHash,
+    #[snafu(display(
+        "invalid number of siblings for {} (expected {}, got {})",
+        name,
+        expected,
+        got
+    ))]
+    InvalidSiblingCount {
+        name: String,
+        expected: usize,
+        got: usize,
+    },
+}
+
+/// Compute the epoch hash claimed on-chain
+pub fn compute_epoch_hash(
+    vouchers_epoch_root_hash: &Hash,
+    notices_epoch_root_hash: &Hash,
+    machine_state_hash: &Hash,
+) -> Hash {
+    let mut hasher = Hasher::new();
+    hasher.update(vouchers_epoch_root_hash.data());
+    hasher.update(notices_epoch_root_hash.data());
+    hasher.update(machine_state_hash.data());
+    hasher.finalize().into()
+}
+
+/// Check that the output with the given hash is part of the epoch
+///
+/// - `output_hash`: Keccak of the encoded output, as computed by the
+///                  functions of the encoding module.
+pub fn validate_output(
+    kind: OutputKind,
+    output_hash: &Hash,
+    proof: &OutputValidityProof,
+    epoch_hash: &Hash,
+) -> Result<(), ValidityError> {
+    ensure!(
+        &compute_epoch_hash(
+            &proof.vouchers_epoch_root_hash,
+            &proof.notices_epoch_root_hash,
+            &proof.machine_state_hash,
+        ) == epoch_hash,
+        IncorrectEpochHashSnafu
+    );
+    let outputs_epoch_root_hash = match kind {
+        OutputKind::Voucher => &proof.vouchers_epoch_root_hash,
+        OutputKind::Notice => &proof.notices_epoch_root_hash,
+    };
+    let epoch_proof = build_proof(
+        "output hashes in epoch",
+        proof.input_index_within_epoch,
+        proof.output_hashes_root_hash.clone(),
+        LOG2_EPOCH_SIZE,
+        outputs_epoch_root_hash.clone(),
+        &proof.output_hashes_in_epoch_siblings,
+    )?;
+    ensure!(epoch_proof.verify(), IncorrectOutputsEpochRootHashSnafu);
+    let output_proof = build_proof(
+        "output hash in output hashes",
+        proof.output_index_within_input,
+        compute_output_node_hash(output_hash),
+        LOG2_OUTPUT_HASHES_SIZE,
+        proof.output_hashes_root_hash.clone(),
+        &proof.output_hash_in_output_hashes_siblings,
+    )?;
+    ensure!(output_proof.verify(), IncorrectOutputHashesRootHashSnafu);
+    Ok(())
+}
+
+fn build_proof(
+    name: &str,
+    index: u64,
+    target_hash: Hash,
+    log2_root_size: usize,
+    root_hash: Hash,
+    sibling_hashes: &[Hash],
+) -> Result<Proof, ValidityError> {
+    let expected = log2_root_size - LOG2_HASH_SIZE;
+    ensure!(
+        sibling_hashes.len() == expected,
+        InvalidSiblingCountSnafu {
+            name,
+            expected,
+            got: sibling_hashes.len(),
+        }
+    );
+    Ok(Proof {
+        target_address: (index as usize) << LOG2_HASH_SIZE,
+        log2_target_size: LOG2_HASH_SIZE,
+        target_hash,
+        log2_root_size,
+        root_hash,
+        sibling_hashes: sibling_hashes.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{compute_notice_hash, compute_voucher_hash};
+    use crate::outputs::{compute_proofs, new_epoch_tree, Proofable};
+
+    struct Output {
+        hash: Hash,
+        proof: Option<Proof>,
+    }
+
+    impl Proofable for Output {
+        fn get_hash(&self) -> &Hash {
+            &self.hash
+        }
+
+        fn set_proof(&mut self, proof: Proof) {
+            self.proof = Some(proof);
+        }
+    }
+
+    /// Builds an epoch with two inputs, where the second one has two vouchers
+    /// and one notice, and returns the proof of the second voucher
+    fn setup() -> (Hash, OutputValidityProof, Hash) {
+        let destination = [0x55; 20];
+        let mut vouchers = vec![
+            Output {
+                hash: compute_voucher_hash(&destination, b"first"),
+                proof: None,
+            },
+            Output {
+                hash: compute_voucher_hash(&destination, b"second"),
+                proof: None,
+            },
+        ];
+        let mut notices = vec![Output {
+            hash: compute_notice_hash(b"notice"),
+            proof: None,
+        }];
+        let voucher_root = compute_proofs(&mut vouchers).unwrap();
+        let notice_root = compute_proofs(&mut notices).unwrap();
+        let mut vouchers_tree = new_epoch_tree();
+        let mut notices_tree = new_epoch_tree();
+        vouchers_tree.push(Hash::default()).unwrap();
+        notices_tree.push(Hash::default()).unwrap();
+        vouchers_tree.push(voucher_root.clone()).unwrap();
+        notices_tree.push(notice_root).unwrap();
+        let machine_state_hash = Hash::from([0xAB; 32]);
+        let epoch_hash = compute_epoch_hash(
+            &vouchers_tree.get_root_hash(),
+            &notices_tree.get_root_hash(),
+            &machine_state_hash,
+        );
+        let in_epoch = vouchers_tree
+            .get_proof(1 << LOG2_HASH_SIZE, LOG2_HASH_SIZE)
+            .unwrap();
+        let voucher = vouchers.pop().unwrap();
+        let proof = OutputValidityProof {
+            input_index_within_epoch: 1,
+            output_index_within_input: 1,
+            output_hashes_root_hash: voucher_root,
+            vouchers_epoch_root_hash: vouchers_tree.get_root_hash(),
+            notices_epoch_root_hash: notices_tree.get_root_hash(),
+            machine_state_hash,
+            output_hash_in_output_hashes_siblings: voucher
+                .proof
+                .unwrap()
+                .sibling_hashes,
+            output_hashes_in_epoch_siblings: in_epoch.sibling_hashes,
+        };
+        (voucher.hash, proof, epoch_hash)
+    }
+
+    #[test]
+    fn test_it_computes_epoch_hash() {
+        let hash = Hash::from([0xFF; 32]);
+        assert_eq!(
+            compute_epoch_hash(&hash, &hash, &hash),
+            Hash::decode("8590bbc3ea43e28e8624fb1a2d59aaca701a5517e08511c4a14d9037de6f6086")
+        );
+    }
+
+    #[test]
+    fn test_it_validates_output() {
+        let (hash, proof, epoch_hash) = setup();
+        validate_output(OutputKind::Voucher, &hash, &proof, &epoch_hash)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_it_fails_to_validate_output_with_wrong_epoch_hash() {
+        let (hash, proof, _) = setup();
+        let err = validate_output(
+            OutputKind::Voucher,
+            &hash,
+            &proof,
+            &Hash::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ValidityError::IncorrectEpochHash);
+    }
+
+    #[test]
+    fn test_it_fails_to_validate_output_of_wrong_kind() {
+        let (hash, proof, epoch_hash) = setup();
+        let err =
+            validate_output(OutputKind::Notice, &hash, &proof, &epoch_hash)
+                .unwrap_err();
+        assert_eq!(err, ValidityError::IncorrectOutputsEpochRootHash);
+    }
+
+    #[test]
+    fn test_it_fails_to_validate_wrong_output() {
+        let (_, mut proof, epoch_hash) = setup();
+        let hash = compute_voucher_hash(&[0x55; 20], b"first");
+        let err =
+            validate_output(OutputKind::Voucher, &hash, &proof, &epoch_hash)
+                .unwrap_err();
+        assert_eq!(err, ValidityError::IncorrectOutputHashesRootHash);
+        proof.output_index_within_input = 0;
+        validate_output(OutputKind::Voucher, &hash, &proof, &epoch_hash)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_it_fails_to_validate_proof_with_missing_siblings() {
+        let (hash, mut proof, epoch_hash) = setup();
+        proof.output_hashes_in_epoch_siblings.pop();
+        let err =
+            validate_output(OutputKind::Voucher, &hash, &proof, &epoch_hash)
+                .unwrap_err();
+        assert_eq!(
+            err,
+            ValidityError::InvalidSiblingCount {
+                name: String::from("output hashes in epoch"),
+                expected: 32,
+                got: 31,
+            }
+        );
+    }
+}
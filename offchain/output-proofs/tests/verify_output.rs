@@ -0,0 +1,131 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use output_proofs::encoding::compute_notice_hash;
+use output_proofs::hash::Hash;
+use output_proofs::merkle_tree::proof::Proof;
+use output_proofs::outputs::{
+    compute_proofs, new_epoch_tree, Proofable, LOG2_HASH_SIZE,
+};
+use output_proofs::validity::compute_epoch_hash;
+use std::process::{Command, Output};
+
+struct Notice {
+    hash: Hash,
+    proof: Option<Proof>,
+}
+
+impl Proofable for Notice {
+    fn get_hash(&self) -> &Hash {
+        &self.hash
+    }
+
+    fn set_proof(&mut self, proof: Proof) {
+        self.proof = Some(proof);
+    }
+}
+
+fn hex_encode(hash: &Hash) -> String {
+    format!("0x{}", hex::encode(hash.data()))
+}
+
+/// Builds an epoch with a single notice and returns the GraphQL response of
+/// the notice query along with the epoch hash
+fn setup() -> (serde_json::Value, Hash) {
+    let payload = b"hello world";
+    let mut notices = vec![Notice {
+        hash: compute_notice_hash(payload),
+        proof: None,
+    }];
+    let notice_root = compute_proofs(&mut notices).unwrap();
+    let vouchers_tree = new_epoch_tree();
+    let mut notices_tree = new_epoch_tree();
+    notices_tree.push(notice_root.clone()).unwrap();
+    let machine_state_hash = Hash::default();
+    let epoch_hash = compute_epoch_hash(
+        &vouchers_tree.get_root_hash(),
+        &notices_tree.get_root_hash(),
+        &machine_state_hash,
+    );
+    let in_epoch = notices_tree.get_proof(0, LOG2_HASH_SIZE).unwrap();
+    let in_outputs = notices.pop().unwrap().proof.unwrap();
+    let json = serde_json::json!({
+        "data": {
+            "notice": {
+                "index": 0,
+                "payload": format!("0x{}", hex::encode(payload)),
+                "proof": {
+                    "validity": {
+                        "inputIndexWithinEpoch": 0,
+                        "outputIndexWithinInput": 0,
+                        "outputHashesRootHash": hex_encode(&notice_root),
+                        "vouchersEpochRootHash":
+                            hex_encode(&vouchers_tree.get_root_hash()),
+                        "noticesEpochRootHash":
+                            hex_encode(&notices_tree.get_root_hash()),
+                        "machineStateHash": hex_encode(&machine_state_hash),
+                        "outputHashInOutputHashesSiblings": in_outputs
+                            .sibling_hashes
+                            .iter()
+                            .map(hex_encode)
+                            .collect::<Vec<_>>(),
+                        "outputHashesInEpochSiblings": in_epoch
+                            .sibling_hashes
+                            .iter()
+                            .map(hex_encode)
+                            .collect::<Vec<_>>(),
+                    },
+                    "context": "0x",
+                }
+            }
+        }
+    });
+    (json, epoch_hash)
+}
+
+fn verify_output(json: &serde_json::Value, epoch_hash: &Hash) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notice.json");
+    std::fs::write(&path, json.to_string()).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_cartesi-rollups-verify-output"))
+        .arg("--output-file")
+        .arg(&path)
+        .arg("--epoch-hash")
+        .arg(hex_encode(epoch_hash))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_it_verifies_valid_output() {
+    let (json, epoch_hash) = setup();
+    let output = verify_output(&json, &epoch_hash);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "output is valid\n"
+    );
+}
+
+#[test]
+fn test_it_rejects_output_from_another_epoch() {
+    let (json, _) = setup();
+    let output = verify_output(&json, &Hash::default());
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "output is invalid: incorrect epoch hash\n"
+    );
+}
+
+#[test]
+fn test_it_rejects_tampered_output() {
+    let (mut json, epoch_hash) = setup();
+    json["data"]["notice"]["payload"] = serde_json::Value::from("0x00");
+    let output = verify_output(&json, &epoch_hash);
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "output is invalid: incorrect output hashes root hash\n"
+    );
+}
@@ -14,22 +14,30 @@ use snafu::ResultExt;
 
 use super::ConnectionSnafu;
 use crate::{
-    Broker, BrokerError, BrokerStream, DAppMetadata, Event, RollupsInput,
-    RollupsInputsStream, RollupsOutput, RollupsOutputsStream, INITIAL_ID,
+    Broker, BrokerError, BrokerStream, DAppMetadata, Event, RollupsClaim,
+    RollupsClaimsStream, RollupsInput, RollupsInputsStream, RollupsOutput,
+    RollupsOutputsStream, RollupsVoucherExecution,
+    RollupsVoucherExecutionsStream, INITIAL_ID,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum IndexerEvent {
     Input(Event<RollupsInput>),
     Output(Event<RollupsOutput>),
+    Claim(Event<RollupsClaim>),
+    VoucherExecution(Event<RollupsVoucherExecution>),
 }
 
 #[derive(Debug)]
 pub struct IndexerState {
     inputs_last_id: String,
     outputs_last_id: String,
+    claims_last_id: String,
+    voucher_executions_last_id: String,
     inputs_stream: RollupsInputsStream,
     outputs_stream: RollupsOutputsStream,
+    claims_stream: RollupsClaimsStream,
+    voucher_executions_stream: RollupsVoucherExecutionsStream,
 }
 
 impl IndexerState {
@@ -37,16 +45,26 @@ impl IndexerState {
         Self {
             inputs_last_id: INITIAL_ID.to_owned(),
             outputs_last_id: INITIAL_ID.to_owned(),
+            claims_last_id: INITIAL_ID.to_owned(),
+            voucher_executions_last_id: INITIAL_ID.to_owned(),
             inputs_stream: RollupsInputsStream::new(dapp_metadata),
             outputs_stream: RollupsOutputsStream::new(dapp_metadata),
+            claims_stream: RollupsClaimsStream::new(dapp_metadata),
+            voucher_executions_stream: RollupsVoucherExecutionsStream::new(
+                dapp_metadata,
+            ),
         }
     }
 }
 
 impl Broker {
     /// Consume an event from the Input stream and if there is none,
-    /// consume from the Output stream. This is a blocking operation.
-    /// Return IndexerEvent::Input if present or IndexerEvent::Output otherwise
+    /// consume from the Output stream, then from the Claim stream and then
+    /// from the Voucher Execution stream.
+    /// This is a blocking operation.
+    /// Return IndexerEvent::Input if present, IndexerEvent::Output if there
+    /// is an output, IndexerEvent::Claim if there is a claim, or
+    /// IndexerEvent::VoucherExecution otherwise
     #[tracing::instrument(level = "trace", skip_all)]
     pub async fn indexer_consume(
         &self,
@@ -54,10 +72,21 @@ impl Broker {
     ) -> Result<IndexerEvent, BrokerError> {
         let input_stream_key = state.inputs_stream.key();
         let output_stream_key = state.outputs_stream.key();
+        let claim_stream_key = state.claims_stream.key();
+        let execution_stream_key = state.voucher_executions_stream.key();
         let mut reply = retry(self.backoff.clone(), || async {
-            let stream_keys = [&input_stream_key, &output_stream_key];
-            let last_consumed_ids =
-                [&state.inputs_last_id, &state.outputs_last_id];
+            let stream_keys = [
+                &input_stream_key,
+                &output_stream_key,
+                &claim_stream_key,
+                &execution_stream_key,
+            ];
+            let last_consumed_ids = [
+                &state.inputs_last_id,
+                &state.outputs_last_id,
+                &state.claims_last_id,
+                &state.voucher_executions_last_id,
+            ];
             tracing::trace!(
                 ?stream_keys,
                 ?last_consumed_ids,
@@ -100,6 +129,30 @@ impl Broker {
             return Ok(IndexerEvent::Output(event));
         }
 
+        let claim_stream_id = reply
+            .keys
+            .iter_mut()
+            .find(|stream| stream.key == claim_stream_key)
+            .and_then(|stream| stream.ids.pop());
+        if let Some(stream_id) = claim_stream_id {
+            tracing::trace!("found claim event; parsing it");
+            let event: Event<RollupsClaim> = stream_id.try_into()?;
+            state.claims_last_id = event.id.clone();
+            return Ok(IndexerEvent::Claim(event));
+        }
+
+        let execution_stream_id = reply
+            .keys
+            .iter_mut()
+            .find(|stream| stream.key == execution_stream_key)
+            .and_then(|stream| stream.ids.pop());
+        if let Some(stream_id) = execution_stream_id {
+            tracing::trace!("found voucher execution event; parsing it");
+            let event: Event<RollupsVoucherExecution> = stream_id.try_into()?;
+            state.voucher_executions_last_id = event.id.clone();
+            return Ok(IndexerEvent::VoucherExecution(event));
+        }
+
         tracing::trace!("indexer consume timed out");
         Err(BrokerError::ConsumeTimeout)
     }
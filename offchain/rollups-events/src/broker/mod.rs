@@ -280,6 +280,52 @@ impl Broker {
             Ok(None)
         }
     }
+
+    /// Consume up to `count` events in stream without blocking
+    /// This function returns an empty vector if there are no more remaining events.
+    /// To consume the first events in the stream, `last_consumed_id` should be `INITIAL_ID`.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn consume_batch_nonblocking<S: BrokerStream>(
+        &mut self,
+        stream: &S,
+        last_consumed_id: &str,
+        count: usize,
+    ) -> Result<Vec<Event<S::Payload>>, BrokerError> {
+        let mut reply = retry(self.backoff.clone(), || async {
+            tracing::trace!(
+                stream_key = stream.key(),
+                last_consumed_id,
+                count,
+                "consuming events (non-blocking)"
+            );
+            let opts = StreamReadOptions::default().count(count);
+            let reply: StreamReadReply = self
+                .connection
+                .clone()
+                .xread_options(&[stream.key()], &[last_consumed_id], &opts)
+                .await?;
+
+            Ok(reply)
+        })
+        .await
+        .context(ConnectionSnafu)?;
+
+        tracing::trace!("checking if events were received");
+        match reply.keys.pop() {
+            Some(events) => {
+                tracing::trace!("parsing received events");
+                events
+                    .ids
+                    .into_iter()
+                    .map(|event| event.try_into())
+                    .collect()
+            }
+            None => {
+                tracing::trace!("stream is empty");
+                Ok(vec![])
+            }
+        }
+    }
 }
 
 /// Custom implementation of Debug because ConnectionManager doesn't implement debug
@@ -4,8 +4,10 @@
 mod broker;
 mod common;
 mod rollups_claims;
+mod rollups_executions;
 mod rollups_inputs;
 mod rollups_outputs;
+mod rollups_reorgs;
 mod rollups_stream;
 
 pub use broker::{
@@ -14,13 +16,19 @@ pub use broker::{
 };
 pub use common::{Address, Hash, Payload, ADDRESS_SIZE, HASH_SIZE};
 pub use rollups_claims::{RollupsClaim, RollupsClaimsStream};
+pub use rollups_executions::{
+    RollupsVoucherExecution, RollupsVoucherExecutionsStream,
+};
 pub use rollups_inputs::{
     InputMetadata, RollupsAdvanceStateInput, RollupsData, RollupsInput,
-    RollupsInputsStream,
+    RollupsInputRejection, RollupsInputsStream, RollupsRejectedInput,
 };
 pub use rollups_outputs::{
     RollupsAdvanceResult, RollupsCompletionStatus, RollupsNotice,
     RollupsOutput, RollupsOutputEnum, RollupsOutputValidityProof,
     RollupsOutputsStream, RollupsProof, RollupsReport, RollupsVoucher,
 };
+pub use rollups_reorgs::{
+    RollupsReorg, RollupsReorgedInput, RollupsReorgsStream,
+};
 pub use rollups_stream::{DAppMetadata, DAppMetadataCLIConfig};
@@ -0,0 +1,31 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use serde::{Deserialize, Serialize};
+
+use crate::{rollups_stream::decl_broker_stream, Hash};
+
+decl_broker_stream!(
+    RollupsVoucherExecutionsStream,
+    RollupsVoucherExecution,
+    "rollups-voucher-executions"
+);
+
+/// Event generated when a voucher is executed in the DApp contract
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RollupsVoucherExecution {
+    /// Number of sent voucher executions, including this one
+    pub executions_sent_count: u64,
+
+    /// Index of the input that produced the voucher
+    pub input_index: u64,
+
+    /// Index of the voucher within the input
+    pub index: u64,
+
+    /// Hash of the transaction that executed the voucher
+    pub tx_hash: Hash,
+
+    /// Number of the block in which the voucher was executed
+    pub block_number: u64,
+}
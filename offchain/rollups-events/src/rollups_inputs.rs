@@ -33,6 +33,10 @@ pub enum RollupsData {
     /// Input that advances the Cartesi Rollups epoch
     AdvanceStateInput(RollupsAdvanceStateInput),
 
+    /// Input rejected by the dispatcher; it takes an input index, so the
+    /// input indices stay consistent with the chain, but carries no payload
+    RejectedInput(RollupsRejectedInput),
+
     /// End of an Cartesi Rollups epoch
     FinishEpoch {},
 }
@@ -50,6 +54,32 @@ pub struct RollupsAdvanceStateInput {
     pub tx_hash: Hash,
 }
 
+/// Input rejected by the dispatcher
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RollupsRejectedInput {
+    /// Information sent via the input metadata memory range
+    pub metadata: InputMetadata,
+
+    /// Transaction hash
+    pub tx_hash: Hash,
+
+    /// Reason why the dispatcher rejected the input
+    pub rejection: RollupsInputRejection,
+}
+
+/// Reason why the dispatcher rejected an input
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RollupsInputRejection {
+    /// The sender is denied or is not in the allowlist
+    SenderNotAllowed,
+
+    /// The payload is larger than the maximum payload size
+    PayloadTooLarge { size: u64, max_size: u64 },
+
+    /// The sender already sent the maximum number of inputs in the epoch
+    SenderRateLimited { max_inputs: u64 },
+}
+
 #[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct InputMetadata {
     /// Address of the message sender
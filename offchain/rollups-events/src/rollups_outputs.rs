@@ -42,7 +42,14 @@ pub struct RollupsVoucher {
     pub index: u64,
     pub input_index: u64,
     pub destination: Address,
+    /// Amount of wei sent with the call, as a big-endian uint256; legacy
+    /// vouchers have no value
+    #[serde(default)]
+    pub value: Option<Payload>,
     pub payload: Payload,
+    /// Whether the voucher runs the payload with DELEGATECALL
+    #[serde(default)]
+    pub is_delegate_call: bool,
 }
 
 #[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
@@ -0,0 +1,43 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use serde::{Deserialize, Serialize};
+
+use crate::{rollups_stream::decl_broker_stream, Hash};
+
+decl_broker_stream!(RollupsReorgsStream, RollupsReorg, "rollups-reorgs");
+
+/// Event generated when a blockchain reorg changes an input that was already
+/// sent to the rollups-inputs stream
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RollupsReorg {
+    /// Index of the first input that diverged from the new chain
+    pub input_index: u64,
+
+    /// Input as it was sent to the rollups-inputs stream
+    pub sent_input: RollupsReorgedInput,
+
+    /// Input with the same index in the new chain; None if it was removed
+    pub new_input: Option<RollupsReorgedInput>,
+
+    /// Number of the block in which the reorg was detected
+    pub block_number: u64,
+
+    /// Hash of the block in which the reorg was detected
+    pub block_hash: Hash,
+}
+
+/// Identifies where an input was added to the blockchain
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RollupsReorgedInput {
+    /// Hash of the transaction that added the input
+    pub tx_hash: Hash,
+
+    /// Number of the block in which the input was added
+    pub block_number: u64,
+
+    /// Hash of the block in which the input was added; unknown for the
+    /// inputs sent before the dispatcher restarted, because the
+    /// rollups-inputs stream doesn't record it
+    pub block_hash: Option<Hash>,
+}
@@ -6,8 +6,9 @@ use rollups_events::indexer::{IndexerEvent, IndexerState};
 use rollups_events::{
     Address, Broker, BrokerConfig, BrokerEndpoint, BrokerError, BrokerStream,
     DAppMetadata, Event, Hash, RedactedUrl, RollupsAdvanceStateInput,
-    RollupsData, RollupsInput, RollupsInputsStream, RollupsOutput,
-    RollupsOutputsStream, Url,
+    RollupsClaim, RollupsClaimsStream, RollupsData, RollupsInput,
+    RollupsInputsStream, RollupsOutput, RollupsOutputsStream,
+    RollupsVoucherExecution, RollupsVoucherExecutionsStream, Url,
 };
 use testcontainers::{
     clients::Cli, core::WaitFor, images::generic::GenericImage, Container,
@@ -157,6 +158,89 @@ async fn it_consumes_inputs_before_outputs() {
     }
 }
 
+#[test_log::test(tokio::test)]
+async fn it_consumes_outputs_before_claims() {
+    let docker = Cli::default();
+    let state = TestState::setup(&docker).await;
+    let mut broker = state.create_broker().await;
+    // First, produce claim events
+    let claims = generate_claims();
+    let metadata = dapp_metadata();
+    let claims_stream = RollupsClaimsStream::new(&metadata);
+    produce_all(&mut broker, &claims_stream, &claims).await;
+    // Then, produce output events
+    let outputs = generate_outputs();
+    let outputs_stream = RollupsOutputsStream::new(&metadata);
+    produce_all(&mut broker, &outputs_stream, &outputs).await;
+    // Finally, consume indexer events
+    let consumed_events =
+        consume_all(&mut broker, &metadata, outputs.len() + claims.len()).await;
+    for (i, output) in outputs.iter().enumerate() {
+        assert!(matches!(&consumed_events[i],
+            IndexerEvent::Output(
+                Event {
+                    payload,
+                    ..
+                }
+            )
+            if payload == output
+        ));
+    }
+    for (i, claim) in claims.iter().enumerate() {
+        assert!(matches!(&consumed_events[outputs.len() + i],
+            IndexerEvent::Claim(
+                Event {
+                    payload,
+                    ..
+                }
+            )
+            if payload == claim
+        ));
+    }
+}
+
+#[test_log::test(tokio::test)]
+async fn it_consumes_claims_before_voucher_executions() {
+    let docker = Cli::default();
+    let state = TestState::setup(&docker).await;
+    let mut broker = state.create_broker().await;
+    // First, produce voucher execution events
+    let executions = generate_voucher_executions();
+    let metadata = dapp_metadata();
+    let executions_stream = RollupsVoucherExecutionsStream::new(&metadata);
+    produce_all(&mut broker, &executions_stream, &executions).await;
+    // Then, produce claim events
+    let claims = generate_claims();
+    let claims_stream = RollupsClaimsStream::new(&metadata);
+    produce_all(&mut broker, &claims_stream, &claims).await;
+    // Finally, consume indexer events
+    let consumed_events =
+        consume_all(&mut broker, &metadata, claims.len() + executions.len())
+            .await;
+    for (i, claim) in claims.iter().enumerate() {
+        assert!(matches!(&consumed_events[i],
+            IndexerEvent::Claim(
+                Event {
+                    payload,
+                    ..
+                }
+            )
+            if payload == claim
+        ));
+    }
+    for (i, execution) in executions.iter().enumerate() {
+        assert!(matches!(&consumed_events[claims.len() + i],
+            IndexerEvent::VoucherExecution(
+                Event {
+                    payload,
+                    ..
+                }
+            )
+            if payload == execution
+        ));
+    }
+}
+
 fn dapp_metadata() -> DAppMetadata {
     DAppMetadata {
         chain_id: CHAIN_ID,
@@ -172,6 +256,42 @@ fn generate_outputs() -> Vec<RollupsOutput> {
     ]
 }
 
+fn generate_claims() -> Vec<RollupsClaim> {
+    vec![
+        RollupsClaim {
+            epoch_index: 0,
+            epoch_hash: Hash::new([0xa0; 32]),
+            first_index: 0,
+            last_index: 0,
+        },
+        RollupsClaim {
+            epoch_index: 1,
+            epoch_hash: Hash::new([0xa1; 32]),
+            first_index: 1,
+            last_index: 1,
+        },
+    ]
+}
+
+fn generate_voucher_executions() -> Vec<RollupsVoucherExecution> {
+    vec![
+        RollupsVoucherExecution {
+            executions_sent_count: 1,
+            input_index: 0,
+            index: 0,
+            tx_hash: Hash::new([0xb0; 32]),
+            block_number: 10,
+        },
+        RollupsVoucherExecution {
+            executions_sent_count: 2,
+            input_index: 1,
+            index: 0,
+            tx_hash: Hash::new([0xb1; 32]),
+            block_number: 11,
+        },
+    ]
+}
+
 fn generate_inputs() -> Vec<RollupsInput> {
     vec![
         RollupsInput {
@@ -275,6 +275,40 @@ async fn test_it_consumes_events_without_blocking() {
     }
 }
 
+#[test_log::test(tokio::test)]
+async fn test_it_consumes_batches_of_events_without_blocking() {
+    let docker = Cli::default();
+    let mut state = TestState::setup(&docker).await;
+    // Produce multiple events directly in Redis
+    const N: usize = 5;
+    for i in 0..N {
+        let id = format!("1-{}", i);
+        let data = format!(r#"{{"data":"{}"}}"#, i);
+        let _: String = state
+            .conn
+            .xadd(STREAM_KEY, id, &[("payload", data)])
+            .await
+            .expect("failed to add events");
+    }
+    // Consume events in batches using the Broker struct
+    let mut broker = state.create_broker().await;
+    let mut last_id = INITIAL_ID.to_owned();
+    let mut batch_lens = vec![];
+    loop {
+        let events = broker
+            .consume_batch_nonblocking(&MockStream {}, &last_id, 2)
+            .await
+            .expect("failed to consume");
+        match events.last() {
+            Some(event) => last_id = event.id.clone(),
+            None => break,
+        }
+        batch_lens.push(events.len());
+    }
+    assert_eq!(batch_lens, vec![2, 2, 1]);
+    assert_eq!(last_id, format!("1-{}", N - 1));
+}
+
 #[test_log::test(tokio::test)]
 async fn test_it_does_not_block_when_consuming_empty_stream() {
     let docker = Cli::default();
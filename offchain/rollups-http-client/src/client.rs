@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0 (see LICENSE)
 
 use crate::rollup::{
-    AdvanceRequest, Exception, IndexResponse, InspectRequest, Notice, Report,
-    RollupRequest, RollupResponse, Voucher,
+    AdvanceRequest, DelegateCallVoucher, Exception, IndexResponse,
+    InspectRequest, Notice, Report, RollupRequest, RollupResponse, Voucher,
 };
 use serde::{Deserialize, Serialize};
 use std::io::ErrorKind;
@@ -44,6 +44,42 @@ pub async fn send_voucher(rollup_http_server_addr: &str, voucher: Voucher) {
     }
 }
 
+pub async fn send_delegate_call_voucher(
+    rollup_http_server_addr: &str,
+    voucher: DelegateCallVoucher,
+) {
+    tracing::debug!(
+        "sending delegate call voucher request to {}",
+        rollup_http_server_addr
+    );
+    let client = hyper::Client::new();
+    let req = hyper::Request::builder()
+        .method(hyper::Method::POST)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .uri(rollup_http_server_addr.to_string() + "/delegate-call-voucher")
+        .body(hyper::Body::from(serde_json::to_string(&voucher).unwrap()))
+        .expect("delegate call voucher request");
+    match client.request(req).await {
+        Ok(res) => {
+            let id_response = serde_json::from_slice::<IndexResponse>(
+                &hyper::body::to_bytes(res)
+                    .await
+                    .expect("error in voucher in response handling"),
+            );
+            tracing::debug!(
+                "delegate call voucher generated: {:?}",
+                &id_response
+            );
+        }
+        Err(e) => {
+            tracing::error!(
+                "failed to send delegate call voucher request to rollup http server: {}",
+                e
+            );
+        }
+    }
+}
+
 pub async fn send_notice(rollup_http_server_addr: &str, notice: Notice) {
     tracing::debug!("sending notice request to {}", rollup_http_server_addr);
     let client = hyper::Client::new();
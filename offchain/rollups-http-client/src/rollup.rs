@@ -37,6 +37,16 @@ pub struct Notice {
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Voucher {
+    pub destination: String,
+    /// Amount of wei sent with the call, as a big-endian uint256 in
+    /// Ethereum hex binary format; legacy vouchers have no value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub payload: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegateCallVoucher {
     pub destination: String,
     pub payload: String,
 }
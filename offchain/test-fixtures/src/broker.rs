@@ -5,8 +5,9 @@ use backoff::ExponentialBackoff;
 use rollups_events::{
     Address, Broker, BrokerConfig, BrokerEndpoint, DAppMetadata, Event,
     RedactedUrl, RollupsClaim, RollupsClaimsStream, RollupsData, RollupsInput,
-    RollupsInputsStream, RollupsOutput, RollupsOutputsStream, Url,
-    ADDRESS_SIZE, INITIAL_ID,
+    RollupsInputsStream, RollupsOutput, RollupsOutputsStream, RollupsReorg,
+    RollupsReorgsStream, RollupsVoucherExecution,
+    RollupsVoucherExecutionsStream, Url, ADDRESS_SIZE, INITIAL_ID,
 };
 use testcontainers::{
     clients::Cli, core::WaitFor, images::generic::GenericImage, Container,
@@ -23,6 +24,8 @@ pub struct BrokerFixture<'d> {
     inputs_stream: RollupsInputsStream,
     claims_stream: RollupsClaimsStream,
     outputs_stream: RollupsOutputsStream,
+    reorgs_stream: RollupsReorgsStream,
+    voucher_executions_stream: RollupsVoucherExecutionsStream,
     redis_endpoint: BrokerEndpoint,
     chain_id: u64,
     dapp_address: Address,
@@ -54,6 +57,9 @@ impl BrokerFixture<'_> {
         let inputs_stream = RollupsInputsStream::new(&metadata);
         let claims_stream = RollupsClaimsStream::new(&metadata);
         let outputs_stream = RollupsOutputsStream::new(&metadata);
+        let reorgs_stream = RollupsReorgsStream::new(&metadata);
+        let voucher_executions_stream =
+            RollupsVoucherExecutionsStream::new(&metadata);
         let config = BrokerConfig {
             redis_endpoint: redis_endpoint.clone(),
             consume_timeout: CONSUME_TIMEOUT,
@@ -75,6 +81,8 @@ impl BrokerFixture<'_> {
             inputs_stream,
             claims_stream,
             outputs_stream,
+            reorgs_stream,
+            voucher_executions_stream,
             redis_endpoint,
             chain_id,
             dapp_address,
@@ -120,7 +128,8 @@ impl BrokerFixture<'_> {
         let last_event = self.get_latest_input_event().await;
         let epoch_index = match last_event.as_ref() {
             Some(event) => match event.payload.data {
-                RollupsData::AdvanceStateInput { .. } => {
+                RollupsData::AdvanceStateInput { .. }
+                | RollupsData::RejectedInput { .. } => {
                     event.payload.epoch_index
                 }
                 RollupsData::FinishEpoch {} => event.payload.epoch_index + 1,
@@ -132,7 +141,8 @@ impl BrokerFixture<'_> {
             None => 0,
         };
         let inputs_sent_count = match data {
-            RollupsData::AdvanceStateInput { .. } => {
+            RollupsData::AdvanceStateInput { .. }
+            | RollupsData::RejectedInput { .. } => {
                 previous_inputs_sent_count + 1
             }
             RollupsData::FinishEpoch {} => previous_inputs_sent_count,
@@ -245,4 +255,61 @@ impl BrokerFixture<'_> {
             .await
             .expect("failed to produce output");
     }
+
+    /// Obtain all produced reorg events
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn consume_all_reorgs(&self) -> Vec<RollupsReorg> {
+        tracing::trace!("consuming all rollups-reorgs events");
+        let mut reorgs = vec![];
+        let mut last_id = INITIAL_ID.to_owned();
+        while let Some(event) = self
+            .client
+            .lock()
+            .await
+            .consume_nonblocking(&self.reorgs_stream, &last_id)
+            .await
+            .expect("failed to consume reorg")
+        {
+            reorgs.push(event.payload);
+            last_id = event.id;
+        }
+        reorgs
+    }
+
+    /// Produce a voucher execution event
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn produce_voucher_execution(
+        &self,
+        execution: RollupsVoucherExecution,
+    ) {
+        tracing::trace!(?execution, "producing voucher execution event");
+        self.client
+            .lock()
+            .await
+            .produce(&self.voucher_executions_stream, execution)
+            .await
+            .expect("failed to produce voucher execution");
+    }
+
+    /// Obtain all produced voucher execution events
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn consume_all_voucher_executions(
+        &self,
+    ) -> Vec<RollupsVoucherExecution> {
+        tracing::trace!("consuming all voucher execution events");
+        let mut executions = vec![];
+        let mut last_id = INITIAL_ID.to_owned();
+        while let Some(event) = self
+            .client
+            .lock()
+            .await
+            .consume_nonblocking(&self.voucher_executions_stream, &last_id)
+            .await
+            .expect("failed to consume voucher execution")
+        {
+            executions.push(event.payload);
+            last_id = event.id;
+        }
+        executions
+    }
 }
@@ -3,6 +3,7 @@
 
 use backoff::ExponentialBackoffBuilder;
 use rollups_data::{RedactedUrl, Repository, RepositoryConfig, Url};
+use std::future::Future;
 use std::time::Duration;
 use testcontainers::clients::Cli;
 
@@ -18,11 +19,12 @@ pub struct RepositoryFixture<'d> {
 }
 
 impl RepositoryFixture<'_> {
-    pub fn setup(docker: &Cli) -> RepositoryFixture {
+    pub async fn setup(docker: &Cli) -> RepositoryFixture {
         let data = DataFixture::setup(docker);
         let config = create_repository_config(data.port());
-        let repository =
-            Repository::new(config).expect("failed to create repository");
+        let repository = Repository::new(config)
+            .await
+            .expect("failed to create repository");
         RepositoryFixture { data, repository }
     }
 
@@ -35,34 +37,30 @@ impl RepositoryFixture<'_> {
     }
 
     /// Calls f until it returns Ok or an error different from ItemNotFound.
-    /// This function is async to allow other services to run in background.
-    pub async fn retry<F, T>(&self, mut f: F) -> T
+    pub async fn retry<F, Fut, T>(&self, mut f: F) -> T
     where
-        F: FnMut(&Repository) -> Result<T, rollups_data::Error>
-            + Send
-            + 'static,
-        T: Send + 'static,
+        F: FnMut(Repository) -> Fut,
+        Fut: Future<Output = Result<T, rollups_data::Error>>,
     {
         let backoff = ExponentialBackoffBuilder::new()
             .with_max_elapsed_time(Some(Duration::from_secs(
                 REPOSITORY_MAX_ELAPSED_TIME,
             )))
             .build();
-        let repository = self.repository.clone();
-        tokio::task::spawn_blocking(move || {
-            backoff::retry(backoff, || {
-                f(&repository).map_err(|e| match &e {
+        backoff::future::retry(backoff, || {
+            let result = f(self.repository.clone());
+            async move {
+                result.await.map_err(|e| match &e {
                     rollups_data::Error::ItemNotFound { item_type } => {
                         tracing::info!("{} not found", item_type);
                         backoff::Error::transient(e)
                     }
                     _ => backoff::Error::permanent(e),
                 })
-            })
-            .expect("failed to get input from DB")
+            }
         })
         .await
-        .expect("failed to wait for task")
+        .expect("failed to get input from DB")
     }
 }
 
@@ -5,6 +5,7 @@ use crate::{
     foldables::{
         claims::{History, HistoryInitialState},
         input_box::{InputBox, InputBoxInitialState},
+        vouchers::{VoucherExecutions, VoucherExecutionsInitialState},
     },
     FoldableError, UserData,
 };
@@ -18,6 +19,7 @@ use eth_state_fold_types::{
 };
 
 use async_trait::async_trait;
+use im::HashMap;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
@@ -25,6 +27,7 @@ use std::sync::{Arc, Mutex};
 pub struct RollupsInitialState {
     pub history_address: Address,
     pub input_box_address: Address,
+    pub dapp_addresses: Vec<Address>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -34,6 +37,10 @@ pub struct RollupsState {
 
     pub history_initial_state: Arc<HistoryInitialState>,
     pub history: Arc<History>,
+
+    pub voucher_executions_initial_states:
+        Arc<Vec<Arc<VoucherExecutionsInitialState>>>,
+    pub voucher_executions: Arc<HashMap<Arc<Address>, Arc<VoucherExecutions>>>,
 }
 
 #[async_trait]
@@ -48,7 +55,7 @@ impl Foldable for RollupsState {
         env: &StateFoldEnvironment<M, Self::UserData>,
         _access: Arc<SyncMiddleware<M>>,
     ) -> Result<Self, Self::Error> {
-        let (input_box_initial_state, history_initial_state) = {
+        let (input_box_initial_state, history_initial_state, v) = {
             let mut user_data = env
                 .user_data()
                 .lock()
@@ -66,7 +73,16 @@ impl Foldable for RollupsState {
                 Arc::new(HistoryInitialState { history_address })
             };
 
-            (i, h)
+            let v = initial_state
+                .dapp_addresses
+                .iter()
+                .map(|dapp_address| {
+                    let dapp_address = user_data.get(*dapp_address);
+                    Arc::new(VoucherExecutionsInitialState { dapp_address })
+                })
+                .collect();
+
+            (i, h, Arc::new(v))
         };
 
         fetch_sub_foldables(
@@ -74,6 +90,7 @@ impl Foldable for RollupsState {
             block,
             input_box_initial_state,
             history_initial_state,
+            v,
         )
         .await
     }
@@ -90,6 +107,7 @@ impl Foldable for RollupsState {
             block,
             previous_state.input_box_initial_state.clone(),
             previous_state.history_initial_state.clone(),
+            previous_state.voucher_executions_initial_states.clone(),
         )
         .await
     }
@@ -100,6 +118,9 @@ async fn fetch_sub_foldables<M: Middleware + 'static>(
     block: &Block,
     input_box_initial_state: Arc<InputBoxInitialState>,
     history_initial_state: Arc<HistoryInitialState>,
+    voucher_executions_initial_states: Arc<
+        Vec<Arc<VoucherExecutionsInitialState>>,
+    >,
 ) -> Result<RollupsState, <RollupsState as Foldable>::Error> {
     // TODO: Change state-fold sync/fold to receive Arc<Block>
     let block = QueryBlock::Block(Arc::new(block.clone()));
@@ -113,14 +134,29 @@ async fn fetch_sub_foldables<M: Middleware + 'static>(
         .state;
 
     let history = env
-        .get_state_for_block::<History>(&history_initial_state, block)
+        .get_state_for_block::<History>(&history_initial_state, block.clone())
         .await?
         .state;
 
+    let mut voucher_executions = HashMap::new();
+    for initial_state in voucher_executions_initial_states.iter() {
+        let executions = env
+            .get_state_for_block::<VoucherExecutions>(
+                initial_state,
+                block.clone(),
+            )
+            .await?
+            .state;
+        voucher_executions
+            .insert(Arc::clone(&initial_state.dapp_address), executions);
+    }
+
     Ok(RollupsState {
         input_box,
         input_box_initial_state,
         history,
         history_initial_state,
+        voucher_executions: Arc::new(voucher_executions),
+        voucher_executions_initial_states,
     })
 }
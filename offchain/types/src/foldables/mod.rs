@@ -5,3 +5,4 @@ pub mod authority;
 
 pub mod claims;
 pub mod input_box;
+pub mod vouchers;
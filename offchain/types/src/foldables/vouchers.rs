@@ -0,0 +1,197 @@
+// (c) Cartesi and individual authors (see AUTHORS)
+// SPDX-License-Identifier: Apache-2.0 (see LICENSE)
+
+use crate::{FoldableError, UserData};
+
+use eth_state_fold::{
+    utils as fold_utils, FoldMiddleware, Foldable, StateFoldEnvironment,
+    SyncMiddleware,
+};
+use eth_state_fold_types::{
+    ethers::{
+        prelude::EthEvent,
+        providers::Middleware,
+        types::{Address, TxHash, U256},
+    },
+    Block,
+};
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use im::Vector;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct VoucherExecutionsInitialState {
+    pub dapp_address: Arc<Address>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct VoucherExecuted {
+    pub input_index: usize,
+    pub output_index: usize,
+    pub tx_hash: Arc<TxHash>,
+    pub block_number: u64,
+}
+
+impl VoucherExecuted {
+    /// Splits the voucher id emitted by the `CartesiDApp`, which packs the
+    /// index of the voucher within the input in the upper 128 bits and the
+    /// index of the input in the lower 128 bits.
+    pub fn from_voucher_id(
+        voucher_id: U256,
+        tx_hash: TxHash,
+        block_number: u64,
+    ) -> Result<Self, FoldableError> {
+        let input_index = voucher_id & ((U256::one() << 128) - 1);
+        let output_index = voucher_id >> 128;
+        Ok(Self {
+            input_index: input_index.try_into().map_err(|_| {
+                anyhow!("Input index of voucher id {} overflows", voucher_id)
+            })?,
+            output_index: output_index.try_into().map_err(|_| {
+                anyhow!("Output index of voucher id {} overflows", voucher_id)
+            })?,
+            tx_hash: Arc::new(tx_hash),
+            block_number,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VoucherExecutions {
+    pub dapp_address: Arc<Address>,
+    pub executions: Vector<Arc<VoucherExecuted>>,
+}
+
+#[async_trait]
+impl Foldable for VoucherExecutions {
+    type InitialState = VoucherExecutionsInitialState;
+    type Error = FoldableError;
+    type UserData = Mutex<UserData>;
+
+    async fn sync<M: Middleware + 'static>(
+        initial_state: &Self::InitialState,
+        _block: &Block,
+        _env: &StateFoldEnvironment<M, Self::UserData>,
+        access: Arc<SyncMiddleware<M>>,
+    ) -> Result<Self, Self::Error> {
+        let dapp_address = Arc::clone(&initial_state.dapp_address);
+
+        let executions =
+            fetch_executions(access, &dapp_address, &Vector::new()).await?;
+
+        Ok(Self {
+            dapp_address,
+            executions,
+        })
+    }
+
+    async fn fold<M: Middleware + 'static>(
+        previous_state: &Self,
+        block: &Block,
+        _env: &StateFoldEnvironment<M, Self::UserData>,
+        access: Arc<FoldMiddleware<M>>,
+    ) -> Result<Self, Self::Error> {
+        let dapp_address = Arc::clone(&previous_state.dapp_address);
+
+        if !(fold_utils::contains_address(&block.logs_bloom, &dapp_address)
+            && (fold_utils::contains_topic(
+                &block.logs_bloom,
+                &contracts::cartesi_dapp::VoucherExecutedFilter::signature(),
+            )))
+        {
+            return Ok(previous_state.clone());
+        }
+
+        let executions =
+            fetch_executions(access, &dapp_address, &previous_state.executions)
+                .await?;
+
+        Ok(Self {
+            dapp_address,
+            executions,
+        })
+    }
+}
+
+async fn fetch_executions<M: Middleware + 'static>(
+    provider: Arc<M>,
+    contract_address: &Address,
+    previous_executions: &Vector<Arc<VoucherExecuted>>,
+) -> Result<Vector<Arc<VoucherExecuted>>, FoldableError> {
+    use contracts::cartesi_dapp::*;
+    let contract = CartesiDApp::new(*contract_address, Arc::clone(&provider));
+
+    let mut executions = previous_executions.clone();
+
+    // Retrieve `VoucherExecuted` events
+    let events = contract
+        .voucher_executed_filter()
+        .query_with_meta()
+        .await
+        .context("Error querying for voucher executed events")?;
+
+    for (event, meta) in events {
+        executions.push_back(Arc::new(VoucherExecuted::from_voucher_id(
+            event.voucher_id,
+            meta.transaction_hash,
+            meta.block_number.as_u64(),
+        )?));
+    }
+
+    Ok(executions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_voucher_id() {
+        let voucher_id = (U256::from(3) << 128) | U256::from(42);
+        let execution = VoucherExecuted::from_voucher_id(
+            voucher_id,
+            TxHash::repeat_byte(1),
+            10,
+        )
+        .unwrap();
+        assert_eq!(execution.input_index, 42);
+        assert_eq!(execution.output_index, 3);
+        assert_eq!(*execution.tx_hash, TxHash::repeat_byte(1));
+        assert_eq!(execution.block_number, 10);
+    }
+
+    #[test]
+    fn test_from_voucher_id_with_overflowing_index() {
+        let voucher_id = U256::one() << 127;
+        let err = VoucherExecuted::from_voucher_id(
+            voucher_id,
+            TxHash::repeat_byte(1),
+            10,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Input index"));
+    }
+
+    #[test]
+    fn test_from_voucher_id_over_128_bits() {
+        let voucher_id = (U256::one() << 192) | U256::from(42);
+        let err = VoucherExecuted::from_voucher_id(
+            voucher_id,
+            TxHash::repeat_byte(1),
+            10,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Output index"));
+
+        let err = VoucherExecuted::from_voucher_id(
+            U256::MAX,
+            TxHash::repeat_byte(1),
+            10,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("overflows"));
+    }
+}